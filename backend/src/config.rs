@@ -1,22 +1,77 @@
-use gamus_scanner::config::ScannerConfig;
-use serde::{Deserialize, Serialize};
+use gamus_core::domain::artist::ArtistPatch;
+use gamus_core::domain::genre_styles::{Genre, Style, display_pairs};
+use gamus_core::domain::release::ReleasePatch;
+use gamus_core::domain::release_track::ReleaseTrack;
+use gamus_core::domain::song::SongPatch;
+use gamus_metadata::config::{AnalysisConfig, AnalysisConfigBuilder};
+use gamus_metadata::thumbnail::PrewarmSummary;
+use gamus_scanner::ScanPreview;
+use gamus_scanner::config::{ContentHashMode, HiddenPolicy, ScanRoot, ScannerConfig, SymlinkScope, ThroughputConfig};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ScannerConfigDto {
-  pub roots: Vec<String>,
+  /// Each root is either a bare path string or `{ "path": ..., "audio_exts": [...] }`.
+  pub roots: Vec<ScanRoot>,
   pub audio_exts: Vec<String>,
   pub ignore_hidden: bool,
+  /// `"dotfiles_only"`, `"platform_attributes"` or `"none"`; defaults to dotfiles when omitted.
+  #[serde(default)]
+  pub hidden_policy: HiddenPolicy,
   pub max_depth: Option<u32>,
+  /// Descend into symlinked directories; older frontends that omit it keep them skipped.
+  #[serde(default)]
+  pub follow_symlinks: bool,
+  /// `"anywhere"` or `"within_root"`: where followed symlinks may lead; anywhere when omitted.
+  #[serde(default)]
+  pub symlink_scope: SymlinkScope,
+  /// `"off"`, `"partial"` or `"full"`; older frontends that omit it get the default.
+  #[serde(default)]
+  pub content_hash: ContentHashMode,
+  /// Device benchmark settings (`sample_mb`, `skip_mb`, `bypass_cache`); defaults when omitted.
+  #[serde(default)]
+  pub throughput: ThroughputConfig,
+  /// Files shorter than this many seconds are not imported; `null` or omitted imports everything.
+  #[serde(default)]
+  pub min_duration_secs: Option<f64>,
+  /// Check the magic bytes of files whose extension is not listed; off when omitted.
+  #[serde(default)]
+  pub sniff_unknown_extensions: bool,
+  /// Seconds a file whose extraction failed is skipped unless it changes; `0` always retries.
+  #[serde(default = "default_failure_cooldown_secs")]
+  pub failure_cooldown_secs: u64,
+  /// Minimum milliseconds between two import progress batches; `0` sends every file.
+  /// Applied on the next launch.
+  #[serde(default = "default_progress_interval_ms")]
+  pub progress_interval_ms: u64,
+}
+
+fn default_failure_cooldown_secs() -> u64 {
+  ScannerConfig::default().failure_cooldown_secs
+}
+
+fn default_progress_interval_ms() -> u64 {
+  ScannerConfig::default().progress_interval_ms
 }
 
 impl From<ScannerConfig> for ScannerConfigDto {
   fn from(cfg: ScannerConfig) -> Self {
     ScannerConfigDto {
-      roots: cfg.roots.into_iter().map(|p| p.to_string_lossy().to_string()).collect(),
+      roots: cfg.roots,
       audio_exts: cfg.audio_exts,
       ignore_hidden: cfg.ignore_hidden,
+      hidden_policy: cfg.hidden_policy,
       max_depth: cfg.max_depth,
+      follow_symlinks: cfg.follow_symlinks,
+      symlink_scope: cfg.symlink_scope,
+      content_hash: cfg.content_hash,
+      throughput: cfg.throughput,
+      min_duration_secs: cfg.min_duration_secs,
+      sniff_unknown_extensions: cfg.sniff_unknown_extensions,
+      failure_cooldown_secs: cfg.failure_cooldown_secs,
+      progress_interval_ms: cfg.progress_interval_ms,
     }
   }
 }
@@ -24,10 +79,224 @@ impl From<ScannerConfig> for ScannerConfigDto {
 impl From<ScannerConfigDto> for ScannerConfig {
   fn from(dto: ScannerConfigDto) -> Self {
     ScannerConfig {
-      roots: dto.roots.into_iter().map(PathBuf::from).collect(),
+      roots: dto.roots,
       audio_exts: dto.audio_exts,
       ignore_hidden: dto.ignore_hidden,
+      hidden_policy: dto.hidden_policy,
       max_depth: dto.max_depth,
+      follow_symlinks: dto.follow_symlinks,
+      symlink_scope: dto.symlink_scope,
+      content_hash: dto.content_hash,
+      throughput: dto.throughput,
+      min_duration_secs: dto.min_duration_secs,
+      sniff_unknown_extensions: dto.sniff_unknown_extensions,
+      failure_cooldown_secs: dto.failure_cooldown_secs,
+      progress_interval_ms: dto.progress_interval_ms,
+    }
+  }
+}
+
+/// Dry-run result of the current scanner configuration, as sent to the frontend.
+#[derive(Debug, Serialize)]
+pub struct ScanPreviewDto {
+  pub total: usize,
+  /// Keyed by lowercase extension without the dot (`"flac"`); `""` for files without one.
+  pub by_extension: HashMap<String, usize>,
+  pub sample_paths: Vec<String>,
+  /// Configured roots that are missing or unreadable; a non-empty list usually means a typo or an unplugged drive.
+  pub unavailable_roots: Vec<String>,
+  /// Roots and folders the OS refused to list; on macOS usually means Full Disk Access is missing.
+  pub permission_denied: Vec<String>,
+}
+
+impl From<ScanPreview> for ScanPreviewDto {
+  fn from(preview: ScanPreview) -> Self {
+    ScanPreviewDto {
+      total: preview.total,
+      by_extension: preview.by_extension,
+      sample_paths: preview.sample_paths.into_iter().map(|p| p.to_string_lossy().to_string()).collect(),
+      unavailable_roots: preview.unavailable_roots.into_iter().map(|p| p.to_string_lossy().to_string()).collect(),
+      permission_denied: preview.permission_denied.into_iter().map(|p| p.to_string_lossy().to_string()).collect(),
+    }
+  }
+}
+
+/// One selectable taxonomy entry: `value` is what the backend expects back (`"FunkSoul"`),
+/// `display` the human-readable text (`"Funk / Soul"`).
+#[derive(Debug, Serialize)]
+pub struct TaxonomyEntryDto<T> {
+  pub value: T,
+  pub display: String,
+}
+
+/// Known genres and built-in styles, in declaration order, for the tag editor selectors.
+#[derive(Debug, Serialize)]
+pub struct TaxonomyDto {
+  pub genres: Vec<TaxonomyEntryDto<Genre>>,
+  pub styles: Vec<TaxonomyEntryDto<Style>>,
+}
+
+impl TaxonomyDto {
+  pub fn builtin() -> Self {
+    fn entries<T: Clone + std::fmt::Display>(values: &[T]) -> Vec<TaxonomyEntryDto<T>> {
+      display_pairs(values).into_iter().map(|(value, display)| TaxonomyEntryDto { value, display }).collect()
+    }
+
+    TaxonomyDto { genres: entries(Genre::all()), styles: entries(Style::all_builtin()) }
+  }
+}
+
+/// One facet of the browse sidebar: a genre or style present in the library and how many
+/// releases carry it.
+#[derive(Debug, Serialize)]
+pub struct FacetDto<T> {
+  pub value: T,
+  pub display: String,
+  pub count: usize,
+}
+
+/// Genres and styles actually present in the library, most common first.
+#[derive(Debug, Serialize)]
+pub struct FacetsDto {
+  pub genres: Vec<FacetDto<Genre>>,
+  pub styles: Vec<FacetDto<Style>>,
+}
+
+impl FacetsDto {
+  pub fn new(genres: Vec<(Genre, usize)>, styles: Vec<(Style, usize)>) -> Self {
+    fn facets<T: std::fmt::Display>(counts: Vec<(T, usize)>) -> Vec<FacetDto<T>> {
+      counts.into_iter().map(|(value, count)| FacetDto { display: value.to_string(), value, count }).collect()
+    }
+
+    FacetsDto { genres: facets(genres), styles: facets(styles) }
+  }
+}
+
+/// One page of the track list plus the library-wide total, for paginated views.
+#[derive(Debug, Serialize)]
+pub struct TrackPageDto {
+  pub tracks: Vec<ReleaseTrack>,
+  pub total: i64,
+}
+
+/// Outcome of `library_prewarm_thumbnails`, counted per distinct artwork hash.
+#[derive(Debug, Serialize)]
+pub struct ThumbnailPrewarmDto {
+  pub generated: usize,
+  /// Already in the cache from an earlier run; only recorded again.
+  pub reused: usize,
+  /// `(artwork path, error)` for images that could not be read or decoded.
+  pub failed: Vec<(PathBuf, String)>,
+}
+
+impl From<PrewarmSummary> for ThumbnailPrewarmDto {
+  fn from(summary: PrewarmSummary) -> Self {
+    let generated = summary.thumbnails.iter().filter(|t| t.generated).count();
+    ThumbnailPrewarmDto { generated, reused: summary.thumbnails.len() - generated, failed: summary.failed }
+  }
+}
+
+/// Manual corrections for one track; omitted fields are left unchanged.
+#[derive(Debug, Deserialize)]
+pub struct TrackMetadataPatchDto {
+  pub track_number: Option<u32>,
+  pub disc_number: Option<u32>,
+  /// Omitted leaves the override as is, `null` clears it.
+  #[serde(default, deserialize_with = "present")]
+  pub title_override: Option<Option<String>>,
+}
+
+/// Song fields to change; omitted fields are left as they are, `null` clears an optional one.
+#[derive(Debug, Deserialize)]
+pub struct SongPatchDto {
+  pub title: Option<String>,
+  #[serde(default, deserialize_with = "present")]
+  pub acoustid: Option<Option<String>>,
+  #[serde(default, deserialize_with = "present")]
+  pub isrc: Option<Option<String>>,
+  #[serde(default, deserialize_with = "present")]
+  pub mbid: Option<Option<String>>,
+}
+
+impl From<SongPatchDto> for SongPatch {
+  fn from(dto: SongPatchDto) -> Self {
+    SongPatch { title: dto.title, acoustid: dto.acoustid, isrc: dto.isrc, mbid: dto.mbid }
+  }
+}
+
+/// Release fields to change, with the same rules as [`SongPatchDto`].
+#[derive(Debug, Deserialize)]
+pub struct ReleasePatchDto {
+  pub title: Option<String>,
+  #[serde(default, deserialize_with = "present")]
+  pub release_date: Option<Option<String>>,
+  #[serde(default, deserialize_with = "present")]
+  pub mbid: Option<Option<String>>,
+}
+
+impl From<ReleasePatchDto> for ReleasePatch {
+  fn from(dto: ReleasePatchDto) -> Self {
+    ReleasePatch { title: dto.title, release_date: dto.release_date, mbid: dto.mbid }
+  }
+}
+
+/// Artist fields to change, with the same rules as [`SongPatchDto`].
+#[derive(Debug, Deserialize)]
+pub struct ArtistPatchDto {
+  pub name: Option<String>,
+  #[serde(default, deserialize_with = "present")]
+  pub bio: Option<Option<String>>,
+}
+
+impl From<ArtistPatchDto> for ArtistPatch {
+  fn from(dto: ArtistPatchDto) -> Self {
+    ArtistPatch { name: dto.name, bio: dto.bio }
+  }
+}
+
+/// Maps a present field to `Some(..)` (also when it is `null`); `default` covers the absent case.
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+  D: Deserializer<'de>,
+  T: Deserialize<'de>,
+{
+  Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Spectral analysis overrides for a one-off re-analysis; omitted fields keep the saved settings.
+#[derive(Debug, Default, Deserialize)]
+pub struct AnalysisConfigDto {
+  pub fft_window_size: Option<usize>,
+  pub overlap_ratio: Option<f32>,
+  pub max_analysis_duration_secs: Option<f32>,
+  pub analysis_start_secs: Option<f32>,
+  pub silence_trim_db: Option<f32>,
+  /// Resample to this rate (Hz) before the FFT instead of analysing at the file's own rate.
+  pub analysis_sample_rate: Option<u32>,
+}
+
+impl AnalysisConfigDto {
+  /// Applies the overrides on top of `base` and validates the result.
+  pub fn apply_to(self, base: AnalysisConfig) -> Result<AnalysisConfig, String> {
+    let mut builder = AnalysisConfigBuilder::from(base);
+    if let Some(size) = self.fft_window_size {
+      builder = builder.fft_window_size(size);
+    }
+    if let Some(ratio) = self.overlap_ratio {
+      builder = builder.overlap_ratio(ratio);
+    }
+    if let Some(secs) = self.max_analysis_duration_secs {
+      builder = builder.max_analysis_duration_secs(secs);
+    }
+    if let Some(secs) = self.analysis_start_secs {
+      builder = builder.analysis_start_secs(secs);
+    }
+    if let Some(db) = self.silence_trim_db {
+      builder = builder.silence_trim_db(db);
+    }
+    if let Some(hz) = self.analysis_sample_rate {
+      builder = builder.analysis_sample_rate(hz);
     }
+    builder.build().map_err(|e| e.to_string())
   }
 }
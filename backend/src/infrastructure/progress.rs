@@ -0,0 +1,147 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use gamus_core::ports::{ImportSummary, ProgressReporter};
+use serde::Serialize;
+
+/// Point-in-time view of the import progress, serialized to the frontend.
+///
+/// `done` counts successful files only; failed files are counted in `errors` and files
+/// left out on purpose (e.g. below the minimum duration) in `skipped`, so
+/// `done + errors + skipped` is the number of files handled so far.
+///
+/// While `scanning` is true the import has not started yet and only `files_found` moves.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ImportProgress {
+  pub total: usize,
+  pub done: usize,
+  pub errors: usize,
+  pub skipped: usize,
+  pub running: bool,
+  pub scanning: bool,
+  pub files_found: usize,
+}
+
+/// Shared, lock-free progress counters.
+///
+/// Each field is read independently, so a snapshot taken mid-import may be off by
+/// one between fields. That is fine for a progress bar and avoids a mutex on the hot path.
+#[derive(Debug, Default)]
+pub struct ImportProgressState {
+  total: AtomicUsize,
+  done: AtomicUsize,
+  errors: AtomicUsize,
+  skipped: AtomicUsize,
+  running: AtomicBool,
+  scanning: AtomicBool,
+  files_found: AtomicUsize,
+  /// Folders the last scan could not list for lack of permission. Written once per scan,
+  /// so the mutex stays off the per-file path.
+  permission_denied: Mutex<Vec<PathBuf>>,
+}
+
+impl ImportProgressState {
+  pub fn snapshot(&self) -> ImportProgress {
+    ImportProgress {
+      total: self.total.load(Ordering::Relaxed),
+      done: self.done.load(Ordering::Relaxed),
+      errors: self.errors.load(Ordering::Relaxed),
+      skipped: self.skipped.load(Ordering::Relaxed),
+      running: self.running.load(Ordering::Relaxed),
+      scanning: self.scanning.load(Ordering::Relaxed),
+      files_found: self.files_found.load(Ordering::Relaxed),
+    }
+  }
+
+  /// Roots and folders the last scan was not allowed to list; empty before the first scan.
+  pub fn permission_denied(&self) -> Vec<PathBuf> {
+    self.permission_denied.lock().unwrap_or_else(|e| e.into_inner()).clone()
+  }
+}
+
+/// A `ProgressReporter` decorator that keeps an `ImportProgressState` up to date
+/// before forwarding every event to the wrapped reporter.
+///
+/// This lets the frontend query the current state on demand (e.g., after remounting
+/// a view mid-import) while the event stream keeps working as before.
+#[derive(Clone)]
+pub struct ProgressObserver<R: ProgressReporter> {
+  inner: R,
+  state: Arc<ImportProgressState>,
+}
+
+impl<R: ProgressReporter> ProgressObserver<R> {
+  pub fn new(inner: R, state: Arc<ImportProgressState>) -> Self {
+    Self { inner, state }
+  }
+}
+
+#[async_trait]
+impl<R: ProgressReporter> ProgressReporter for ProgressObserver<R> {
+  async fn start(&self, total_files: usize) {
+    self.state.total.store(total_files, Ordering::Relaxed);
+    self.state.done.store(0, Ordering::Relaxed);
+    self.state.errors.store(0, Ordering::Relaxed);
+    self.state.skipped.store(0, Ordering::Relaxed);
+    self.state.running.store(true, Ordering::Relaxed);
+    self.inner.start(total_files).await;
+  }
+
+  async fn on_success(&self, path: &str) {
+    self.state.done.fetch_add(1, Ordering::Relaxed);
+    self.inner.on_success(path).await;
+  }
+
+  async fn on_success_batch(&self, count: usize, last_path: &str) {
+    self.state.done.fetch_add(count, Ordering::Relaxed);
+    self.inner.on_success_batch(count, last_path).await;
+  }
+
+  async fn on_error(&self, path: &str, error: &str) {
+    self.state.errors.fetch_add(1, Ordering::Relaxed);
+    self.inner.on_error(path, error).await;
+  }
+
+  async fn on_group_error(&self, device: &str, error: &str) {
+    self.inner.on_group_error(device, error).await;
+  }
+
+  async fn on_skipped(&self, path: &str, reason: &str) {
+    self.state.skipped.fetch_add(1, Ordering::Relaxed);
+    self.inner.on_skipped(path, reason).await;
+  }
+
+  async fn finish(&self, summary: ImportSummary) {
+    self.state.running.store(false, Ordering::Relaxed);
+    self.inner.finish(summary).await;
+  }
+
+  async fn scan_started(&self) {
+    self.state.files_found.store(0, Ordering::Relaxed);
+    self.state.scanning.store(true, Ordering::Relaxed);
+    self.state.permission_denied.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    self.inner.scan_started().await;
+  }
+
+  async fn on_scan_progress(&self, files_found: usize) {
+    self.state.files_found.store(files_found, Ordering::Relaxed);
+    self.inner.on_scan_progress(files_found).await;
+  }
+
+  async fn scan_finished(&self, files_found: usize) {
+    self.state.files_found.store(files_found, Ordering::Relaxed);
+    self.state.scanning.store(false, Ordering::Relaxed);
+    self.inner.scan_finished(files_found).await;
+  }
+
+  async fn on_roots_unavailable(&self, roots: &[PathBuf]) {
+    self.inner.on_roots_unavailable(roots).await;
+  }
+
+  async fn on_permission_denied(&self, paths: &[PathBuf]) {
+    *self.state.permission_denied.lock().unwrap_or_else(|e| e.into_inner()) = paths.to_vec();
+    self.inner.on_permission_denied(paths).await;
+  }
+}
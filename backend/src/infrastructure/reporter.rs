@@ -1,5 +1,7 @@
+use std::path::PathBuf;
+
 use async_trait::async_trait;
-use gamus_core::ports::ProgressReporter;
+use gamus_core::ports::{ImportSummary, ProgressReporter};
 use serde::Serialize;
 use tauri::{AppHandle, Emitter};
 
@@ -10,6 +12,27 @@ struct ErrorPayload {
   error: String,
 }
 
+/// Payload of `library:import:group_error`: a failure of a whole scan group, not of a file.
+#[derive(Clone, Serialize)]
+struct GroupErrorPayload {
+  device: String,
+  error: String,
+}
+
+/// Payload of `library:import:skipped`: the file and why it was left out.
+#[derive(Clone, Serialize)]
+struct SkippedPayload {
+  path: String,
+  reason: String,
+}
+
+/// Payload of `library:import:success_batch`: files imported since the previous batch.
+#[derive(Clone, Serialize)]
+struct SuccessBatchPayload {
+  count: usize,
+  last_path: String,
+}
+
 /// A `ProgressReporter` implementation that bridges backend events to the Tauri frontend.
 ///
 /// This struct holds a reference to the `AppHandle`, allowing it to emit global events
@@ -36,12 +59,51 @@ impl ProgressReporter for TauriReporter {
     let _ = self.app_handle.emit("library:import:success", path);
   }
 
+  async fn on_success_batch(&self, count: usize, last_path: &str) {
+    let payload = SuccessBatchPayload { count, last_path: last_path.to_string() };
+    let _ = self.app_handle.emit("library:import:success_batch", payload);
+  }
+
   async fn on_error(&self, path: &str, error: &str) {
     let payload = ErrorPayload { path: path.to_string(), error: error.to_string() };
     let _ = self.app_handle.emit("library:import:error", payload);
   }
 
-  async fn finish(&self) {
-    let _ = self.app_handle.emit("library:import:finish", ());
+  async fn on_group_error(&self, device: &str, error: &str) {
+    let payload = GroupErrorPayload { device: device.to_string(), error: error.to_string() };
+    let _ = self.app_handle.emit("library:import:group_error", payload);
+  }
+
+  async fn on_skipped(&self, path: &str, reason: &str) {
+    let payload = SkippedPayload { path: path.to_string(), reason: reason.to_string() };
+    let _ = self.app_handle.emit("library:import:skipped", payload);
+  }
+
+  async fn finish(&self, summary: ImportSummary) {
+    // Payload: `{ total, succeeded, failed, skipped, elapsed_secs }` for the completion toast.
+    let _ = self.app_handle.emit("library:import:finish", summary);
+  }
+
+  async fn scan_started(&self) {
+    let _ = self.app_handle.emit("library:scan:start", ());
+  }
+
+  async fn on_scan_progress(&self, files_found: usize) {
+    // Payload: running count of audio files discovered so far.
+    let _ = self.app_handle.emit("library:scan:progress", files_found);
+  }
+
+  async fn scan_finished(&self, files_found: usize) {
+    let _ = self.app_handle.emit("library:scan:finish", files_found);
+  }
+
+  async fn on_roots_unavailable(&self, roots: &[PathBuf]) {
+    // Payload: list of root paths, so the UI can say "Drive X not connected".
+    let _ = self.app_handle.emit("library:import:roots_unavailable", roots);
+  }
+
+  async fn on_permission_denied(&self, paths: &[PathBuf]) {
+    // Payload: list of folder paths, for a "grant access to these folders" prompt.
+    let _ = self.app_handle.emit("library:import:permission_denied", paths);
   }
 }
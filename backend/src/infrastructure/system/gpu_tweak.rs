@@ -4,7 +4,7 @@ pub fn apply_linux_patches() {
   #[cfg(target_os = "linux")]
   unsafe {
     if is_dangerous_combo() {
-      println!("🔧 Infraestructura: Ajustando renderizado para Nvidia+Wayland");
+      tracing::info!("Nvidia+Wayland detectado: desactivando el renderer DMABUF de WebKit");
       std::env::set_var("WEBKIT_DISABLE_DMABUF_RENDERER", "1");
     }
   }
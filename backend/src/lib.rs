@@ -1,23 +1,54 @@
 mod config;
 mod infrastructure;
 
-use gamus_core::services::LibraryService;
-use gamus_metadata::FfmpegProbe;
-use gamus_scanner::{FsScanner, ScannerConfig};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use gamus_config::GenreMap;
+use gamus_core::domain::browse::{Page, Paged, ReleaseFilter, ReleaseSummary, SortBy};
+use gamus_core::domain::library_stats::LibraryStats;
+use gamus_core::domain::release::Release;
+use gamus_core::domain::release_track::{AudioQuality, ReleaseTrack, TrackQualitySummary};
+use gamus_core::domain::song::Song;
+use gamus_core::domain::{ArtistId, ReleaseId, ReleaseTrackId, SongId};
+use gamus_core::ports::{ExtractionFailure, ImportCheckpoint, Library};
+use gamus_core::services::{
+  LibraryService, RestoreSummary, ThrottledReporter, export_library_json, import_library_json,
+};
+use gamus_metadata::config::{AnalysisConfig, LosslessPolicy};
+use gamus_metadata::{FfmpegProbe, ThumbnailCache};
+use gamus_scanner::{FsScanner, ScannerConfig, scan_music_with_cfg};
 use gamus_storage::LibraryStore;
+use gamus_storage::paths::LibraryRoot;
 
 use tauri::{Manager, State};
 
-use crate::config::ScannerConfigDto;
+use crate::config::{
+  AnalysisConfigDto, ArtistPatchDto, FacetsDto, ReleasePatchDto, ScanPreviewDto, ScannerConfigDto, SongPatchDto,
+  TaxonomyDto, ThumbnailPrewarmDto, TrackMetadataPatchDto, TrackPageDto,
+};
+use infrastructure::progress::{ImportProgress, ImportProgressState, ProgressObserver};
 use infrastructure::reporter::TauriReporter;
 use infrastructure::system::gpu_tweak;
 
 /// Type alias to simplify the generic signature of the Service.
-type ConcreteLibraryService = LibraryService<FsScanner, FfmpegProbe, LibraryStore, TauriReporter>;
+type ConcreteLibraryService =
+  LibraryService<FsScanner, FfmpegProbe, LibraryStore, ProgressObserver<ThrottledReporter<TauriReporter>>>;
 
 /// Global application state managed by Tauri.
 struct AppState {
   library: ConcreteLibraryService,
+  /// Same pool the service uses; needed for store-level maintenance that isn't part of the `Library` port.
+  store: LibraryStore,
+  /// Clone of the service's probe; clones share their analysis settings, so `config_reload`
+  /// can swap them through it.
+  metadata: FfmpegProbe,
+  /// Shared with the service's reporter; read by `library_get_progress`.
+  progress: Arc<ImportProgressState>,
 }
 
 /// Command: Triggers the full library ingestion process.
@@ -30,6 +61,353 @@ async fn library_import_full(state: State<'_, AppState>) -> Result<(), String> {
   state.library.import_full().await.map_err(|e| e.to_string())
 }
 
+/// Command: Imports only files that are new or changed since the last import.
+///
+/// Same progress events as `library_import_full`; `total` counts only the changed files.
+#[tauri::command]
+async fn library_import_incremental(state: State<'_, AppState>) -> Result<(), String> {
+  state.library.import_incremental().await.map_err(|e| e.to_string())
+}
+
+/// Command: Full import that resumes where an interrupted one stopped.
+///
+/// Same progress events as `library_import_full`. When resuming, files saved before the
+/// interruption count as `skipped`.
+#[tauri::command]
+async fn library_import_resumable(state: State<'_, AppState>) -> Result<(), String> {
+  state.library.import_full_resumable().await.map_err(|e| e.to_string())
+}
+
+/// Command: Returns the interrupted resumable import, if any, so the UI can offer
+/// "Resume" vs "Start over".
+#[tauri::command]
+fn library_pending_import(state: State<'_, AppState>) -> Result<Option<ImportCheckpoint>, String> {
+  state.library.pending_import().map_err(|e| e.to_string())
+}
+
+/// Command: Forgets the interrupted resumable import; the next one starts over.
+#[tauri::command]
+fn library_discard_pending_import(state: State<'_, AppState>) -> Result<(), String> {
+  state.library.discard_pending_import().map_err(|e| e.to_string())
+}
+
+/// Command: Runs the quality analysis for tracks imported without one.
+///
+/// Reports through the same `library:import:*` events as an import, with `total` counting
+/// the pending files. Meant to run after the library is already browsable.
+#[tauri::command]
+async fn library_analyze_pending(state: State<'_, AppState>) -> Result<(), String> {
+  state.library.analyze_pending().await.map_err(|e| e.to_string())
+}
+
+/// Command: Returns a snapshot of the current (or last) import progress.
+///
+/// Lets the frontend resync its progress UI after mounting mid-import, since the
+/// `library:import:*` events are fire-and-forget and are not replayed.
+#[tauri::command]
+fn library_get_progress(state: State<'_, AppState>) -> ImportProgress {
+  state.progress.snapshot()
+}
+
+/// Command: Lists the roots and folders the last scan was not allowed to read.
+///
+/// Same payload as the `library:import:permission_denied` event, kept so the UI can show
+/// the permissions prompt after missing it. `scanner_preview` reports the same for the
+/// current configuration without importing.
+#[tauri::command]
+fn library_permission_denied(state: State<'_, AppState>) -> Vec<PathBuf> {
+  state.progress.permission_denied()
+}
+
+/// Command: Returns aggregated counts and totals for the dashboard.
+#[tauri::command]
+fn library_stats(state: State<'_, AppState>) -> Result<LibraryStats, String> {
+  state.library.stats().map_err(|e| e.to_string())
+}
+
+/// Command: Returns the `limit` most recently added tracks, newest first.
+#[tauri::command]
+fn library_recent_tracks(state: State<'_, AppState>, limit: i64) -> Result<Vec<ReleaseTrack>, String> {
+  state.library.list_recent_tracks(limit).map_err(|e| e.to_string())
+}
+
+/// Command: Lists tracks encoded with the given codec (`"flac"`, `"mp3"`, `"opus"`...).
+#[tauri::command]
+fn library_tracks_by_codec(state: State<'_, AppState>, codec: String) -> Result<Vec<ReleaseTrack>, String> {
+  state.library.list_tracks_by_codec(&codec).map_err(|e| e.to_string())
+}
+
+/// Command: Lists tracks whose quality score is within `[min, max]`, worst first, for the
+/// "re-rip candidates" worklist. Paged by `limit`/`offset`; unanalysed tracks are left out.
+#[tauri::command]
+fn library_tracks_by_quality(
+  state: State<'_, AppState>,
+  min: f32,
+  max: f32,
+  limit: i64,
+  offset: i64,
+) -> Result<Vec<TrackQualitySummary>, String> {
+  state.library.list_tracks_by_quality(min, max, limit, offset).map_err(|e| e.to_string())
+}
+
+/// Command: Finds the `limit` tracks whose feature embedding is closest to the track `id`.
+///
+/// Payload: `[[track_id, cosine_similarity], ...]`, most similar first. Fails if the track
+/// has not been analysed into an embedding yet.
+#[tauri::command]
+fn library_similar_tracks(
+  state: State<'_, AppState>,
+  id: ReleaseTrackId,
+  limit: usize,
+) -> Result<Vec<(ReleaseTrackId, f32)>, String> {
+  state.library.find_similar(id, limit).map_err(|e| e.to_string())
+}
+
+/// Command: Groups tracks whose files are byte-identical copies (same size and content
+/// hash), for the duplicate cleanup screen.
+///
+/// Payload: `[[track_id, ...], ...]`, largest files first. Files imported without a content
+/// hash are never reported.
+#[tauri::command]
+fn library_duplicate_files(state: State<'_, AppState>) -> Result<Vec<Vec<ReleaseTrackId>>, String> {
+  state.library.find_duplicate_files().map_err(|e| e.to_string())
+}
+
+/// Command: Returns the cached cover thumbnail of a release, if one was generated.
+///
+/// Payload: an absolute file path or `null`; run `library_prewarm_thumbnails` to fill the cache.
+#[tauri::command]
+fn library_release_thumbnail(state: State<'_, AppState>, id: ReleaseId) -> Result<Option<PathBuf>, String> {
+  state.library.release_thumbnail(id).map_err(|e| e.to_string())
+}
+
+/// Command: Generates the cover thumbnails of the whole library and records them.
+///
+/// Thumbnails live in the cache dir keyed by artwork hash, so images already there are
+/// only recorded, not decoded again. `max_dimension` defaults to 300 px. Runs on a
+/// blocking thread.
+#[tauri::command]
+async fn library_prewarm_thumbnails(
+  state: State<'_, AppState>,
+  max_dimension: Option<u32>,
+) -> Result<ThumbnailPrewarmDto, String> {
+  let store = state.store.clone();
+  tauri::async_runtime::spawn_blocking(move || {
+    let mut cache = ThumbnailCache::in_cache_dir();
+    if let Some(max_dimension) = max_dimension {
+      cache = cache.with_max_dimension(max_dimension);
+    }
+    let artworks = store.list_artworks().map_err(|e| e.to_string())?;
+    let summary = cache.ensure_all(&artworks);
+    for thumbnail in &summary.thumbnails {
+      store.set_artwork_thumbnail(&thumbnail.hash, &thumbnail.path).map_err(|e| e.to_string())?;
+    }
+    Ok(ThumbnailPrewarmDto::from(summary))
+  })
+  .await
+  .map_err(|e| e.to_string())?
+}
+
+/// Command: Returns `limit` tracks starting at `offset` (ordered by file path) and the total.
+///
+/// The total is a `COUNT`, so paging through a large library never loads it whole.
+#[tauri::command]
+fn library_tracks_page(state: State<'_, AppState>, offset: i64, limit: i64) -> Result<TrackPageDto, String> {
+  let tracks = state.library.list_tracks_page(offset, limit).map_err(|e| e.to_string())?;
+  let total = state.library.count_tracks().map_err(|e| e.to_string())?;
+  Ok(TrackPageDto { tracks, total })
+}
+
+/// Command: Genres and styles present in the library with their release counts, for the
+/// faceted browse sidebar.
+///
+/// Unlike `taxonomy_list`, only values that some release carries are returned, and custom
+/// styles are included.
+#[tauri::command]
+fn library_facets(state: State<'_, AppState>) -> Result<FacetsDto, String> {
+  let genres = state.library.distinct_genres().map_err(|e| e.to_string())?;
+  let styles = state.library.distinct_styles().map_err(|e| e.to_string())?;
+  Ok(FacetsDto::new(genres, styles))
+}
+
+/// Command: One page of releases for the main library view, filtered and sorted in a single
+/// query, with the total matching the filter.
+///
+/// Missing arguments mean no filter, title order and the first 50 releases.
+#[tauri::command]
+fn library_browse_releases(
+  state: State<'_, AppState>,
+  filter: Option<ReleaseFilter>,
+  sort: Option<SortBy>,
+  page: Option<Page>,
+) -> Result<Paged<ReleaseSummary>, String> {
+  state
+    .library
+    .browse_releases(filter.unwrap_or_default(), sort.unwrap_or_default(), page.unwrap_or_default())
+    .map_err(|e| e.to_string())
+}
+
+/// Command: Lists songs that no track points to, for the cleanup view.
+#[tauri::command]
+fn library_orphan_songs(state: State<'_, AppState>) -> Result<Vec<Song>, String> {
+  state.library.list_orphan_songs().map_err(|e| e.to_string())
+}
+
+/// Command: Lists releases with no tracks, for the cleanup view.
+#[tauri::command]
+fn library_empty_releases(state: State<'_, AppState>) -> Result<Vec<Release>, String> {
+  state.library.list_empty_releases().map_err(|e| e.to_string())
+}
+
+/// Command: Lists files whose extraction failed and that imports skip until they change or
+/// the cooldown passes, with the error and how many times it has been tried.
+#[tauri::command]
+fn library_extraction_failures(state: State<'_, AppState>) -> Result<Vec<ExtractionFailure>, String> {
+  state.library.list_extraction_failures().map_err(|e| e.to_string())
+}
+
+/// Command: Forgets every recorded extraction failure so the next import retries those
+/// files. Returns how many were cleared.
+#[tauri::command]
+fn library_clear_extraction_failures(state: State<'_, AppState>) -> Result<usize, String> {
+  state.library.clear_extraction_failures().map_err(|e| e.to_string())
+}
+
+/// Command: Lists the library roots file paths are stored relative to, deepest first.
+///
+/// Every configured scanner root is registered as one (at startup and whenever the scanner
+/// config is saved or reloaded); files outside all of them are stored with absolute paths.
+#[tauri::command]
+fn library_roots(state: State<'_, AppState>) -> Vec<LibraryRoot> {
+  state.store.library_roots()
+}
+
+/// Command: Points library root `id` at `path`, e.g. after moving the database to another
+/// machine or remounting the drive under a different mount point.
+///
+/// The files under the root are found at `path` from then on, without re-importing them.
+#[tauri::command]
+fn library_relocate_root(state: State<'_, AppState>, id: String, path: PathBuf) -> Result<(), String> {
+  state.store.relocate_library_root(&id, &path).map_err(|e| e.to_string())
+}
+
+/// Command: Fixes a track's number, disc or per-release title without re-importing.
+///
+/// Numbers must be ≥ 1. In `patch`, an omitted field is left as is and
+/// `"title_override": null` removes the override.
+#[tauri::command]
+fn library_update_track(
+  state: State<'_, AppState>,
+  id: ReleaseTrackId,
+  patch: TrackMetadataPatchDto,
+) -> Result<(), String> {
+  state
+    .library
+    .update_track_metadata(id, patch.track_number, patch.disc_number, patch.title_override)
+    .map_err(|e| e.to_string())
+}
+
+/// Command: Changes only the song fields present in `patch` (title, acoustid, isrc, mbid).
+///
+/// An omitted field is left as is and `null` clears an optional one; a blank title is rejected.
+#[tauri::command]
+fn library_update_song(state: State<'_, AppState>, id: SongId, patch: SongPatchDto) -> Result<(), String> {
+  state.library.update_song(id, &patch.into()).map_err(|e| e.to_string())
+}
+
+/// Command: Changes only the release fields present in `patch` (title, release_date, mbid).
+///
+/// Same rules as `library_update_song`.
+#[tauri::command]
+fn library_update_release(state: State<'_, AppState>, id: ReleaseId, patch: ReleasePatchDto) -> Result<(), String> {
+  state.library.update_release(id, &patch.into()).map_err(|e| e.to_string())
+}
+
+/// Command: Changes only the artist fields present in `patch` (name, bio).
+///
+/// Same rules as `library_update_song`; renaming onto another artist's name is rejected.
+#[tauri::command]
+fn library_update_artist(state: State<'_, AppState>, id: ArtistId, patch: ArtistPatchDto) -> Result<(), String> {
+  state.library.update_artist(id, &patch.into()).map_err(|e| e.to_string())
+}
+
+/// Command: Re-runs the quality analysis of one track, optionally with custom settings.
+///
+/// `config` overrides are applied on top of the saved `[analysis]` settings, re-read on
+/// every call. Lossless files are always measured here, whatever `lossless_policy` says.
+/// Fails if the file is no longer on disk. The new result replaces the stored analysis and
+/// is returned for the track-detail view.
+#[tauri::command]
+async fn library_reanalyze_track(
+  state: State<'_, AppState>,
+  id: ReleaseTrackId,
+  config: Option<AnalysisConfigDto>,
+) -> Result<AudioQuality, String> {
+  let saved = AnalysisConfig::load().map_err(|e| e.to_string())?;
+  let saved = AnalysisConfig { lossless_policy: LosslessPolicy::AlwaysVerify, ..saved };
+  let config = config.unwrap_or_default().apply_to(saved)?;
+  let probe = FfmpegProbe::new_with_analysis(config);
+  state.library.reanalyze_track(id, &probe).await.map_err(|e| e.to_string())
+}
+
+/// Command: Returns every tag FFmpeg finds in the file at `path`, mapped or not.
+///
+/// Backs the tag inspector; keys are lower-cased. Only headers are read, the database is not touched.
+#[tauri::command]
+async fn track_raw_tags(path: String) -> Result<HashMap<String, String>, String> {
+  FfmpegProbe::new_without_analysis().raw_tags(Path::new(&path)).await.map_err(|e| e.to_string())
+}
+
+/// Command: Runs database maintenance (`PRAGMA optimize`, `VACUUM`, WAL checkpoint).
+///
+/// Must not be triggered while `library_import_full` is running: `VACUUM` locks the
+/// whole database and the import's writers would time out.
+#[tauri::command]
+async fn library_maintenance(state: State<'_, AppState>) -> Result<(), String> {
+  let store = state.store.clone();
+  tauri::async_runtime::spawn_blocking(move || store.maintenance())
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())
+}
+
+/// Command: Exports the whole library to `path` as newline-delimited JSON.
+///
+/// Each line is `{"kind": "artist" | "release" | "song" | "track", "data": {...}}`.
+/// Runs on a blocking thread; tracks are read page by page, so memory stays bounded.
+#[tauri::command]
+async fn library_export(state: State<'_, AppState>, path: String) -> Result<(), String> {
+  let store = state.store.clone();
+  tauri::async_runtime::spawn_blocking(move || {
+    let file = File::create(&path).map_err(|e| e.to_string())?;
+    export_library_json(&store, BufWriter::new(file)).map_err(|e| e.to_string())
+  })
+  .await
+  .map_err(|e| e.to_string())?
+}
+
+/// Command: Restores a dump written by `library_export`, keeping the original ids.
+///
+/// Malformed or rejected lines are skipped and counted in `failed` instead of aborting.
+#[tauri::command]
+async fn library_import_backup(state: State<'_, AppState>, path: String) -> Result<RestoreSummary, String> {
+  let store = state.store.clone();
+  tauri::async_runtime::spawn_blocking(move || {
+    let file = File::open(&path).map_err(|e| e.to_string())?;
+    import_library_json(&store, file).map_err(|e| e.to_string())
+  })
+  .await
+  .map_err(|e| e.to_string())?
+}
+
+/// Command: Lists the known genres and built-in styles with their display text.
+///
+/// Custom styles are free text and are not part of the list.
+#[tauri::command]
+fn taxonomy_list() -> TaxonomyDto {
+  TaxonomyDto::builtin()
+}
+
 /// Command: Retrieves the current scanner configuration.
 ///
 /// Maps the domain configuration object to a DTO suitable for serialization to the frontend.
@@ -40,14 +418,86 @@ fn scanner_get_config() -> Result<ScannerConfigDto, String> {
 }
 
 /// Command: Persists updated scanner configuration from the frontend.
+///
+/// Takes effect without a restart, like `config_reload`.
 #[tauri::command]
-fn scanner_save_config(input: ScannerConfigDto) -> Result<(), String> {
+fn scanner_save_config(state: State<'_, AppState>, input: ScannerConfigDto) -> Result<(), String> {
   let cfg = ScannerConfig::from(input);
-  cfg.save().map_err(|e| e.to_string())
+  cfg.save().map_err(|e| e.to_string())?;
+  state.library.set_min_duration_secs(cfg.min_duration_secs);
+  state.library.set_failure_cooldown_secs(cfg.failure_cooldown_secs);
+  register_library_roots(&state.store, &cfg);
+  Ok(())
+}
+
+/// Command: Re-reads the saved configuration and applies it without a restart.
+///
+/// Hot settings, picked up by the next import (an import already running keeps the values
+/// it started with):
+/// - every scanner setting (roots, extensions, hidden files, depth, symlinks, content hash,
+///   throughput); the scanner reads its config file at the start of each scan, so these
+///   need no reload at all. New roots are registered as library roots here, though.
+/// - `min_duration_secs` and `failure_cooldown_secs`, which the service caches and this
+///   command refreshes.
+/// - the `[analysis]` settings, swapped into the import probe here. `library_reanalyze_track`
+///   re-reads them on every call anyway.
+///
+/// An invalid `[analysis]` section fails the command after the scanner settings are applied,
+/// and imports keep the analysis settings they had.
+///
+/// Restart required: the database location (storage config), the genre/style aliases and
+/// `progress_interval_ms`, all fixed when the adapters are built.
+#[tauri::command]
+fn config_reload(state: State<'_, AppState>) -> Result<(), String> {
+  let cfg = ScannerConfig::load().map_err(|e| e.to_string())?;
+  state.library.set_min_duration_secs(cfg.min_duration_secs);
+  state.library.set_failure_cooldown_secs(cfg.failure_cooldown_secs);
+  register_library_roots(&state.store, &cfg);
+
+  let analysis = AnalysisConfig::load().map_err(|e| e.to_string())?;
+  state.metadata.set_analysis_config(analysis);
+  Ok(())
+}
+
+/// Registers every configured scanner root as a library root, so the files found under it
+/// are stored relative to it. Roots that are already registered are left as they are.
+fn register_library_roots(store: &LibraryStore, cfg: &ScannerConfig) {
+  for root in &cfg.roots {
+    if let Err(e) = store.add_library_root(&root.path) {
+      tracing::warn!(root = %root.path.display(), error = %e, "could not register library root");
+    }
+  }
+}
+
+/// Default number of example paths returned by `scanner_preview`.
+const DEFAULT_PREVIEW_SAMPLE_SIZE: usize = 20;
+
+/// Command: Lists what the saved scanner configuration matches, without importing anything.
+///
+/// Only walks the filesystem: no tags are read, no hashes computed and the database is not touched.
+#[tauri::command]
+async fn scanner_preview(sample_size: Option<usize>) -> Result<ScanPreviewDto, String> {
+  let cfg = ScannerConfig::load().map_err(|e| e.to_string())?;
+  let outcome = scan_music_with_cfg(&cfg).await.map_err(|e| e.to_string())?;
+  Ok(ScanPreviewDto::from(outcome.preview(sample_size.unwrap_or(DEFAULT_PREVIEW_SAMPLE_SIZE))))
+}
+
+/// Installs the global `tracing` subscriber.
+///
+/// The level is taken from `RUST_LOG` (e.g. `RUST_LOG=gamus_scanner=debug`); without it
+/// only warnings and errors are printed.
+fn init_tracing() {
+  use tracing_subscriber::EnvFilter;
+
+  let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn"));
+  // `try_init` so a subscriber installed by a test harness or host doesn't make us panic.
+  let _ = tracing_subscriber::fmt().with_env_filter(filter).try_init();
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+  init_tracing();
+
   // Linux-specific workarounds for WebKitGTK rendering glitches/crashes on specific GPUs.
   gpu_tweak::apply_linux_patches();
 
@@ -65,24 +515,107 @@ pub fn run() {
       let scanner = FsScanner::new();
 
       // 3. Metadata Adapter (FFmpeg)
-      // Initializes internal FFmpeg contexts.
-      let metadata = FfmpegProbe::default();
+      // Initializes internal FFmpeg contexts. A broken alias table only costs the aliases,
+      // it should not keep the app from starting.
+      let genre_map = GenreMap::load().unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "could not load genre/style aliases, using built-in matching only");
+        GenreMap::default()
+      });
+      // Bad `[analysis]` tuning falls back to the defaults for the same reason.
+      let analysis = AnalysisConfig::load().unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "could not load the analysis settings, using the defaults");
+        AnalysisConfig::default()
+      });
+      let metadata = FfmpegProbe::new_with_analysis(analysis).with_genre_map(genre_map);
+
+      // The minimum duration, the failure cooldown and the progress interval are import
+      // policies, so they are read from the scanner config; `config_reload` refreshes the
+      // first two.
+      let scanner_cfg = ScannerConfig::load().unwrap_or_default();
 
       // 4. Output Port Adapter (UI Events)
-      // Wraps the Tauri AppHandle to emit events back to the WebView.
-      let reporter = TauriReporter::new(app.handle().clone());
+      // Wraps the Tauri AppHandle to emit events back to the WebView, and mirrors
+      // progress into shared counters that commands can query synchronously.
+      let progress = Arc::new(ImportProgressState::default());
+      // The observer sits outside the throttle so `library_get_progress` stays exact while
+      // the WebView only gets a success batch every `progress_interval_ms` instead of one
+      // event per file.
+      let events = ThrottledReporter::with_interval(
+        TauriReporter::new(app.handle().clone()),
+        Duration::from_millis(scanner_cfg.progress_interval_ms),
+      );
+      let reporter = ProgressObserver::new(events, Arc::clone(&progress));
 
       // 5. Service Wiring
       // Inject all adapters into the core domain service.
-      let library = LibraryService::new(scanner, metadata, storage, reporter);
+      register_library_roots(&storage, &scanner_cfg);
+      let library = LibraryService::new(scanner, metadata.clone(), storage.clone(), reporter)
+        .with_min_duration_secs(scanner_cfg.min_duration_secs)
+        .with_failure_cooldown_secs(scanner_cfg.failure_cooldown_secs);
 
       // 6. State Registration
       // Moves the service instance into Tauri's managed state container.
-      app.manage(AppState { library });
+      app.manage(AppState { library, store: storage, metadata, progress });
 
       Ok(())
     })
-    .invoke_handler(tauri::generate_handler![library_import_full, scanner_get_config, scanner_save_config,])
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .invoke_handler(tauri::generate_handler![
+      library_import_full,
+      library_import_incremental,
+      library_import_resumable,
+      library_pending_import,
+      library_discard_pending_import,
+      library_analyze_pending,
+      library_get_progress,
+      library_permission_denied,
+      library_stats,
+      library_recent_tracks,
+      library_tracks_by_codec,
+      library_tracks_by_quality,
+      library_tracks_page,
+      library_facets,
+      library_browse_releases,
+      library_similar_tracks,
+      library_duplicate_files,
+      library_release_thumbnail,
+      library_prewarm_thumbnails,
+      library_orphan_songs,
+      library_empty_releases,
+      library_extraction_failures,
+      library_clear_extraction_failures,
+      library_roots,
+      library_relocate_root,
+      library_update_track,
+      library_update_song,
+      library_update_release,
+      library_update_artist,
+      library_reanalyze_track,
+      library_maintenance,
+      library_export,
+      library_import_backup,
+      track_raw_tags,
+      scanner_get_config,
+      scanner_save_config,
+      config_reload,
+      scanner_preview,
+      taxonomy_list,
+    ])
+    .build(tauri::generate_context!())
+    .expect("error while building tauri application")
+    .run(|app, event| {
+      // Flush the WAL and refresh statistics on the way out, so the next startup is fast
+      // and the database file is self-contained for backups.
+      if !matches!(event, tauri::RunEvent::Exit) {
+        return;
+      }
+      let Some(state) = app.try_state::<AppState>() else {
+        return;
+      };
+      // Only this clone is closed, but every clone (the service's included) shares one
+      // pool, so the checkpoint covers whatever was written through any of them. Nothing
+      // writes after `Exit`.
+      if let Err(e) = state.store.clone().close() {
+        tracing::warn!(error = %e, "could not flush the library database on exit");
+      }
+    });
 }
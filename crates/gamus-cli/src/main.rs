@@ -0,0 +1,116 @@
+//! Headless full import: wires the same adapters as the desktop app, runs `import_full`
+//! and draws the progress on the terminal.
+//!
+//! Uses the saved scanner, storage and genre configuration, exactly like the app does.
+//! That includes the minimum duration and the failure cooldown, so short clips and files
+//! that failed recently are skipped here too.
+
+use std::io::{self, Write};
+
+use gamus_config::GenreMap;
+use gamus_core::services::{ChannelReporter, LibraryService, ProgressEvent};
+use gamus_metadata::FfmpegProbe;
+use gamus_metadata::config::AnalysisConfig;
+use gamus_scanner::{FsScanner, ScannerConfig};
+use gamus_storage::LibraryStore;
+use tokio::sync::mpsc;
+
+/// Events buffered between the import and the renderer before the import waits.
+const EVENT_BUFFER: usize = 256;
+
+/// Width of the progress bar, in characters.
+const BAR_WIDTH: usize = 40;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+  let storage = LibraryStore::new_from_config()?;
+  let genre_map = GenreMap::load().unwrap_or_else(|e| {
+    eprintln!("warning: could not load genre/style aliases ({e}), using built-in matching only");
+    GenreMap::default()
+  });
+  let analysis = AnalysisConfig::load().unwrap_or_else(|e| {
+    eprintln!("warning: could not load the analysis settings ({e}), using the defaults");
+    AnalysisConfig::default()
+  });
+  let metadata = FfmpegProbe::new_with_analysis(analysis).with_genre_map(genre_map);
+  let (reporter, events) = ChannelReporter::channel(EVENT_BUFFER);
+
+  let scanner_cfg = ScannerConfig::load()?;
+  // Files under a configured root are stored relative to it (see `gamus_storage::paths`).
+  for root in &scanner_cfg.roots {
+    if let Err(e) = storage.add_library_root(&root.path) {
+      eprintln!("warning: could not register library root {} ({e})", root.path.display());
+    }
+  }
+
+  let library = LibraryService::new(FsScanner::new(), metadata, storage, reporter)
+    .with_min_duration_secs(scanner_cfg.min_duration_secs)
+    .with_failure_cooldown_secs(scanner_cfg.failure_cooldown_secs);
+  // The service owns the only sender: once the import ends and the task drops it,
+  // the channel closes and the renderer returns.
+  let import = tokio::spawn(async move { library.import_full().await });
+
+  render(events).await;
+  import.await??;
+  Ok(())
+}
+
+/// Draws every event until the channel is closed.
+async fn render(mut events: mpsc::Receiver<ProgressEvent>) {
+  let mut stderr = io::stderr();
+  let mut total = 0;
+  let mut done = 0;
+
+  while let Some(event) = events.recv().await {
+    match event {
+      ProgressEvent::ScanStarted => eprint!("scanning..."),
+      ProgressEvent::ScanProgress { files_found } => eprint!("\r\x1b[2Kscanning... {files_found} files"),
+      ProgressEvent::ScanFinished { files_found } => eprintln!("\r\x1b[2Kfound {files_found} audio files"),
+      ProgressEvent::RootsUnavailable { roots } => {
+        for root in roots {
+          eprintln!("skipped unavailable root {}", root.display());
+        }
+      }
+      ProgressEvent::PermissionDenied { paths } => {
+        for path in paths {
+          eprintln!("permission denied, not scanned: {}", path.display());
+        }
+      }
+      ProgressEvent::Started { total: t } => {
+        total = t;
+        draw_bar(done, total);
+      }
+      ProgressEvent::Succeeded { .. } => {
+        done += 1;
+        draw_bar(done, total);
+      }
+      ProgressEvent::Skipped { .. } => {
+        done += 1;
+        draw_bar(done, total);
+      }
+      ProgressEvent::Failed { path, error } => {
+        done += 1;
+        eprintln!("\r\x1b[2Kfailed {path}: {error}");
+        draw_bar(done, total);
+      }
+      ProgressEvent::GroupFailed { device, error } => {
+        eprintln!("\r\x1b[2Kdevice {device}: {error}");
+        draw_bar(done, total);
+      }
+      ProgressEvent::Finished(summary) => {
+        eprintln!();
+        eprintln!(
+          "{} imported, {} failed, {} skipped in {:.1}s",
+          summary.succeeded, summary.failed, summary.skipped, summary.elapsed_secs
+        );
+      }
+    }
+    let _ = stderr.flush();
+  }
+}
+
+/// Redraws `[#####-----] done/total` over the current line.
+fn draw_bar(done: usize, total: usize) {
+  let filled = (done * BAR_WIDTH).checked_div(total).unwrap_or(BAR_WIDTH).min(BAR_WIDTH);
+  eprint!("\r[{}{}] {done}/{total}", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled));
+}
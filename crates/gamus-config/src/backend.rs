@@ -6,9 +6,18 @@ use std::fs;
 /// NUEVO: usa toml_edit para escritura preservando comentarios
 use toml_edit::{DocumentMut, Item};
 
+/// Resultado de [`ConfigBackend::save_section`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveOutcome {
+  /// La sección cambió y se reescribió el archivo.
+  Written,
+  /// La sección ya tenía esos valores; el archivo no se tocó.
+  Unchanged,
+}
+
 pub trait ConfigBackend {
   fn load_section<T: DeserializeOwned>(&self, section: &str) -> Result<T, ConfigError>;
-  fn save_section<T: Serialize>(&self, section: &str, value: &T) -> Result<(), ConfigError>;
+  fn save_section<T: Serialize>(&self, section: &str, value: &T) -> Result<SaveOutcome, ConfigError>;
 }
 
 pub struct TomlConfigBackend {
@@ -69,16 +78,29 @@ impl ConfigBackend for TomlConfigBackend {
     Ok(t)
   }
 
-  fn save_section<T: Serialize>(&self, section: &str, value: &T) -> Result<(), ConfigError> {
+  /// Reemplaza `[section]` por `value` conservando el resto del documento.
+  ///
+  /// Si la sección guardada ya vale lo mismo (comparando valores, no texto) no se escribe
+  /// nada y devuelve [`SaveOutcome::Unchanged`]: los `load` que persisten los defaults no
+  /// reescriben el archivo ni le cambian el `mtime` en cada llamada.
+  fn save_section<T: Serialize>(&self, section: &str, value: &T) -> Result<SaveOutcome, ConfigError> {
     use std::io::ErrorKind;
 
     let path = self.paths.config_file();
 
     // 1) Leer config actual como DocumentMut o crear doc vacío si no existe.
     let mut doc: DocumentMut = match fs::read_to_string(&path) {
-      Ok(content) => content
-        .parse::<DocumentMut>()
-        .map_err(|e| ConfigError::Other(format!("parse toml_edit doc: {e}")))?,
+      Ok(content) => {
+        // 1b) Sin cambios en la sección: no hay nada que escribir.
+        let current = toml::from_str::<toml::Table>(&content).ok().and_then(|mut t| t.remove(section));
+        let new =
+          toml::Value::try_from(value).map_err(|e| ConfigError::Other(format!("encode section [{section}]: {e}")))?;
+        if current.as_ref() == Some(&new) {
+          return Ok(SaveOutcome::Unchanged);
+        }
+
+        content.parse::<DocumentMut>().map_err(|e| ConfigError::Other(format!("parse toml_edit doc: {e}")))?
+      }
       Err(e) if e.kind() == ErrorKind::NotFound => {
         // documento nuevo
         DocumentMut::new()
@@ -108,6 +130,44 @@ impl ConfigBackend for TomlConfigBackend {
     // 6) Escritura atómica usando gamus-fs.
     gamus_fs::atomic_write_str(&path, &serialized)?;
 
-    Ok(())
+    Ok(SaveOutcome::Written)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::BTreeMap;
+
+  use super::*;
+
+  fn backend(dir: &std::path::Path) -> TomlConfigBackend {
+    TomlConfigBackend::new(GamusPaths {
+      base_dir: dir.to_path_buf(),
+      config_dir: dir.to_path_buf(),
+      data_dir: dir.to_path_buf(),
+      cache_dir: dir.to_path_buf(),
+      audio_dir: None,
+      download_dir: None,
+    })
+  }
+
+  #[test]
+  fn saving_the_same_values_does_not_rewrite_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let backend = backend(dir.path());
+    let section = BTreeMap::from([("depth", 3)]);
+
+    assert_eq!(backend.save_section("scanner", &section).unwrap(), SaveOutcome::Written);
+    // Un comentario a mano dentro de la sección sobrevive mientras los valores no cambien.
+    let path = backend.paths.config_file();
+    let edited = fs::read_to_string(&path).unwrap().replace("depth", "# a mano\ndepth");
+    fs::write(&path, &edited).unwrap();
+
+    assert_eq!(backend.save_section("scanner", &section).unwrap(), SaveOutcome::Unchanged);
+    assert_eq!(fs::read_to_string(&path).unwrap(), edited);
+
+    let changed = BTreeMap::from([("depth", 4)]);
+    assert_eq!(backend.save_section("scanner", &changed).unwrap(), SaveOutcome::Written);
+    assert_eq!(backend.load_section::<BTreeMap<String, i32>>("scanner").unwrap()["depth"], 4);
   }
 }
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use crate::CONFIG_BACKEND;
+use crate::paths::ConfigError;
+
+/// Alias de géneros y estilos editables por el usuario.
+///
+/// Se lee de dos tablas del `config.toml`:
+///
+/// ```toml
+/// [genre_aliases]
+/// "Hip-Hop/Rap" = "Hip Hop"
+/// "Électronique" = "Electronic"
+///
+/// [style_aliases]
+/// "Drum & Bass" = "Drum n Bass"
+/// ```
+///
+/// Las claves son la grafía que aparece en los tags y se comparan sin distinguir
+/// mayúsculas ni espacios repetidos; los valores son el nombre canónico que entienden
+/// `Genre::from_str` / `Style::from_str`. Este crate no conoce esos tipos: validar el
+/// destino es cosa de quien consume el mapa.
+#[derive(Debug, Clone, Default)]
+pub struct GenreMap {
+  genres: HashMap<String, String>,
+  styles: HashMap<String, String>,
+}
+
+impl GenreMap {
+  pub fn new(genre_aliases: HashMap<String, String>, style_aliases: HashMap<String, String>) -> Self {
+    Self { genres: normalize_keys(genre_aliases), styles: normalize_keys(style_aliases) }
+  }
+
+  /// Carga `[genre_aliases]` y `[style_aliases]`. Las tablas ausentes equivalen a vacías.
+  ///
+  /// A diferencia de otras secciones no se reescribe el archivo al cargar: son tablas
+  /// pensadas para editarse a mano y `save_section` perdería los comentarios de dentro.
+  pub fn load() -> Result<Self, ConfigError> {
+    let genres = CONFIG_BACKEND.load_section_with_default("genre_aliases")?;
+    let styles = CONFIG_BACKEND.load_section_with_default("style_aliases")?;
+    Ok(Self::new(genres, styles))
+  }
+
+  /// Nombre canónico del género configurado para `raw`, si lo hay.
+  pub fn genre_alias(&self, raw: &str) -> Option<&str> {
+    self.genres.get(&normalize_key(raw)).map(String::as_str)
+  }
+
+  /// Nombre canónico del estilo configurado para `raw`, si lo hay.
+  pub fn style_alias(&self, raw: &str) -> Option<&str> {
+    self.styles.get(&normalize_key(raw)).map(String::as_str)
+  }
+}
+
+fn normalize_keys(aliases: HashMap<String, String>) -> HashMap<String, String> {
+  aliases.into_iter().map(|(k, v)| (normalize_key(&k), v.trim().to_string())).collect()
+}
+
+fn normalize_key(raw: &str) -> String {
+  raw.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn aliases_match_regardless_of_case_and_repeated_spaces() {
+    let map = GenreMap::new(
+      HashMap::from([("Hip-Hop/Rap".to_string(), " Hip Hop ".to_string())]),
+      HashMap::from([("Drum  &  Bass".to_string(), "Drum n Bass".to_string())]),
+    );
+
+    assert_eq!(map.genre_alias("  HIP-HOP/RAP "), Some("Hip Hop"));
+    assert_eq!(map.style_alias("drum & bass"), Some("Drum n Bass"));
+    // Cada tabla solo responde por lo suyo.
+    assert_eq!(map.genre_alias("Drum & Bass"), None);
+    assert_eq!(map.style_alias("Hip-Hop/Rap"), None);
+  }
+}
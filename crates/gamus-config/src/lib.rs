@@ -1,7 +1,9 @@
 mod backend;
+mod genre_map;
 mod paths;
 
-pub use backend::{ConfigBackend, TomlConfigBackend};
+pub use backend::{ConfigBackend, SaveOutcome, TomlConfigBackend};
+pub use genre_map::GenreMap;
 pub use paths::{ConfigError, GamusPaths};
 
 use once_cell::sync::Lazy;
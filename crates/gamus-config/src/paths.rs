@@ -1,5 +1,6 @@
 use directories::{ProjectDirs, UserDirs};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -22,31 +23,68 @@ pub struct GamusPaths {
   pub cache_dir: PathBuf,
 
   // User dirs
+  /// Carpeta de música del usuario; `None` si el sistema no define una.
   pub audio_dir: Option<PathBuf>,
+  /// Carpeta de descargas del usuario; `None` si el sistema no define una.
   pub download_dir: Option<PathBuf>,
 }
 
+/// `ProjectDirs` del sistema, resuelto una sola vez por proceso.
+///
+/// Solo se consulta para los directorios que no vienen de una variable de entorno.
+fn project_dirs() -> Result<&'static ProjectDirs, ConfigError> {
+  static PROJECT_DIRS: OnceLock<Option<ProjectDirs>> = OnceLock::new();
+  PROJECT_DIRS.get_or_init(|| ProjectDirs::from("com", "gamus", "gamus")).as_ref().ok_or(ConfigError::Directories)
+}
+
+/// `UserDirs` del sistema, resuelto una sola vez por proceso. `None` si no hay `$HOME`.
+fn user_dirs() -> Option<&'static UserDirs> {
+  static USER_DIRS: OnceLock<Option<UserDirs>> = OnceLock::new();
+  USER_DIRS.get_or_init(UserDirs::new).as_ref()
+}
+
+/// Lee una variable de entorno de ruta, ignorando valores vacíos.
+fn env_dir(key: &str) -> Option<PathBuf> {
+  std::env::var_os(key).filter(|v| !v.is_empty()).map(PathBuf::from)
+}
+
 impl GamusPaths {
+  /// Resuelve los directorios de Gamus.
+  ///
+  /// Precedencia, por directorio:
+  /// 1. `GAMUS_CONFIG_DIR` / `GAMUS_DATA_DIR` / `GAMUS_CACHE_DIR`.
+  /// 2. `GAMUS_BASE_DIR` (`<base>/config`, `<base>/data`, `<base>/cache`).
+  /// 3. `ProjectDirs` del sistema (XDG en Linux).
+  ///
+  /// Las carpetas de usuario (`audio_dir`, `download_dir`) salen de `GAMUS_AUDIO_DIR` /
+  /// `GAMUS_DOWNLOAD_DIR` o, si no, de `UserDirs` (`XDG_MUSIC_DIR`... en Linux). Son
+  /// opcionales: donde el sistema no las define quedan en `None` y no se crean.
   pub fn new() -> Result<Self, ConfigError> {
-    let (config_dir, data_dir, cache_dir, base_dir);
-
-    if let Ok(env_base) = std::env::var("GAMUS_BASE_DIR") {
-      let base = PathBuf::from(env_base);
-      base_dir = base.clone();
-      config_dir = base.join("config");
-      data_dir = base.join("data");
-      cache_dir = base.join("cache");
-    } else {
-      let proj_dirs = ProjectDirs::from("com", "gamus", "gamus").ok_or(ConfigError::Directories)?;
-      base_dir = proj_dirs.config_dir().to_path_buf();
-      config_dir = proj_dirs.config_dir().to_path_buf();
-      data_dir = proj_dirs.data_dir().to_path_buf();
-      cache_dir = proj_dirs.cache_dir().to_path_buf();
-    }
+    let env_base = env_dir("GAMUS_BASE_DIR");
 
-    let user_dirs = UserDirs::new().ok_or(ConfigError::Directories)?;
-    let audio_dir = user_dirs.audio_dir().map(|v| v.into());
-    let download_dir = user_dirs.download_dir().map(|v| v.into());
+    let resolve = |override_key: &str, base_sub: &str, from_project: fn(&ProjectDirs) -> PathBuf| {
+      if let Some(dir) = env_dir(override_key) {
+        return Ok(dir);
+      }
+      match &env_base {
+        Some(base) => Ok(base.join(base_sub)),
+        None => project_dirs().map(from_project),
+      }
+    };
+
+    let config_dir = resolve("GAMUS_CONFIG_DIR", "config", |p| p.config_dir().to_path_buf())?;
+    let data_dir = resolve("GAMUS_DATA_DIR", "data", |p| p.data_dir().to_path_buf())?;
+    let cache_dir = resolve("GAMUS_CACHE_DIR", "cache", |p| p.cache_dir().to_path_buf())?;
+    let base_dir = match env_base {
+      Some(base) => base,
+      None => project_dirs()?.config_dir().to_path_buf(),
+    };
+
+    let user_dir = |override_key: &str, from_user: fn(&UserDirs) -> Option<&Path>| {
+      env_dir(override_key).or_else(|| user_dirs().and_then(from_user).map(Path::to_path_buf))
+    };
+    let audio_dir = user_dir("GAMUS_AUDIO_DIR", UserDirs::audio_dir);
+    let download_dir = user_dir("GAMUS_DOWNLOAD_DIR", UserDirs::download_dir);
 
     std::fs::create_dir_all(&config_dir)?;
     std::fs::create_dir_all(&data_dir)?;
@@ -77,6 +115,14 @@ mod tests {
   use super::*;
   use tempfile::tempdir;
 
+  /// Los tests corren en paralelo y el entorno es global al proceso: cada test que toca
+  /// variables `GAMUS_*` toma este lock durante toda su ejecución.
+  static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+  fn env_lock() -> std::sync::MutexGuard<'static, ()> {
+    ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
   struct EnvVarGuard {
     key: String,
     original: Option<String>,
@@ -88,6 +134,12 @@ mod tests {
       unsafe { std::env::set_var(key, value) };
       EnvVarGuard { key: key.to_owned(), original }
     }
+
+    fn unset(key: &str) -> Self {
+      let original = std::env::var(key).ok();
+      unsafe { std::env::remove_var(key) };
+      EnvVarGuard { key: key.to_owned(), original }
+    }
   }
 
   impl Drop for EnvVarGuard {
@@ -101,8 +153,12 @@ mod tests {
 
   #[test]
   fn test_gamus_base_dir_override() {
+    let _lock = env_lock();
     let tmp = tempdir().unwrap();
     let _env = EnvVarGuard::new("GAMUS_BASE_DIR", tmp.path().to_str().unwrap());
+    let _config = EnvVarGuard::unset("GAMUS_CONFIG_DIR");
+    let _data = EnvVarGuard::unset("GAMUS_DATA_DIR");
+    let _cache = EnvVarGuard::unset("GAMUS_CACHE_DIR");
 
     let paths = GamusPaths::new().unwrap();
 
@@ -115,4 +171,73 @@ mod tests {
     assert!(paths.data_dir.exists());
     assert!(paths.cache_dir.exists());
   }
+
+  #[test]
+  fn test_per_dir_override_beats_base_dir() {
+    let _lock = env_lock();
+    let base = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    let _env = EnvVarGuard::new("GAMUS_BASE_DIR", base.path().to_str().unwrap());
+    let _data = EnvVarGuard::new("GAMUS_DATA_DIR", data.path().join("db").to_str().unwrap());
+    let _config = EnvVarGuard::unset("GAMUS_CONFIG_DIR");
+    let _cache = EnvVarGuard::unset("GAMUS_CACHE_DIR");
+
+    let paths = GamusPaths::new().unwrap();
+
+    assert_eq!(paths.base_dir, base.path());
+    assert_eq!(paths.config_dir, base.path().join("config"));
+    assert_eq!(paths.data_dir, data.path().join("db"));
+    assert_eq!(paths.cache_dir, base.path().join("cache"));
+
+    assert!(paths.data_dir.exists());
+    assert!(!base.path().join("data").exists());
+  }
+
+  #[test]
+  fn test_all_per_dir_overrides_without_base() {
+    let _lock = env_lock();
+    let tmp = tempdir().unwrap();
+    let _base = EnvVarGuard::unset("GAMUS_BASE_DIR");
+    let _config = EnvVarGuard::new("GAMUS_CONFIG_DIR", tmp.path().join("cfg").to_str().unwrap());
+    let _data = EnvVarGuard::new("GAMUS_DATA_DIR", tmp.path().join("share").to_str().unwrap());
+    let _cache = EnvVarGuard::new("GAMUS_CACHE_DIR", tmp.path().join("tmp").to_str().unwrap());
+
+    let paths = GamusPaths::new().unwrap();
+
+    assert_eq!(paths.config_dir, tmp.path().join("cfg"));
+    assert_eq!(paths.data_dir, tmp.path().join("share"));
+    assert_eq!(paths.cache_dir, tmp.path().join("tmp"));
+    assert!(paths.config_dir.exists() && paths.data_dir.exists() && paths.cache_dir.exists());
+  }
+
+  #[test]
+  fn test_user_dirs_env_overrides() {
+    let _lock = env_lock();
+    let tmp = tempdir().unwrap();
+    let _base = EnvVarGuard::new("GAMUS_BASE_DIR", tmp.path().to_str().unwrap());
+    let _audio = EnvVarGuard::new("GAMUS_AUDIO_DIR", tmp.path().join("Música").to_str().unwrap());
+    let _download = EnvVarGuard::new("GAMUS_DOWNLOAD_DIR", tmp.path().join("Descargas").to_str().unwrap());
+
+    let paths = GamusPaths::new().unwrap();
+
+    assert_eq!(paths.music_dir(), Some(tmp.path().join("Música")));
+    assert_eq!(paths.downloads_dir(), Some(tmp.path().join("Descargas")));
+    // Solo se informan, no se crean.
+    assert!(!tmp.path().join("Música").exists());
+  }
+
+  #[test]
+  fn test_empty_user_dir_override_falls_back_to_the_system() {
+    let _lock = env_lock();
+    let tmp = tempdir().unwrap();
+    let _base = EnvVarGuard::new("GAMUS_BASE_DIR", tmp.path().to_str().unwrap());
+    let _audio = EnvVarGuard::new("GAMUS_AUDIO_DIR", "");
+    let _download = EnvVarGuard::unset("GAMUS_DOWNLOAD_DIR");
+
+    let paths = GamusPaths::new().unwrap();
+
+    let system = UserDirs::new();
+    assert_eq!(paths.audio_dir.as_deref(), system.as_ref().and_then(UserDirs::audio_dir));
+    assert_eq!(paths.download_dir.as_deref(), system.as_ref().and_then(UserDirs::download_dir));
+  }
 }
@@ -23,3 +23,21 @@ pub struct Artist {
   /// Enlaces relevantes: páginas oficiales, redes, Wikipedia, Discogs, etc.
   pub sites: Vec<String>,
 }
+
+/// Cambios parciales de un artista (ver `Library::update_artist`).
+///
+/// `None` deja el campo como está; en `bio`, `Some(None)` la borra. Variaciones y enlaces se
+/// siguen guardando con `save_artist`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ArtistPatch {
+  pub name: Option<String>,
+  pub bio: Option<Option<String>>,
+}
+
+/// Forma canónica de un nombre de artista para detectar duplicados.
+///
+/// Recorta, pasa a minúsculas y colapsa espacios internos: `"The  Beatles "` y
+/// `"the beatles"` dan lo mismo. No quita acentos ni artículos.
+pub fn normalize_artist_name(name: &str) -> String {
+  name.split_whitespace().map(str::to_lowercase).collect::<Vec<_>>().join(" ")
+}
@@ -1,5 +1,9 @@
+use std::fmt;
+use std::str::FromStr;
+
 use crate::domain::ids::{ArtistId, ReleaseTrackId};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 /// Rol específico de un artista respecto a una pista concreta.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -16,6 +20,48 @@ pub enum ArtistRole {
   Remixer,
 }
 
+impl ArtistRole {
+  /// Identificador estable, el que se guarda en `release_track_artists.role`.
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ArtistRole::Performer => "performer",
+      ArtistRole::Featured => "featured",
+      ArtistRole::Composer => "composer",
+      ArtistRole::Producer => "producer",
+      ArtistRole::Remixer => "remixer",
+    }
+  }
+}
+
+impl fmt::Display for ArtistRole {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+/// Error al parsear una cadena que no es ninguno de los identificadores de [`ArtistRole`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid artist role: {input}")]
+pub struct ArtistRoleParseError {
+  pub input: String,
+}
+
+impl FromStr for ArtistRole {
+  type Err = ArtistRoleParseError;
+
+  /// Acepta los identificadores de `Display`, sin distinguir mayúsculas ni espacios alrededor.
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_ascii_lowercase().as_str() {
+      "performer" => Ok(ArtistRole::Performer),
+      "featured" => Ok(ArtistRole::Featured),
+      "composer" => Ok(ArtistRole::Composer),
+      "producer" => Ok(ArtistRole::Producer),
+      "remixer" => Ok(ArtistRole::Remixer),
+      _ => Err(ArtistRoleParseError { input: s.to_string() }),
+    }
+  }
+}
+
 /// Crédito de un artista en una pista concreta de un release.
 ///
 /// Esto representa la misma idea que `release_track_artists` en la base de datos.
@@ -0,0 +1,76 @@
+//! Consulta de la vista principal de la biblioteca: releases filtrados, ordenados y paginados.
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::genre_styles::{Genre, Style};
+use crate::domain::ids::{ArtistId, ReleaseId};
+
+/// Filtros de [`crate::ports::Library::browse_releases`]. Los campos a `None` no filtran;
+/// los que traen valor se combinan con AND.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReleaseFilter {
+  pub genre: Option<Genre>,
+  pub style: Option<Style>,
+  /// Año mínimo de publicación (incluido). Los releases sin fecha no pasan ningún filtro de año.
+  pub year_from: Option<i32>,
+  /// Año máximo de publicación (incluido).
+  pub year_to: Option<i32>,
+  /// Nota media mínima (0.0–10.0) de los archivos analizados del release. Los que no tienen
+  /// ninguno analizado no pasan.
+  pub min_quality: Option<f32>,
+  /// Solo los releases en los que este artista es artista principal.
+  pub artist: Option<ArtistId>,
+}
+
+/// Orden de [`crate::ports::Library::browse_releases`]. Cada criterio tiene su sentido
+/// natural; los empates se resuelven por título.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortBy {
+  /// Título, de la A a la Z, sin distinguir mayúsculas.
+  #[default]
+  Title,
+  /// Año, del más reciente al más antiguo; los que no tienen fecha, al final.
+  Year,
+  /// Fecha de alta en la biblioteca, del más reciente al más antiguo.
+  RecentlyAdded,
+  /// Nota media, de la mejor a la peor; los que no tienen ninguna, al final.
+  Quality,
+}
+
+/// Ventana de resultados: `limit` elementos a partir del `offset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Page {
+  pub offset: i64,
+  pub limit: i64,
+}
+
+impl Default for Page {
+  fn default() -> Self {
+    Self { offset: 0, limit: 50 }
+  }
+}
+
+/// Una página de resultados y el total de elementos que cumplen los filtros.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Paged<T> {
+  pub items: Vec<T>,
+  pub total: i64,
+  pub page: Page,
+}
+
+/// Fila ligera de la vista de releases: lo justo para pintar la cuadrícula sin cargar el
+/// `Release` entero ni sus pistas.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReleaseSummary {
+  pub id: ReleaseId,
+  pub title: String,
+  /// Nombres de los artistas principales, en el orden en que se guardaron.
+  pub artist_names: Vec<String>,
+  /// Año de `release_date`, si la fecha empieza por uno.
+  pub year: Option<i32>,
+  pub track_count: usize,
+  /// Nota media de los archivos analizados; `None` si no hay ninguno.
+  pub avg_quality: Option<f32>,
+}
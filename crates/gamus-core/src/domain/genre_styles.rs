@@ -29,6 +29,29 @@ pub enum Genre {
   BrassAndMilitary,
 }
 
+impl Genre {
+  /// Todos los géneros, en el orden de declaración.
+  pub fn all() -> &'static [Genre] {
+    &[
+      Genre::Rock,
+      Genre::Electronic,
+      Genre::Pop,
+      Genre::FolkWorldAndCountry,
+      Genre::Jazz,
+      Genre::FunkSoul,
+      Genre::Classical,
+      Genre::HipHop,
+      Genre::Latin,
+      Genre::StageAndScreen,
+      Genre::Reggae,
+      Genre::Blues,
+      Genre::NonMusic,
+      Genre::Childrens,
+      Genre::BrassAndMilitary,
+    ]
+  }
+}
+
 impl fmt::Display for Genre {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     let text = match self {
@@ -67,7 +90,7 @@ impl FromStr for Genre {
   /// Normaliza la cadena eliminando espacios, guiones y separadores comunes.
   /// Si la cadena no coincide con ningún género conocido, se devuelve un error.
   fn from_str(s: &str) -> Result<Self, Self::Err> {
-    let normalized = s.trim().to_lowercase().replace(['-', ' ', ',', '&', '/'], "");
+    let normalized = s.trim().to_lowercase().replace(['-', ' ', ',', '&', '/', '\''], "");
 
     let genre = match normalized.as_str() {
       "rock" => Genre::Rock,
@@ -136,6 +159,48 @@ pub enum Style {
   Custom(String),
 }
 
+impl Style {
+  /// Todos los estilos conocidos, en el orden de declaración; excluye [`Style::Custom`].
+  pub fn all_builtin() -> &'static [Style] {
+    &[
+      Style::PopRock,
+      Style::House,
+      Style::Vocal,
+      Style::Experimental,
+      Style::Punk,
+      Style::AlternativeRock,
+      Style::SynthPop,
+      Style::Techno,
+      Style::IndieRock,
+      Style::Ambient,
+      Style::Soul,
+      Style::Disco,
+      Style::Hardcore,
+      Style::Folk,
+      Style::Ballad,
+      Style::Country,
+      Style::HardRock,
+      Style::Electro,
+      Style::RockAndRoll,
+      Style::Chanson,
+      Style::Romantic,
+      Style::Trance,
+      Style::HeavyMetal,
+      Style::PsychedelicRock,
+      Style::FolkRock,
+      Style::Jpop,
+      Style::Vocaloid,
+    ]
+  }
+}
+
+/// Pares `(valor, texto)` para selectores: el valor es la variante y el texto su `Display`.
+///
+/// Pensado para [`Genre::all`] y [`Style::all_builtin`].
+pub fn display_pairs<T: Clone + fmt::Display>(values: &[T]) -> Vec<(T, String)> {
+  values.iter().map(|v| (v.clone(), v.to_string())).collect()
+}
+
 impl FromStr for Style {
   type Err = std::convert::Infallible;
 
@@ -217,3 +282,29 @@ impl fmt::Display for Style {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn every_genre_round_trips_through_its_display_text() {
+    for genre in Genre::all() {
+      assert_eq!(&genre.to_string().parse::<Genre>().unwrap(), genre);
+    }
+  }
+
+  #[test]
+  fn builtin_styles_round_trip_and_exclude_custom() {
+    for style in Style::all_builtin() {
+      assert!(!matches!(style, Style::Custom(_)));
+      assert_eq!(&style.to_string().parse::<Style>().unwrap(), style);
+    }
+  }
+
+  #[test]
+  fn display_pairs_keep_order() {
+    let pairs = display_pairs(&Genre::all()[..2]);
+    assert_eq!(pairs, [(Genre::Rock, "Rock".to_string()), (Genre::Electronic, "Electronic".to_string())]);
+  }
+}
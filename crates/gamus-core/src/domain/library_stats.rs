@@ -0,0 +1,26 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::genre_styles::Genre;
+use crate::domain::release_track::QualityLevel;
+
+/// Resumen agregado de la biblioteca para el dashboard.
+///
+/// Lo calcula el repositorio con agregados SQL, sin cargar las entidades.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LibraryStats {
+  pub songs: usize,
+  pub releases: usize,
+  pub artists: usize,
+  /// Archivos físicos (una canción puede tener varios).
+  pub files: usize,
+  /// Suma de la duración de todos los archivos, en milisegundos.
+  pub total_duration_ms: u64,
+  /// Suma del tamaño en disco de todos los archivos, en bytes.
+  pub total_size_bytes: u64,
+  /// Número de releases etiquetados con cada género.
+  pub by_genre: HashMap<Genre, usize>,
+  /// Número de archivos por nivel de calidad. Los archivos sin puntuación cuentan como `Inconclusive`.
+  pub by_quality_level: HashMap<QualityLevel, usize>,
+}
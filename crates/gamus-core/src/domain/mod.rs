@@ -1,12 +1,15 @@
 pub mod artist;
 pub mod artist_role;
+pub mod browse;
 pub mod genre_styles;
 pub mod ids;
+pub mod library_stats;
 pub mod rating;
 pub mod release;
 pub mod release_track;
 pub mod release_type;
 pub mod song;
 pub mod song_stats;
+pub mod sync;
 
 pub use ids::{ArtistId, ReleaseId, ReleaseTrackId, SongId};
@@ -70,6 +70,19 @@ impl Rating {
   pub fn as_f32(&self) -> f32 {
     self.0 as f32 / Self::SCALE_FACTOR as f32
   }
+
+  /// Reconstruye una `Rating` desde su valor *fixed-point* (p. ej. `song_ratings.value_fixed_point`).
+  ///
+  /// Devuelve `None` si supera `5.0` escalado. A diferencia de pasar por `new(f32)`, no hay
+  /// redondeo: el valor guardado se recupera exacto.
+  pub fn from_fixed_point(raw: u32) -> Option<Self> {
+    (raw <= Self::MAX_VALUE).then_some(Self(raw))
+  }
+
+  /// Valor *fixed-point* interno, el que se persiste.
+  pub fn as_fixed_point(&self) -> u32 {
+    self.0
+  }
 }
 
 impl fmt::Display for Rating {
@@ -92,3 +105,29 @@ impl fmt::Display for Rating {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn fixed_point_round_trip_is_exact() {
+    let rating = Rating::new(3.5).unwrap();
+    assert_eq!(rating.as_fixed_point(), 35_000);
+
+    let restored = Rating::from_fixed_point(rating.as_fixed_point()).unwrap();
+    assert_eq!(restored, rating);
+    assert_eq!(restored.as_f32(), 3.5);
+
+    // Every representable value survives write/read unchanged.
+    for raw in 0..=Rating::MAX_VALUE {
+      assert_eq!(Rating::from_fixed_point(raw).unwrap().as_fixed_point(), raw);
+    }
+  }
+
+  #[test]
+  fn from_fixed_point_rejects_values_above_five() {
+    assert_eq!(Rating::from_fixed_point(50_000).map(|r| r.as_f32()), Some(5.0));
+    assert!(Rating::from_fixed_point(50_001).is_none());
+  }
+}
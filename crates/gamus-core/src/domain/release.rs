@@ -50,6 +50,22 @@ pub struct Release {
 
   /// Estilos específicos (más granulares que los géneros).
   pub styles: Vec<Style>,
+
+  /// MusicBrainz Release ID. Las pistas de un mismo release lo comparten, así que al importar
+  /// sirve para agruparlas en un único release.
+  #[serde(default)]
+  pub mbid: Option<String>,
+}
+
+/// Cambios parciales de un release (ver `Library::update_release`).
+///
+/// `None` deja el campo como está; en los opcionales, `Some(None)` lo borra. Tipos, géneros,
+/// estilos y artworks se siguen guardando con `save_release`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReleasePatch {
+  pub title: Option<String>,
+  pub release_date: Option<Option<String>>,
+  pub mbid: Option<Option<String>>,
 }
 
 /// Representa una imagen asociada al release
@@ -70,4 +86,9 @@ pub struct Artwork {
 
   /// Créditos opcionales del artwork (fotógrafo, diseñador, etc.).
   pub credits: Option<String>,
+
+  /// Miniatura en la caché, si ya se generó. La comparten todos los artworks con el
+  /// mismo `hash`.
+  #[serde(default)]
+  pub thumbnail_path: Option<PathBuf>,
 }
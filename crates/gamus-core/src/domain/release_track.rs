@@ -1,6 +1,7 @@
-use std::{path::PathBuf, time::Duration};
+use std::{fmt, path::PathBuf, str::FromStr, time::Duration};
 
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use crate::domain::{
   artist_role::ReleaseTrackArtistCredit,
@@ -92,6 +93,34 @@ pub struct AudioDetails {
 
   /// Huella digital acústica (AcoustID, Chromaprint, etc.).
   pub fingerprint: Option<String>,
+
+  /// Códec del stream de audio, con el nombre corto de FFmpeg (`"flac"`, `"mp3"`, `"opus"`, `"alac"`…).
+  #[serde(default)]
+  pub codec: Option<String>,
+
+  /// Contenedor, con el nombre largo de FFmpeg (p. ej. `"QuickTime / MOV"`, `"Ogg"`).
+  #[serde(default)]
+  pub container: Option<String>,
+
+  /// `true` si el códec es sin pérdida (FLAC, ALAC, PCM…). `None` si no se conoce el códec.
+  #[serde(default)]
+  pub is_lossless: Option<bool>,
+
+  /// Ganancia ReplayGain de pista (dB), tal como viene en los tags.
+  #[serde(default)]
+  pub replaygain_track_db: Option<f32>,
+
+  /// Ganancia ReplayGain de álbum (dB).
+  #[serde(default)]
+  pub replaygain_album_db: Option<f32>,
+
+  /// Pico de muestra de la pista, lineal (1.0 = fondo de escala), para no saturar al aplicar la ganancia.
+  #[serde(default)]
+  pub replaygain_track_peak: Option<f32>,
+
+  /// Pico de muestra del álbum, lineal.
+  #[serde(default)]
+  pub replaygain_album_peak: Option<f32>,
 }
 
 /// Resultado de análisis avanzado del audio.
@@ -121,16 +150,80 @@ pub struct AudioAnalysis {
 ///   - `"Compresión fuerte: artefactos audibles"`
 
 /// Categorical quality level for UI consumption (badges, filtering).
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
+///
+/// The serde names are the stable representation: they are what the frontend receives and
+/// what `library_files.quality_level` stores, so renaming a variant must not change them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum QualityLevel {
+  #[serde(rename = "perfect")]
   Perfect,
+  #[serde(rename = "high")]
   High,
+  #[serde(rename = "medium")]
   Medium,
+  #[serde(rename = "low")]
   Low,
+  #[serde(rename = "inconclusive")]
   Inconclusive,
 }
 
+impl QualityLevel {
+  /// Stable identifier, identical to the serde representation.
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      QualityLevel::Perfect => "perfect",
+      QualityLevel::High => "high",
+      QualityLevel::Medium => "medium",
+      QualityLevel::Low => "low",
+      QualityLevel::Inconclusive => "inconclusive",
+    }
+  }
+
+  /// Maps a 0.0–10.0 quality score to its level.
+  ///
+  /// `Inconclusive` is never produced here: it means "no score", not a low one.
+  pub fn from_score(score: f32) -> Self {
+    if score >= 9.5 {
+      QualityLevel::Perfect
+    } else if score >= 8.0 {
+      QualityLevel::High
+    } else if score >= 5.5 {
+      QualityLevel::Medium
+    } else {
+      QualityLevel::Low
+    }
+  }
+}
+
+impl fmt::Display for QualityLevel {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+/// Error returned when a string is not one of the [`QualityLevel`] identifiers.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid quality level: {input}")]
+pub struct QualityLevelParseError {
+  pub input: String,
+}
+
+impl FromStr for QualityLevel {
+  type Err = QualityLevelParseError;
+
+  /// Accepts the identifiers produced by `Display`, ignoring case and surrounding whitespace.
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_ascii_lowercase().as_str() {
+      "perfect" => Ok(QualityLevel::Perfect),
+      "high" => Ok(QualityLevel::High),
+      "medium" => Ok(QualityLevel::Medium),
+      "low" => Ok(QualityLevel::Low),
+      "inconclusive" => Ok(QualityLevel::Inconclusive),
+      _ => Err(QualityLevelParseError { input: s.to_string() }),
+    }
+  }
+}
+
 /// High-level report designed for API/Frontend consumption.
 /// Abstracts away FFT internals (bins, window functions) into human-readable metrics.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -143,6 +236,10 @@ pub struct AudioQualityReport {
   pub details: Option<String>,
   pub cutoff_freq_hz: Option<f32>,
   pub max_freq_hz: Option<f32>,
+  /// Pearson correlation between left and right channels (stereo sources only).
+  /// Values close to 1.0 indicate dual mono / pseudo-stereo.
+  #[serde(default)]
+  pub stereo_correlation: Option<f32>,
 }
 
 // --- Internal Result ---
@@ -159,8 +256,19 @@ pub struct AudioQuality {
 /// Used for pattern matching the specific heuristic triggered during analysis.
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum AnalysisOutcome {
-  CutoffDetected { freq: f32, ref_db: f32, cut_db: f32 },
-  NoCutoffDetected { ref_db: f32, max_freq: f32 },
+  CutoffDetected {
+    freq: f32,
+    ref_db: f32,
+    cut_db: f32,
+  },
+  NoCutoffDetected {
+    ref_db: f32,
+    max_freq: f32,
+  },
+  /// The spectrum was not measured: the codec is lossless and the analysis settings trust it.
+  AssumedLossless {
+    codec: String,
+  },
   Inconclusive(String),
 }
 
@@ -179,4 +287,42 @@ pub struct FileDetails {
   ///
   /// Útil para detectar cambios y decidir si es necesario reescaneo.
   pub modified: u64,
+
+  /// Hash de contenido calculado por el scanner (ver [`crate::ports::ScannedFile::content_hash`]).
+  #[serde(default)]
+  pub content_hash: Option<String>,
+}
+
+/// Fila de [`crate::ports::Library::list_tracks_by_quality`]: lo justo para la lista de
+/// candidatos a re-ripear sin cargar la pista, la canción ni el release enteros.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackQualitySummary {
+  pub track_id: ReleaseTrackId,
+  /// Ruta absoluta del archivo.
+  pub path: PathBuf,
+  /// Nota de calidad (0.0–10.0).
+  pub quality_score: f32,
+  /// `None` si el archivo se analizó antes de que se guardara el nivel.
+  pub quality_level: Option<QualityLevel>,
+  /// Título de la pista: el `title_override` si lo tiene y, si no, el de la canción.
+  pub title: String,
+  pub release_title: String,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn quality_level_display_parses_back_and_matches_serde() {
+    let levels =
+      [QualityLevel::Perfect, QualityLevel::High, QualityLevel::Medium, QualityLevel::Low, QualityLevel::Inconclusive];
+
+    for level in levels {
+      assert_eq!(level.to_string().parse::<QualityLevel>(), Ok(level));
+      assert_eq!(serde_json::to_string(&level).unwrap(), format!("\"{level}\""));
+    }
+    assert_eq!(" HIGH ".parse::<QualityLevel>(), Ok(QualityLevel::High));
+    assert_eq!("great".parse::<QualityLevel>(), Err(QualityLevelParseError { input: "great".into() }));
+  }
 }
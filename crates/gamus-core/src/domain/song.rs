@@ -10,4 +10,28 @@ pub struct Song {
   pub acoustid: Option<String>,
   /// El título de la canción.
   pub title: String,
+  /// Letra sin sincronizar, tal como venía en los tags del archivo.
+  #[serde(default)]
+  pub lyrics: Option<String>,
+  /// Comentarios libres asociados a la canción.
+  #[serde(default)]
+  pub comments: Vec<String>,
+  /// Código ISRC de la grabación.
+  #[serde(default)]
+  pub isrc: Option<String>,
+  /// MusicBrainz Recording ID; es la clave preferida para reconocer la canción al importar.
+  #[serde(default)]
+  pub mbid: Option<String>,
+}
+
+/// Cambios parciales de una canción (ver `Library::update_song`).
+///
+/// `None` deja el campo como está; en los opcionales, `Some(None)` lo borra. La letra y los
+/// comentarios viven en sus propias tablas y no se tocan aquí.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SongPatch {
+  pub title: Option<String>,
+  pub acoustid: Option<Option<String>>,
+  pub isrc: Option<Option<String>>,
+  pub mbid: Option<Option<String>>,
 }
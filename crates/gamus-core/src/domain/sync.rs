@@ -0,0 +1,23 @@
+//! Sincronización incremental: entidades modificadas desde la última que recibió un cliente.
+
+use serde::{Deserialize, Serialize};
+
+/// Posición de un cliente en el historial de cambios: fecha de modificación (segundos UNIX,
+/// UTC) e id de la última entidad recibida.
+///
+/// Los cambios se recorren en orden `(updated_at, id)`, así que las entidades modificadas en
+/// el mismo segundo no se pierden ni se repiten entre páginas. El cursor por defecto empieza
+/// desde el principio.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncCursor {
+  pub updated_at: i64,
+  pub id: String,
+}
+
+/// Entidad modificada y el cursor que la sitúa. El de la última de una página es desde
+/// donde se pide la siguiente.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Synced<T> {
+  pub item: T,
+  pub cursor: SyncCursor,
+}
@@ -16,7 +16,14 @@ pub enum CoreError {
   #[error("metadata error: {0}")]
   Metadata(String),
 
+  #[error("export error: {0}")]
+  Export(String),
+
   #[error("not found")]
   NotFound,
+
+  /// Datos de entrada rechazados antes de tocar el repositorio (p. ej. un número de pista 0).
+  #[error("invalid input: {0}")]
+  InvalidInput(String),
   // Puedes ir afinando casos concretos a medida que avances
 }
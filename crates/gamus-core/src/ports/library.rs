@@ -1,20 +1,260 @@
-use crate::domain::ids::{ArtistId, ReleaseId, SongId};
-use crate::domain::{artist::Artist, release::Release, song::Song};
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use serde::Serialize;
+
+use crate::domain::artist::{Artist, ArtistPatch};
+use crate::domain::artist_role::ReleaseTrackArtistCredit;
+use crate::domain::browse::{Page, Paged, ReleaseFilter, ReleaseSummary, SortBy};
+use crate::domain::genre_styles::{Genre, Style};
+use crate::domain::ids::{ArtistId, ReleaseId, ReleaseTrackId, SongId};
+use crate::domain::library_stats::LibraryStats;
+use crate::domain::release::{Artwork, Release, ReleasePatch};
+use crate::domain::release_track::{AudioAnalysis, ReleaseTrack, TrackQualitySummary};
+use crate::domain::song::{Song, SongPatch};
+use crate::domain::sync::{SyncCursor, Synced};
 use crate::errors::CoreError;
 
+/// Estado guardado de un archivo ya importado, para decidir si hay que reimportarlo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredFile {
+  pub path: PathBuf,
+  pub size_bytes: u64,
+  pub modified_unix: u64,
+  pub content_hash: Option<String>,
+}
+
+/// Importación completa reanudable que empezó y no ha terminado
+/// (ver `LibraryService::import_full_resumable`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ImportCheckpoint {
+  /// Inicio de la importación, en segundos UNIX (UTC).
+  pub started_at: i64,
+}
+
+/// Archivo cuya extracción de metadatos falló en una importación (DRM, archivo corrupto...).
+///
+/// Mientras el archivo no cambie, las importaciones lo saltan durante un tiempo en vez de
+/// reintentarlo (ver `LibraryService::with_failure_cooldown_secs`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExtractionFailure {
+  pub path: PathBuf,
+  /// Error del último intento.
+  pub error: String,
+  /// Último intento, en segundos UNIX (UTC).
+  pub failed_at: i64,
+  /// Intentos fallidos seguidos.
+  pub attempts: u32,
+  /// `mtime` del archivo en el último intento; si cambia, se reintenta sin esperar.
+  pub modified_unix: u64,
+}
+
+/// Repositorio de la biblioteca.
+///
+/// Todos los métodos son síncronos y bloquean el hilo que los llama mientras dura la E/S
+/// de la base de datos. Desde código async, ver [`AsyncLibrary`].
 pub trait Library {
   // --- Métodos de Comando (Escritura) ---
   fn save_artist(&self, artist: &Artist) -> Result<(), CoreError>;
+  /// Guarda varios artistas en una sola transacción. Los ids repetidos se colapsan
+  /// (gana la última aparición), así que el orden de entrada importa.
+  fn save_artists_batch(&self, artists: &[Artist]) -> Result<(), CoreError>;
   fn save_song(&self, song: &Song) -> Result<(), CoreError>;
+  /// Inserta o actualiza el release. Sus listas (tipos, géneros, estilos, artistas
+  /// principales y artworks) reemplazan a las guardadas, aunque vengan vacías.
   fn save_release(&self, release: &Release) -> Result<(), CoreError>;
+  /// Guarda la pista y el archivo físico asociado.
+  ///
+  /// Un archivo es una sola pista: si su path ya estaba guardado, se actualiza esa pista y
+  /// conserva su id (`track.id` solo se usa para archivos nuevos), así que reimportar el
+  /// mismo archivo no la duplica.
+  ///
+  /// Si `artist_credits` no está vacío, sustituye también los créditos de la pista (como
+  /// [`Self::save_track_credits`]); vacío los deja como estaban.
+  fn save_track(&self, track: &ReleaseTrack) -> Result<(), CoreError>;
+  /// Sustituye los créditos de la pista `track_id` por `credits`, en ese orden. El
+  /// `release_track_id` de cada crédito se ignora, y un mismo artista con el mismo rol
+  /// repetido se guarda una vez. Los artistas tienen que existir.
+  fn save_track_credits(&self, track_id: ReleaseTrackId, credits: &[ReleaseTrackArtistCredit])
+  -> Result<(), CoreError>;
+  /// Sustituye solo el análisis (calidad, BPM, features) del archivo de la pista.
+  fn update_track_analysis(&self, track_id: ReleaseTrackId, analysis: &AudioAnalysis) -> Result<(), CoreError>;
+  /// Guarda el checkpoint de la importación reanudable en curso, sustituyendo al anterior.
+  fn save_import_checkpoint(&self, checkpoint: &ImportCheckpoint) -> Result<(), CoreError>;
+  /// Borra el checkpoint, si lo hay.
+  fn clear_import_checkpoint(&self) -> Result<(), CoreError>;
+  /// Anota un fallo de extracción de `path`: sustituye error, fecha y `mtime` del anterior
+  /// y suma uno a `attempts`.
+  fn record_extraction_failure(
+    &self,
+    path: &Path,
+    error: &str,
+    modified_unix: u64,
+    failed_at: i64,
+  ) -> Result<(), CoreError>;
+  /// Olvida el fallo de `path`, si lo hay (p. ej. porque ya se importó bien).
+  fn forget_extraction_failure(&self, path: &Path) -> Result<(), CoreError>;
+  /// Olvida todos los fallos; devuelve cuántos había.
+  fn clear_extraction_failures(&self) -> Result<usize, CoreError>;
+  /// Anota `thumbnail` como miniatura de todos los artworks con ese `hash`; devuelve cuántos
+  /// se actualizaron.
+  fn set_artwork_thumbnail(&self, hash: &str, thumbnail: &Path) -> Result<usize, CoreError>;
+  /// Corrige número de pista/disco y el título propio de la pista sin reimportar.
+  ///
+  /// `None` deja el campo como está; en `title_override`, `Some(None)` lo borra. Los números
+  /// deben ser ≥ 1 (`CoreError::InvalidInput`) y una pista inexistente da `CoreError::NotFound`.
+  fn update_track_metadata(
+    &self,
+    id: ReleaseTrackId,
+    track_number: Option<u32>,
+    disc_number: Option<u32>,
+    title_override: Option<Option<String>>,
+  ) -> Result<(), CoreError>;
+  /// Cambia solo los campos de `patch` que traen valor y actualiza `updated_at`, sin pisar
+  /// el resto como haría [`Self::save_song`]. Un título vacío da `CoreError::InvalidInput`
+  /// y una canción inexistente `CoreError::NotFound`.
+  fn update_song(&self, id: SongId, patch: &SongPatch) -> Result<(), CoreError>;
+  /// Como [`Self::update_song`], para releases.
+  fn update_release(&self, id: ReleaseId, patch: &ReleasePatch) -> Result<(), CoreError>;
+  /// Como [`Self::update_song`], para artistas. Renombrar a un nombre que ya usa otro
+  /// artista (según `normalize_artist_name`) da `CoreError::InvalidInput`.
+  fn update_artist(&self, id: ArtistId, patch: &ArtistPatch) -> Result<(), CoreError>;
 
   // --- Métodos de Consulta (Lectura) por ID ---
   fn find_artist(&self, id: ArtistId) -> Result<Option<Artist>, CoreError>;
+  /// Busca un artista por nombre, comparando la forma de `normalize_artist_name`.
+  fn find_artist_by_name(&self, name: &str) -> Result<Option<Artist>, CoreError>;
   fn find_song(&self, id: SongId) -> Result<Option<Song>, CoreError>;
   fn find_release(&self, id: ReleaseId) -> Result<Option<Release>, CoreError>;
+  /// Pista con su archivo físico.
+  fn find_track(&self, id: ReleaseTrackId) -> Result<Option<ReleaseTrack>, CoreError>;
+  /// Busca una canción por su huella acústica (`songs.acoustid` o la huella de alguno de sus archivos).
+  fn find_song_by_fingerprint(&self, fingerprint: &str) -> Result<Option<Song>, CoreError>;
+  /// Busca una canción por su MusicBrainz Recording ID.
+  fn find_song_by_mbid(&self, mbid: &str) -> Result<Option<Song>, CoreError>;
+  /// Busca una canción por su ISRC. Varias canciones pueden compartirlo (remasterizaciones,
+  /// recopilatorios); se devuelve la más antigua.
+  fn find_song_by_isrc(&self, isrc: &str) -> Result<Option<Song>, CoreError>;
+  /// Busca un release por su MusicBrainz Release ID.
+  fn find_release_by_mbid(&self, mbid: &str) -> Result<Option<Release>, CoreError>;
+  /// Miniatura del release: la del primer artwork (por ruta) que ya tenga una. `None` si no
+  /// hay ninguna generada o el release no existe.
+  fn release_thumbnail(&self, id: ReleaseId) -> Result<Option<PathBuf>, CoreError>;
 
   // --- Métodos de Consulta (Lectura) de Listado ---
   fn list_artists(&self) -> Result<Vec<Artist>, CoreError>;
   fn list_songs(&self) -> Result<Vec<Song>, CoreError>;
   fn list_releases(&self) -> Result<Vec<Release>, CoreError>;
+  /// Hasta `limit` artistas modificados después de `after`, en orden `(updated_at, id)`, para
+  /// que un cliente de sincronización avance página a página con el cursor del último.
+  ///
+  /// Guardar un artista solo cuenta como modificación si cambia algo de lo guardado (sus
+  /// variaciones y sitios incluidos); re-guardarlo igual no lo vuelve a enviar.
+  fn list_artists_updated_after(&self, after: &SyncCursor, limit: i64) -> Result<Vec<Synced<Artist>>, CoreError>;
+  /// Como [`Self::list_artists_updated_after`], para canciones.
+  fn list_songs_updated_after(&self, after: &SyncCursor, limit: i64) -> Result<Vec<Synced<Song>>, CoreError>;
+  /// Como [`Self::list_artists_updated_after`], para releases.
+  fn list_releases_updated_after(&self, after: &SyncCursor, limit: i64) -> Result<Vec<Synced<Release>>, CoreError>;
+  /// Últimas `limit` pistas añadidas a la biblioteca, de la más reciente a la más antigua.
+  ///
+  /// La fecha de alta es la del primer guardado del archivo; re-importarlo no la cambia.
+  fn list_recent_tracks(&self, limit: i64) -> Result<Vec<ReleaseTrack>, CoreError>;
+  /// Pistas cuyo archivo usa el códec indicado (nombre corto de FFmpeg: `"flac"`, `"mp3"`…),
+  /// ordenadas por ruta.
+  fn list_tracks_by_codec(&self, codec: &str) -> Result<Vec<ReleaseTrack>, CoreError>;
+  /// Créditos de la pista ordenados por `position` (los que no tienen, al final, en el
+  /// orden en que se guardaron).
+  fn list_track_credits(&self, track_id: ReleaseTrackId) -> Result<Vec<ReleaseTrackArtistCredit>, CoreError>;
+  /// Página de pistas ordenadas por ruta de archivo, para recorrer toda la biblioteca
+  /// sin cargarla entera. `offset` y `limit` cuentan pistas.
+  fn list_tracks_page(&self, offset: i64, limit: i64) -> Result<Vec<ReleaseTrack>, CoreError>;
+  /// Pistas con nota de calidad entre `min` y `max` (ambos incluidos), de la peor a la
+  /// mejor, paginadas como [`Self::list_tracks_page`]. Las que no tienen nota (sin
+  /// analizar o con análisis inconcluso) no aparecen.
+  fn list_tracks_by_quality(
+    &self,
+    min: f32,
+    max: f32,
+    limit: i64,
+    offset: i64,
+  ) -> Result<Vec<TrackQualitySummary>, CoreError>;
+  /// Canciones sin ninguna pista (`release_tracks`) que las referencie.
+  fn list_orphan_songs(&self) -> Result<Vec<Song>, CoreError>;
+  /// Releases sin ninguna pista.
+  fn list_empty_releases(&self) -> Result<Vec<Release>, CoreError>;
+  /// Pistas cuyo archivo nunca se ha analizado (sin puntuación ni valoración de calidad),
+  /// ordenadas por ruta. Un análisis no concluyente cuenta como hecho.
+  fn list_tracks_pending_analysis(&self) -> Result<Vec<ReleaseTrack>, CoreError>;
+  /// Tamaño, fecha y hash de todos los archivos importados.
+  fn list_file_states(&self) -> Result<Vec<StoredFile>, CoreError>;
+  /// Checkpoint de la importación reanudable interrumpida o en curso.
+  fn load_import_checkpoint(&self) -> Result<Option<ImportCheckpoint>, CoreError>;
+  /// Rutas de los archivos guardados o actualizados en `unix_ts` (segundos UNIX, UTC) o después.
+  fn list_paths_saved_since(&self, unix_ts: i64) -> Result<Vec<PathBuf>, CoreError>;
+  /// Fallos de extracción anotados, ordenados por ruta.
+  fn list_extraction_failures(&self) -> Result<Vec<ExtractionFailure>, CoreError>;
+  /// Artworks de todos los releases, ordenados por ruta.
+  fn list_artworks(&self) -> Result<Vec<Artwork>, CoreError>;
+  /// Las `limit` pistas cuyo embedding (`AudioAnalysis::features`) más se parece al de
+  /// `track_id` por similitud coseno, de la más a la menos parecida, con su similitud.
+  ///
+  /// Las pistas sin embedding no participan. Da `CoreError::NotFound` si la pista no existe
+  /// y `CoreError::InvalidInput` si no tiene embedding.
+  fn find_similar(&self, track_id: ReleaseTrackId, limit: usize) -> Result<Vec<(ReleaseTrackId, f32)>, CoreError>;
+  /// Archivos idénticos: grupos de pistas cuyos archivos tienen el mismo tamaño y el mismo
+  /// hash de contenido, solo los de más de un miembro, los de archivos más grandes primero.
+  ///
+  /// Los archivos sin hash no participan: compartir tamaño no basta. Tampoco se comparan
+  /// hashes de modos distintos (parcial y completo), que llevan prefijos diferentes.
+  fn find_duplicate_files(&self) -> Result<Vec<Vec<ReleaseTrackId>>, CoreError>;
+
+  // --- Métodos de Consulta (Lectura) agregados ---
+  fn stats(&self) -> Result<LibraryStats, CoreError>;
+  /// Número de canciones, sin cargar las filas (para el total de una vista paginada).
+  fn count_songs(&self) -> Result<i64, CoreError>;
+  /// Número de releases.
+  fn count_releases(&self) -> Result<i64, CoreError>;
+  /// Número de artistas.
+  fn count_artists(&self) -> Result<i64, CoreError>;
+  /// Número de pistas con archivo: el total que recorre [`Self::list_tracks_page`].
+  fn count_tracks(&self) -> Result<i64, CoreError>;
+  /// Géneros presentes en la biblioteca con el número de releases de cada uno, de más a
+  /// menos releases. Los valores guardados que no corresponden a ningún [`Genre`] se omiten.
+  fn distinct_genres(&self) -> Result<Vec<(Genre, usize)>, CoreError>;
+  /// Igual que [`Self::distinct_genres`] para los estilos; los desconocidos salen como
+  /// [`Style::Custom`].
+  fn distinct_styles(&self) -> Result<Vec<(Style, usize)>, CoreError>;
+  /// Releases que cumplen `filter`, ordenados por `sort`, en la ventana `page`, con el total
+  /// de los que cumplen el filtro para la paginación.
+  ///
+  /// Género y estilo se comparan ya interpretados, como en [`Self::distinct_genres`]: el
+  /// filtro `Genre::HipHop` encuentra también los guardados como `"hip-hop"`.
+  fn browse_releases(
+    &self,
+    filter: ReleaseFilter,
+    sort: SortBy,
+    page: Page,
+  ) -> Result<Paged<ReleaseSummary>, CoreError>;
+}
+
+/// Acceso a un [`Library`] desde tareas async sin bloquear el executor.
+///
+/// # Frontera síncrono / async
+/// [`Library`] es síncrono: llamarlo dentro de una tarea ocupa un worker del runtime
+/// mientras SQLite lee o escribe, y con una importación de miles de archivos eso para al
+/// resto de tareas (el progreso, la UI). [`Self::offload`] ejecuta el trabajo donde bloquear
+/// no importe y lo espera; el trabajo en sí sigue siendo una llamada normal a [`Library`].
+///
+/// `LibraryService` pasa por aquí todo acceso al repositorio de la importación y del
+/// análisis. Las consultas sueltas del servicio (listados, búsquedas, ediciones) siguen
+/// siendo síncronas: el llamante decide si las saca del runtime.
+#[async_trait]
+pub trait AsyncLibrary: Library + Clone + Send + Sync + 'static {
+  /// Ejecuta `work` con el repositorio fuera del executor y espera su resultado.
+  ///
+  /// Una implementación sin E/S bloqueante puede ejecutarlo en el mismo hilo.
+  async fn offload<T, F>(&self, work: F) -> Result<T, CoreError>
+  where
+    F: FnOnce(&Self) -> Result<T, CoreError> + Send + 'static,
+    T: Send + 'static;
 }
@@ -1,6 +1,10 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use crate::domain::{release::Release, release_track::ReleaseTrack, song::Song};
+use futures::stream::{self, Stream, StreamExt};
+
+use crate::domain::{artist::Artist, release::Release, release_track::ReleaseTrack, song::Song};
 
 #[derive(Debug, thiserror::Error)]
 pub enum MetadataError {
@@ -20,16 +24,30 @@ pub enum MetadataError {
   Internal(String),
 }
 
+impl MetadataError {
+  /// `true` si el fallo es del propio archivo (ilegible, formato no soportado, tags rotos) y
+  /// se repetiría al reintentarlo. Los `Internal` (plazo agotado, pool sin hilos, pánicos)
+  /// son del adaptador y no dicen nada del archivo.
+  pub fn is_file_error(&self) -> bool {
+    !matches!(self, MetadataError::Internal(_))
+  }
+}
+
 /// Resultado de extraer metadatos de un archivo.
 ///
 /// - `song`  → siempre presente (en el peor caso, derivado del filename)
 /// - `release` → opcional (puede no haber álbum claro)
 /// - `track`   → opcional (puede no haber track/disc number)
+/// - `artists` → artistas referenciados por `release.main_artist_ids` (puede estar vacío)
+/// - `raw_tags` → todos los tags del archivo, mapeados o no; vacío salvo que el adaptador
+///   se haya configurado para conservarlos
 #[derive(Debug, Clone)]
 pub struct ExtractedMetadata {
   pub song: Song,
   pub release: Option<Release>,
   pub track: Option<ReleaseTrack>,
+  pub artists: Vec<Artist>,
+  pub raw_tags: HashMap<String, String>,
 }
 
 /// Port que abstrae la lectura de metadatos desde un archivo de audio.
@@ -39,7 +57,52 @@ pub struct ExtractedMetadata {
 /// - Lofty
 /// - Symphonia
 /// - combinaciones + servicios externos (MusicBrainz, etc.)
+///
+/// # Importación en dos fases
+/// El análisis de calidad (decodificar + FFT) domina el coste de `extract_from_path`. Para
+/// que la biblioteca aparezca en la UI en segundos, un importador puede:
+/// 1. Pasar por todos los archivos con [`Probe::extract_tags_only`] y persistir el resultado
+///    (títulos, álbumes, artistas, duración, códec...; `analysis.quality` queda en `None`).
+/// 2. Más tarde, en segundo plano, analizar esos archivos y guardar solo el análisis, como
+///    hace [`crate::services::LibraryService::analyze_pending`].
+///
+/// Volver a persistir la extracción completa en la segunda fase no es equivalente: la fila
+/// del archivo sí se actualiza (la ruta es su clave natural), pero la canción y el release
+/// solo se reconocen por MBID, ISRC o huella. Sin ellos se crean de nuevo y los de la
+/// primera fase quedan huérfanos.
 #[async_trait::async_trait]
 pub trait Probe: Send + Sync {
   async fn extract_from_path(&self, path: &Path) -> Result<ExtractedMetadata, MetadataError>;
+
+  /// Solo tags e información de contenedor/stream, sin decodificar audio ni analizar calidad.
+  ///
+  /// Es la primera fase de una importación en dos fases (ver la documentación del trait).
+  /// La implementación por defecto hace la extracción completa, así que es correcta para
+  /// cualquier adaptador aunque no sea más rápida.
+  async fn extract_tags_only(&self, path: &Path) -> Result<ExtractedMetadata, MetadataError> {
+    self.extract_from_path(path).await
+  }
+
+  /// Extrae metadatos de varios archivos, emitiendo cada resultado en cuanto está listo.
+  ///
+  /// Cada elemento lleva su propio `Result`: un archivo roto no corta el lote.
+  /// La implementación por defecto llama a `extract_from_path` en secuencia; los
+  /// adaptadores con un coste de arranque por archivo (hilos bloqueantes, contextos
+  /// de decodificación, planes FFT) deberían sobrescribirla para reutilizarlo.
+  fn extract_batch(
+    &self,
+    paths: &[PathBuf],
+  ) -> impl Stream<Item = (PathBuf, Result<ExtractedMetadata, MetadataError>)> + Send {
+    stream::iter(paths.to_vec()).then(move |path| async move {
+      let result = self.extract_from_path(&path).await;
+      (path, result)
+    })
+  }
+
+  /// Duración por debajo de la cual el archivo no se va a importar. `None` la quita.
+  ///
+  /// Es una pista para ahorrarse el análisis de calidad cuando la cabecera ya dice que el
+  /// archivo es demasiado corto: la extracción se devuelve igual, sin análisis, y quien
+  /// llama sigue comprobando la duración. La implementación por defecto la ignora.
+  fn set_min_duration(&self, _min: Option<Duration>) {}
 }
@@ -3,7 +3,7 @@ pub mod metadata;
 pub mod progress;
 pub mod scanner;
 
-pub use library::Library;
+pub use library::{AsyncLibrary, ExtractionFailure, ImportCheckpoint, Library, StoredFile};
 pub use metadata::{ExtractedMetadata, MetadataError, Probe};
-pub use progress::ProgressReporter;
-pub use scanner::{ScanDevice, ScanError, ScanGroup, ScannedFile, Scanner};
+pub use progress::{ImportSummary, ProgressReporter};
+pub use scanner::{ScanDevice, ScanError, ScanGroup, ScanOutcome, ScanProgressFn, ScannedFile, Scanner};
@@ -1,4 +1,22 @@
+use std::path::PathBuf;
+
 use async_trait::async_trait;
+use serde::Serialize;
+
+/// Totals of a finished batch operation, handed to [`ProgressReporter::finish`].
+///
+/// `succeeded + failed` is the number of processed units; `skipped` ones were never
+/// processed (e.g. files left untouched by an incremental import) or were dropped after
+/// extraction (see [`ProgressReporter::on_skipped`]).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ImportSummary {
+  pub total: usize,
+  pub succeeded: usize,
+  pub failed: usize,
+  pub skipped: usize,
+  /// Wall-clock time of the whole operation, including the filesystem walk.
+  pub elapsed_secs: f64,
+}
 
 /// Contract for reporting the status of long-running operations.
 ///
@@ -15,9 +33,51 @@ pub trait ProgressReporter: Send + Sync + Clone {
   /// Reports a single successful unit of work.
   async fn on_success(&self, path: &str);
 
+  /// Reports `count` successful units at once, `last_path` being the most recent one.
+  ///
+  /// Sent by batching decorators (`ThrottledReporter`) instead of one `on_success` per unit.
+  /// The default calls `on_success` `count` times with `last_path`, so reporters that don't
+  /// care about batches still count every unit.
+  async fn on_success_batch(&self, count: usize, last_path: &str) {
+    for _ in 0..count {
+      self.on_success(last_path).await;
+    }
+  }
+
   /// Reports a failure for a specific unit of work without aborting the batch.
   async fn on_error(&self, path: &str, error: &str);
 
-  /// Signals that the batch operation has concluded (successfully or otherwise).
-  async fn finish(&self);
+  /// Reports a failure that belongs to a whole scan group (`device` is its device id) rather
+  /// than to one file, e.g. the batched artist upsert. It does not count towards
+  /// [`ImportSummary::failed`], so reporters must not count it as a failed file either.
+  async fn on_group_error(&self, _device: &str, _error: &str) {}
+
+  /// Reports a unit that was counted in `start` but deliberately left out (e.g. a file below
+  /// the minimum duration). It is neither a success nor an error.
+  async fn on_skipped(&self, _path: &str, _reason: &str) {}
+
+  /// Signals that the batch operation has concluded (successfully or otherwise), with its totals.
+  async fn finish(&self, _summary: ImportSummary) {}
+
+  /// Signals that the filesystem walk (discovery phase) has begun. Sent before `start`.
+  async fn scan_started(&self) {}
+
+  /// Reports the number of audio files discovered so far during the walk.
+  ///
+  /// Sent repeatedly while scanning; intermediate counts may be coalesced, so consumers
+  /// must not expect one call per file.
+  async fn on_scan_progress(&self, _files_found: usize) {}
+
+  /// Signals that the walk is over, with the number of files it found. Sent even when
+  /// the scan fails, in which case the count is the last one reported.
+  async fn scan_finished(&self, _files_found: usize) {}
+
+  /// Signals that some configured roots were skipped because they are missing or unreadable
+  /// (e.g. an unplugged external drive). Sent before `start`; the rest of the library is still imported.
+  async fn on_roots_unavailable(&self, _roots: &[PathBuf]) {}
+
+  /// Signals the roots and folders the OS refused to list (`PermissionDenied`), so the UI can
+  /// ask the user to fix their permissions (or grant Full Disk Access on macOS). Sent before
+  /// `start`; files in them are simply not imported.
+  async fn on_permission_denied(&self, _paths: &[PathBuf]) {}
 }
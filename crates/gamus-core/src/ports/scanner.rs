@@ -1,5 +1,6 @@
 use async_trait::async_trait;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Información básica de un archivo detectado por el scanner.
 ///
@@ -10,6 +11,9 @@ pub struct ScannedFile {
   pub path: PathBuf,
   pub size_bytes: u64,
   pub modified_unix: u64,
+  /// Hash de contenido, prefijado con su esquema (`"<esquema>:<hex>"`). Dos hashes solo son
+  /// comparables si comparten esquema. `None` si el hash está desactivado o falló.
+  pub content_hash: Option<String>,
 }
 
 /// Información de un dispositivo lógico donde se encontraron archivos.
@@ -34,6 +38,19 @@ pub struct ScanGroup {
   pub files: Vec<ScannedFile>,
 }
 
+/// Resultado de un escaneo completo de la biblioteca.
+#[derive(Debug, Clone, Default)]
+pub struct ScanOutcome {
+  pub groups: Vec<ScanGroup>,
+  /// Raíces configuradas que no existen o no se pudieron leer (p. ej. un disco externo
+  /// desconectado). El escaneo del resto sigue adelante.
+  pub unavailable_roots: Vec<PathBuf>,
+  /// Raíces y carpetas que el sistema no dejó listar por falta de permisos. A diferencia
+  /// de otros errores de lectura, el usuario puede arreglarlo (permisos, o el Acceso total
+  /// al disco en macOS), así que se le muestran.
+  pub permission_denied: Vec<PathBuf>,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ScanError {
   #[error("io error: {0}")]
@@ -41,8 +58,17 @@ pub enum ScanError {
 
   #[error("internal error: {0}")]
   Internal(String),
+
+  /// Ninguna de las raíces pedidas está disponible: no hay nada que escanear.
+  #[error("scan roots unavailable: {0:?}")]
+  RootsUnavailable(Vec<PathBuf>),
 }
 
+/// Callback de progreso del recorrido: recibe cuántos archivos de audio se llevan encontrados.
+///
+/// Se invoca desde dentro del escaneo, así que debe ser barato (p. ej. mandar a un canal).
+pub type ScanProgressFn = Arc<dyn Fn(usize) + Send + Sync>;
+
 /// Port de scanner de archivos de biblioteca.
 ///
 /// No expone detalles de implementación (Tokio, async, etc.). El adapter
@@ -50,5 +76,21 @@ pub enum ScanError {
 /// una operación síncrona que devuelve los resultados ya agrupados.
 #[async_trait]
 pub trait Scanner: Send + Sync {
-  async fn scan_library_files(&self) -> Result<Vec<ScanGroup>, ScanError>;
+  /// Escanea todas las raíces configuradas. Las que no estén disponibles se saltan y se
+  /// devuelven en [`ScanOutcome::unavailable_roots`]; si no queda ninguna, falla con
+  /// [`ScanError::RootsUnavailable`].
+  async fn scan_library_files(&self) -> Result<ScanOutcome, ScanError>;
+
+  /// Igual que [`Scanner::scan_library_files`], avisando a `on_progress` durante el recorrido.
+  ///
+  /// La implementación por defecto no informa de progreso.
+  async fn scan_library_files_with_progress(&self, on_progress: ScanProgressFn) -> Result<ScanOutcome, ScanError> {
+    let _ = on_progress;
+    self.scan_library_files().await
+  }
+
+  /// Escanea solo el subárbol `root` con los mismos filtros y agrupación por dispositivo.
+  ///
+  /// Pensado para reimportaciones dirigidas (una carpeta nueva, un evento del watcher).
+  async fn scan_path(&self, root: &Path) -> Result<Vec<ScanGroup>, ScanError>;
 }
@@ -0,0 +1,181 @@
+//! Volcado de la biblioteca completa a JSON (copias de seguridad, depuración) y restauración.
+
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::artist::Artist;
+use crate::domain::release::Release;
+use crate::domain::release_track::ReleaseTrack;
+use crate::domain::song::Song;
+use crate::errors::CoreError;
+use crate::ports::Library;
+
+/// Pistas pedidas al repositorio por cada página durante el volcado.
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+/// Artistas acumulados antes de cada `save_artists_batch` al restaurar.
+const IMPORT_ARTIST_BATCH: usize = 500;
+
+/// Una línea del volcado: `{"kind":"artist","data":{...}}`.
+#[derive(Serialize)]
+struct ExportLine<'a, T> {
+  kind: &'a str,
+  data: &'a T,
+}
+
+/// Escribe la biblioteca como JSON delimitado por líneas (NDJSON): una entidad por línea,
+/// en el orden artistas, releases, canciones y pistas.
+///
+/// Las pistas, que son con diferencia lo más numeroso, se leen por páginas (con su análisis
+/// de calidad y sus créditos) y se escriben según llegan; artistas, releases y canciones se
+/// cargan de una vez porque el puerto aún no tiene listados paginados para ellos. `writer`
+/// conviene que vaya con buffer.
+pub fn export_library_json<R: Library + ?Sized>(repo: &R, mut writer: impl Write) -> Result<(), CoreError> {
+  for artist in repo.list_artists()? {
+    write_line(&mut writer, "artist", &artist)?;
+  }
+  for release in repo.list_releases()? {
+    write_line(&mut writer, "release", &release)?;
+  }
+  for song in repo.list_songs()? {
+    write_line(&mut writer, "song", &song)?;
+  }
+
+  let mut offset = 0;
+  loop {
+    let page = repo.list_tracks_page(offset, EXPORT_PAGE_SIZE)?;
+    for track in &page {
+      // Los listados de pistas no traen los créditos; sin ellos la restauración los perdería.
+      let track = ReleaseTrack { artist_credits: repo.list_track_credits(track.id)?, ..track.clone() };
+      write_line(&mut writer, "track", &track)?;
+    }
+    if (page.len() as i64) < EXPORT_PAGE_SIZE {
+      break;
+    }
+    offset += EXPORT_PAGE_SIZE;
+  }
+
+  writer.flush().map_err(|e| CoreError::Export(e.to_string()))
+}
+
+fn write_line<T: Serialize>(writer: &mut impl Write, kind: &str, data: &T) -> Result<(), CoreError> {
+  serde_json::to_writer(&mut *writer, &ExportLine { kind, data }).map_err(|e| CoreError::Export(e.to_string()))?;
+  writer.write_all(b"\n").map_err(|e| CoreError::Export(e.to_string()))
+}
+
+/// Lectura de una línea del volcado; mismo formato que `ExportLine`.
+#[derive(Deserialize)]
+#[serde(tag = "kind", content = "data", rename_all = "lowercase")]
+enum BackupRecord {
+  Artist(Artist),
+  Release(Release),
+  Song(Song),
+  Track(Box<ReleaseTrack>),
+}
+
+/// Resultado de [`import_library_json`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RestoreSummary {
+  pub artists: usize,
+  pub releases: usize,
+  pub songs: usize,
+  pub tracks: usize,
+  /// Líneas que no se pudieron leer, no pasaron la validación o fallaron al guardarse.
+  pub failed: usize,
+}
+
+impl RestoreSummary {
+  /// Entidades restauradas de cualquier tipo.
+  pub fn restored(&self) -> usize {
+    self.artists + self.releases + self.songs + self.tracks
+  }
+}
+
+/// Restaura un volcado de [`export_library_json`] haciendo upsert de cada entidad.
+///
+/// Los ids se conservan tal cual, así que las referencias entre entidades siguen valiendo
+/// y restaurar dos veces el mismo volcado no duplica nada. Una línea mal formada, inválida
+/// o que el repositorio rechaza se cuenta en `failed` y se sigue con la siguiente; solo un
+/// error de lectura de `reader` aborta.
+pub fn import_library_json<R: Library + ?Sized>(repo: &R, reader: impl Read) -> Result<RestoreSummary, CoreError> {
+  let mut summary = RestoreSummary::default();
+  let mut artists: Vec<Artist> = Vec::new();
+
+  for line in BufReader::new(reader).lines() {
+    let line = match line {
+      Ok(line) => line,
+      Err(e) if e.kind() == ErrorKind::InvalidData => {
+        summary.failed += 1;
+        continue;
+      }
+      Err(e) => return Err(CoreError::Export(e.to_string())),
+    };
+    if line.trim().is_empty() {
+      continue;
+    }
+
+    let Ok(record) = serde_json::from_str::<BackupRecord>(&line) else {
+      summary.failed += 1;
+      continue;
+    };
+    if !is_valid(&record) {
+      summary.failed += 1;
+      continue;
+    }
+
+    // Releases y créditos apuntan a artistas: los pendientes se guardan antes que nada más.
+    if !matches!(record, BackupRecord::Artist(_)) {
+      flush_artists(repo, &mut artists, &mut summary);
+    }
+
+    let saved = match &record {
+      BackupRecord::Artist(artist) => {
+        artists.push(artist.clone());
+        if artists.len() >= IMPORT_ARTIST_BATCH {
+          flush_artists(repo, &mut artists, &mut summary);
+        }
+        continue;
+      }
+      BackupRecord::Release(release) => repo.save_release(release).map(|()| &mut summary.releases),
+      BackupRecord::Song(song) => repo.save_song(song).map(|()| &mut summary.songs),
+      BackupRecord::Track(track) => repo.save_track(track).map(|()| &mut summary.tracks),
+    };
+    match saved {
+      Ok(count) => *count += 1,
+      Err(_) => summary.failed += 1,
+    }
+  }
+
+  flush_artists(repo, &mut artists, &mut summary);
+  Ok(summary)
+}
+
+/// Guarda los artistas pendientes en lote; si el lote falla, uno a uno para aislar el culpable.
+fn flush_artists<R: Library + ?Sized>(repo: &R, artists: &mut Vec<Artist>, summary: &mut RestoreSummary) {
+  if artists.is_empty() {
+    return;
+  }
+
+  if repo.save_artists_batch(artists).is_ok() {
+    summary.artists += artists.len();
+  } else {
+    for artist in artists.iter() {
+      match repo.save_artist(artist) {
+        Ok(()) => summary.artists += 1,
+        Err(_) => summary.failed += 1,
+      }
+    }
+  }
+  artists.clear();
+}
+
+/// Comprobaciones mínimas que el esquema JSON no cubre.
+fn is_valid(record: &BackupRecord) -> bool {
+  match record {
+    BackupRecord::Artist(artist) => !artist.name.trim().is_empty(),
+    BackupRecord::Release(release) => !release.title.trim().is_empty(),
+    BackupRecord::Track(track) => !track.file_details.path.as_os_str().is_empty(),
+    BackupRecord::Song(_) => true,
+  }
+}
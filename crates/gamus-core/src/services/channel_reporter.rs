@@ -0,0 +1,124 @@
+//! `ProgressReporter` que reenvía cada aviso por un canal, para front-ends sin UI (CLI, scripts).
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::ports::{ImportSummary, ProgressReporter};
+
+/// Un aviso de progreso; hay una variante por cada método de [`ProgressReporter`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ProgressEvent {
+  ScanStarted,
+  ScanProgress { files_found: usize },
+  ScanFinished { files_found: usize },
+  RootsUnavailable { roots: Vec<PathBuf> },
+  PermissionDenied { paths: Vec<PathBuf> },
+  Started { total: usize },
+  Succeeded { path: String },
+  Failed { path: String, error: String },
+  GroupFailed { device: String, error: String },
+  Skipped { path: String, reason: String },
+  Finished(ImportSummary),
+}
+
+/// Envía un [`ProgressEvent`] por cada llamada al reporter.
+///
+/// El canal es acotado: si el consumidor se queda atrás, la importación espera en cada envío
+/// en vez de acumular eventos sin límite. Si el receptor se ha cerrado los eventos se
+/// descartan en silencio; la importación no depende de que alguien la esté mirando.
+#[derive(Debug, Clone)]
+pub struct ChannelReporter {
+  tx: mpsc::Sender<ProgressEvent>,
+}
+
+impl ChannelReporter {
+  pub fn new(tx: mpsc::Sender<ProgressEvent>) -> Self {
+    Self { tx }
+  }
+
+  /// Crea el reporter junto con el receptor de un canal nuevo de `capacity` eventos.
+  pub fn channel(capacity: usize) -> (Self, mpsc::Receiver<ProgressEvent>) {
+    let (tx, rx) = mpsc::channel(capacity);
+    (Self::new(tx), rx)
+  }
+
+  async fn send(&self, event: ProgressEvent) {
+    let _ = self.tx.send(event).await;
+  }
+}
+
+#[async_trait]
+impl ProgressReporter for ChannelReporter {
+  async fn start(&self, total_files: usize) {
+    self.send(ProgressEvent::Started { total: total_files }).await;
+  }
+
+  async fn on_success(&self, path: &str) {
+    self.send(ProgressEvent::Succeeded { path: path.to_string() }).await;
+  }
+
+  async fn on_error(&self, path: &str, error: &str) {
+    self.send(ProgressEvent::Failed { path: path.to_string(), error: error.to_string() }).await;
+  }
+
+  async fn on_group_error(&self, device: &str, error: &str) {
+    self.send(ProgressEvent::GroupFailed { device: device.to_string(), error: error.to_string() }).await;
+  }
+
+  async fn on_skipped(&self, path: &str, reason: &str) {
+    self.send(ProgressEvent::Skipped { path: path.to_string(), reason: reason.to_string() }).await;
+  }
+
+  async fn finish(&self, summary: ImportSummary) {
+    self.send(ProgressEvent::Finished(summary)).await;
+  }
+
+  async fn scan_started(&self) {
+    self.send(ProgressEvent::ScanStarted).await;
+  }
+
+  async fn on_scan_progress(&self, files_found: usize) {
+    self.send(ProgressEvent::ScanProgress { files_found }).await;
+  }
+
+  async fn scan_finished(&self, files_found: usize) {
+    self.send(ProgressEvent::ScanFinished { files_found }).await;
+  }
+
+  async fn on_roots_unavailable(&self, roots: &[PathBuf]) {
+    self.send(ProgressEvent::RootsUnavailable { roots: roots.to_vec() }).await;
+  }
+
+  async fn on_permission_denied(&self, paths: &[PathBuf]) {
+    self.send(ProgressEvent::PermissionDenied { paths: paths.to_vec() }).await;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use futures::executor::block_on;
+
+  use super::*;
+
+  #[test]
+  fn events_arrive_in_order_and_a_closed_receiver_is_ignored() {
+    let (reporter, mut rx) = ChannelReporter::channel(8);
+
+    block_on(async {
+      reporter.start(2).await;
+      reporter.on_success("/a.flac").await;
+      reporter.on_error("/b.flac", "corrupt").await;
+    });
+
+    assert_eq!(rx.try_recv().unwrap(), ProgressEvent::Started { total: 2 });
+    assert_eq!(rx.try_recv().unwrap(), ProgressEvent::Succeeded { path: "/a.flac".into() });
+    assert_eq!(rx.try_recv().unwrap(), ProgressEvent::Failed { path: "/b.flac".into(), error: "corrupt".into() });
+
+    drop(rx);
+    block_on(reporter.finish(ImportSummary::default()));
+  }
+}
@@ -1,10 +1,28 @@
-use crate::domain::artist::Artist;
-use crate::domain::release::Release;
-use crate::domain::song::Song;
-use crate::domain::{ArtistId, ReleaseId, SongId};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::domain::artist::{Artist, ArtistPatch, normalize_artist_name};
+use crate::domain::artist_role::ReleaseTrackArtistCredit;
+use crate::domain::browse::{Page, Paged, ReleaseFilter, ReleaseSummary, SortBy};
+use crate::domain::genre_styles::{Genre, Style};
+use crate::domain::library_stats::LibraryStats;
+use crate::domain::release::{Artwork, Release, ReleasePatch};
+use crate::domain::release_track::{AudioAnalysis, AudioQuality, ReleaseTrack, TrackQualitySummary};
+use crate::domain::song::{Song, SongPatch};
+use crate::domain::sync::{SyncCursor, Synced};
+use crate::domain::{ArtistId, ReleaseId, ReleaseTrackId, SongId};
 use crate::errors::CoreError;
-use crate::ports::{Library, Probe, ProgressReporter, Scanner};
+use crate::ports::{
+  AsyncLibrary, ExtractedMetadata, ExtractionFailure, ImportCheckpoint, ImportSummary, Library, Probe,
+  ProgressReporter, ScanGroup, ScanOutcome, ScanProgressFn, ScannedFile, Scanner, StoredFile,
+};
+use crate::services::backup::{RestoreSummary, export_library_json, import_library_json};
 
+use futures::FutureExt;
+use futures::channel::mpsc;
 use futures::stream::{self, StreamExt};
 
 /// Servicio de Aplicación para gestionar la Biblioteca.
@@ -15,24 +33,86 @@ pub struct LibraryService<S, M, R, P>
 where
   S: Scanner + Clone,  // Necesitamos Clone para pasarlo a hilos si fuera necesario
   M: Probe + Clone,    // Necesitamos Clone para que cada hilo tenga su extractor
-  R: Library + Clone,  // Necesitamos Clone para que cada hilo tenga su conexión a DB
+  R: AsyncLibrary,     // Clone para que cada hilo tenga su conexión a DB; la importación escribe vía `offload`
   P: ProgressReporter, // El reporter suele ser un canal (mpsc) o Arc interno, no necesita Clone explícito aquí si es referencia compartida, pero Clone ayuda.
 {
   scanner: S,
   metadata: M,
   repo: R,
   reporter: P,
+  /// Los archivos más cortos no se importan (ver [`Self::with_min_duration_secs`]).
+  ///
+  /// Tras un `RwLock` para poder cambiarlo sin reconstruir el servicio
+  /// ([`Self::set_min_duration_secs`]); cada importación lee el valor una sola vez.
+  min_duration: RwLock<Option<Duration>>,
+  /// Cuánto se saltan los archivos cuya extracción falló (ver [`Self::with_failure_cooldown_secs`]).
+  failure_cooldown: RwLock<Option<Duration>>,
 }
 
 impl<S, M, R, P> LibraryService<S, M, R, P>
 where
   S: Scanner + Clone,
   M: Probe + Clone,
-  R: Library + Clone,
+  R: AsyncLibrary,
   P: ProgressReporter,
 {
   pub fn new(scanner: S, metadata: M, repo: R, reporter: P) -> Self {
-    Self { scanner, metadata, repo, reporter }
+    Self { scanner, metadata, repo, reporter, min_duration: RwLock::new(None), failure_cooldown: RwLock::new(None) }
+  }
+
+  /// Descarta en la importación los archivos que duran menos de `secs` segundos (tonos,
+  /// efectos de sonido, grabaciones sueltas). `None`, el valor por defecto, no filtra nada.
+  ///
+  /// La duración sale de la misma apertura que extrae las etiquetas, así que el filtro no
+  /// abre los archivos una vez más, y el valor se pasa al adaptador
+  /// ([`Probe::set_min_duration`]) para que no analice los que ya sabe demasiado cortos.
+  /// Los descartados se cuentan en `skipped` y se avisan con
+  /// [`ProgressReporter::on_skipped`]; `analyze_pending` los salta igual. Un archivo sin
+  /// duración conocida (cero) se importa.
+  pub fn with_min_duration_secs(self, secs: Option<f64>) -> Self {
+    self.set_min_duration_secs(secs);
+    self
+  }
+
+  /// Cambia la duración mínima en caliente, p. ej. tras recargar la configuración.
+  ///
+  /// Una importación en curso conserva el valor con el que empezó su extracción; el nuevo
+  /// se aplica a partir de la siguiente. El adaptador, en cambio, lo recibe en seguida: si
+  /// sube a mitad de una importación, algún archivo puede entrar sin análisis y quedar
+  /// pendiente para `analyze_pending`.
+  pub fn set_min_duration_secs(&self, secs: Option<f64>) {
+    let min = secs.and_then(|s| Duration::try_from_secs_f64(s).ok()).filter(|d| !d.is_zero());
+    *self.min_duration.write().unwrap_or_else(|e| e.into_inner()) = min;
+    self.metadata.set_min_duration(min);
+  }
+
+  /// Duración mínima vigente (ver [`Self::with_min_duration_secs`]).
+  pub fn min_duration(&self) -> Option<Duration> {
+    *self.min_duration.read().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Salta en las importaciones, durante `secs` segundos desde el último intento, los
+  /// archivos cuya extracción de metadatos falló, salvo que su `mtime` haya cambiado desde
+  /// entonces. `0`, el valor por defecto, los reintenta siempre.
+  ///
+  /// Evita reintentar en cada importación archivos con DRM o corruptos. Los fallos se
+  /// anotan con [`Library::record_extraction_failure`] y se olvidan cuando el archivo se
+  /// importa bien o con [`Self::clear_extraction_failures`]. Los saltados cuentan en
+  /// `skipped`, sin aviso por archivo.
+  pub fn with_failure_cooldown_secs(self, secs: u64) -> Self {
+    self.set_failure_cooldown_secs(secs);
+    self
+  }
+
+  /// Cambia el plazo de [`Self::with_failure_cooldown_secs`] en caliente; como con la
+  /// duración mínima, una importación en curso conserva el suyo.
+  pub fn set_failure_cooldown_secs(&self, secs: u64) {
+    let cooldown = (secs > 0).then(|| Duration::from_secs(secs));
+    *self.failure_cooldown.write().unwrap_or_else(|e| e.into_inner()) = cooldown;
+  }
+
+  fn failure_cooldown(&self) -> Option<Duration> {
+    *self.failure_cooldown.read().unwrap_or_else(|e| e.into_inner())
   }
 
   /// Determina cuántos archivos procesar en paralelo basándose en la velocidad del disco.
@@ -40,6 +120,10 @@ where
   /// - NVMe (>500MB/s): 50 hilos (limitado por CPU para ffmpeg)
   /// - SSD/SATA (>100MB/s): 20 hilos
   /// - USB/Red/HDD (<100MB/s): 4 hilos (para evitar thrashing del cabezal o saturar bus)
+  ///
+  /// Es el número de lotes abiertos a la vez, no de núcleos ocupados: el adaptador de
+  /// metadatos puede tener su propio límite de CPU (el `FfmpegProbe` decodifica en un pool
+  /// de un hilo por núcleo), y los lotes que no caben en él esperan en cola.
   fn decide_concurrency(&self, mb_s_hint: Option<u64>) -> usize {
     match mb_s_hint {
       Some(speed) if speed > 500 => 50,
@@ -50,18 +134,312 @@ where
   }
 
   /// Importa la biblioteca completa de manera asíncrona y reactiva.
+  ///
+  /// # Fusión por huella acústica
+  /// Si un archivo trae huella (`song.acoustid` o `audio_details.fingerprint`) y ya existe
+  /// una canción con esa huella (en la base de datos o importada antes en esta misma
+  /// pasada), se reutiliza su `SongId`: el archivo se guarda como un `ReleaseTrack` más
+  /// de esa canción y la fila de `songs` existente no se sobrescribe (gana el primero).
+  /// Así un FLAC y un MP3 de la misma grabación quedan como una canción con dos pistas.
   pub async fn import_full(&self) -> Result<(), CoreError> {
-    // 1. ESCANEO: Obtener grupos de archivos (agrupados por dispositivo físico)
-    //    Esto llama al puerto, que a su vez usa el adaptador de gamus-scanner
-    let groups = self.scanner.scan_library_files().await.map_err(|e| CoreError::Scan(e.to_string()))?;
+    let started = Instant::now();
+    let groups = self.scan_all().await?;
+    self.import_groups(groups, 0, started).await
+  }
+
+  /// Como [`Self::import_full`], pero solo procesa archivos nuevos o modificados.
+  ///
+  /// # Detección de cambios
+  /// Si el archivo escaneado y el guardado tienen hash de contenido del mismo esquema, el
+  /// hash es la única señal: un `mtime` distinto con el mismo contenido no reimporta, y un
+  /// contenido distinto con el mismo `mtime` sí. Sin hash comparable se usa tamaño + `mtime`.
+  /// Los archivos que ya no están en disco no se eliminan.
+  pub async fn import_incremental(&self) -> Result<(), CoreError> {
+    let started = Instant::now();
+    let mut groups = self.scan_all().await?;
+    let scanned: usize = groups.iter().map(|g| g.files.len()).sum();
+
+    let stored: HashMap<PathBuf, StoredFile> =
+      self.repo.offload(|repo| repo.list_file_states()).await?.into_iter().map(|f| (f.path.clone(), f)).collect();
+
+    for group in &mut groups {
+      group.files.retain(|f| file_changed(f, stored.get(&f.path)));
+    }
+    groups.retain(|g| !g.files.is_empty());
+
+    let skipped = scanned - groups.iter().map(|g| g.files.len()).sum::<usize>();
+    self.import_groups(groups, skipped, started).await
+  }
+
+  /// Como [`Self::import_full`], pero si se interrumpe (cierre, caída) la siguiente llamada
+  /// continúa donde se quedó en vez de empezar de cero.
+  ///
+  /// Al empezar se guarda un [`ImportCheckpoint`] con la hora de inicio, y se borra al
+  /// terminar. Si al llamar ya hay uno, se reanuda: se saltan los archivos que el
+  /// repositorio guardó desde esa hora, salvo que hayan cambiado después (misma detección
+  /// que [`Self::import_incremental`]). Los lotes terminan en cualquier orden, así que no
+  /// basta con recordar la última ruta procesada; las fechas de guardado de cada archivo
+  /// hacen de checkpoint. Los archivos que fallaron no se guardaron y se reintentan.
+  ///
+  /// Para descartar la importación pendiente y empezar de cero, ver
+  /// [`Self::discard_pending_import`].
+  pub async fn import_full_resumable(&self) -> Result<(), CoreError> {
+    let started = Instant::now();
+    let checkpoint = self
+      .repo
+      .offload(|repo| match repo.load_import_checkpoint()? {
+        Some(checkpoint) => Ok(checkpoint),
+        None => {
+          let checkpoint = ImportCheckpoint { started_at: unix_now() };
+          repo.save_import_checkpoint(&checkpoint)?;
+          Ok(checkpoint)
+        }
+      })
+      .await?;
+
+    let mut groups = self.scan_all().await?;
+    let scanned: usize = groups.iter().map(|g| g.files.len()).sum();
+
+    let since = checkpoint.started_at;
+    let done: HashSet<PathBuf> =
+      self.repo.offload(move |repo| repo.list_paths_saved_since(since)).await?.into_iter().collect();
+    if !done.is_empty() {
+      let stored: HashMap<PathBuf, StoredFile> =
+        self.repo.offload(|repo| repo.list_file_states()).await?.into_iter().map(|f| (f.path.clone(), f)).collect();
+      for group in &mut groups {
+        group.files.retain(|f| !done.contains(&f.path) || file_changed(f, stored.get(&f.path)));
+      }
+      groups.retain(|g| !g.files.is_empty());
+    }
+
+    let skipped = scanned - groups.iter().map(|g| g.files.len()).sum::<usize>();
+    self.import_groups(groups, skipped, started).await?;
+    self.repo.offload(|repo| repo.clear_import_checkpoint()).await
+  }
+
+  /// Archivos cuya extracción falló y que las importaciones saltan durante el plazo de
+  /// [`Self::with_failure_cooldown_secs`].
+  pub fn list_extraction_failures(&self) -> Result<Vec<ExtractionFailure>, CoreError> {
+    self.repo.list_extraction_failures()
+  }
+
+  /// Olvida todos los fallos de extracción: la próxima importación reintenta esos archivos.
+  /// Devuelve cuántos había.
+  pub fn clear_extraction_failures(&self) -> Result<usize, CoreError> {
+    self.repo.clear_extraction_failures()
+  }
+
+  /// Miniatura de la portada del release, si ya se generó.
+  pub fn release_thumbnail(&self, id: ReleaseId) -> Result<Option<PathBuf>, CoreError> {
+    self.repo.release_thumbnail(id)
+  }
+
+  /// Artworks de todos los releases, para generar sus miniaturas.
+  pub fn list_artworks(&self) -> Result<Vec<Artwork>, CoreError> {
+    self.repo.list_artworks()
+  }
+
+  /// Anota la miniatura generada para los artworks con ese hash.
+  pub fn set_artwork_thumbnail(&self, hash: &str, thumbnail: &Path) -> Result<usize, CoreError> {
+    self.repo.set_artwork_thumbnail(hash, thumbnail)
+  }
+
+  /// Importación reanudable interrumpida (o en curso), para ofrecer "Reanudar" o "Empezar de cero".
+  pub fn pending_import(&self) -> Result<Option<ImportCheckpoint>, CoreError> {
+    self.repo.load_import_checkpoint()
+  }
+
+  /// Olvida la importación reanudable pendiente: la próxima [`Self::import_full_resumable`]
+  /// procesa todos los archivos.
+  pub fn discard_pending_import(&self) -> Result<(), CoreError> {
+    self.repo.clear_import_checkpoint()
+  }
+
+  /// Completa el análisis de calidad de las pistas que aún no lo tienen.
+  ///
+  /// Pensado como segunda fase tras una importación rápida con `Probe::extract_tags_only`:
+  /// la biblioteca ya es navegable y esto rellena calidad/BPM en segundo plano. Solo se
+  /// actualizan las columnas de análisis; canción, release y pista no se tocan.
+  ///
+  /// Los archivos guardados no recuerdan su dispositivo, así que la concurrencia es la de
+  /// `decide_concurrency` sin dato de velocidad. El progreso va por el reporter como en una
+  /// importación (`start` / `on_success` / `on_error` / `finish`), y los archivos más cortos
+  /// que la duración mínima se saltan con `on_skipped`.
+  pub async fn analyze_pending(&self) -> Result<(), CoreError> {
+    let started = Instant::now();
+    let pending = self.repo.offload(|repo| repo.list_tracks_pending_analysis()).await?;
+    let mut summary = ImportSummary { total: pending.len(), ..Default::default() };
+    self.reporter.start(summary.total).await;
+
+    let track_ids: HashMap<PathBuf, ReleaseTrackId> =
+      pending.iter().map(|t| (t.file_details.path.clone(), t.id)).collect();
+    let paths: Vec<PathBuf> = pending.into_iter().map(|t| t.file_details.path).collect();
+
+    let min_duration = self.min_duration();
+    let concurrency = self.decide_concurrency(None);
+    let batch_size = paths.len().div_ceil(concurrency).max(1);
+    let batches = paths.chunks(batch_size).map(|chunk| self.metadata.extract_batch(chunk).boxed());
+    let mut analyzed_stream = stream::select_all(batches);
+
+    while let Some((path, result)) = analyzed_stream.next().await {
+      let path_str = path.to_string_lossy().to_string();
+
+      if let Ok(extracted) = &result
+        && let Some(min) = min_duration
+        && let Some(duration) = too_short(extracted, min)
+      {
+        summary.skipped += 1;
+        self.reporter.on_skipped(&path_str, &shorter_than_minimum(duration, min)).await;
+        continue;
+      }
+
+      let analysis = result
+        .map_err(|e| format!("Metadata error: {}", e))
+        .and_then(|extracted| analysis_of(extracted).ok_or_else(|| "No quality analysis produced".to_string()));
+      let updated = match analysis {
+        Ok(analysis) => match track_ids.get(&path).copied() {
+          Some(track_id) => self
+            .repo
+            .offload(move |repo| repo.update_track_analysis(track_id, &analysis))
+            .await
+            .map_err(|e| format!("Repo analysis error: {}", e)),
+          None => Err("Analyzed a path that was not pending analysis".to_string()),
+        },
+        Err(e) => Err(e),
+      };
+
+      match updated {
+        Ok(()) => {
+          summary.succeeded += 1;
+          self.reporter.on_success(&path_str).await;
+        }
+        Err(error_msg) => {
+          summary.failed += 1;
+          self.reporter.on_error(&path_str, &error_msg).await;
+        }
+      }
+    }
+
+    summary.elapsed_secs = started.elapsed().as_secs_f64();
+    self.reporter.finish(summary).await;
+
+    Ok(())
+  }
+
+  /// Repite el análisis de calidad de una sola pista y guarda el resultado.
+  ///
+  /// El análisis lo hace `probe` y no el adaptador del servicio, para poder usar otros
+  /// ajustes (p. ej. una ventana más larga) sin reconfigurar la importación. Como en
+  /// `analyze_pending`, se sustituye todo el análisis guardado del archivo.
+  pub async fn reanalyze_track<Q: Probe>(
+    &self,
+    track_id: ReleaseTrackId,
+    probe: &Q,
+  ) -> Result<AudioQuality, CoreError> {
+    let track = self.repo.offload(move |repo| repo.find_track(track_id)).await?.ok_or(CoreError::NotFound)?;
+    let path = &track.file_details.path;
+    if !path.exists() {
+      return Err(CoreError::Metadata(format!("file no longer exists on disk: {}", path.display())));
+    }
+
+    let extracted = probe.extract_from_path(path).await.map_err(|e| CoreError::Metadata(e.to_string()))?;
+    let no_report = || CoreError::Metadata(format!("no quality analysis produced for {}", path.display()));
+    let analysis = analysis_of(extracted).ok_or_else(no_report)?;
+    let quality = analysis.quality.clone().ok_or_else(no_report)?;
+    self.repo.offload(move |repo| repo.update_track_analysis(track_id, &analysis)).await?;
+
+    Ok(quality)
+  }
+
+  /// ESCANEO: grupos de archivos por dispositivo físico, avisando de las raíces saltadas.
+  ///
+  /// Informa al reporter del progreso del recorrido (`scan_started` / `on_scan_progress` /
+  /// `scan_finished`), que puede tardar minutos en bibliotecas grandes antes de `start`.
+  async fn scan_all(&self) -> Result<Vec<ScanGroup>, CoreError> {
+    self.reporter.scan_started().await;
+
+    // El callback del scanner es síncrono; los conteos pasan por un canal y se reenvían al
+    // reporter aquí. El canal se cierra cuando el escaneo termina y suelta el callback.
+    let (tx, mut rx) = mpsc::unbounded::<usize>();
+    let on_progress: ScanProgressFn = Arc::new(move |found| {
+      let _ = tx.unbounded_send(found);
+    });
+
+    // Esto llama al puerto, que a su vez usa el adaptador de gamus-scanner
+    let scan = self.scanner.scan_library_files_with_progress(on_progress);
+    let forward = async {
+      let mut last_reported = 0;
+      while let Some(mut found) = rx.next().await {
+        // Si el reporter va más lento que el recorrido, solo cuenta el último valor.
+        while let Some(Some(newer)) = rx.next().now_or_never() {
+          found = newer;
+        }
+        if found != last_reported {
+          last_reported = found;
+          self.reporter.on_scan_progress(found).await;
+        }
+      }
+      last_reported
+    };
+    let (result, last_reported) = futures::join!(scan, forward);
+
+    let files_found =
+      result.as_ref().map_or(last_reported, |outcome| outcome.groups.iter().map(|g| g.files.len()).sum());
+    self.reporter.scan_finished(files_found).await;
+
+    let ScanOutcome { groups, unavailable_roots, permission_denied } =
+      result.map_err(|e| CoreError::Scan(e.to_string()))?;
+
+    // Raíces saltadas (disco externo desconectado...): se avisa y se importa el resto.
+    if !unavailable_roots.is_empty() {
+      self.reporter.on_roots_unavailable(&unavailable_roots).await;
+    }
+    if !permission_denied.is_empty() {
+      self.reporter.on_permission_denied(&permission_denied).await;
+    }
+
+    Ok(groups)
+  }
+
+  /// Extrae y persiste los archivos de `groups`, reportando el progreso.
+  ///
+  /// `skipped` (archivos descartados antes de llegar aquí) y `started` solo alimentan el
+  /// resumen que recibe `finish`.
+  async fn import_groups(
+    &self,
+    mut groups: Vec<ScanGroup>,
+    mut skipped: usize,
+    started: Instant,
+  ) -> Result<(), CoreError> {
+    // Una sola lectura: cambiarlas a mitad de importación no debe mezclar dos criterios.
+    let min_duration = self.min_duration();
+    let failure_cooldown = self.failure_cooldown();
+
+    // 1. Fuera los archivos que fallaron hace poco y no han cambiado desde entonces.
+    let failures: HashMap<PathBuf, ExtractionFailure> = self
+      .repo
+      .offload(|repo| repo.list_extraction_failures())
+      .await?
+      .into_iter()
+      .map(|f| (f.path.clone(), f))
+      .collect();
+    if let Some(cooldown) = failure_cooldown {
+      let now = unix_now();
+      for group in &mut groups {
+        let before = group.files.len();
+        group.files.retain(|f| !cooling_down(f, failures.get(&f.path), now, cooldown));
+        skipped += before - group.files.len();
+      }
+      groups.retain(|g| !g.files.is_empty());
+    }
 
     // Calculamos el total global para inicializar la barra de progreso
     let total_files: usize = groups.iter().map(|g| g.files.len()).sum();
+    let mut summary = ImportSummary { total: total_files, skipped, ..Default::default() };
     self.reporter.start(total_files).await;
 
-    // Preparamos referencias clonables de los servicios para inyectarlas en los closures async
-    let meta_service_base = self.metadata.clone();
-    let repo_service_base = self.repo.clone();
+    // Claves ya resueltas durante esta importación (ver `ResolvedKeys`).
+    let mut resolved = ResolvedKeys::default();
 
     // 2. PROCESAMIENTO: Iteramos grupo por grupo (Disco por Disco)
     //    Es importante procesar los discos de uno en uno para no saturar el sistema I/O global,
@@ -70,58 +448,102 @@ where
       // A) Decidir concurrencia para ESTE dispositivo
       let concurrency = self.decide_concurrency(group.device.bandwidth_mb_s);
 
-      // B) Crear el Stream de procesamiento
-      let mut stream = stream::iter(group.files)
-        .map(|scanned_file| {
-          // Clonamos 'handles' para esta tarea específica
-          let meta = meta_service_base.clone();
-          let repo = repo_service_base.clone();
-
-          // El bloque async move captura las variables clonadas y el archivo
-          async move {
-            let path_str = scanned_file.path.to_string_lossy().to_string();
-
-            // --- PASO 1: Extracción (CPU Bound / IO Read) ---
-            let extracted = meta
-              .extract_from_path(&scanned_file.path)
-              .await
-              .map_err(|e| (path_str.clone(), format!("Metadata error: {}", e)))?;
-
-            // --- PASO 2: Persistencia (IO Write / DB) ---
-            // Guardar Song
-            repo.save_song(&extracted.song).map_err(|e| (path_str.clone(), format!("Repo song error: {}", e)))?;
-
-            // Guardar Release (si existe)
-            if let Some(release) = &extracted.release {
-              repo.save_release(release).map_err(|e| (path_str.clone(), format!("Repo release error: {}", e)))?;
-            }
+      // B) Repartir el grupo en `concurrency` lotes. Cada lote es un `extract_batch`, así el
+      //    adaptador reutiliza su estado caliente entre archivos, y los lotes corren en paralelo.
+      let paths: Vec<PathBuf> = group.files.iter().map(|f| f.path.clone()).collect();
+      let content_hashes: HashMap<&Path, &str> =
+        group.files.iter().filter_map(|f| Some((f.path.as_path(), f.content_hash.as_deref()?))).collect();
+      let mtimes: HashMap<&Path, u64> = group.files.iter().map(|f| (f.path.as_path(), f.modified_unix)).collect();
+      let batch_size = paths.len().div_ceil(concurrency).max(1);
+      let batches = paths.chunks(batch_size).map(|chunk| self.metadata.extract_batch(chunk).boxed());
+      let mut extracted_stream = stream::select_all(batches);
+
+      // C) CONSUMIR RESULTADOS: cada archivo se persiste en cuanto su lote lo entrega
+      let mut group_artists: Vec<Artist> = Vec::new();
 
-            // Guardar Track / Relación (Pendiente de implementar en tus repos)
-            // ...
+      while let Some((path, result)) = extracted_stream.next().await {
+        let path_str = path.to_string_lossy().to_string();
 
-            // Retornamos el path como éxito
-            Ok::<String, (String, String)>(path_str)
+        if let Ok(extracted) = &result
+          && let Some(min) = min_duration
+          && let Some(duration) = too_short(extracted, min)
+        {
+          summary.skipped += 1;
+          self.reporter.on_skipped(&path_str, &shorter_than_minimum(duration, min)).await;
+          continue;
+        }
+
+        let persisted = match result {
+          Ok(mut extracted) => {
+            // El hash lo calcula el scanner; se guarda con la pista para la próxima importación incremental.
+            if let Some(track) = &mut extracted.track {
+              track.file_details.content_hash = content_hashes.get(path.as_path()).map(|h| h.to_string());
+            }
+            // Las claves viajan con el trabajo y vuelven con él. Si la tarea muere por el
+            // camino se pierden, y los archivos siguientes las resuelven de nuevo contra el repositorio.
+            let mut keys = std::mem::take(&mut resolved);
+            let outcome = self
+              .repo
+              .offload(move |repo| {
+                let persisted = persist_extracted(repo, extracted, &mut keys);
+                Ok((keys, persisted))
+              })
+              .await;
+            match outcome {
+              Ok((keys, persisted)) => {
+                resolved = keys;
+                persisted
+              }
+              Err(e) => Err(format!("Repo error: {}", e)),
+            }
           }
-        })
-        // C) BUFFER_UNORDERED: Aquí ocurre la magia de la concurrencia
-        .buffer_unordered(concurrency);
-
-      // D) CONSUMIR RESULTADOS: Mientras el buffer procesa, recibimos los resultados uno a uno
-      while let Some(result) = stream.next().await {
-        match result {
-          Ok(path) => {
-            self.reporter.on_success(&path).await;
+          // Un fallo del propio archivo se anota para no reintentarlo en cada importación; uno
+          // del adaptador (p. ej. un timeout) puede no repetirse, así que no.
+          Err(e) if e.is_file_error() => {
+            let mtime = mtimes.get(path.as_path()).copied().unwrap_or_default();
+            let (failed, error) = (path.clone(), e.to_string());
+            let recorded =
+              self.repo.offload(move |repo| repo.record_extraction_failure(&failed, &error, mtime, unix_now())).await;
+            Err(match recorded {
+              Ok(()) => format!("Metadata error: {}", e),
+              Err(re) => format!("Metadata error: {} (failure not recorded: {})", e, re),
+            })
           }
-          Err((path, error_msg)) => {
+          Err(e) => Err(format!("Metadata error: {}", e)),
+        };
+
+        match persisted {
+          Ok(artists) => {
+            group_artists.extend(artists);
+            summary.succeeded += 1;
+            self.reporter.on_success(&path_str).await;
+            // Como el lote de artistas, un fallo aquí no es del archivo: no cuenta en `failed`.
+            let forget = path.clone();
+            if failures.contains_key(&path)
+              && let Err(e) = self.repo.offload(move |repo| repo.forget_extraction_failure(&forget)).await
+            {
+              self.reporter.on_error(&path_str, &format!("Repo failure cleanup error: {}", e)).await;
+            }
+          }
+          Err(error_msg) => {
             // Reportamos el error pero NO detenemos la importación
-            self.reporter.on_error(&path, &error_msg).await;
+            summary.failed += 1;
+            self.reporter.on_error(&path_str, &error_msg).await;
           }
         }
       }
+
+      // D) ARTISTAS: un único UPSERT por dispositivo en lugar de uno por pista.
+      if !group_artists.is_empty()
+        && let Err(e) = self.repo.offload(move |repo| repo.save_artists_batch(&group_artists)).await
+      {
+        self.reporter.on_group_error(&group.device.id, &format!("Repo artist batch error: {}", e)).await;
+      }
     }
 
-    // 3. FINALIZAR
-    self.reporter.finish().await;
+    // 3. FINALIZAR: el fallo del lote de artistas no es de ningún archivo, no cuenta en `failed`.
+    summary.elapsed_secs = started.elapsed().as_secs_f64();
+    self.reporter.finish(summary).await;
 
     Ok(())
   }
@@ -141,6 +563,154 @@ where
     self.repo.list_releases()
   }
 
+  /// Ver [`Library::list_tracks_page`].
+  pub fn list_tracks_page(&self, offset: i64, limit: i64) -> Result<Vec<ReleaseTrack>, CoreError> {
+    self.repo.list_tracks_page(offset, limit)
+  }
+
+  pub fn count_songs(&self) -> Result<i64, CoreError> {
+    self.repo.count_songs()
+  }
+
+  pub fn count_releases(&self) -> Result<i64, CoreError> {
+    self.repo.count_releases()
+  }
+
+  pub fn count_artists(&self) -> Result<i64, CoreError> {
+    self.repo.count_artists()
+  }
+
+  pub fn count_tracks(&self) -> Result<i64, CoreError> {
+    self.repo.count_tracks()
+  }
+
+  /// Ver [`Library::distinct_genres`].
+  pub fn distinct_genres(&self) -> Result<Vec<(Genre, usize)>, CoreError> {
+    self.repo.distinct_genres()
+  }
+
+  /// Ver [`Library::distinct_styles`].
+  pub fn distinct_styles(&self) -> Result<Vec<(Style, usize)>, CoreError> {
+    self.repo.distinct_styles()
+  }
+
+  /// Ver [`Library::browse_releases`].
+  pub fn browse_releases(
+    &self,
+    filter: ReleaseFilter,
+    sort: SortBy,
+    page: Page,
+  ) -> Result<Paged<ReleaseSummary>, CoreError> {
+    self.repo.browse_releases(filter, sort, page)
+  }
+
+  /// Ver [`Library::update_track_metadata`].
+  pub fn update_track_metadata(
+    &self,
+    id: ReleaseTrackId,
+    track_number: Option<u32>,
+    disc_number: Option<u32>,
+    title_override: Option<Option<String>>,
+  ) -> Result<(), CoreError> {
+    self.repo.update_track_metadata(id, track_number, disc_number, title_override)
+  }
+
+  /// Ver [`Library::update_song`].
+  pub fn update_song(&self, id: SongId, patch: &SongPatch) -> Result<(), CoreError> {
+    self.repo.update_song(id, patch)
+  }
+
+  /// Ver [`Library::update_release`].
+  pub fn update_release(&self, id: ReleaseId, patch: &ReleasePatch) -> Result<(), CoreError> {
+    self.repo.update_release(id, patch)
+  }
+
+  /// Ver [`Library::update_artist`].
+  pub fn update_artist(&self, id: ArtistId, patch: &ArtistPatch) -> Result<(), CoreError> {
+    self.repo.update_artist(id, patch)
+  }
+
+  pub fn list_artists_updated_after(&self, after: &SyncCursor, limit: i64) -> Result<Vec<Synced<Artist>>, CoreError> {
+    self.repo.list_artists_updated_after(after, limit)
+  }
+
+  pub fn list_songs_updated_after(&self, after: &SyncCursor, limit: i64) -> Result<Vec<Synced<Song>>, CoreError> {
+    self.repo.list_songs_updated_after(after, limit)
+  }
+
+  pub fn list_releases_updated_after(&self, after: &SyncCursor, limit: i64) -> Result<Vec<Synced<Release>>, CoreError> {
+    self.repo.list_releases_updated_after(after, limit)
+  }
+
+  pub fn list_recent_tracks(&self, limit: i64) -> Result<Vec<ReleaseTrack>, CoreError> {
+    self.repo.list_recent_tracks(limit)
+  }
+
+  pub fn list_tracks_by_codec(&self, codec: &str) -> Result<Vec<ReleaseTrack>, CoreError> {
+    self.repo.list_tracks_by_codec(codec)
+  }
+
+  /// Ver [`Library::save_track_credits`].
+  pub fn save_track_credits(
+    &self,
+    track_id: ReleaseTrackId,
+    credits: &[ReleaseTrackArtistCredit],
+  ) -> Result<(), CoreError> {
+    self.repo.save_track_credits(track_id, credits)
+  }
+
+  /// Ver [`Library::list_track_credits`].
+  pub fn list_track_credits(&self, track_id: ReleaseTrackId) -> Result<Vec<ReleaseTrackArtistCredit>, CoreError> {
+    self.repo.list_track_credits(track_id)
+  }
+
+  /// Ver [`Library::list_tracks_by_quality`].
+  pub fn list_tracks_by_quality(
+    &self,
+    min: f32,
+    max: f32,
+    limit: i64,
+    offset: i64,
+  ) -> Result<Vec<TrackQualitySummary>, CoreError> {
+    self.repo.list_tracks_by_quality(min, max, limit, offset)
+  }
+
+  /// Ver [`Library::find_similar`].
+  pub fn find_similar(&self, track_id: ReleaseTrackId, limit: usize) -> Result<Vec<(ReleaseTrackId, f32)>, CoreError> {
+    self.repo.find_similar(track_id, limit)
+  }
+
+  /// Ver [`Library::find_duplicate_files`].
+  pub fn find_duplicate_files(&self) -> Result<Vec<Vec<ReleaseTrackId>>, CoreError> {
+    self.repo.find_duplicate_files()
+  }
+
+  /// Vuelca la biblioteca como NDJSON en `writer` (ver [`export_library_json`]).
+  pub fn export_json(&self, writer: impl Write) -> Result<(), CoreError> {
+    export_library_json(&self.repo, writer)
+  }
+
+  /// Restaura un volcado de [`Self::export_json`] (ver [`import_library_json`]).
+  pub fn import_json(&self, reader: impl Read) -> Result<RestoreSummary, CoreError> {
+    import_library_json(&self.repo, reader)
+  }
+
+  pub fn list_orphan_songs(&self) -> Result<Vec<Song>, CoreError> {
+    self.repo.list_orphan_songs()
+  }
+
+  pub fn list_empty_releases(&self) -> Result<Vec<Release>, CoreError> {
+    self.repo.list_empty_releases()
+  }
+
+  pub fn list_file_states(&self) -> Result<Vec<StoredFile>, CoreError> {
+    self.repo.list_file_states()
+  }
+
+  pub fn stats(&self) -> Result<LibraryStats, CoreError> {
+    self.repo.stats()
+  }
+
   pub fn get_artist(&self, id: ArtistId) -> Result<Option<Artist>, CoreError> {
     self.repo.find_artist(id)
   }
@@ -152,4 +722,1136 @@ where
   pub fn get_release(&self, id: ReleaseId) -> Result<Option<Release>, CoreError> {
     self.repo.find_release(id)
   }
+
+  pub fn find_song_by_fingerprint(&self, fingerprint: &str) -> Result<Option<Song>, CoreError> {
+    self.repo.find_song_by_fingerprint(fingerprint)
+  }
+}
+
+/// `true` si `scanned` es nuevo o difiere de lo guardado (ver [`LibraryService::import_incremental`]).
+fn file_changed(scanned: &ScannedFile, stored: Option<&StoredFile>) -> bool {
+  let Some(stored) = stored else {
+    return true;
+  };
+
+  if let (Some(new), Some(old)) = (&scanned.content_hash, &stored.content_hash)
+    && hash_scheme(new) == hash_scheme(old)
+  {
+    return new != old;
+  }
+
+  scanned.size_bytes != stored.size_bytes || scanned.modified_unix != stored.modified_unix
+}
+
+/// Segundos UNIX actuales; un reloj anterior a 1970 cuenta como 0.
+fn unix_now() -> i64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64)
+}
+
+fn hash_scheme(hash: &str) -> &str {
+  hash.split_once(':').map_or("", |(scheme, _)| scheme)
+}
+
+/// `file` falló hace menos de `cooldown` y no ha cambiado desde el último intento.
+fn cooling_down(file: &ScannedFile, failure: Option<&ExtractionFailure>, now: i64, cooldown: Duration) -> bool {
+  failure.is_some_and(|f| {
+    f.modified_unix == file.modified_unix && now.saturating_sub(f.failed_at) < cooldown.as_secs() as i64
+  })
+}
+
+/// Duración de `extracted` si es conocida y menor que `min`.
+fn too_short(extracted: &ExtractedMetadata, min: Duration) -> Option<Duration> {
+  let duration = extracted.track.as_ref()?.audio_details.duration;
+  (!duration.is_zero() && duration < min).then_some(duration)
+}
+
+/// Motivo de [`ProgressReporter::on_skipped`] para un archivo más corto que `min`.
+fn shorter_than_minimum(duration: Duration, min: Duration) -> String {
+  format!("{:.1}s is shorter than the minimum of {:.1}s", duration.as_secs_f64(), min.as_secs_f64())
+}
+
+/// Análisis de calidad de una extracción, si el adaptador llegó a producirlo.
+fn analysis_of(extracted: ExtractedMetadata) -> Option<AudioAnalysis> {
+  extracted.track?.audio_details.analysis.filter(|a| a.quality.is_some())
+}
+
+/// Claves resueltas durante una importación, para que dos archivos de la misma canción,
+/// release o artista no creen dos entradas aunque lleguen en lotes distintos.
+#[derive(Debug, Default)]
+struct ResolvedKeys {
+  /// Por MBID, ISRC o huella.
+  songs: HashMap<SongKey, SongId>,
+  /// Por MusicBrainz Release ID.
+  releases_by_mbid: HashMap<String, ReleaseId>,
+  /// Por nombre normalizado. Los artistas se guardan en lote al final de cada grupo, así
+  /// que hasta entonces solo este mapa sabe qué id se le dio a cada nombre.
+  artists_by_name: HashMap<String, ArtistId>,
+  /// Artistas que ya están en el repositorio: encontrados por nombre o adelantados al lote
+  /// porque un release o los créditos de una pista los enlazan (ver [`persist_extracted`]).
+  stored_artists: HashSet<ArtistId>,
+}
+
+/// Persiste canción, release y pista de un archivo ya extraído.
+///
+/// Bloquea mientras dura la E/S: se llama desde [`AsyncLibrary::offload`]. Los artistas se
+/// devuelven para persistirlos en lote por grupo; solo se adelantan los que aún no estén
+/// guardados y enlacen el release (principales) o la pista (créditos).
+fn persist_extracted<R: Library>(
+  repo: &R,
+  mut extracted: ExtractedMetadata,
+  keys: &mut ResolvedKeys,
+) -> Result<Vec<Artist>, String> {
+  resolve_artists_by_name(repo, &mut keys.artists_by_name, &mut keys.stored_artists, &mut extracted)
+    .map_err(|e| format!("Repo artist lookup error: {}", e))?;
+
+  // Guardar Song (o reutilizar una existente con el mismo MBID, ISRC o huella)
+  let is_new_song =
+    resolve_song(repo, &mut keys.songs, &mut extracted).map_err(|e| format!("Repo song lookup error: {}", e))?;
+
+  let reused_release = resolve_release_by_mbid(repo, &mut keys.releases_by_mbid, &mut extracted)
+    .map_err(|e| format!("Repo release lookup error: {}", e))?;
+  if reused_release {
+    keep_stored_release_lists(repo, &mut extracted).map_err(|e| format!("Repo release lookup error: {}", e))?;
+  }
+
+  if is_new_song {
+    repo.save_song(&extracted.song).map_err(|e| format!("Repo song error: {}", e))?;
+  }
+
+  // Adelantar los artistas que el release o la pista enlazan y aún no están guardados
+  let linked: HashSet<ArtistId> = extracted
+    .release
+    .iter()
+    .flat_map(|r| r.main_artist_ids.iter().copied())
+    .chain(extracted.track.iter().flat_map(|t| t.artist_credits.iter().map(|c| c.artist_id)))
+    .collect();
+  let unsaved: Vec<Artist> = extracted
+    .artists
+    .iter()
+    .filter(|a| linked.contains(&a.id) && !keys.stored_artists.contains(&a.id))
+    .cloned()
+    .collect();
+  if !unsaved.is_empty() {
+    repo.save_artists_batch(&unsaved).map_err(|e| format!("Repo artist error: {}", e))?;
+    keys.stored_artists.extend(unsaved.iter().map(|a| a.id));
+  }
+
+  // Guardar Release (si existe)
+  if let Some(release) = &extracted.release {
+    repo.save_release(release).map_err(|e| format!("Repo release error: {}", e))?;
+  }
+
+  // Guardar Track + archivo físico
+  if let Some(track) = &extracted.track {
+    repo.save_track(track).map_err(|e| format!("Repo track error: {}", e))?;
+  }
+
+  Ok(extracted.artists)
+}
+
+/// Clave con la que se reconoce una canción ya importada.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum SongKey {
+  Mbid(String),
+  Isrc(String),
+  Fingerprint(String),
+}
+
+impl SongKey {
+  /// Claves de la canción extraída, de la más fiable a la menos: MusicBrainz Recording ID,
+  /// ISRC y por último la huella acústica.
+  fn of(extracted: &ExtractedMetadata) -> Vec<SongKey> {
+    let fingerprint = extracted
+      .song
+      .acoustid
+      .clone()
+      .or_else(|| extracted.track.as_ref().and_then(|t| t.audio_details.fingerprint.clone()));
+
+    [
+      extracted.song.mbid.clone().map(SongKey::Mbid),
+      extracted.song.isrc.clone().map(SongKey::Isrc),
+      fingerprint.map(SongKey::Fingerprint),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+  }
+
+  fn find<R: Library>(&self, repo: &R) -> Result<Option<SongId>, CoreError> {
+    let song = match self {
+      SongKey::Mbid(mbid) => repo.find_song_by_mbid(mbid)?,
+      SongKey::Isrc(isrc) => repo.find_song_by_isrc(isrc)?,
+      SongKey::Fingerprint(fingerprint) => repo.find_song_by_fingerprint(fingerprint)?,
+    };
+    Ok(song.map(|song| song.id))
+  }
+}
+
+/// Reescribe el `SongId` de `extracted` si alguna de sus claves ([`SongKey::of`]) ya
+/// pertenece a otra canción; la primera que coincide manda.
+///
+/// Devuelve `true` cuando la canción es nueva y hay que persistirla. `known` recuerda las
+/// claves vistas en esta importación antes de que el repositorio las tenga guardadas.
+fn resolve_song<R: Library>(
+  repo: &R,
+  known: &mut HashMap<SongKey, SongId>,
+  extracted: &mut ExtractedMetadata,
+) -> Result<bool, CoreError> {
+  let keys = SongKey::of(extracted);
+
+  let mut existing = None;
+  for key in &keys {
+    existing = match known.get(key) {
+      Some(id) => Some(*id),
+      None => key.find(repo)?,
+    };
+    if existing.is_some() {
+      break;
+    }
+  }
+
+  let song_id = existing.unwrap_or(extracted.song.id);
+  for key in keys {
+    known.entry(key).or_insert(song_id);
+  }
+
+  if existing.is_none() {
+    return Ok(true);
+  }
+  extracted.song.id = song_id;
+  if let Some(track) = extracted.track.as_mut() {
+    track.song_id = song_id;
+  }
+  Ok(false)
+}
+
+/// Reutiliza el `ReleaseId` de un release ya conocido con el mismo MusicBrainz Release ID,
+/// para que las pistas de un álbum acaben en un único release.
+///
+/// Reescribe el id del release y el `release_id` de la pista. `known` cubre los releases
+/// de esta importación. Devuelve `true` si el release ya estaba guardado.
+fn resolve_release_by_mbid<R: Library>(
+  repo: &R,
+  known: &mut HashMap<String, ReleaseId>,
+  extracted: &mut ExtractedMetadata,
+) -> Result<bool, CoreError> {
+  let Some(release) = extracted.release.as_mut() else {
+    return Ok(false);
+  };
+  let Some(mbid) = release.mbid.clone() else {
+    return Ok(false);
+  };
+
+  let resolved = match known.get(&mbid) {
+    Some(id) => *id,
+    None => repo.find_release_by_mbid(&mbid)?.map_or(release.id, |r| r.id),
+  };
+  known.insert(mbid, resolved);
+
+  let reused = resolved != release.id;
+  release.id = resolved;
+  if let Some(track) = extracted.track.as_mut() {
+    track.release_id = resolved;
+  }
+  Ok(reused)
+}
+
+/// `save_release` reemplaza las listas guardadas, así que a un release ya existente se le
+/// copian las que la extracción no trae: los artworks (aún no se extraen) y los artistas
+/// principales de un archivo sin album artist.
+fn keep_stored_release_lists<R: Library>(repo: &R, extracted: &mut ExtractedMetadata) -> Result<(), CoreError> {
+  let Some(release) = extracted.release.as_mut() else {
+    return Ok(());
+  };
+  let Some(stored) = repo.find_release(release.id)? else {
+    return Ok(());
+  };
+  if release.main_artist_ids.is_empty() {
+    release.main_artist_ids = stored.main_artist_ids;
+  }
+  if release.artworks.is_empty() {
+    release.artworks = stored.artworks;
+  }
+  Ok(())
+}
+
+/// Reutiliza el `ArtistId` de un artista ya conocido con el mismo nombre normalizado.
+///
+/// Reescribe los artistas extraídos, `release.main_artist_ids` y los créditos de la pista.
+/// `known` cubre los artistas de esta importación que aún no se han guardado; los que
+/// aparecen en el repositorio se apuntan en `stored`.
+fn resolve_artists_by_name<R: Library>(
+  repo: &R,
+  known: &mut HashMap<String, ArtistId>,
+  stored: &mut HashSet<ArtistId>,
+  extracted: &mut ExtractedMetadata,
+) -> Result<(), CoreError> {
+  for artist in &mut extracted.artists {
+    let key = normalize_artist_name(&artist.name);
+
+    let resolved = match known.get(&key) {
+      Some(id) => *id,
+      None => match repo.find_artist_by_name(&artist.name)? {
+        Some(existing) => {
+          stored.insert(existing.id);
+          existing.id
+        }
+        None => artist.id,
+      },
+    };
+    known.insert(key, resolved);
+
+    if resolved != artist.id {
+      let minted = std::mem::replace(&mut artist.id, resolved);
+      if let Some(release) = extracted.release.as_mut() {
+        for id in &mut release.main_artist_ids {
+          if *id == minted {
+            *id = resolved;
+          }
+        }
+      }
+      if let Some(track) = extracted.track.as_mut() {
+        for credit in &mut track.artist_credits {
+          if credit.artist_id == minted {
+            credit.artist_id = resolved;
+          }
+        }
+      }
+    }
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::path::Path;
+  use std::sync::{Arc, Mutex};
+  use std::time::Duration;
+
+  use async_trait::async_trait;
+
+  use crate::domain::ReleaseTrackId;
+  use crate::domain::artist_role::ArtistRole;
+  use crate::domain::release_track::{
+    AnalysisOutcome, AudioDetails, AudioQuality, AudioQualityReport, FileDetails, QualityLevel, ReleaseTrack,
+  };
+  use crate::ports::{MetadataError, ScanDevice, ScanError, ScanGroup, ScannedFile};
+
+  #[derive(Clone)]
+  struct FakeScanner {
+    paths: Vec<PathBuf>,
+  }
+
+  #[async_trait]
+  impl Scanner for FakeScanner {
+    async fn scan_library_files(&self) -> Result<ScanOutcome, ScanError> {
+      Ok(ScanOutcome {
+        groups: self.scan_path(Path::new("/")).await?,
+        unavailable_roots: Vec::new(),
+        permission_denied: Vec::new(),
+      })
+    }
+
+    async fn scan_library_files_with_progress(&self, on_progress: ScanProgressFn) -> Result<ScanOutcome, ScanError> {
+      for found in 1..=self.paths.len() {
+        on_progress(found);
+      }
+      self.scan_library_files().await
+    }
+
+    async fn scan_path(&self, _root: &Path) -> Result<Vec<ScanGroup>, ScanError> {
+      let files = self
+        .paths
+        .iter()
+        .map(|p| ScannedFile { path: p.clone(), size_bytes: 0, modified_unix: 0, content_hash: None })
+        .collect();
+      Ok(vec![ScanGroup { device: ScanDevice { id: "test".into(), bandwidth_mb_s: None }, files }])
+    }
+  }
+
+  /// Cada archivo produce una canción y una pista nuevas, todas con la misma huella.
+  #[derive(Clone)]
+  struct SameFingerprintProbe;
+
+  #[async_trait]
+  impl Probe for SameFingerprintProbe {
+    async fn extract_from_path(&self, path: &Path) -> Result<ExtractedMetadata, MetadataError> {
+      let song = Song {
+        id: SongId::new(),
+        acoustid: Some("AQAA-same-recording".into()),
+        title: "Song".into(),
+        lyrics: None,
+        comments: Vec::new(),
+        isrc: None,
+        mbid: None,
+      };
+      let track = ReleaseTrack {
+        id: ReleaseTrackId::new(),
+        song_id: song.id,
+        release_id: ReleaseId::new(),
+        track_number: 1,
+        disc_number: 1,
+        title_override: None,
+        artist_credits: Vec::new(),
+        audio_details: AudioDetails {
+          duration: Duration::from_secs(180),
+          bitrate_kbps: None,
+          sample_rate_hz: None,
+          channels: None,
+          analysis: None,
+          fingerprint: song.acoustid.clone(),
+          codec: None,
+          container: None,
+          is_lossless: None,
+          replaygain_track_db: None,
+          replaygain_album_db: None,
+          replaygain_track_peak: None,
+          replaygain_album_peak: None,
+        },
+        file_details: FileDetails { path: path.to_path_buf(), size: 0, modified: 0, content_hash: None },
+      };
+      Ok(ExtractedMetadata { song, release: None, track: Some(track), artists: Vec::new(), raw_tags: HashMap::new() })
+    }
+  }
+
+  /// Como [`SameFingerprintProbe`], pero los archivos `sfx*` duran un segundo. Guarda la
+  /// última duración mínima que le pasó el servicio.
+  #[derive(Clone, Default)]
+  struct ShortClipProbe {
+    min_duration: Arc<Mutex<Option<Duration>>>,
+  }
+
+  #[async_trait]
+  impl Probe for ShortClipProbe {
+    async fn extract_from_path(&self, path: &Path) -> Result<ExtractedMetadata, MetadataError> {
+      let mut extracted = SameFingerprintProbe.extract_from_path(path).await?;
+      if let Some(track) = &mut extracted.track
+        && path.file_name().is_some_and(|n| n.to_string_lossy().starts_with("sfx"))
+      {
+        track.audio_details.duration = Duration::from_secs(1);
+      }
+      Ok(extracted)
+    }
+
+    fn set_min_duration(&self, min: Option<Duration>) {
+      *self.min_duration.lock().unwrap() = min;
+    }
+  }
+
+  /// Como [`SameFingerprintProbe`], pero los archivos `drm*` no se pueden leer y los
+  /// `slow*` agotan su plazo.
+  #[derive(Clone)]
+  struct ProtectedFileProbe;
+
+  #[async_trait]
+  impl Probe for ProtectedFileProbe {
+    async fn extract_from_path(&self, path: &Path) -> Result<ExtractedMetadata, MetadataError> {
+      let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+      if name.starts_with("drm") {
+        return Err(MetadataError::Unsupported("encrypted stream".into()));
+      }
+      if name.starts_with("slow") {
+        return Err(MetadataError::Internal("extraction timeout".into()));
+      }
+      SameFingerprintProbe.extract_from_path(path).await
+    }
+  }
+
+  /// Como [`SameFingerprintProbe`], pero la extracción completa trae análisis de calidad.
+  #[derive(Clone)]
+  struct AnalyzingProbe;
+
+  #[async_trait]
+  impl Probe for AnalyzingProbe {
+    async fn extract_from_path(&self, path: &Path) -> Result<ExtractedMetadata, MetadataError> {
+      let mut extracted = SameFingerprintProbe.extract_from_path(path).await?;
+      let quality = AudioQuality {
+        outcome: AnalysisOutcome::NoCutoffDetected { ref_db: -20.0, max_freq: 22_000.0 },
+        quality_score: 9.5,
+        assessment: "full band".into(),
+        report: AudioQualityReport {
+          level: QualityLevel::High,
+          score: 9.5,
+          label: "High".into(),
+          summary: "full band".into(),
+          details: None,
+          cutoff_freq_hz: None,
+          max_freq_hz: Some(22_000.0),
+          stereo_correlation: None,
+        },
+      };
+      if let Some(track) = &mut extracted.track {
+        track.audio_details.analysis = Some(AudioAnalysis { quality: Some(quality), features: None, bpm: Some(120.0) });
+      }
+      Ok(extracted)
+    }
+  }
+
+  /// Como [`SameFingerprintProbe`], pero cada archivo tiene su propia huella y todos comparten
+  /// MusicBrainz Recording y Release ID.
+  #[derive(Clone)]
+  struct SameMbidProbe;
+
+  #[async_trait]
+  impl Probe for SameMbidProbe {
+    async fn extract_from_path(&self, path: &Path) -> Result<ExtractedMetadata, MetadataError> {
+      let mut extracted = SameFingerprintProbe.extract_from_path(path).await?;
+      let fingerprint = Some(format!("AQAA-{}", path.display()));
+      extracted.song.acoustid = fingerprint.clone();
+      extracted.song.mbid = Some("b1a9c0e9-d987-4042-ae91-78d6a3267d69".into());
+      let release = Release {
+        id: ReleaseId::new(),
+        title: "Album".into(),
+        release_type: Vec::new(),
+        main_artist_ids: Vec::new(),
+        release_tracks: Vec::new(),
+        release_date: None,
+        artworks: Vec::new(),
+        genres: Vec::new(),
+        styles: Vec::new(),
+        mbid: Some("f4b3b7a4-6f1c-4f7a-9c55-4a1f43b9ed2a".into()),
+      };
+      if let Some(track) = &mut extracted.track {
+        track.audio_details.fingerprint = fingerprint;
+        track.release_id = release.id;
+      }
+      extracted.release = Some(release);
+      Ok(extracted)
+    }
+  }
+
+  /// Como [`SameMbidProbe`], pero cada archivo acuña su propio artista de álbum, escrito
+  /// con otras mayúsculas en los `.mp3`.
+  #[derive(Clone)]
+  struct AlbumArtistProbe;
+
+  #[async_trait]
+  impl Probe for AlbumArtistProbe {
+    async fn extract_from_path(&self, path: &Path) -> Result<ExtractedMetadata, MetadataError> {
+      let mut extracted = SameMbidProbe.extract_from_path(path).await?;
+      let name = if path.extension().is_some_and(|e| e == "mp3") { "boards of canada" } else { "Boards of Canada" };
+      let artist = Artist { id: ArtistId::new(), name: name.into(), variations: vec![], bio: None, sites: vec![] };
+      if let Some(release) = &mut extracted.release {
+        release.main_artist_ids = vec![artist.id];
+      }
+      extracted.artists = vec![artist];
+      Ok(extracted)
+    }
+  }
+
+  /// Como [`SameFingerprintProbe`], con un artista invitado que solo aparece en los créditos
+  /// de la pista.
+  #[derive(Clone)]
+  struct FeaturedArtistProbe;
+
+  #[async_trait]
+  impl Probe for FeaturedArtistProbe {
+    async fn extract_from_path(&self, path: &Path) -> Result<ExtractedMetadata, MetadataError> {
+      let mut extracted = SameFingerprintProbe.extract_from_path(path).await?;
+      let artist =
+        Artist { id: ArtistId::new(), name: "Broadcast".into(), variations: vec![], bio: None, sites: vec![] };
+      if let Some(track) = &mut extracted.track {
+        track.artist_credits = vec![ReleaseTrackArtistCredit {
+          release_track_id: track.id,
+          artist_id: artist.id,
+          role: ArtistRole::Featured,
+          position: None,
+        }];
+      }
+      extracted.artists = vec![artist];
+      Ok(extracted)
+    }
+  }
+
+  #[derive(Clone, Default)]
+  struct MemoryLibrary {
+    artists: Arc<Mutex<Vec<Artist>>>,
+    songs: Arc<Mutex<Vec<Song>>>,
+    tracks: Arc<Mutex<Vec<ReleaseTrack>>>,
+    checkpoint: Arc<Mutex<Option<ImportCheckpoint>>>,
+    failures: Arc<Mutex<HashMap<PathBuf, ExtractionFailure>>>,
+  }
+
+  #[async_trait]
+  impl AsyncLibrary for MemoryLibrary {
+    // Sin E/S: el trabajo corre en el mismo hilo.
+    async fn offload<T, F>(&self, work: F) -> Result<T, CoreError>
+    where
+      F: FnOnce(&Self) -> Result<T, CoreError> + Send + 'static,
+      T: Send + 'static,
+    {
+      work(self)
+    }
+  }
+
+  impl Library for MemoryLibrary {
+    fn save_artist(&self, _: &Artist) -> Result<(), CoreError> {
+      Ok(())
+    }
+    fn save_artists_batch(&self, artists: &[Artist]) -> Result<(), CoreError> {
+      self.artists.lock().unwrap().extend_from_slice(artists);
+      Ok(())
+    }
+    fn save_song(&self, song: &Song) -> Result<(), CoreError> {
+      self.songs.lock().unwrap().push(song.clone());
+      Ok(())
+    }
+    fn save_release(&self, _: &Release) -> Result<(), CoreError> {
+      Ok(())
+    }
+    fn save_track(&self, track: &ReleaseTrack) -> Result<(), CoreError> {
+      // Como la clave foránea de `release_track_artists`: el artista acreditado ya debe existir.
+      let artists = self.artists.lock().unwrap();
+      if let Some(credit) = track.artist_credits.iter().find(|c| !artists.iter().any(|a| a.id == c.artist_id)) {
+        return Err(CoreError::Repository(format!("credited artist {} is not stored", credit.artist_id)));
+      }
+      let mut tracks = self.tracks.lock().unwrap();
+      match tracks.iter_mut().find(|t| t.file_details.path == track.file_details.path) {
+        Some(stored) => *stored = ReleaseTrack { id: stored.id, ..track.clone() },
+        None => tracks.push(track.clone()),
+      }
+      Ok(())
+    }
+    fn update_track_analysis(&self, track_id: ReleaseTrackId, analysis: &AudioAnalysis) -> Result<(), CoreError> {
+      let mut tracks = self.tracks.lock().unwrap();
+      for track in tracks.iter_mut().filter(|t| t.id == track_id) {
+        track.audio_details.analysis = Some(analysis.clone());
+      }
+      Ok(())
+    }
+    fn save_import_checkpoint(&self, checkpoint: &ImportCheckpoint) -> Result<(), CoreError> {
+      *self.checkpoint.lock().unwrap() = Some(*checkpoint);
+      Ok(())
+    }
+    fn clear_import_checkpoint(&self) -> Result<(), CoreError> {
+      *self.checkpoint.lock().unwrap() = None;
+      Ok(())
+    }
+    fn record_extraction_failure(
+      &self,
+      path: &Path,
+      error: &str,
+      modified_unix: u64,
+      failed_at: i64,
+    ) -> Result<(), CoreError> {
+      let mut failures = self.failures.lock().unwrap();
+      let attempts = failures.get(path).map_or(0, |f| f.attempts) + 1;
+      let failure =
+        ExtractionFailure { path: path.to_path_buf(), error: error.to_string(), failed_at, attempts, modified_unix };
+      failures.insert(path.to_path_buf(), failure);
+      Ok(())
+    }
+    fn forget_extraction_failure(&self, path: &Path) -> Result<(), CoreError> {
+      self.failures.lock().unwrap().remove(path);
+      Ok(())
+    }
+    fn clear_extraction_failures(&self) -> Result<usize, CoreError> {
+      Ok(self.failures.lock().unwrap().drain().count())
+    }
+    fn set_artwork_thumbnail(&self, _: &str, _: &Path) -> Result<usize, CoreError> {
+      Ok(0)
+    }
+    fn update_track_metadata(
+      &self,
+      id: ReleaseTrackId,
+      track_number: Option<u32>,
+      disc_number: Option<u32>,
+      title_override: Option<Option<String>>,
+    ) -> Result<(), CoreError> {
+      let mut tracks = self.tracks.lock().unwrap();
+      let track = tracks.iter_mut().find(|t| t.id == id).ok_or(CoreError::NotFound)?;
+      if let Some(n) = track_number {
+        track.track_number = n;
+      }
+      if let Some(n) = disc_number {
+        track.disc_number = n;
+      }
+      if let Some(title) = title_override {
+        track.title_override = title;
+      }
+      Ok(())
+    }
+    fn update_song(&self, id: SongId, patch: &SongPatch) -> Result<(), CoreError> {
+      let mut songs = self.songs.lock().unwrap();
+      let song = songs.iter_mut().find(|s| s.id == id).ok_or(CoreError::NotFound)?;
+      if let Some(title) = &patch.title {
+        song.title = title.clone();
+      }
+      if let Some(acoustid) = &patch.acoustid {
+        song.acoustid = acoustid.clone();
+      }
+      if let Some(isrc) = &patch.isrc {
+        song.isrc = isrc.clone();
+      }
+      if let Some(mbid) = &patch.mbid {
+        song.mbid = mbid.clone();
+      }
+      Ok(())
+    }
+    fn update_release(&self, _: ReleaseId, _: &ReleasePatch) -> Result<(), CoreError> {
+      Err(CoreError::NotFound)
+    }
+    fn update_artist(&self, _: ArtistId, _: &ArtistPatch) -> Result<(), CoreError> {
+      Err(CoreError::NotFound)
+    }
+    fn find_artist(&self, _: ArtistId) -> Result<Option<Artist>, CoreError> {
+      Ok(None)
+    }
+    fn find_artist_by_name(&self, _: &str) -> Result<Option<Artist>, CoreError> {
+      Ok(None)
+    }
+    fn find_song(&self, id: SongId) -> Result<Option<Song>, CoreError> {
+      Ok(self.songs.lock().unwrap().iter().find(|s| s.id == id).cloned())
+    }
+    fn find_release(&self, _: ReleaseId) -> Result<Option<Release>, CoreError> {
+      Ok(None)
+    }
+    fn find_track(&self, id: ReleaseTrackId) -> Result<Option<ReleaseTrack>, CoreError> {
+      Ok(self.tracks.lock().unwrap().iter().find(|t| t.id == id).cloned())
+    }
+    fn find_song_by_fingerprint(&self, fingerprint: &str) -> Result<Option<Song>, CoreError> {
+      Ok(self.songs.lock().unwrap().iter().find(|s| s.acoustid.as_deref() == Some(fingerprint)).cloned())
+    }
+    fn find_song_by_mbid(&self, mbid: &str) -> Result<Option<Song>, CoreError> {
+      Ok(self.songs.lock().unwrap().iter().find(|s| s.mbid.as_deref() == Some(mbid)).cloned())
+    }
+    fn find_song_by_isrc(&self, isrc: &str) -> Result<Option<Song>, CoreError> {
+      Ok(self.songs.lock().unwrap().iter().find(|s| s.isrc.as_deref() == Some(isrc)).cloned())
+    }
+    fn find_release_by_mbid(&self, _: &str) -> Result<Option<Release>, CoreError> {
+      Ok(None)
+    }
+    fn release_thumbnail(&self, _: ReleaseId) -> Result<Option<PathBuf>, CoreError> {
+      Ok(None)
+    }
+    fn list_artists(&self) -> Result<Vec<Artist>, CoreError> {
+      Ok(Vec::new())
+    }
+    fn list_songs(&self) -> Result<Vec<Song>, CoreError> {
+      Ok(self.songs.lock().unwrap().clone())
+    }
+    fn list_releases(&self) -> Result<Vec<Release>, CoreError> {
+      Ok(Vec::new())
+    }
+    fn list_artists_updated_after(&self, _: &SyncCursor, _: i64) -> Result<Vec<Synced<Artist>>, CoreError> {
+      Ok(Vec::new())
+    }
+    fn list_songs_updated_after(&self, _: &SyncCursor, _: i64) -> Result<Vec<Synced<Song>>, CoreError> {
+      Ok(Vec::new())
+    }
+    fn list_releases_updated_after(&self, _: &SyncCursor, _: i64) -> Result<Vec<Synced<Release>>, CoreError> {
+      Ok(Vec::new())
+    }
+    fn list_recent_tracks(&self, limit: i64) -> Result<Vec<ReleaseTrack>, CoreError> {
+      Ok(self.tracks.lock().unwrap().iter().rev().take(limit.max(0) as usize).cloned().collect())
+    }
+    fn list_tracks_by_codec(&self, codec: &str) -> Result<Vec<ReleaseTrack>, CoreError> {
+      let tracks = self.tracks.lock().unwrap();
+      Ok(tracks.iter().filter(|t| t.audio_details.codec.as_deref() == Some(codec)).cloned().collect())
+    }
+    fn save_track_credits(&self, _: ReleaseTrackId, _: &[ReleaseTrackArtistCredit]) -> Result<(), CoreError> {
+      Ok(())
+    }
+    fn list_track_credits(&self, _: ReleaseTrackId) -> Result<Vec<ReleaseTrackArtistCredit>, CoreError> {
+      Ok(Vec::new())
+    }
+    fn list_tracks_page(&self, offset: i64, limit: i64) -> Result<Vec<ReleaseTrack>, CoreError> {
+      let tracks = self.tracks.lock().unwrap();
+      Ok(tracks.iter().skip(offset.max(0) as usize).take(limit.max(0) as usize).cloned().collect())
+    }
+    fn list_tracks_by_quality(&self, _: f32, _: f32, _: i64, _: i64) -> Result<Vec<TrackQualitySummary>, CoreError> {
+      Ok(Vec::new())
+    }
+    fn list_orphan_songs(&self) -> Result<Vec<Song>, CoreError> {
+      let tracks = self.tracks.lock().unwrap();
+      let songs = self.songs.lock().unwrap();
+      Ok(songs.iter().filter(|s| !tracks.iter().any(|t| t.song_id == s.id)).cloned().collect())
+    }
+    fn list_empty_releases(&self) -> Result<Vec<Release>, CoreError> {
+      Ok(Vec::new())
+    }
+    fn list_tracks_pending_analysis(&self) -> Result<Vec<ReleaseTrack>, CoreError> {
+      let tracks = self.tracks.lock().unwrap();
+      Ok(tracks.iter().filter(|t| analysis_of_track(t).is_none()).cloned().collect())
+    }
+    fn list_file_states(&self) -> Result<Vec<StoredFile>, CoreError> {
+      let tracks = self.tracks.lock().unwrap();
+      Ok(
+        tracks
+          .iter()
+          .map(|t| StoredFile {
+            path: t.file_details.path.clone(),
+            size_bytes: t.file_details.size,
+            modified_unix: t.file_details.modified,
+            content_hash: t.file_details.content_hash.clone(),
+          })
+          .collect(),
+      )
+    }
+    fn load_import_checkpoint(&self) -> Result<Option<ImportCheckpoint>, CoreError> {
+      Ok(*self.checkpoint.lock().unwrap())
+    }
+    /// Sin fechas de guardado: cualquier archivo guardado cuenta como reciente.
+    fn list_paths_saved_since(&self, _: i64) -> Result<Vec<PathBuf>, CoreError> {
+      Ok(self.tracks.lock().unwrap().iter().map(|t| t.file_details.path.clone()).collect())
+    }
+    fn list_extraction_failures(&self) -> Result<Vec<ExtractionFailure>, CoreError> {
+      Ok(self.failures.lock().unwrap().values().cloned().collect())
+    }
+    fn list_artworks(&self) -> Result<Vec<Artwork>, CoreError> {
+      Ok(Vec::new())
+    }
+    fn find_similar(&self, _: ReleaseTrackId, _: usize) -> Result<Vec<(ReleaseTrackId, f32)>, CoreError> {
+      Ok(Vec::new())
+    }
+    fn find_duplicate_files(&self) -> Result<Vec<Vec<ReleaseTrackId>>, CoreError> {
+      Ok(Vec::new())
+    }
+    fn stats(&self) -> Result<LibraryStats, CoreError> {
+      Ok(LibraryStats::default())
+    }
+    fn count_songs(&self) -> Result<i64, CoreError> {
+      Ok(self.songs.lock().unwrap().len() as i64)
+    }
+    fn count_releases(&self) -> Result<i64, CoreError> {
+      Ok(0)
+    }
+    fn count_artists(&self) -> Result<i64, CoreError> {
+      Ok(0)
+    }
+    fn count_tracks(&self) -> Result<i64, CoreError> {
+      Ok(self.tracks.lock().unwrap().len() as i64)
+    }
+    fn distinct_genres(&self) -> Result<Vec<(Genre, usize)>, CoreError> {
+      Ok(Vec::new())
+    }
+    fn distinct_styles(&self) -> Result<Vec<(Style, usize)>, CoreError> {
+      Ok(Vec::new())
+    }
+    fn browse_releases(&self, _: ReleaseFilter, _: SortBy, page: Page) -> Result<Paged<ReleaseSummary>, CoreError> {
+      Ok(Paged { items: Vec::new(), total: 0, page })
+    }
+  }
+
+  #[derive(Clone)]
+  struct SilentReporter;
+
+  #[async_trait]
+  impl ProgressReporter for SilentReporter {
+    async fn start(&self, _: usize) {}
+    async fn on_success(&self, _: &str) {}
+    async fn on_error(&self, path: &str, error: &str) {
+      panic!("unexpected import error for {path}: {error}");
+    }
+  }
+
+  /// Registra los eventos de la fase de escaneo, el `start` de la importación y su resumen final.
+  #[derive(Clone, Default)]
+  struct ScanEventsReporter {
+    events: Arc<Mutex<Vec<String>>>,
+    summary: Arc<Mutex<Option<ImportSummary>>>,
+  }
+
+  #[async_trait]
+  impl ProgressReporter for ScanEventsReporter {
+    async fn start(&self, total: usize) {
+      self.events.lock().unwrap().push(format!("start:{total}"));
+    }
+    async fn on_success(&self, _: &str) {}
+    async fn on_error(&self, _: &str, _: &str) {}
+    async fn finish(&self, summary: ImportSummary) {
+      *self.summary.lock().unwrap() = Some(summary);
+    }
+    async fn scan_started(&self) {
+      self.events.lock().unwrap().push("scan_started".into());
+    }
+    async fn on_scan_progress(&self, found: usize) {
+      self.events.lock().unwrap().push(format!("scan_progress:{found}"));
+    }
+    async fn scan_finished(&self, found: usize) {
+      self.events.lock().unwrap().push(format!("scan_finished:{found}"));
+    }
+  }
+
+  #[test]
+  fn scan_phase_is_reported_before_import_starts() {
+    let scanner = FakeScanner { paths: vec![PathBuf::from("/music/a.flac"), PathBuf::from("/music/a.mp3")] };
+    let reporter = ScanEventsReporter::default();
+    let service = LibraryService::new(scanner, SameFingerprintProbe, MemoryLibrary::default(), reporter.clone());
+
+    futures::executor::block_on(service.import_full()).unwrap();
+
+    let events = reporter.events.lock().unwrap();
+    assert_eq!(events.first().map(String::as_str), Some("scan_started"));
+    let finished = events.iter().position(|e| e == "scan_finished:2").expect("scan_finished missing");
+    let started = events.iter().position(|e| e == "start:2").expect("start missing");
+    assert!(finished < started);
+    // Intermediate counts may be coalesced, but the last one reported is the final total.
+    assert_eq!(events[finished - 1], "scan_progress:2");
+  }
+
+  #[test]
+  fn finish_reports_the_import_totals() {
+    let scanner = FakeScanner { paths: vec![PathBuf::from("/music/a.flac"), PathBuf::from("/music/a.mp3")] };
+    let reporter = ScanEventsReporter::default();
+    let service = LibraryService::new(scanner, SameFingerprintProbe, MemoryLibrary::default(), reporter.clone());
+
+    futures::executor::block_on(service.import_full()).unwrap();
+
+    let summary = reporter.summary.lock().unwrap().expect("finish not called");
+    assert_eq!((summary.total, summary.succeeded, summary.failed, summary.skipped), (2, 2, 0, 0));
+    assert!(summary.elapsed_secs >= 0.0);
+  }
+
+  #[test]
+  fn files_below_the_minimum_duration_are_skipped() {
+    let scanner = FakeScanner { paths: vec![PathBuf::from("/music/a.flac"), PathBuf::from("/music/sfx_ding.wav")] };
+    let repo = MemoryLibrary::default();
+    let reporter = ScanEventsReporter::default();
+    let probe = ShortClipProbe::default();
+    let service =
+      LibraryService::new(scanner, probe.clone(), repo.clone(), reporter.clone()).with_min_duration_secs(Some(10.0));
+    assert_eq!(*probe.min_duration.lock().unwrap(), Some(Duration::from_secs(10)), "the probe gets the hint");
+
+    futures::executor::block_on(service.import_full()).unwrap();
+
+    let summary = reporter.summary.lock().unwrap().expect("finish not called");
+    assert_eq!((summary.total, summary.succeeded, summary.failed, summary.skipped), (2, 1, 0, 1));
+    let tracks = repo.tracks.lock().unwrap();
+    assert_eq!(tracks.len(), 1);
+    assert_eq!(tracks[0].file_details.path, PathBuf::from("/music/a.flac"));
+    drop(tracks);
+
+    // Quitar el mínimo en caliente vale para la siguiente importación.
+    service.set_min_duration_secs(None);
+    assert_eq!(*probe.min_duration.lock().unwrap(), None);
+    futures::executor::block_on(service.import_full()).unwrap();
+    let summary = reporter.summary.lock().unwrap().expect("finish not called");
+    assert_eq!((summary.succeeded, summary.skipped), (2, 0));
+  }
+
+  #[test]
+  fn a_failing_file_is_skipped_within_the_cooldown() {
+    let scanner = FakeScanner {
+      paths: vec![PathBuf::from("/music/a.flac"), PathBuf::from("/music/drm.m4p"), PathBuf::from("/music/slow.flac")],
+    };
+    let repo = MemoryLibrary::default();
+    let reporter = ScanEventsReporter::default();
+    let service = LibraryService::new(scanner, ProtectedFileProbe, repo.clone(), reporter.clone())
+      .with_failure_cooldown_secs(3_600);
+    let summary = || {
+      let summary = reporter.summary.lock().unwrap().expect("finish not called");
+      (summary.total, summary.succeeded, summary.failed, summary.skipped)
+    };
+
+    futures::executor::block_on(service.import_full()).unwrap();
+    assert_eq!(summary(), (3, 1, 2, 0));
+
+    // Segunda pasada dentro del plazo: el archivo roto ni se intenta; el del timeout, que no
+    // es culpa del archivo, sí.
+    futures::executor::block_on(service.import_full()).unwrap();
+    assert_eq!(summary(), (2, 1, 1, 1));
+    let failures = service.list_extraction_failures().unwrap();
+    assert_eq!(failures.len(), 1);
+    assert_eq!((failures[0].path.as_path(), failures[0].attempts), (Path::new("/music/drm.m4p"), 1));
+
+    // Tras olvidar los fallos se vuelve a intentar.
+    assert_eq!(service.clear_extraction_failures().unwrap(), 1);
+    futures::executor::block_on(service.import_full()).unwrap();
+    assert_eq!(summary(), (3, 1, 2, 0));
+  }
+
+  #[test]
+  fn same_fingerprint_collapses_into_one_song_with_two_tracks() {
+    let scanner = FakeScanner { paths: vec![PathBuf::from("/music/a.flac"), PathBuf::from("/music/a.mp3")] };
+    let repo = MemoryLibrary::default();
+    let service = LibraryService::new(scanner, SameFingerprintProbe, repo.clone(), SilentReporter);
+
+    futures::executor::block_on(service.import_full()).unwrap();
+
+    let songs = repo.songs.lock().unwrap();
+    let tracks = repo.tracks.lock().unwrap();
+    assert_eq!(songs.len(), 1);
+    assert_eq!(tracks.len(), 2);
+    assert!(tracks.iter().all(|t| t.song_id == songs[0].id));
+  }
+
+  #[test]
+  fn same_mbid_wins_over_different_fingerprints() {
+    let scanner = FakeScanner { paths: vec![PathBuf::from("/music/a.flac"), PathBuf::from("/music/a.mp3")] };
+    let repo = MemoryLibrary::default();
+    let service = LibraryService::new(scanner, SameMbidProbe, repo.clone(), SilentReporter);
+
+    futures::executor::block_on(service.import_full()).unwrap();
+
+    let songs = repo.songs.lock().unwrap();
+    let tracks = repo.tracks.lock().unwrap();
+    assert_eq!(songs.len(), 1);
+    assert!(tracks.iter().all(|t| t.song_id == songs[0].id));
+    assert_eq!(tracks[0].release_id, tracks[1].release_id);
+  }
+
+  #[test]
+  fn album_artists_sharing_a_name_are_batched_under_one_id() {
+    let scanner = FakeScanner { paths: vec![PathBuf::from("/music/a.flac"), PathBuf::from("/music/a.mp3")] };
+    let repo = MemoryLibrary::default();
+    let service = LibraryService::new(scanner, AlbumArtistProbe, repo.clone(), SilentReporter);
+
+    futures::executor::block_on(service.import_full()).unwrap();
+
+    // El primer archivo adelanta al artista porque su release lo enlaza; el segundo ya lo da
+    // por guardado. El lote del grupo lo repite dos veces: el id lo deduplica el repositorio.
+    let artists = repo.artists.lock().unwrap();
+    assert_eq!(artists.len(), 3);
+    assert!(artists.iter().all(|a| a.id == artists[0].id));
+  }
+
+  #[test]
+  fn credited_artists_are_saved_before_their_track() {
+    let reporter = ScanEventsReporter::default();
+    let scanner = FakeScanner { paths: vec![PathBuf::from("/music/a.flac")] };
+    let repo = MemoryLibrary::default();
+    let service = LibraryService::new(scanner, FeaturedArtistProbe, repo.clone(), reporter.clone());
+
+    futures::executor::block_on(service.import_full()).unwrap();
+
+    let summary = reporter.summary.lock().unwrap().expect("finish not called");
+    assert_eq!((summary.succeeded, summary.failed), (1, 0));
+    let tracks = repo.tracks.lock().unwrap();
+    assert_eq!(tracks[0].artist_credits.len(), 1);
+    assert_eq!(repo.artists.lock().unwrap()[0].id, tracks[0].artist_credits[0].artist_id);
+  }
+
+  #[test]
+  fn resumable_import_skips_files_saved_before_the_interruption() {
+    let a = PathBuf::from("/music/a.flac");
+    let repo = MemoryLibrary::default();
+    // Importación interrumpida tras guardar `a`.
+    repo.save_import_checkpoint(&ImportCheckpoint { started_at: 0 }).unwrap();
+    let first =
+      LibraryService::new(FakeScanner { paths: vec![a.clone()] }, SameFingerprintProbe, repo.clone(), SilentReporter);
+    futures::executor::block_on(first.import_full()).unwrap();
+
+    let reporter = ScanEventsReporter::default();
+    let scanner = FakeScanner { paths: vec![a, PathBuf::from("/music/b.flac")] };
+    let service = LibraryService::new(scanner, SameFingerprintProbe, repo.clone(), reporter.clone());
+    assert_eq!(service.pending_import().unwrap(), Some(ImportCheckpoint { started_at: 0 }));
+
+    futures::executor::block_on(service.import_full_resumable()).unwrap();
+
+    let summary = reporter.summary.lock().unwrap().expect("finish not called");
+    assert_eq!((summary.total, summary.succeeded, summary.skipped), (1, 1, 1));
+    assert_eq!(repo.tracks.lock().unwrap().len(), 2);
+    assert_eq!(service.pending_import().unwrap(), None);
+  }
+
+  #[test]
+  fn content_hash_overrides_mtime_only_when_schemes_match() {
+    let scanned = |modified_unix, hash: Option<&str>| ScannedFile {
+      path: PathBuf::from("/music/a.flac"),
+      size_bytes: 100,
+      modified_unix,
+      content_hash: hash.map(str::to_string),
+    };
+    let stored = StoredFile {
+      path: PathBuf::from("/music/a.flac"),
+      size_bytes: 100,
+      modified_unix: 1,
+      content_hash: Some("xxh3p64:aaaa".into()),
+    };
+
+    // Same content, touched mtime: not a change.
+    assert!(!file_changed(&scanned(2, Some("xxh3p64:aaaa")), Some(&stored)));
+    // Edited in place, mtime preserved: a change.
+    assert!(file_changed(&scanned(1, Some("xxh3p64:bbbb")), Some(&stored)));
+    // Different scheme (mode switched): fall back to size + mtime.
+    assert!(!file_changed(&scanned(1, Some("xxh3f:cccc")), Some(&stored)));
+    assert!(file_changed(&scanned(2, None), Some(&stored)));
+    assert!(file_changed(&scanned(1, None), None));
+  }
+
+  #[test]
+  fn export_writes_one_json_line_per_entity() {
+    let scanner = FakeScanner { paths: vec![PathBuf::from("/music/a.flac"), PathBuf::from("/music/a.mp3")] };
+    let repo = MemoryLibrary::default();
+    let service = LibraryService::new(scanner, SameFingerprintProbe, repo, SilentReporter);
+    futures::executor::block_on(service.import_full()).unwrap();
+
+    let mut out = Vec::new();
+    service.export_json(&mut out).unwrap();
+
+    let kinds: Vec<String> = String::from_utf8(out)
+      .unwrap()
+      .lines()
+      .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["kind"].as_str().unwrap().to_string())
+      .collect();
+    assert_eq!(kinds, ["song", "track", "track"]);
+  }
+
+  #[test]
+  fn import_restores_export_and_counts_malformed_lines() {
+    let scanner = FakeScanner { paths: vec![PathBuf::from("/music/a.flac"), PathBuf::from("/music/a.mp3")] };
+    let source = LibraryService::new(scanner, SameFingerprintProbe, MemoryLibrary::default(), SilentReporter);
+    futures::executor::block_on(source.import_full()).unwrap();
+
+    let mut dump = Vec::new();
+    source.export_json(&mut dump).unwrap();
+    dump.extend_from_slice(b"{not json}\n\n");
+
+    let target = LibraryService::new(
+      FakeScanner { paths: Vec::new() },
+      SameFingerprintProbe,
+      MemoryLibrary::default(),
+      SilentReporter,
+    );
+    let summary = target.import_json(dump.as_slice()).unwrap();
+
+    assert_eq!(summary.songs, 1);
+    assert_eq!(summary.tracks, 2);
+    assert_eq!(summary.failed, 1);
+    assert_eq!(summary.restored(), 3);
+    assert_eq!(target.repo.list_songs().unwrap(), source.repo.list_songs().unwrap());
+  }
+
+  fn analysis_of_track(track: &ReleaseTrack) -> Option<&AudioQuality> {
+    track.audio_details.analysis.as_ref()?.quality.as_ref()
+  }
+
+  #[test]
+  fn analyze_pending_fills_in_quality_after_a_tags_only_import() {
+    let scanner = FakeScanner { paths: vec![PathBuf::from("/music/a.flac"), PathBuf::from("/music/a.mp3")] };
+    let repo = MemoryLibrary::default();
+    let quick = LibraryService::new(scanner.clone(), SameFingerprintProbe, repo.clone(), SilentReporter);
+    futures::executor::block_on(quick.import_full()).unwrap();
+    assert_eq!(repo.list_tracks_pending_analysis().unwrap().len(), 2);
+
+    let analyzer = LibraryService::new(scanner, AnalyzingProbe, repo.clone(), SilentReporter);
+    futures::executor::block_on(analyzer.analyze_pending()).unwrap();
+
+    assert!(repo.list_tracks_pending_analysis().unwrap().is_empty());
+    let tracks = repo.tracks.lock().unwrap();
+    assert_eq!(tracks.len(), 2, "analysis must not add tracks");
+    assert!(tracks.iter().all(|t| t.audio_details.analysis.as_ref().and_then(|a| a.bpm) == Some(120.0)));
+  }
+
+  #[test]
+  fn reanalyze_track_updates_one_track_and_rejects_missing_files() {
+    // Los probes de prueba no leen el archivo, pero el servicio comprueba que exista.
+    let on_disk = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    let scanner = FakeScanner { paths: vec![on_disk.clone(), PathBuf::from("/music/gone.flac")] };
+    let repo = MemoryLibrary::default();
+    let service = LibraryService::new(scanner, SameFingerprintProbe, repo.clone(), SilentReporter);
+    futures::executor::block_on(service.import_full()).unwrap();
+    let id_of = |path: &Path| repo.tracks.lock().unwrap().iter().find(|t| t.file_details.path == path).unwrap().id;
+
+    let quality = futures::executor::block_on(service.reanalyze_track(id_of(&on_disk), &AnalyzingProbe)).unwrap();
+    assert_eq!(quality.report.level, QualityLevel::High);
+    assert_eq!(repo.list_tracks_pending_analysis().unwrap().len(), 1);
+
+    let missing =
+      futures::executor::block_on(service.reanalyze_track(id_of(Path::new("/music/gone.flac")), &AnalyzingProbe));
+    assert!(matches!(missing, Err(CoreError::Metadata(msg)) if msg.contains("no longer exists")));
+  }
 }
@@ -1,3 +1,9 @@
+pub mod backup;
+pub mod channel_reporter;
 pub mod library_service;
+pub mod throttled_reporter;
 
+pub use backup::{RestoreSummary, export_library_json, import_library_json};
+pub use channel_reporter::{ChannelReporter, ProgressEvent};
 pub use library_service::LibraryService;
+pub use throttled_reporter::ThrottledReporter;
@@ -0,0 +1,178 @@
+//! Decorador de `ProgressReporter` que agrupa los éxitos para no inundar la UI de eventos.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use crate::ports::{ImportSummary, ProgressReporter};
+
+/// Intervalo por defecto entre dos lotes de éxitos.
+pub const DEFAULT_SUCCESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Éxitos acumulados desde el último lote.
+#[derive(Debug, Default)]
+struct Pending {
+  count: usize,
+  last_path: String,
+  /// Cuándo salió el último lote; `None` hasta el primero, que sale sin esperar.
+  last_flush: Option<Instant>,
+}
+
+/// Reenvía los éxitos a `inner` agrupados con [`ProgressReporter::on_success_batch`], como
+/// mucho un lote cada `interval`. Los demás avisos pasan al momento.
+///
+/// No hay temporizador: un lote sale con el éxito que cumple el intervalo, o justo antes de
+/// un error, un salto o `finish`, para que `inner` vea los avisos en orden y con los totales
+/// completos. Lo que quede pendiente tras el último éxito sale, como tarde, con `finish`.
+///
+/// Los clones comparten el acumulado: todas las tareas de una importación alimentan los
+/// mismos lotes.
+#[derive(Debug, Clone)]
+pub struct ThrottledReporter<R> {
+  inner: R,
+  interval: Duration,
+  pending: Arc<Mutex<Pending>>,
+}
+
+impl<R: ProgressReporter> ThrottledReporter<R> {
+  /// Envuelve `inner` con [`DEFAULT_SUCCESS_INTERVAL`].
+  pub fn new(inner: R) -> Self {
+    Self::with_interval(inner, DEFAULT_SUCCESS_INTERVAL)
+  }
+
+  /// Envuelve `inner` con un lote de éxitos como mucho cada `interval`. Con
+  /// `Duration::ZERO` cada éxito sale en su propio lote.
+  pub fn with_interval(inner: R, interval: Duration) -> Self {
+    Self { inner, interval, pending: Arc::default() }
+  }
+
+  /// Saca el acumulado si hay algo y ya toca (o `force`), dejando el contador a cero.
+  fn take(&self, force: bool) -> Option<(usize, String)> {
+    let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+    let due = force || pending.last_flush.is_none_or(|at| at.elapsed() >= self.interval);
+    if pending.count == 0 || !due {
+      return None;
+    }
+    pending.last_flush = Some(Instant::now());
+    Some((std::mem::take(&mut pending.count), std::mem::take(&mut pending.last_path)))
+  }
+
+  async fn flush(&self) {
+    if let Some((count, last_path)) = self.take(true) {
+      self.inner.on_success_batch(count, &last_path).await;
+    }
+  }
+}
+
+#[async_trait]
+impl<R: ProgressReporter> ProgressReporter for ThrottledReporter<R> {
+  async fn start(&self, total_files: usize) {
+    self.flush().await;
+    self.pending.lock().unwrap_or_else(|e| e.into_inner()).last_flush = None;
+    self.inner.start(total_files).await;
+  }
+
+  async fn on_success(&self, path: &str) {
+    self.on_success_batch(1, path).await;
+  }
+
+  async fn on_success_batch(&self, count: usize, last_path: &str) {
+    {
+      let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+      pending.count += count;
+      pending.last_path.clear();
+      pending.last_path.push_str(last_path);
+    }
+    if let Some((count, last_path)) = self.take(false) {
+      self.inner.on_success_batch(count, &last_path).await;
+    }
+  }
+
+  async fn on_error(&self, path: &str, error: &str) {
+    self.flush().await;
+    self.inner.on_error(path, error).await;
+  }
+
+  async fn on_group_error(&self, device: &str, error: &str) {
+    self.flush().await;
+    self.inner.on_group_error(device, error).await;
+  }
+
+  async fn on_skipped(&self, path: &str, reason: &str) {
+    self.flush().await;
+    self.inner.on_skipped(path, reason).await;
+  }
+
+  async fn finish(&self, summary: ImportSummary) {
+    self.flush().await;
+    self.inner.finish(summary).await;
+  }
+
+  async fn scan_started(&self) {
+    self.inner.scan_started().await;
+  }
+
+  async fn on_scan_progress(&self, files_found: usize) {
+    self.inner.on_scan_progress(files_found).await;
+  }
+
+  async fn scan_finished(&self, files_found: usize) {
+    self.inner.scan_finished(files_found).await;
+  }
+
+  async fn on_roots_unavailable(&self, roots: &[PathBuf]) {
+    self.inner.on_roots_unavailable(roots).await;
+  }
+
+  async fn on_permission_denied(&self, paths: &[PathBuf]) {
+    self.inner.on_permission_denied(paths).await;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use futures::executor::block_on;
+
+  use super::*;
+
+  /// Anota cada lote y cada error recibido, en orden.
+  #[derive(Debug, Clone, Default)]
+  struct Recorder {
+    events: Arc<Mutex<Vec<(usize, String)>>>,
+  }
+
+  #[async_trait]
+  impl ProgressReporter for Recorder {
+    async fn start(&self, _: usize) {}
+    async fn on_success(&self, path: &str) {
+      self.on_success_batch(1, path).await;
+    }
+    async fn on_success_batch(&self, count: usize, last_path: &str) {
+      self.events.lock().unwrap().push((count, last_path.to_string()));
+    }
+    async fn on_error(&self, path: &str, _: &str) {
+      self.events.lock().unwrap().push((0, path.to_string()));
+    }
+  }
+
+  #[test]
+  fn rapid_successes_are_coalesced_and_errors_flush_them_first() {
+    let recorder = Recorder::default();
+    let reporter = ThrottledReporter::with_interval(recorder.clone(), Duration::from_secs(60));
+
+    block_on(async {
+      reporter.start(1001).await;
+      for i in 0..1000 {
+        reporter.on_success(&format!("/{i}.flac")).await;
+      }
+      reporter.on_error("/bad.flac", "corrupt").await;
+      reporter.finish(ImportSummary::default()).await;
+    });
+
+    // El primer éxito sale solo; los 999 siguientes esperan hasta el error, que los empuja antes que él.
+    let events = recorder.events.lock().unwrap().clone();
+    assert_eq!(events, [(1, "/0.flac".to_string()), (999, "/999.flac".to_string()), (0, "/bad.flac".to_string())]);
+  }
+}
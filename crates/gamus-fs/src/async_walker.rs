@@ -3,7 +3,8 @@ use std::future::Future;
 use std::io;
 use std::path::{Path, PathBuf};
 
-use futures::stream::{self, Stream};
+use futures::future;
+use futures::stream::{self, Stream, StreamExt};
 use tokio::fs::{self, ReadDir};
 
 // =============================================================================
@@ -35,19 +36,56 @@ fn get_file_id(_meta: &std::fs::Metadata) -> FileId {
 // 2. Configuración y Tipos
 // =============================================================================
 
+/// Qué symlinks a directorio se siguen durante el recorrido.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+  /// No se sigue ninguno.
+  None,
+  /// Se siguen todos, apunten a donde apunten.
+  All,
+  /// Solo los que, resueltos (`canonicalize`), quedan dentro de `root`. Evita que un
+  /// enlace a `/` o a rutas del sistema acabe recorriendo medio disco.
+  ///
+  /// `root` se compara tal cual, así que debe estar ya resuelta: créala con
+  /// [`SymlinkPolicy::within_root`].
+  WithinRoot { root: PathBuf },
+}
+
+impl SymlinkPolicy {
+  /// `WithinRoot` con `root` resuelta una sola vez para todo el recorrido. Si no se puede
+  /// resolver (no existe, sin permisos), no se sigue ningún enlace.
+  pub async fn within_root(root: impl AsRef<Path>) -> Self {
+    match fs::canonicalize(root).await {
+      Ok(root) => SymlinkPolicy::WithinRoot { root },
+      Err(_) => SymlinkPolicy::None,
+    }
+  }
+
+  /// `true` si el symlink `link` se puede seguir según la política.
+  ///
+  /// Con `WithinRoot`, un enlace roto no se sigue.
+  async fn follows(&self, link: &Path) -> bool {
+    match self {
+      SymlinkPolicy::None => false,
+      SymlinkPolicy::All => true,
+      SymlinkPolicy::WithinRoot { root } => fs::canonicalize(link).await.is_ok_and(|target| target.starts_with(root)),
+    }
+  }
+}
+
 /// Configuración para controlar el recorrido.
 #[derive(Debug, Clone)]
 pub struct WalkConfig {
-  pub follow_symlinks: bool,
+  pub symlink_policy: SymlinkPolicy,
   pub max_depth: usize,
   /// Deduplica directorios visitados para evitar ciclos infinitos.
-  /// Recomendado true si follow_symlinks es true.
+  /// Recomendado true si se sigue algún symlink.
   pub dedup_dirs: bool,
 }
 
 impl Default for WalkConfig {
   fn default() -> Self {
-    Self { follow_symlinks: true, max_depth: 100, dedup_dirs: true }
+    Self { symlink_policy: SymlinkPolicy::All, max_depth: 100, dedup_dirs: true }
   }
 }
 
@@ -56,6 +94,7 @@ pub enum Filtering {
   Ignore,    // Ignorar archivo, pero si es dir, entrar.
   IgnoreDir, // Ignorar archivo y NO entrar si es dir.
   Continue,  // Procesar normalmente.
+  Stop,      // Emitir esta entrada y terminar el recorrido (sin entrar si es dir).
 }
 
 #[derive(Debug)]
@@ -72,6 +111,28 @@ impl WalkEntry {
   }
 }
 
+/// Evento emitido por [`walk_with_events`].
+///
+/// Cada `DirEnter` tiene su `DirLeave` correspondiente, incluso si leer el
+/// directorio falla a mitad (el `DirUnreadable` se emite antes del `DirLeave`). La excepción es
+/// [`Filtering::Stop`]: el stream termina tras la entrada y los directorios abiertos se
+/// quedan sin `DirLeave`.
+#[derive(Debug)]
+pub enum WalkEvent {
+  /// Se abrió un directorio y se van a emitir sus entradas.
+  DirEnter { path: PathBuf, depth: usize },
+  /// Entrada que pasó el filtro (igual que en [`walk_filtered`]).
+  Entry(WalkEntry),
+  /// Se terminaron las entradas del directorio.
+  DirLeave { path: PathBuf, depth: usize },
+  /// Directorio ya visitado que `dedup_dirs` no vuelve a recorrer: un ciclo de symlinks o
+  /// un segundo enlace al mismo directorio. No lleva `DirEnter`/`DirLeave`.
+  DirSkipped { path: PathBuf, depth: usize },
+  /// No se pudo abrir o terminar de leer el directorio (p. ej. permiso denegado). El
+  /// recorrido sigue con el resto; en [`walk_filtered`] llega como un `Err` sin la ruta.
+  DirUnreadable { path: PathBuf, depth: usize, error: io::Error },
+}
+
 // =============================================================================
 // 3. Estado Interno (Máquina de Estados)
 // =============================================================================
@@ -85,7 +146,7 @@ enum Frame {
     id_hint: Option<FileId>,
   },
   /// Estado: Estamos iterando un directorio abierto
-  Open { rd: ReadDir, depth: usize },
+  Open { rd: ReadDir, path: PathBuf, depth: usize },
 }
 
 // =============================================================================
@@ -93,10 +154,7 @@ enum Frame {
 // =============================================================================
 
 /// Crea un Stream que recorre el directorio recursivamente (sin filtrar).
-pub fn walk(
-  root: impl Into<PathBuf>,
-  cfg: WalkConfig,
-) -> impl Stream<Item = io::Result<WalkEntry>> {
+pub fn walk(root: impl Into<PathBuf>, cfg: WalkConfig) -> impl Stream<Item = io::Result<WalkEntry>> {
   walk_filtered(root, cfg, |_| async { Filtering::Continue })
 }
 
@@ -110,7 +168,41 @@ where
   F: FnMut(&WalkEntry) -> Fut + Send + 'static,
   Fut: Future<Output = Filtering> + Send,
 {
-  let root = root.into();
+  walk_inner(root.into(), cfg, filter, false).filter_map(|res| {
+    future::ready(match res {
+      Ok(WalkEvent::Entry(entry)) => Some(Ok(entry)),
+      Ok(WalkEvent::DirUnreadable { error, .. }) => Some(Err(error)),
+      Ok(_) => None,
+      Err(e) => Some(Err(e)),
+    })
+  })
+}
+
+/// Igual que [`walk_filtered`], pero además emite `DirEnter`/`DirLeave` al entrar y
+/// salir de cada directorio (incluida la raíz). Útil para mostrar progreso del escaneo.
+pub fn walk_with_events<F, Fut>(
+  root: impl Into<PathBuf>,
+  cfg: WalkConfig,
+  filter: F,
+) -> impl Stream<Item = io::Result<WalkEvent>>
+where
+  F: FnMut(&WalkEntry) -> Fut + Send + 'static,
+  Fut: Future<Output = Filtering> + Send,
+{
+  walk_inner(root.into(), cfg, filter, true)
+}
+
+/// Motor común. Con `dir_events = false` nunca emite `DirEnter`/`DirLeave`.
+fn walk_inner<F, Fut>(
+  root: PathBuf,
+  cfg: WalkConfig,
+  filter: F,
+  dir_events: bool,
+) -> impl Stream<Item = io::Result<WalkEvent>>
+where
+  F: FnMut(&WalkEntry) -> Fut + Send + 'static,
+  Fut: Future<Output = Filtering> + Send,
+{
   // Optimizamos memoria reservando un poco de espacio inicial
   let mut stack = Vec::with_capacity(16);
 
@@ -119,10 +211,15 @@ where
 
   let visited = HashSet::new();
   // Usamos Arc para el filtro si fuera necesario compartir, pero aquí lo movemos al closure.
-  // El 'state' del unfold contiene: (Pila, Set de Visitados, Config, Filtro)
-  let state = (stack, visited, cfg, filter);
+  // El 'state' del unfold contiene: (Pila, Set de Visitados, Config, Filtro, DirLeave diferido)
+  let state = (stack, visited, cfg, filter, None::<WalkEvent>);
+
+  stream::unfold(state, move |(mut stack, mut visited, cfg, mut filter, mut deferred)| async move {
+    // Un DirLeave que quedó pendiente tras emitir un error de lectura.
+    if let Some(event) = deferred.take() {
+      return Some((Ok(event), (stack, visited, cfg, filter, deferred)));
+    }
 
-  stream::unfold(state, |(mut stack, mut visited, cfg, mut filter)| async move {
     loop {
       // 1. Obtener el tope de la pila
       let top = stack.last_mut()?; // Si None, termina el stream
@@ -155,36 +252,46 @@ where
                       None // Raro: path raíz no era dir
                     }
                   }
-                  Err(e) => {
+                  Err(error) => {
                     // Emitimos error y seguimos
-                    return Some((Err(e), (stack, visited, cfg, filter)));
+                    let event = WalkEvent::DirUnreadable { path, depth, error };
+                    return Some((Ok(event), (stack, visited, cfg, filter, deferred)));
                   }
                 }
               }
             };
 
-            if let Some(id) = file_id {
-              if !visited.insert(id) {
-                // Ya visitado, cortamos ciclo.
-                continue;
+            if let Some(id) = file_id
+              && !visited.insert(id)
+            {
+              // Ya visitado, cortamos ciclo.
+              if dir_events {
+                let event = WalkEvent::DirSkipped { path, depth };
+                return Some((Ok(event), (stack, visited, cfg, filter, deferred)));
               }
+              continue;
             }
           }
 
           // --- Abrir Directorio ---
           match fs::read_dir(&path).await {
             Ok(rd) => {
-              stack.push(Frame::Open { rd, depth });
+              stack.push(Frame::Open { rd, path: path.clone(), depth });
+              if dir_events {
+                let event = WalkEvent::DirEnter { path, depth };
+                return Some((Ok(event), (stack, visited, cfg, filter, deferred)));
+              }
             }
-            Err(e) => {
+            Err(error) => {
               // Error al abrir (ej. Permiso Denegado). Lo emitimos pero no crasheamos.
-              return Some((Err(e), (stack, visited, cfg, filter)));
+              let event = WalkEvent::DirUnreadable { path, depth, error };
+              return Some((Ok(event), (stack, visited, cfg, filter, deferred)));
             }
           }
         }
 
         // CASO B: Leer entradas de un directorio abierto
-        Frame::Open { rd, depth } => {
+        Frame::Open { rd, path: dir_path, depth } => {
           let depth = *depth;
 
           match rd.next_entry().await {
@@ -194,7 +301,7 @@ where
               // Obtenemos tipo (lstat)
               let ft = match entry.file_type().await {
                 Ok(ft) => ft,
-                Err(e) => return Some((Err(e), (stack, visited, cfg, filter))),
+                Err(e) => return Some((Err(e), (stack, visited, cfg, filter, deferred))),
               };
 
               let entry_depth = depth + 1;
@@ -204,8 +311,9 @@ where
               let filtering = filter(&walk_entry).await;
 
               // Decidir si recursamos
-              // Solo recursamos si NO es IgnoreDir Y no excedemos profundidad
-              let recurse = filtering != Filtering::IgnoreDir && entry_depth <= cfg.max_depth;
+              // Solo recursamos si NO es IgnoreDir/Stop Y no excedemos profundidad
+              let recurse =
+                !matches!(filtering, Filtering::IgnoreDir | Filtering::Stop) && entry_depth <= cfg.max_depth;
 
               // Determinamos si es un target válido para recursión (Dir o Symlink->Dir)
               let mut pending_frame = None;
@@ -217,14 +325,13 @@ where
                     depth: entry_depth,
                     id_hint: None, // Se calculará al entrar
                   });
-                } else if ft.is_symlink() && cfg.follow_symlinks {
+                } else if ft.is_symlink() && cfg.symlink_policy != SymlinkPolicy::None {
                   // Truco de optimización: Resolvemos metadata AHORA.
                   // Si es dir, obtenemos su ID y lo pasamos como hint.
                   match fs::metadata(&walk_entry.path).await {
-                    Ok(m) if m.is_dir() => {
+                    Ok(m) if m.is_dir() && cfg.symlink_policy.follows(&walk_entry.path).await => {
                       let id = if cfg.dedup_dirs { Some(get_file_id(&m)) } else { None };
-                      pending_frame =
-                        Some(Frame::Pending { path, depth: entry_depth, id_hint: id });
+                      pending_frame = Some(Frame::Pending { path, depth: entry_depth, id_hint: id });
                     }
                     _ => {} // No es dir o error, no recursamos
                   }
@@ -239,19 +346,32 @@ where
               // Emitir resultado (si no es Ignore)
               match filtering {
                 Filtering::Continue => {
-                  return Some((Ok(walk_entry), (stack, visited, cfg, filter)));
+                  return Some((Ok(WalkEvent::Entry(walk_entry)), (stack, visited, cfg, filter, deferred)));
+                }
+                Filtering::Stop => {
+                  // Sin frames en la pila, la próxima vuelta del unfold termina el stream.
+                  stack.clear();
+                  return Some((Ok(WalkEvent::Entry(walk_entry)), (stack, visited, cfg, filter, deferred)));
                 }
                 _ => continue, // Ignore/IgnoreDir: bucle para siguiente entrada
               }
             }
             Ok(None) => {
               // Fin del directorio actual, sacamos el Frame Open
+              let leave = WalkEvent::DirLeave { path: dir_path.clone(), depth };
               stack.pop();
+              if dir_events {
+                return Some((Ok(leave), (stack, visited, cfg, filter, deferred)));
+              }
             }
-            Err(e) => {
+            Err(error) => {
               // Error leyendo entrada, sacamos el dir y reportamos
+              let event = WalkEvent::DirUnreadable { path: dir_path.clone(), depth, error };
+              if dir_events {
+                deferred = Some(WalkEvent::DirLeave { path: dir_path.clone(), depth });
+              }
               stack.pop();
-              return Some((Err(e), (stack, visited, cfg, filter)));
+              return Some((Ok(event), (stack, visited, cfg, filter, deferred)));
             }
           }
         }
@@ -1,12 +1,12 @@
 use futures::StreamExt;
-use gamus_fs::async_walker::{Filtering, WalkConfig, walk_filtered};
+use gamus_fs::async_walker::{Filtering, SymlinkPolicy, WalkConfig, walk_filtered};
 use std::time::Instant;
 
 #[tokio::main]
 async fn main() {
   let start_time = Instant::now();
 
-  let cfg = WalkConfig { follow_symlinks: false, max_depth: 50, dedup_dirs: true };
+  let cfg = WalkConfig { symlink_policy: SymlinkPolicy::None, max_depth: 50, dedup_dirs: true };
   let root = "/home/";
 
   let entries = walk_filtered(root, cfg, |entry| {
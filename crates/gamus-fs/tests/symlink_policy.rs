@@ -0,0 +1,61 @@
+#![cfg(unix)]
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+
+use futures::StreamExt;
+use gamus_fs::async_walker::{SymlinkPolicy, WalkConfig, walk};
+
+/// `root/{a.flac, album/b.flac, inside -> album, outside -> <dir>/system}`, con `system/c.flac`.
+fn tree() -> (tempfile::TempDir, PathBuf) {
+  let dir = tempfile::tempdir().unwrap();
+  let root = dir.path().join("music");
+  let system = dir.path().join("system");
+  fs::create_dir_all(root.join("album")).unwrap();
+  fs::create_dir_all(&system).unwrap();
+
+  fs::write(root.join("a.flac"), b"a").unwrap();
+  fs::write(root.join("album/b.flac"), b"b").unwrap();
+  fs::write(system.join("c.flac"), b"c").unwrap();
+
+  symlink(root.join("album"), root.join("inside")).unwrap();
+  symlink(&system, root.join("outside")).unwrap();
+
+  (dir, root)
+}
+
+async fn files(root: &Path, symlink_policy: SymlinkPolicy) -> BTreeSet<PathBuf> {
+  // Sin deduplicar, `inside/b.flac` aparece además de `album/b.flac`.
+  let cfg = WalkConfig { symlink_policy, max_depth: 10, dedup_dirs: false };
+  walk(root, cfg)
+    .filter_map(|entry| async move { entry.ok().filter(|e| e.file_type.is_file()) })
+    .map(|entry| entry.path.strip_prefix(root).unwrap().to_path_buf())
+    .collect()
+    .await
+}
+
+fn paths(list: &[&str]) -> BTreeSet<PathBuf> {
+  list.iter().map(PathBuf::from).collect()
+}
+
+#[tokio::test]
+async fn within_root_follows_links_into_the_root_only() {
+  let (_dir, root) = tree();
+
+  let found = files(&root, SymlinkPolicy::within_root(&root).await).await;
+
+  assert_eq!(found, paths(&["a.flac", "album/b.flac", "inside/b.flac"]));
+}
+
+#[tokio::test]
+async fn all_and_none_keep_their_all_or_nothing_behaviour() {
+  let (_dir, root) = tree();
+
+  assert_eq!(
+    files(&root, SymlinkPolicy::All).await,
+    paths(&["a.flac", "album/b.flac", "inside/b.flac", "outside/c.flac"])
+  );
+  assert_eq!(files(&root, SymlinkPolicy::None).await, paths(&["a.flac", "album/b.flac"]));
+}
@@ -0,0 +1,24 @@
+use std::io;
+
+use futures::StreamExt;
+use gamus_fs::async_walker::{Filtering, WalkConfig, WalkEvent, walk, walk_with_events};
+
+#[tokio::test]
+async fn an_unreadable_directory_is_reported_with_its_path() {
+  let dir = tempfile::tempdir().unwrap();
+  let missing = dir.path().join("gone");
+
+  let events: Vec<_> =
+    walk_with_events(&missing, WalkConfig::default(), |_| async { Filtering::Continue }).collect().await;
+  match events.as_slice() {
+    [Ok(WalkEvent::DirUnreadable { path, depth: 0, error })] => {
+      assert_eq!(path, &missing);
+      assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+    other => panic!("unexpected events: {other:?}"),
+  }
+
+  // Sin eventos de directorio, el mismo fallo sigue llegando como `Err`.
+  let entries: Vec<_> = walk(&missing, WalkConfig::default()).collect().await;
+  assert!(matches!(entries.as_slice(), [Err(e)] if e.kind() == io::ErrorKind::NotFound));
+}
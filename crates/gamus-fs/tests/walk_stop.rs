@@ -0,0 +1,38 @@
+use std::fs;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use futures::StreamExt;
+use gamus_fs::async_walker::{Filtering, WalkConfig, walk_filtered};
+
+#[tokio::test]
+async fn stop_on_the_tenth_file_yields_exactly_ten_entries() {
+  let dir = tempfile::tempdir().unwrap();
+  for album in 0..5 {
+    let album_dir = dir.path().join(format!("album{album}"));
+    fs::create_dir_all(&album_dir).unwrap();
+    for track in 0..20 {
+      fs::write(album_dir.join(format!("{track:02}.flac")), b"x").unwrap();
+    }
+  }
+
+  let seen = Arc::new(AtomicUsize::new(0));
+  let counter = Arc::clone(&seen);
+  let entries: Vec<_> = walk_filtered(dir.path(), WalkConfig::default(), move |entry| {
+    let filtering = if !entry.file_type.is_file() {
+      Filtering::Ignore
+    } else if counter.fetch_add(1, Ordering::SeqCst) + 1 == 10 {
+      Filtering::Stop
+    } else {
+      Filtering::Continue
+    };
+    async move { filtering }
+  })
+  .collect()
+  .await;
+
+  assert_eq!(entries.len(), 10);
+  assert!(entries.iter().all(|e| e.as_ref().is_ok_and(|e| e.file_type.is_file())));
+  // Nada se consulta al filtro después de `Stop`.
+  assert_eq!(seen.load(Ordering::SeqCst), 10);
+}
@@ -2,12 +2,22 @@
 //!
 //! La idea es sacar todos los “magic numbers” del código y hacerlos
 //! explicitamente tuneables desde configuración o tests.
+//!
+//! Todo se puede fijar en la sección `[analysis]` del archivo de configuración (ver
+//! [`AnalysisConfig::load`]); lo que no aparezca toma el valor por defecto.
+
+use std::ops::Range;
+
+use gamus_config::{CONFIG_BACKEND, ConfigBackend, ConfigError};
+use gamus_core::domain::release_track::QualityLevel;
+use serde::{Deserialize, Serialize};
 
 /// Ajustes de cómo se calcula el ruido de fondo.
 ///
 /// Se usa para distinguir entre energía “real” en alta frecuencia y
 /// ruido / silencio numérico del espectro.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct NoiseConfig {
   /// Umbral base de ruido (dB).
   ///
@@ -23,6 +33,17 @@ pub struct NoiseConfig {
   /// hacen el floor más bajo (más agresivo encontrando energía débil),
   /// valores bajos lo acercan al máximo (más conservador).
   pub dynamic_margin_db: f32,
+
+  /// Máximo del espectro (dB, misma escala que `base_floor_db`) por debajo del cual la
+  /// pista se considera silencio digital y el análisis es no concluyente.
+  ///
+  /// Por encima, una pista sin ninguna banda sobre `base_floor_db` (música muy baja o sin
+  /// agudos) no es un error: se busca el corte solo con el margen dinámico. El silencio
+  /// puro queda en -200 dB (el suelo del cálculo); el ruido de un LSB a 16 bits, hacia -60.
+  pub digital_silence_db: f32,
+
+  /// Forma del floor con el que se compara cada banda del reverse scan.
+  pub floor_mode: NoiseFloorMode,
 }
 
 impl Default for NoiseConfig {
@@ -30,14 +51,39 @@ impl Default for NoiseConfig {
     Self {
       base_floor_db: -65.0,
       dynamic_margin_db: 70.0, // antes estaba “hardcoded” en detect_cutoff
+      digital_silence_db: -120.0,
+      floor_mode: NoiseFloorMode::default(),
     }
   }
 }
 
+/// Cómo se calcula el noise floor a lo largo del espectro.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseFloorMode {
+  /// Un único valor para todas las bandas, a partir de `base_floor_db` y
+  /// `dynamic_margin_db` (comportamiento histórico).
+  #[default]
+  Flat,
+  /// Floor inclinado que sigue al ruido real, más alto en graves que en agudos.
+  ///
+  /// Se ajusta una recta (dB frente a Hz, mínimos cuadrados) a la envolvente inferior
+  /// del espectro, y cada banda se compara con la recta en su frecuencia más
+  /// `margin_db`. Los tramos por debajo de `digital_silence_db` (lo que queda por encima
+  /// del corte de un codec con pérdida) no entran en el ajuste. Evita los falsos cortes
+  /// de pistas con mucho grave y agudos débiles pero presentes, que con un floor plano
+  /// quedan por debajo de él.
+  Tilted {
+    /// dB que una banda debe superar a la envolvente para contar como contenido.
+    margin_db: f32,
+  },
+}
+
 /// Ajustes del reverse scan en alta frecuencia.
 ///
 /// Controla cómo buscamos la presencia/ausencia de energía cerca de Nyquist.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ReverseScanConfig {
   /// Ancho de banda usado en el reverse scan (Hz).
   ///
@@ -66,7 +112,8 @@ impl Default for ReverseScanConfig {
 /// Cómo se mapea el cutoff (o ausencia de cutoff) a una puntuación numérica.
 ///
 /// Separa la detección acústica de la política de puntuación.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ScoringConfig {
   /// Pares `(freq_hz, score)` ordenados de mayor freq a menor.
   ///
@@ -83,6 +130,42 @@ pub struct ScoringConfig {
   ///
   /// Se usa cuando el análisis considera el espectro “full band”.
   pub full_band_scores: (f32, f32, f32),
+
+  /// Puntuaciones mínimas de cada `QualityLevel` en el informe.
+  pub level_thresholds: LevelThresholds,
+}
+
+/// Puntuación mínima (inclusive) para cada nivel de calidad; por debajo de `medium` es `Low`.
+///
+/// Los valores por defecto son los de `QualityLevel::from_score`. Subir `perfect` a `10.0`
+/// deja “Perfect” solo para espectros completos de verdad.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LevelThresholds {
+  pub perfect: f32,
+  pub high: f32,
+  pub medium: f32,
+}
+
+impl Default for LevelThresholds {
+  fn default() -> Self {
+    Self { perfect: 9.5, high: 8.0, medium: 5.5 }
+  }
+}
+
+impl LevelThresholds {
+  /// Nivel que corresponde a `score`. Nunca devuelve `Inconclusive`.
+  pub fn level_for(&self, score: f32) -> QualityLevel {
+    if score >= self.perfect {
+      QualityLevel::Perfect
+    } else if score >= self.high {
+      QualityLevel::High
+    } else if score >= self.medium {
+      QualityLevel::Medium
+    } else {
+      QualityLevel::Low
+    }
+  }
 }
 
 impl ScoringConfig {
@@ -128,6 +211,7 @@ impl Default for ScoringConfig {
       ],
       cutoff_fallback_score: 4.0,
       full_band_scores: (10.0, 9.5, 9.0),
+      level_thresholds: LevelThresholds::default(),
     }
   }
 }
@@ -136,7 +220,8 @@ impl Default for ScoringConfig {
 ///
 /// Evita que una pista de bitrate muy bajo obtenga una nota
 /// “imposible” solo por cómo cae el espectro.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct BitrateSafetyConfig {
   // Umbrales en bps: definen los tramos de bitrate.
   pub very_low_bps_max: i64, // < 80 kbps
@@ -208,11 +293,132 @@ impl Default for BitrateSafetyConfig {
   }
 }
 
+/// Ajustes de la medición de correlación entre canales.
+///
+/// Sirve para detectar "estéreo" falso: rips dual mono o upmixes donde
+/// ambos canales llevan exactamente la misma señal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StereoConfig {
+  /// Si es `true` y la fuente tiene 2+ canales, se mide la correlación L/R
+  /// antes de mezclar a mono. Tiene un coste extra pequeño por muestra.
+  pub measure_correlation: bool,
+
+  /// Correlación a partir de la cual se considera pseudo-estéreo.
+  ///
+  /// Material estéreo real rara vez pasa de ~0.95 de forma sostenida.
+  pub pseudo_stereo_threshold: f32,
+}
+
+impl Default for StereoConfig {
+  fn default() -> Self {
+    Self { measure_correlation: true, pseudo_stereo_threshold: 0.995 }
+  }
+}
+
+/// Cómo se reducen los canales a la señal mono que recibe la FFT.
+///
+/// Con fuentes mono no tiene efecto. Con 3+ canales se aplica sobre la mezcla
+/// estéreo que produce el resampler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownmixMode {
+  /// Media de L y R (comportamiento histórico).
+  #[default]
+  Average,
+  /// Solo el canal izquierdo.
+  Left,
+  /// Solo el canal derecho.
+  Right,
+  /// Muestra de mayor magnitud entre L y R.
+  ///
+  /// Un corte presente en un único canal sigue apareciendo en el espectro,
+  /// mientras que la media lo enmascara con el canal sano.
+  Max,
+}
+
+impl DownmixMode {
+  /// Reduce una pareja de muestras L/R a una sola.
+  pub(crate) fn mix(self, left: f32, right: f32) -> f32 {
+    match self {
+      DownmixMode::Average => (left + right) * 0.5,
+      DownmixMode::Left => left,
+      DownmixMode::Right => right,
+      DownmixMode::Max => {
+        if left.abs() >= right.abs() {
+          left
+        } else {
+          right
+        }
+      }
+    }
+  }
+}
+
+/// Qué parte de la pista se decodifica para construir el espectro medio.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplingStrategy {
+  /// Un único tramo contiguo desde `analysis_start_secs`, acotado por
+  /// `max_analysis_duration_secs` (comportamiento histórico).
+  #[default]
+  Prefix,
+  /// `count` tramos de `secs_each` segundos repartidos a lo largo de la pista
+  /// (p.ej. 10%, 50% y 90% con `count = 3`), cuyos espectros se promedian juntos.
+  ///
+  /// Evita que una intro lossless tape un upsampling en el resto del tema.
+  /// Ignora `analysis_start_secs` y `max_analysis_duration_secs`. Si la duración
+  /// es desconocida o no caben los tramos sin solaparse, se usa `Prefix`.
+  Segments { count: usize, secs_each: f32 },
+}
+
+impl SamplingStrategy {
+  /// Tramos (en segundos) a analizar en una pista de `duration_secs`.
+  ///
+  /// Devuelve `None` cuando hay que caer a `Prefix`: estrategia `Prefix`,
+  /// parámetros vacíos, duración desconocida o pista demasiado corta.
+  pub(crate) fn segment_spans(self, duration_secs: f64) -> Option<Vec<Range<f64>>> {
+    let SamplingStrategy::Segments { count, secs_each } = self else {
+      return None;
+    };
+    let secs_each = f64::from(secs_each);
+    if count == 0 || secs_each <= 0.0 || !duration_secs.is_finite() || duration_secs < count as f64 * secs_each {
+      return None;
+    }
+
+    // Puntos repartidos entre el 10% y el 90% para no caer en fades de entrada/salida.
+    let latest_start = duration_secs - secs_each;
+    let spans = (0..count)
+      .map(|i| {
+        let fraction = if count == 1 { 0.5 } else { 0.1 + 0.8 * i as f64 / (count - 1) as f64 };
+        let start = (fraction * duration_secs).min(latest_start);
+        start..start + secs_each
+      })
+      .collect();
+    Some(spans)
+  }
+}
+
+/// Qué hacer con los archivos cuyo códec es sin pérdida (FLAC, ALAC, PCM…).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LosslessPolicy {
+  /// Se analizan como cualquier otro (comportamiento histórico). Es lo que detecta un FLAC
+  /// hecho a partir de un MP3: el contenedor es sin pérdida, el espectro no.
+  #[default]
+  AlwaysVerify,
+  /// No se decodifican: se les da la puntuación de espectro completo (el primer valor de
+  /// `scoring.full_band_scores`). Acelera mucho las importaciones de bibliotecas con mucho
+  /// lossless, a cambio de no detectar transcodes.
+  SkipAnalysis,
+}
+
 /// Configuración de análisis de espectro completa.
 ///
 /// Punto único de entrada para ajustar el comportamiento del
 /// analizador sin tocar la lógica de `SpectralAnalyzer`.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AnalysisConfig {
   /// Tamaño de ventana FFT (en muestras).
   ///
@@ -220,12 +426,56 @@ pub struct AnalysisConfig {
   /// consistente con el plan FFT y el tamaño de los buffers internos.
   pub fft_window_size: usize,
 
+  /// Solapamiento entre ventanas FFT consecutivas, en `[0.0, MAX_OVERLAP_RATIO]`.
+  ///
+  /// Con `0.0` las ventanas son contiguas (comportamiento histórico). Con `0.5`
+  /// (Welch) cada ventana avanza media ventana: el espectro medio tiene menos
+  /// varianza, lo que ayuda en clips cortos, a cambio de ~2x de FFTs.
+  pub overlap_ratio: f32,
+
   /// Máxima duración de audio a analizar (en segundos).
   ///
   /// Permite acotar el tiempo de análisis en pistas muy largas para
   /// evitar tiempos de CPU desproporcionados. `<= 0` desactiva el límite.
   pub max_analysis_duration_secs: f32,
 
+  /// Punto de inicio del análisis (en segundos desde el principio de la pista).
+  ///
+  /// Evita puntuar intros silenciosas o ambientales, cuyo espectro no dice nada
+  /// del encoding. Si supera la duración de la pista se analiza desde el inicio.
+  /// `<= 0` analiza desde el principio.
+  pub analysis_start_secs: f32,
+
+  /// Umbral (dBFS) del recorte de silencio inicial; `None` lo desactiva.
+  ///
+  /// Con `Prefix`, las muestras por debajo del umbral se descartan hasta la primera
+  /// que lo supere, y `max_analysis_duration_secs` se cuenta a partir de ella. Evita que
+  /// el silencio de un vinilo ripeado diluya el espectro medio. No afecta a `Segments`.
+  /// Debe ser finito y `<= 0`.
+  pub silence_trim_db: Option<f32>,
+
+  /// Qué tramos de la pista se analizan.
+  pub sampling: SamplingStrategy,
+
+  /// Estrategia para reducir los canales antes de la FFT.
+  pub downmix: DownmixMode,
+
+  /// Frecuencia (Hz) a la que se re-muestrea antes de la FFT; `None` analiza a la
+  /// frecuencia nativa del archivo.
+  ///
+  /// Con la nativa, el ancho de cada bin es `rate / fft_window_size`: un archivo de 96 kHz
+  /// reparte la ventana hasta 48 kHz y tiene la mitad de resolución que uno de 44.1 kHz en
+  /// la zona de 15–22 kHz donde caen los cortes de los codecs con pérdida, y Nyquist (del
+  /// que se miden el reverse scan y sus márgenes) cambia de un archivo a otro. Fijándola
+  /// (p. ej. 44 100), todos los archivos se miden con los mismos bins y cortes iguales dan
+  /// puntuaciones iguales.
+  ///
+  /// Solo se baja: las fuentes a esa frecuencia o menos se analizan a la suya, porque subir
+  /// la frecuencia no añade contenido y fabricaría un corte en el Nyquist original. El
+  /// precio es que lo que haya por encima de `rate / 2` en archivos hi-res deja de contar.
+  /// Debe ser `>= MIN_VALIDATED_SAMPLE_RATE_HZ`.
+  pub analysis_sample_rate: Option<u32>,
+
   /// Parámetros de cálculo del ruido de fondo.
   pub noise: NoiseConfig,
 
@@ -237,21 +487,75 @@ pub struct AnalysisConfig {
 
   /// Safety net basado en bitrate.
   pub bitrate_safety: BitrateSafetyConfig,
+
+  /// Detección de pseudo-estéreo.
+  pub stereo: StereoConfig,
+
+  /// Si los archivos con códec sin pérdida se analizan o se dan por buenos.
+  pub lossless_policy: LosslessPolicy,
 }
 
 impl Default for AnalysisConfig {
   fn default() -> Self {
     Self {
       fft_window_size: 8192,
+      overlap_ratio: 0.0,
       max_analysis_duration_secs: 15.0,
+      analysis_start_secs: 0.0,
+      silence_trim_db: None,
+      sampling: SamplingStrategy::default(),
+      downmix: DownmixMode::default(),
+      analysis_sample_rate: None,
       noise: NoiseConfig::default(),
       reverse_scan: ReverseScanConfig::default(),
       scoring: ScoringConfig::default(),
       bitrate_safety: BitrateSafetyConfig::default(),
+      stereo: StereoConfig::default(),
+      lossless_policy: LosslessPolicy::default(),
     }
   }
 }
 
+/// Solapamiento máximo admitido entre ventanas FFT.
+///
+/// Por encima, el coste crece mucho (10x FFTs con 0.9) sin mejorar la estimación.
+pub const MAX_OVERLAP_RATIO: f32 = 0.9;
+
+/// Tamaño mínimo de ventana FFT admitido (muestras).
+pub const MIN_FFT_WINDOW_SIZE: usize = 64;
+
+/// Frecuencia de muestreo más baja con la que se comprueba que cabe una ventana FFT
+/// entera en el tiempo analizado. Cubre desde audio de 22.05 kHz hacia arriba.
+pub const MIN_VALIDATED_SAMPLE_RATE_HZ: u32 = 22_050;
+
+/// Errores de validación al construir un `AnalysisConfig`.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+pub enum AnalysisConfigError {
+  #[error("overlap_ratio must be in [0.0, {MAX_OVERLAP_RATIO}], got {0}")]
+  OverlapOutOfRange(f32),
+
+  #[error("fft_window_size must be a power of two, got {0}")]
+  WindowSizeNotPowerOfTwo(usize),
+
+  #[error("fft_window_size must be at least {MIN_FFT_WINDOW_SIZE}, got {0}")]
+  WindowSizeTooSmall(usize),
+
+  #[error("silence_trim_db must be a finite level <= 0 dBFS, got {0}")]
+  SilenceThresholdOutOfRange(f32),
+
+  #[error("analysis_sample_rate must be at least {MIN_VALIDATED_SAMPLE_RATE_HZ} Hz, got {0}")]
+  AnalysisSampleRateTooLow(u32),
+
+  /// El tramo analizado no llega a una ventana FFT completa a `MIN_VALIDATED_SAMPLE_RATE_HZ`,
+  /// así que el análisis acabaría sin ventanas y fallaría con cualquier archivo.
+  #[error("{secs} s of audio is shorter than one {window}-sample FFT window at {MIN_VALIDATED_SAMPLE_RATE_HZ} Hz")]
+  AnalysisTooShort { secs: f32, window: usize },
+
+  /// Los umbrales deben cumplir `perfect >= high >= medium`; si no, algún nivel sería inalcanzable.
+  #[error("level thresholds must satisfy perfect >= high >= medium, got {0:?}")]
+  LevelThresholdsUnordered(LevelThresholds),
+}
+
 /// Builder para `AnalysisConfig` para evitar tocar todos los campos a mano.
 ///
 /// Pensado para tests, estrategias de A/B y tuning avanzado.
@@ -260,6 +564,20 @@ pub struct AnalysisConfigBuilder {
   inner: AnalysisConfig,
 }
 
+impl Default for AnalysisConfigBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl From<AnalysisConfig> for AnalysisConfigBuilder {
+  /// Builder que parte de `config` en vez de los valores por defecto, p. ej. para ajustar
+  /// encima de lo que devuelve [`AnalysisConfig::load`].
+  fn from(config: AnalysisConfig) -> Self {
+    Self { inner: config }
+  }
+}
+
 impl AnalysisConfigBuilder {
   /// Crea un builder con `AnalysisConfig::default()`.
   pub fn new() -> Self {
@@ -272,12 +590,49 @@ impl AnalysisConfigBuilder {
     self
   }
 
+  /// Ajusta el solapamiento entre ventanas FFT (se valida en `build`).
+  pub fn overlap_ratio(mut self, ratio: f32) -> Self {
+    self.inner.overlap_ratio = ratio;
+    self
+  }
+
   /// Ajusta la duración máxima de análisis (segundos).
   pub fn max_analysis_duration_secs(mut self, secs: f32) -> Self {
     self.inner.max_analysis_duration_secs = secs;
     self
   }
 
+  /// Ajusta el punto de inicio del análisis (segundos).
+  pub fn analysis_start_secs(mut self, secs: f32) -> Self {
+    self.inner.analysis_start_secs = secs;
+    self
+  }
+
+  /// Activa el recorte del silencio inicial por debajo de `db` dBFS (se valida en `build`).
+  pub fn silence_trim_db(mut self, db: f32) -> Self {
+    self.inner.silence_trim_db = Some(db);
+    self
+  }
+
+  /// Ajusta qué tramos de la pista se analizan.
+  pub fn sampling(mut self, strategy: SamplingStrategy) -> Self {
+    self.inner.sampling = strategy;
+    self
+  }
+
+  /// Ajusta la estrategia de downmix previa a la FFT.
+  pub fn downmix(mut self, mode: DownmixMode) -> Self {
+    self.inner.downmix = mode;
+    self
+  }
+
+  /// Re-muestrea a `hz` antes de la FFT en vez de analizar a la frecuencia nativa (se
+  /// valida en `build`).
+  pub fn analysis_sample_rate(mut self, hz: u32) -> Self {
+    self.inner.analysis_sample_rate = Some(hz);
+    self
+  }
+
   /// Ajusta el floor de ruido base (dB).
   pub fn noise_floor_db(mut self, db: f32) -> Self {
     self.inner.noise.base_floor_db = db;
@@ -290,6 +645,18 @@ impl AnalysisConfigBuilder {
     self
   }
 
+  /// Ajusta el máximo espectral (dB) por debajo del cual la pista cuenta como silencio digital.
+  pub fn digital_silence_db(mut self, db: f32) -> Self {
+    self.inner.noise.digital_silence_db = db;
+    self
+  }
+
+  /// Ajusta la forma del noise floor (plano o inclinado).
+  pub fn noise_floor_mode(mut self, mode: NoiseFloorMode) -> Self {
+    self.inner.noise.floor_mode = mode;
+    self
+  }
+
   /// Ajusta el ancho de banda del reverse scan (Hz).
   pub fn reverse_scan_band_width_hz(mut self, hz: f32) -> Self {
     self.inner.reverse_scan.band_width_hz = hz;
@@ -308,15 +675,58 @@ impl AnalysisConfigBuilder {
     self
   }
 
+  /// Ajusta las puntuaciones mínimas de cada nivel de calidad (se validan en `build`).
+  pub fn level_thresholds(mut self, thresholds: LevelThresholds) -> Self {
+    self.inner.scoring.level_thresholds = thresholds;
+    self
+  }
+
+  /// Ajusta la puntuación mínima para `QualityLevel::Perfect`.
+  pub fn perfect_threshold(mut self, score: f32) -> Self {
+    self.inner.scoring.level_thresholds.perfect = score;
+    self
+  }
+
+  /// Ajusta la puntuación mínima para `QualityLevel::High`.
+  pub fn high_threshold(mut self, score: f32) -> Self {
+    self.inner.scoring.level_thresholds.high = score;
+    self
+  }
+
+  /// Ajusta la puntuación mínima para `QualityLevel::Medium`.
+  pub fn medium_threshold(mut self, score: f32) -> Self {
+    self.inner.scoring.level_thresholds.medium = score;
+    self
+  }
+
   /// Permite inyectar una política de caps por bitrate distinta.
   pub fn bitrate_safety(mut self, bs: BitrateSafetyConfig) -> Self {
     self.inner.bitrate_safety = bs;
     self
   }
 
-  /// Consume el builder y devuelve la configuración final.
-  pub fn build(self) -> AnalysisConfig {
-    self.inner
+  /// Activa o desactiva la medición de correlación estéreo.
+  pub fn measure_stereo_correlation(mut self, enabled: bool) -> Self {
+    self.inner.stereo.measure_correlation = enabled;
+    self
+  }
+
+  /// Ajusta el umbral de correlación para marcar pseudo-estéreo.
+  pub fn pseudo_stereo_threshold(mut self, threshold: f32) -> Self {
+    self.inner.stereo.pseudo_stereo_threshold = threshold;
+    self
+  }
+
+  /// Ajusta si los archivos con códec sin pérdida se analizan o se dan por buenos.
+  pub fn lossless_policy(mut self, policy: LosslessPolicy) -> Self {
+    self.inner.lossless_policy = policy;
+    self
+  }
+
+  /// Consume el builder y devuelve la configuración final, validada (ver `AnalysisConfig::validate`).
+  pub fn build(self) -> Result<AnalysisConfig, AnalysisConfigError> {
+    self.inner.validate()?;
+    Ok(self.inner)
   }
 }
 
@@ -325,4 +735,185 @@ impl AnalysisConfig {
   pub fn builder() -> AnalysisConfigBuilder {
     AnalysisConfigBuilder::new()
   }
+
+  /// Carga la sección `[analysis]` del archivo de configuración y la valida (ver
+  /// [`Self::validate`]).
+  ///
+  /// Los campos que falten toman su valor por defecto, y sin sección (o sin archivo) sale
+  /// `AnalysisConfig::default()`. Si es válida, como `[scanner]` y `[storage]`, la sección se
+  /// reescribe completa para que el usuario vea todo lo que puede ajustar; si no, el archivo
+  /// no se toca. `FfmpegProbe::default()`
+  /// no la lee: quien quiera los ajustes del usuario construye el probe con esto.
+  pub fn load() -> Result<Self, ConfigError> {
+    let cfg: Self = CONFIG_BACKEND.load_section_with_default("analysis")?;
+    cfg.validate().map_err(|e| ConfigError::Other(format!("[analysis]: {e}")))?;
+    CONFIG_BACKEND.save_section("analysis", &cfg)?;
+    Ok(cfg)
+  }
+
+  /// Comprueba que la configuración puede producir al menos una ventana FFT.
+  ///
+  /// - `fft_window_size` potencia de dos y `>= MIN_FFT_WINDOW_SIZE`.
+  /// - `overlap_ratio` en `[0.0, MAX_OVERLAP_RATIO]`.
+  /// - `silence_trim_db`, si está, finito y `<= 0`.
+  /// - `analysis_sample_rate`, si está, `>= MIN_VALIDATED_SAMPLE_RATE_HZ`.
+  /// - `max_analysis_duration_secs` (si limita) y `secs_each` de `Segments` dan al menos
+  ///   una ventana completa a `MIN_VALIDATED_SAMPLE_RATE_HZ`.
+  /// - `scoring.level_thresholds` ordenados (`perfect >= high >= medium`).
+  pub fn validate(&self) -> Result<(), AnalysisConfigError> {
+    let window = self.fft_window_size;
+    if window < MIN_FFT_WINDOW_SIZE {
+      return Err(AnalysisConfigError::WindowSizeTooSmall(window));
+    }
+    if !window.is_power_of_two() {
+      return Err(AnalysisConfigError::WindowSizeNotPowerOfTwo(window));
+    }
+
+    let ratio = self.overlap_ratio;
+    if !(0.0..=MAX_OVERLAP_RATIO).contains(&ratio) {
+      return Err(AnalysisConfigError::OverlapOutOfRange(ratio));
+    }
+
+    if let Some(db) = self.silence_trim_db
+      && !(db.is_finite() && db <= 0.0)
+    {
+      return Err(AnalysisConfigError::SilenceThresholdOutOfRange(db));
+    }
+
+    if let Some(rate) = self.analysis_sample_rate
+      && rate < MIN_VALIDATED_SAMPLE_RATE_HZ
+    {
+      return Err(AnalysisConfigError::AnalysisSampleRateTooLow(rate));
+    }
+
+    let fits_one_window = |secs: f32| secs * MIN_VALIDATED_SAMPLE_RATE_HZ as f32 >= window as f32;
+    let max_secs = self.max_analysis_duration_secs;
+    if max_secs > 0.0 && !fits_one_window(max_secs) {
+      return Err(AnalysisConfigError::AnalysisTooShort { secs: max_secs, window });
+    }
+    if let SamplingStrategy::Segments { secs_each, .. } = self.sampling
+      && !fits_one_window(secs_each)
+    {
+      return Err(AnalysisConfigError::AnalysisTooShort { secs: secs_each, window });
+    }
+
+    let t = self.scoring.level_thresholds;
+    if !(t.perfect >= t.high && t.high >= t.medium) {
+      return Err(AnalysisConfigError::LevelThresholdsUnordered(t));
+    }
+
+    Ok(())
+  }
+
+  /// Avance (en muestras) entre ventanas FFT consecutivas según `overlap_ratio`.
+  ///
+  /// Los campos son públicos y se pueden fijar sin pasar por `build`, así que el
+  /// ratio se acota aquí también; el avance nunca baja de 1 muestra.
+  pub(crate) fn fft_hop_size(&self) -> usize {
+    let ratio = if self.overlap_ratio.is_finite() { self.overlap_ratio.clamp(0.0, MAX_OVERLAP_RATIO) } else { 0.0 };
+    let overlap = (self.fft_window_size as f32 * ratio).round() as usize;
+    self.fft_window_size.saturating_sub(overlap).max(1)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn a_partial_analysis_section_keeps_the_other_defaults() {
+    let cfg: AnalysisConfig = toml::from_str(
+      r#"
+        analysis_sample_rate = 44100
+        sampling = { segments = { count = 3, secs_each = 5.0 } }
+
+        [noise]
+        base_floor_db = -70.0
+        floor_mode = { tilted = { margin_db = 6.0 } }
+
+        [scoring.level_thresholds]
+        perfect = 10.0
+      "#,
+    )
+    .unwrap();
+
+    assert_eq!(cfg.analysis_sample_rate, Some(44_100));
+    assert_eq!(cfg.sampling, SamplingStrategy::Segments { count: 3, secs_each: 5.0 });
+    assert_eq!(cfg.noise.base_floor_db, -70.0);
+    assert_eq!(cfg.noise.floor_mode, NoiseFloorMode::Tilted { margin_db: 6.0 });
+    assert_eq!(cfg.noise.dynamic_margin_db, NoiseConfig::default().dynamic_margin_db);
+    assert_eq!(cfg.scoring.level_thresholds, LevelThresholds { perfect: 10.0, ..LevelThresholds::default() });
+    assert_eq!(cfg.scoring.cutoff_bands, ScoringConfig::default().cutoff_bands);
+    assert_eq!(cfg.fft_window_size, AnalysisConfig::default().fft_window_size);
+    cfg.validate().unwrap();
+
+    // Lo que `load` reescribe en el archivo se vuelve a leer igual.
+    let saved: AnalysisConfig = toml::from_str(&toml::to_string(&cfg).unwrap()).unwrap();
+    assert_eq!(saved.sampling, cfg.sampling);
+    assert_eq!(saved.scoring.full_band_scores, cfg.scoring.full_band_scores);
+    assert_eq!(saved.silence_trim_db, None);
+  }
+
+  #[test]
+  fn segments_spread_over_the_track_and_fall_back_when_they_do_not_fit() {
+    let whole_secs =
+      |spans: Vec<Range<f64>>| spans.iter().map(|r| (r.start.round(), r.end.round())).collect::<Vec<_>>();
+    let three = SamplingStrategy::Segments { count: 3, secs_each: 10.0 };
+
+    assert_eq!(three.segment_spans(200.0).map(whole_secs), Some(vec![(20.0, 30.0), (100.0, 110.0), (180.0, 190.0)]));
+    // El último tramo no se sale del final de la pista.
+    let two = SamplingStrategy::Segments { count: 2, secs_each: 10.0 };
+    assert_eq!(two.segment_spans(40.0).map(whole_secs), Some(vec![(4.0, 14.0), (30.0, 40.0)]));
+    let one = SamplingStrategy::Segments { count: 1, secs_each: 10.0 };
+    assert_eq!(one.segment_spans(100.0).map(whole_secs), Some(vec![(50.0, 60.0)]));
+
+    assert_eq!(three.segment_spans(25.0), None);
+    assert_eq!(three.segment_spans(f64::NAN), None);
+    assert_eq!(SamplingStrategy::Segments { count: 0, secs_each: 10.0 }.segment_spans(200.0), None);
+    assert_eq!(SamplingStrategy::Prefix.segment_spans(200.0), None);
+  }
+
+  #[test]
+  fn overlap_outside_its_range_is_rejected_and_sets_the_hop() {
+    assert_eq!(
+      AnalysisConfig::builder().overlap_ratio(0.95).build().unwrap_err(),
+      AnalysisConfigError::OverlapOutOfRange(0.95)
+    );
+    assert_eq!(
+      AnalysisConfig::builder().overlap_ratio(-0.1).build().unwrap_err(),
+      AnalysisConfigError::OverlapOutOfRange(-0.1)
+    );
+
+    let half = AnalysisConfig::builder().fft_window_size(4096).overlap_ratio(0.5).build().unwrap();
+    assert_eq!(half.fft_hop_size(), 2048);
+    let none = AnalysisConfig::builder().fft_window_size(4096).overlap_ratio(0.0).build().unwrap();
+    assert_eq!(none.fft_hop_size(), 4096);
+  }
+
+  #[test]
+  fn window_size_must_fit_the_analysed_audio() {
+    let build = |builder: AnalysisConfigBuilder| builder.build().unwrap_err();
+
+    assert_eq!(build(AnalysisConfig::builder().fft_window_size(32)), AnalysisConfigError::WindowSizeTooSmall(32));
+    assert_eq!(
+      build(AnalysisConfig::builder().fft_window_size(3000)),
+      AnalysisConfigError::WindowSizeNotPowerOfTwo(3000)
+    );
+    // 0.1 s a 22050 Hz son 2205 muestras: no llegan a una ventana de 4096.
+    assert_eq!(
+      build(AnalysisConfig::builder().fft_window_size(4096).max_analysis_duration_secs(0.1)),
+      AnalysisConfigError::AnalysisTooShort { secs: 0.1, window: 4096 }
+    );
+    assert_eq!(
+      build(
+        AnalysisConfig::builder()
+          .fft_window_size(4096)
+          .sampling(SamplingStrategy::Segments { count: 3, secs_each: 0.1 })
+      ),
+      AnalysisConfigError::AnalysisTooShort { secs: 0.1, window: 4096 }
+    );
+
+    // Sin límite de duración (0) no hay nada que comprobar.
+    AnalysisConfig::builder().fft_window_size(65_536).max_analysis_duration_secs(0.0).build().unwrap();
+  }
 }
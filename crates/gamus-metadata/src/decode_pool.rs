@@ -0,0 +1,160 @@
+//! Pool de hilos dedicado a la decodificación y la FFT.
+//!
+//! `spawn_blocking` tira del pool bloqueante de Tokio (hasta 512 hilos), así que una
+//! importación con 50 lotes en paralelo acababa con 50 hilos de FFmpeg peleándose por
+//! los núcleos. Aquí el trabajo de CPU corre en un pool de tamaño fijo, por defecto uno
+//! por núcleo, y lo que no cabe espera en cola.
+
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use gamus_core::ports::MetadataError;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use tokio::sync::oneshot;
+use tracing::warn;
+
+/// `available_parallelism()`, o 1 si el sistema no lo sabe.
+pub(crate) fn cpu_count() -> usize {
+  thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// Pool compartido por todos los `FfmpegProbe` que no piden uno propio.
+static SHARED: OnceLock<DecodePool> = OnceLock::new();
+
+#[derive(Clone)]
+pub(crate) struct DecodePool {
+  pool: Arc<ThreadPool>,
+}
+
+impl DecodePool {
+  /// Crea un pool de `threads` hilos (mínimo uno). Falla si el sistema no deja crear los
+  /// hilos.
+  pub(crate) fn new(threads: usize) -> Result<Self, MetadataError> {
+    let pool = ThreadPoolBuilder::new()
+      .num_threads(threads.max(1))
+      .thread_name(|i| format!("gamus-decode-{i}"))
+      // Sin handler, rayon aborta el proceso ante un pánico que escape de un trabajo.
+      .panic_handler(|_| warn!("panic escaped a decode job"))
+      .build()
+      .map_err(|e| MetadataError::Internal(format!("failed to spawn decode threads: {e}")))?;
+    Ok(Self { pool: Arc::new(pool) })
+  }
+
+  /// Pool de [`cpu_count`] hilos, creado la primera vez que se pide.
+  ///
+  /// Si crearlo falla, se devuelve el error y la siguiente llamada lo vuelve a intentar. Dos
+  /// llamadas simultáneas pueden crear un pool cada una; solo se guarda el primero.
+  pub(crate) fn shared() -> Result<Self, MetadataError> {
+    if let Some(pool) = SHARED.get() {
+      return Ok(pool.clone());
+    }
+    let pool = Self::new(cpu_count())?;
+    Ok(SHARED.get_or_init(|| pool).clone())
+  }
+
+  pub(crate) fn threads(&self) -> usize {
+    self.pool.current_num_threads()
+  }
+
+  /// Encola `job` en el pool sin esperar a que termine.
+  ///
+  /// Los pánicos que escapen de `job` solo se registran; quien necesite convertirlos en
+  /// error debe capturarlos dentro (como hace `run`).
+  pub(crate) fn spawn(&self, job: impl FnOnce() + Send + 'static) {
+    self.pool.spawn(job);
+  }
+
+  /// Ejecuta `job` en el pool y espera su resultado sin bloquear el runtime.
+  ///
+  /// Un pánico dentro de `job` se convierte en `MetadataError::Internal`. Con `timeout`, si
+  /// `job` tarda más en terminar se devuelve `MetadataError::Internal("extraction timeout")`
+  /// sin esperarlo.
+  ///
+  /// El plazo empieza cuando un hilo del pool toma el trabajo, no al encolarlo: la espera en
+  /// cola no cuenta. Al vencer, el hilo no se puede matar y sigue ocupado hasta que `job`
+  /// vuelva; quien lo encole debe hacer que `job` aborte por su cuenta (ver
+  /// `ffmpeg_extractor::with_extract_deadline`).
+  pub(crate) async fn run<T: Send + 'static>(
+    &self,
+    timeout: Option<Duration>,
+    job: impl FnOnce() -> Result<T, MetadataError> + Send + 'static,
+  ) -> Result<T, MetadataError> {
+    let (started_tx, started_rx) = oneshot::channel();
+    let (tx, rx) = oneshot::channel();
+    self.spawn(move || {
+      let _ = started_tx.send(());
+      let result = panic::catch_unwind(AssertUnwindSafe(job))
+        .unwrap_or_else(|_| Err(MetadataError::Internal("panic while extracting metadata".to_string())));
+      let _ = tx.send(result);
+    });
+
+    let dropped = |e: oneshot::error::RecvError| MetadataError::Internal(format!("decode pool dropped the job: {e}"));
+    let Some(timeout) = timeout else {
+      return rx.await.map_err(dropped)?;
+    };
+    started_rx.await.map_err(dropped)?;
+    match tokio::time::timeout(timeout, rx).await {
+      Ok(result) => result.map_err(dropped)?,
+      Err(_) => Err(MetadataError::Internal("extraction timeout".to_string())),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  use super::*;
+
+  #[tokio::test]
+  async fn never_runs_more_jobs_than_threads() {
+    let pool = DecodePool::new(2).unwrap();
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+
+    let jobs = (0..8).map(|_| {
+      let (running, peak) = (Arc::clone(&running), Arc::clone(&peak));
+      pool.run(None, move || {
+        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+        peak.fetch_max(now, Ordering::SeqCst);
+        thread::sleep(std::time::Duration::from_millis(20));
+        running.fetch_sub(1, Ordering::SeqCst);
+        Ok(())
+      })
+    });
+    for result in futures::future::join_all(jobs).await {
+      result.unwrap();
+    }
+
+    assert_eq!(pool.threads(), 2);
+    assert!(peak.load(Ordering::SeqCst) <= 2);
+  }
+
+  #[tokio::test]
+  async fn a_job_over_its_timeout_is_abandoned_but_queue_time_does_not_count() {
+    let pool = DecodePool::new(1).unwrap();
+    let timeout = Some(Duration::from_millis(50));
+
+    // El primer trabajo ocupa el único hilo más que el plazo; el segundo espera en cola
+    // ese tiempo y aun así termina a tiempo, porque su plazo empieza al arrancar.
+    let slow = pool.run(timeout, || {
+      thread::sleep(Duration::from_millis(150));
+      Ok(())
+    });
+    let queued = pool.run(timeout, || Ok(()));
+    let (slow, queued) = futures::join!(slow, queued);
+
+    assert!(matches!(slow, Err(MetadataError::Internal(msg)) if msg == "extraction timeout"));
+    assert!(queued.is_ok());
+  }
+
+  #[tokio::test]
+  async fn a_panicking_job_becomes_an_error() {
+    let pool = DecodePool::new(1).unwrap();
+    let result: Result<(), _> = pool.run(None, || panic!("boom")).await;
+    assert!(matches!(result, Err(MetadataError::Internal(_))));
+  }
+}
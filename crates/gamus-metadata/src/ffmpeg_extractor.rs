@@ -1,52 +1,257 @@
-use std::collections::HashMap;
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
+use std::ffi::{CStr, CString, c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
 use std::path::{Path, PathBuf};
+use std::ptr;
 use std::str::FromStr;
-use std::time::{Duration, UNIX_EPOCH};
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::{Duration, Instant, UNIX_EPOCH};
 
 use async_trait::async_trait;
 use ffmpeg_next as ffmpeg;
+use ffmpeg_next::ffi;
+use futures::stream::{self, Stream, StreamExt};
+use tokio::sync::{Semaphore, SemaphorePermit, mpsc};
+use tracing::{debug, warn};
 
+use gamus_config::GenreMap;
+use gamus_core::domain::artist::Artist;
 use gamus_core::domain::release::Release;
 use gamus_core::domain::release_track::{AudioAnalysis, AudioQuality, QualityLevel};
 use gamus_core::domain::release_type::ReleaseType;
 use gamus_core::domain::{
   genre_styles::{Genre, Style},
-  ids::{ReleaseId, ReleaseTrackId, SongId},
+  ids::{ArtistId, ReleaseId, ReleaseTrackId, SongId},
   release_track::{AudioDetails, FileDetails, ReleaseTrack},
   song::Song,
 };
 use gamus_core::ports::{ExtractedMetadata, MetadataError, Probe};
 
 use crate::config::AnalysisConfig;
+use crate::decode_pool::{DecodePool, cpu_count};
 use crate::spectral_analyzer::SpectralAnalyzer;
 use crate::tag_keys::*;
+use crate::tag_split::TagSplitter;
 
 /// Adaptador FFmpeg que implementa el port `Probe`.
 ///
 /// - Se mantiene completamente en la capa de infraestructura.
 /// - No expone tipos de FFmpeg hacia el dominio.
 /// - El análisis espectral es opcional y configurable.
+///
+/// # Concurrencia
+/// Todo el trabajo de FFmpeg y FFT corre en un pool de hilos propio, de
+/// `available_parallelism()` hilos y compartido entre probes salvo que se pida otro con
+/// [`Self::with_decode_threads`]. El servicio decide cuántos lotes abre a la vez según el
+/// disco (`decide_concurrency`), pero eso solo limita cuántos archivos hay en cola: los que
+/// se decodifican de verdad a la vez nunca superan los hilos del pool. Con más lotes que
+/// hilos, los sobrantes esperan a que uno termine.
+///
+/// Dentro del pool, el análisis espectral (decodificar todo el audio + FFT) tiene además su
+/// propio límite, [`Self::with_analysis_concurrency`], por defecto también un núcleo por
+/// análisis. Con los valores por defecto no cambia nada; sirve para subir los hilos del pool
+/// (lecturas de tags y cabeceras, dominadas por E/S en discos lentos o de red) sin que
+/// crezca a la vez el número de FFT simultáneas.
+///
+/// # Plazo por archivo
+/// Un archivo corrupto puede dejar a FFmpeg colgado leyendo (un recurso de red que no
+/// responde, un demuxer en bucle). Cada archivo tiene un plazo, [`Self::with_extract_timeout`],
+/// que empieza cuando un hilo del pool lo toma; al vencer, la extracción devuelve
+/// `MetadataError::Internal("extraction timeout")` y la importación sigue con el resto.
+///
+/// Un hilo no se puede matar: el plazo también se instala como callback de interrupción del
+/// contexto de FFmpeg, que corta la E/S pendiente y hace volver al hilo. Un bucle dentro de
+/// un decodificador que no toque E/S no lo comprueba, y ese hilo queda ocupado hasta que
+/// termine por su cuenta.
+///
+/// # Detección de streams
+/// Al abrir un archivo, FFmpeg lee un trozo limitado para descubrir sus streams
+/// ([`ProbeLimits`], por defecto los suyos). Si con eso no aparece ninguno de audio, se
+/// reabre una vez con [`RETRY_PROBE_LIMITS`] antes de darlo por perdido.
 #[derive(Clone)]
 pub struct FfmpegProbe {
-  analysis_config: Option<AnalysisConfig>,
+  /// `None` desactiva el análisis. Compartido por los clones y sustituible en caliente
+  /// ([`Self::set_analysis_config`]).
+  analysis_config: Arc<RwLock<Option<AnalysisConfig>>>,
+  genre_map: Arc<GenreMap>,
+  /// Pool propio de [`Self::with_decode_threads`]; `None` usa el compartido.
+  decode_pool: Option<DecodePool>,
+  analysis_permits: Arc<Semaphore>,
+  keep_raw_tags: bool,
+  extract_timeout: Option<Duration>,
+  probe_limits: ProbeLimits,
+  /// Ver [`Probe::set_min_duration`]; compartido por los clones, como los permisos.
+  min_duration: Arc<RwLock<Option<Duration>>>,
 }
 
+/// Cuánto lee FFmpeg al abrir un archivo para descubrir sus streams.
+///
+/// `None` deja el valor por defecto de FFmpeg (5 MB y 5 s). Algunos contenedores (MKV
+/// grandes con solo audio, volcados de streams) necesitan más para encontrar la pista de
+/// audio; sin ella la extracción no trae datos de audio y el análisis falla con
+/// `NoCompatibleTrack`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ProbeLimits {
+  /// `probesize`: bytes que se leen como máximo.
+  pub probe_size: Option<u64>,
+  /// `analyzeduration`: cuánto contenido se examina como máximo.
+  pub analyze_duration: Option<Duration>,
+}
+
+/// Límites del segundo intento cuando el primero no encuentra audio.
+pub const RETRY_PROBE_LIMITS: ProbeLimits =
+  ProbeLimits { probe_size: Some(64 * 1024 * 1024), analyze_duration: Some(Duration::from_secs(60)) };
+
+impl ProbeLimits {
+  /// Límites del reintento: los de [`RETRY_PROBE_LIMITS`], sin bajar de los actuales.
+  /// `None` si ya eran al menos esos y no hay nada que subir.
+  fn escalated(self) -> Option<Self> {
+    let larger = Self {
+      probe_size: self.probe_size.max(RETRY_PROBE_LIMITS.probe_size),
+      analyze_duration: self.analyze_duration.max(RETRY_PROBE_LIMITS.analyze_duration),
+    };
+    (larger != self).then_some(larger)
+  }
+
+  fn to_dictionary(self) -> ffmpeg::Dictionary<'static> {
+    let mut options = ffmpeg::Dictionary::new();
+    if let Some(size) = self.probe_size {
+      options.set("probesize", &size.to_string());
+    }
+    if let Some(duration) = self.analyze_duration {
+      // En microsegundos.
+      options.set("analyzeduration", &duration.as_micros().to_string());
+    }
+    options
+  }
+}
+
+/// Plazo por defecto de cada archivo: de sobra para leer tags y analizar la ventana por
+/// defecto incluso en un disco de red lento.
+pub const DEFAULT_EXTRACT_TIMEOUT: Duration = Duration::from_secs(120);
+
 impl FfmpegProbe {
   pub fn new_with_analysis(config: AnalysisConfig) -> Self {
     if let Err(e) = ffmpeg::init() {
       // Log deliberado: no abortamos, pero queremos visibilidad en entorno de servidor.
-      eprintln!("Aviso: error inicializando FFmpeg: {e}");
+      warn!(error = %e, "error inicializando FFmpeg");
     }
 
-    Self { analysis_config: Some(config) }
+    Self {
+      analysis_config: Arc::new(RwLock::new(Some(config))),
+      genre_map: Arc::default(),
+      decode_pool: None,
+      analysis_permits: Arc::new(Semaphore::new(cpu_count())),
+      keep_raw_tags: false,
+      extract_timeout: Some(DEFAULT_EXTRACT_TIMEOUT),
+      probe_limits: ProbeLimits::default(),
+      min_duration: Arc::default(),
+    }
   }
 
   pub fn new_without_analysis() -> Self {
     if let Err(e) = ffmpeg::init() {
-      eprintln!("Aviso: error inicializando FFmpeg: {e}");
+      warn!(error = %e, "error inicializando FFmpeg");
     }
 
-    Self { analysis_config: None }
+    Self {
+      analysis_config: Arc::default(),
+      genre_map: Arc::default(),
+      decode_pool: None,
+      analysis_permits: Arc::new(Semaphore::new(cpu_count())),
+      keep_raw_tags: false,
+      extract_timeout: Some(DEFAULT_EXTRACT_TIMEOUT),
+      probe_limits: ProbeLimits::default(),
+      min_duration: Arc::default(),
+    }
+  }
+
+  /// Alias de géneros/estilos que se consultan antes del matching incorporado.
+  pub fn with_genre_map(mut self, genre_map: GenreMap) -> Self {
+    self.genre_map = Arc::new(genre_map);
+    self
+  }
+
+  /// Usa un pool de decodificación propio de `threads` hilos en vez del compartido. Falla
+  /// si el sistema no deja crear los hilos.
+  pub fn with_decode_threads(mut self, threads: usize) -> Result<Self, MetadataError> {
+    self.decode_pool = Some(DecodePool::new(threads)?);
+    Ok(self)
+  }
+
+  /// Hilos del pool donde corre la decodificación.
+  pub fn decode_threads(&self) -> usize {
+    self.decode_pool.as_ref().map_or_else(cpu_count, DecodePool::threads)
+  }
+
+  /// Sustituye los ajustes de análisis de este probe y de sus clones, p. ej. tras recargar
+  /// la configuración. Vale desde el siguiente archivo que se empiece a extraer; un lote en
+  /// curso sigue con los ajustes con los que empezó.
+  pub fn set_analysis_config(&self, config: AnalysisConfig) {
+    *self.analysis_config.write().unwrap_or_else(|e| e.into_inner()) = Some(config);
+  }
+
+  fn analysis_config(&self) -> Option<AnalysisConfig> {
+    self.analysis_config.read().unwrap_or_else(|e| e.into_inner()).clone()
+  }
+
+  fn min_duration(&self) -> Option<Duration> {
+    *self.min_duration.read().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// El pool propio, o el compartido (que se crea la primera vez que hace falta).
+  fn decode_pool(&self) -> Result<DecodePool, MetadataError> {
+    match &self.decode_pool {
+      Some(pool) => Ok(pool.clone()),
+      None => DecodePool::shared(),
+    }
+  }
+
+  /// Máximo de análisis espectrales simultáneos (mínimo uno), compartido por los clones
+  /// de este probe. Por defecto, uno por núcleo.
+  pub fn with_analysis_concurrency(mut self, limit: usize) -> Self {
+    self.analysis_permits = Arc::new(Semaphore::new(limit.max(1)));
+    self
+  }
+
+  /// Conserva en `ExtractedMetadata::raw_tags` todos los tags del archivo, no solo los que
+  /// se mapean al dominio.
+  ///
+  /// Desactivado por defecto: en importaciones grandes es un `HashMap` más por archivo que
+  /// nadie lee. Para inspeccionar un archivo suelto basta con [`Self::raw_tags`].
+  pub fn with_raw_tags(mut self, keep: bool) -> Self {
+    self.keep_raw_tags = keep;
+    self
+  }
+
+  /// Plazo de cada archivo (ver "Plazo por archivo" arriba). `None` lo desactiva; por
+  /// defecto, [`DEFAULT_EXTRACT_TIMEOUT`].
+  pub fn with_extract_timeout(mut self, timeout: Option<Duration>) -> Self {
+    self.extract_timeout = timeout;
+    self
+  }
+
+  /// Límites de detección de streams al abrir cada archivo (ver "Detección de streams"
+  /// arriba). Por defecto, los de FFmpeg.
+  pub fn with_probe_limits(mut self, limits: ProbeLimits) -> Self {
+    self.probe_limits = limits;
+    self
+  }
+
+  /// Todos los tags del contenedor de `path`, con las claves en minúsculas.
+  ///
+  /// Solo lee cabeceras: ni decodifica audio ni construye las entidades del dominio.
+  pub async fn raw_tags(&self, path: &Path) -> Result<HashMap<String, String>, MetadataError> {
+    let path_buf = PathBuf::from(path);
+    let timeout = self.extract_timeout;
+    let limits = self.probe_limits;
+    self
+      .decode_pool()?
+      .run(timeout, move || {
+        with_extract_deadline(timeout, || Ok(collect_normalized_tags(&open_ffmpeg_input(&path_buf, limits)?)))
+      })
+      .await
   }
 }
 
@@ -56,48 +261,292 @@ impl Default for FfmpegProbe {
   }
 }
 
+/// Resultados en vuelo por lote antes de que el hilo bloqueante espere al consumidor.
+const BATCH_CHANNEL_CAPACITY: usize = 16;
+
 #[async_trait]
 impl Probe for FfmpegProbe {
   async fn extract_from_path(&self, path: &Path) -> Result<ExtractedMetadata, MetadataError> {
     let path_buf = PathBuf::from(path);
-    let analysis_config = self.analysis_config.clone();
+    let analysis_config = self.analysis_config();
+    let genre_map = Arc::clone(&self.genre_map);
+    let permits = Arc::clone(&self.analysis_permits);
+    let keep_raw_tags = self.keep_raw_tags;
+    let timeout = self.extract_timeout;
+    let limits = self.probe_limits;
+    let min_duration = self.min_duration();
+
+    // Toda la parte bloqueante (FFmpeg + FFT) se delega al pool de decodificación.
+    self
+      .decode_pool()?
+      .run(timeout, move || {
+        let mut analyzer = analysis_config.map(SpectralAnalyzer::new_with_config);
+        with_extract_deadline(timeout, || {
+          extract_sync(&path_buf, analyzer.as_mut(), &permits, &genre_map, keep_raw_tags, limits, min_duration)
+        })
+      })
+      .await
+  }
+
+  /// Lee tags y cabeceras sin crear el `SpectralAnalyzer`: no se decodifica ningún paquete.
+  async fn extract_tags_only(&self, path: &Path) -> Result<ExtractedMetadata, MetadataError> {
+    let path_buf = PathBuf::from(path);
+    let genre_map = Arc::clone(&self.genre_map);
+    let permits = Arc::clone(&self.analysis_permits);
+    let keep_raw_tags = self.keep_raw_tags;
+    let timeout = self.extract_timeout;
+    let limits = self.probe_limits;
 
-    // Toda la parte bloqueante (FFmpeg + FFT) se delega a un hilo de trabajo.
-    tokio::task::spawn_blocking(move || extract_sync(&path_buf, analysis_config))
+    self
+      .decode_pool()?
+      .run(timeout, move || {
+        with_extract_deadline(timeout, || {
+          extract_sync(&path_buf, None, &permits, &genre_map, keep_raw_tags, limits, None)
+        })
+      })
       .await
-      .map_err(|e| MetadataError::Internal(format!("Tokio task join error: {e}")))?
+  }
+
+  /// Procesa todo el lote como un único trabajo del pool de decodificación, reutilizando el
+  /// `SpectralAnalyzer` (plan FFT y buffers) entre archivos.
+  ///
+  /// Los resultados viajan por un canal acotado: si el consumidor deja de leer, el hilo
+  /// se detiene en vez de seguir decodificando. Un pánico dentro de FFmpeg en un archivo
+  /// se convierte en error de ese archivo y el resto del lote continúa.
+  ///
+  /// Si un archivo agota su plazo, el hilo sigue atascado en él y el resto del lote no
+  /// llegaría nunca: se entrega el timeout de ese archivo y los que quedaban pasan a un
+  /// trabajo nuevo del pool, que empieza en cuanto haya un hilo libre. Si no se puede crear
+  /// el pool de decodificación, cada archivo sale con ese error.
+  fn extract_batch(
+    &self,
+    paths: &[PathBuf],
+  ) -> impl Stream<Item = (PathBuf, Result<ExtractedMetadata, MetadataError>)> + Send {
+    let pool = match self.decode_pool() {
+      Ok(pool) => pool,
+      Err(e) => {
+        // Cada archivo se lleva su propio error: `MetadataError` no es `Clone`.
+        let message = match e {
+          MetadataError::Internal(message) => message,
+          e => e.to_string(),
+        };
+        let failed = paths.to_vec().into_iter().map(move |path| (path, Err(MetadataError::Internal(message.clone()))));
+        return stream::iter(failed).left_stream();
+      }
+    };
+    let job = BatchJob {
+      pool,
+      analysis_config: self.analysis_config(),
+      genre_map: Arc::clone(&self.genre_map),
+      permits: Arc::clone(&self.analysis_permits),
+      keep_raw_tags: self.keep_raw_tags,
+      timeout: self.extract_timeout,
+      limits: self.probe_limits,
+      min_duration: self.min_duration(),
+    };
+
+    let rx = job.spawn(paths.to_vec());
+    let state = BatchState { job, rx, pending: paths.iter().cloned().collect() };
+    stream::unfold(state, |mut state| async move { state.next().await.map(|item| (item, state)) }).right_stream()
+  }
+
+  /// Los archivos cuya cabecera da una duración menor que `min` se devuelven sin análisis.
+  fn set_min_duration(&self, min: Option<Duration>) {
+    *self.min_duration.write().unwrap_or_else(|e| e.into_inner()) = min;
+  }
+}
+
+/// Mensajes del hilo de un lote: `Started` antes de cada archivo (arranca su plazo) y
+/// `Done` con su resultado.
+enum BatchMessage {
+  Started,
+  Done(PathBuf, Box<Result<ExtractedMetadata, MetadataError>>),
+}
+
+/// Ajustes del hilo de un lote, guardados para relanzar lo que queda tras un timeout.
+#[derive(Clone)]
+struct BatchJob {
+  pool: DecodePool,
+  analysis_config: Option<AnalysisConfig>,
+  genre_map: Arc<GenreMap>,
+  permits: Arc<Semaphore>,
+  keep_raw_tags: bool,
+  timeout: Option<Duration>,
+  limits: ProbeLimits,
+  min_duration: Option<Duration>,
+}
+
+impl BatchJob {
+  /// Encola en el pool un trabajo que extrae `paths` en orden y devuelve su canal.
+  fn spawn(&self, paths: Vec<PathBuf>) -> mpsc::Receiver<BatchMessage> {
+    let (tx, rx) = mpsc::channel(BATCH_CHANNEL_CAPACITY);
+    let job = self.clone();
+
+    self.pool.spawn(move || {
+      let mut analyzer = job.analysis_config.clone().map(SpectralAnalyzer::new_with_config);
+
+      for path in paths {
+        if tx.blocking_send(BatchMessage::Started).is_err() {
+          break;
+        }
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+          with_extract_deadline(job.timeout, || {
+            extract_sync(
+              &path,
+              analyzer.as_mut(),
+              &job.permits,
+              &job.genre_map,
+              job.keep_raw_tags,
+              job.limits,
+              job.min_duration,
+            )
+          })
+        }))
+        .unwrap_or_else(|_| {
+          // El estado interno del analizador ya no es fiable tras un pánico.
+          analyzer = job.analysis_config.clone().map(SpectralAnalyzer::new_with_config);
+          Err(MetadataError::Internal("panic while extracting metadata".to_string()))
+        });
+
+        if tx.blocking_send(BatchMessage::Done(path, Box::new(result))).is_err() {
+          break;
+        }
+      }
+    });
+
+    rx
+  }
+}
+
+/// Lado asíncrono de `extract_batch`: cuenta el plazo de cada archivo desde su `Started`.
+struct BatchState {
+  job: BatchJob,
+  rx: mpsc::Receiver<BatchMessage>,
+  /// Archivos aún sin resultado, en el orden en que el hilo los procesa.
+  pending: VecDeque<PathBuf>,
+}
+
+impl BatchState {
+  async fn next(&mut self) -> Option<(PathBuf, Result<ExtractedMetadata, MetadataError>)> {
+    loop {
+      let message = match self.rx.recv().await? {
+        BatchMessage::Started => match self.job.timeout {
+          None => continue,
+          Some(timeout) => match tokio::time::timeout(timeout, self.rx.recv()).await {
+            Ok(message) => message?,
+            Err(_) => {
+              // Cerrar el canal hace que el hilo pare en cuanto vuelva del archivo atascado;
+              // los que le quedaban siguen en un trabajo nuevo.
+              self.rx.close();
+              let path = self.pending.pop_front()?;
+              if !self.pending.is_empty() {
+                self.rx = self.job.spawn(self.pending.iter().cloned().collect());
+              }
+              return Some((path, Err(MetadataError::Internal("extraction timeout".to_string()))));
+            }
+          },
+        },
+        message => message,
+      };
+
+      if let BatchMessage::Done(path, result) = message {
+        self.pending.pop_front();
+        return Some((path, *result));
+      }
+    }
+  }
+}
+
+thread_local! {
+  /// Fin del plazo del archivo que se extrae en este hilo, consultado por el callback de
+  /// interrupción de FFmpeg (ver [`open_ffmpeg_input`]).
+  static EXTRACT_DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Ejecuta `extract` con un plazo de `timeout` desde ahora para las lecturas de FFmpeg.
+///
+/// Si vence, FFmpeg aborta la E/S en curso y el error resultante se sustituye por
+/// `"extraction timeout"`, el mismo que devuelve el lado asíncrono.
+fn with_extract_deadline<T>(
+  timeout: Option<Duration>,
+  extract: impl FnOnce() -> Result<T, MetadataError>,
+) -> Result<T, MetadataError> {
+  let deadline = timeout.map(|t| Instant::now() + t);
+  EXTRACT_DEADLINE.set(deadline);
+  let result = extract();
+  EXTRACT_DEADLINE.set(None);
+
+  match result {
+    Err(_) if deadline.is_some_and(|d| Instant::now() >= d) => {
+      Err(MetadataError::Internal("extraction timeout".to_string()))
+    }
+    result => result,
   }
 }
 
-/// Lógica principal síncrona, pensada para correrse en `spawn_blocking`.
-fn extract_sync(path: &Path, analysis_config: Option<AnalysisConfig>) -> Result<ExtractedMetadata, MetadataError> {
+/// Lógica principal síncrona, pensada para correrse en el pool de decodificación.
+///
+/// `analyzer` es opcional (análisis desactivado) y se puede reutilizar entre archivos;
+/// cuando está, el análisis espera a uno de los `analysis_permits`. No se analiza un archivo
+/// cuya cabecera da una duración menor que `min_duration`, que no se va a importar. Con
+/// `keep_raw_tags` el resultado se lleva también el mapa completo de tags.
+fn extract_sync(
+  path: &Path,
+  analyzer: Option<&mut SpectralAnalyzer>,
+  analysis_permits: &Semaphore,
+  genre_map: &GenreMap,
+  keep_raw_tags: bool,
+  limits: ProbeLimits,
+  min_duration: Option<Duration>,
+) -> Result<ExtractedMetadata, MetadataError> {
   let file_details = build_file_details(path)?;
-  let mut context = open_ffmpeg_input(path)?;
+  let mut context = open_ffmpeg_input(path, limits)?;
 
   let tags = collect_normalized_tags(&context);
 
   let song = build_song(path, &tags);
-  let release = build_release(&tags)?;
+  let artists = build_album_artists(&tags);
+  let mut release = build_release(&tags, genre_map)?;
+  release.main_artist_ids = artists.iter().map(|a| a.id).collect();
   let (duration, bitrate_kbps) = extract_container_level_audio_info(&context);
-  let (sample_rate_hz, channels) = extract_stream_level_audio_info(&mut context);
-  let quality = run_spectral_analysis(path, analysis_config)?;
-
-  let r = quality.clone().unwrap().report.level;
-  let a = quality.clone().unwrap().report.details;
+  let container = extract_container_name(&context);
+  let (sample_rate_hz, channels, codec_id) = extract_stream_level_audio_info(&mut context);
+  // Reutiliza la entrada ya abierta: hasta aquí solo se han leído cabeceras, no paquetes.
+  let lossless_codec = codec_id.filter(|&id| is_lossless_codec(id)).map(|id| id.name());
+  let too_short = min_duration.is_some_and(|min| !duration.is_zero() && duration < min);
+  let analyzer = analyzer.filter(|_| !too_short);
+  let quality = run_spectral_analysis(path, &mut context, analyzer, analysis_permits, lossless_codec)?;
 
-  match r {
-    QualityLevel::Low => println!("{} - Audio quality: Low ({:?})", path.display(), a),
-    _ => {}
+  if let Some(q) = &quality
+    && q.report.level == QualityLevel::Low
+  {
+    debug!(path = %path.display(), details = ?q.report.details, "audio quality: low");
   }
 
   let analysis = AudioAnalysis { bpm: None, features: None, quality };
 
-  let audio_details =
-    AudioDetails { duration, bitrate_kbps, sample_rate_hz, channels, analysis: Some(analysis), fingerprint: None };
+  let fingerprint = song.acoustid.clone();
+  let audio_details = AudioDetails {
+    duration,
+    bitrate_kbps,
+    sample_rate_hz,
+    channels,
+    analysis: Some(analysis),
+    fingerprint,
+    codec: codec_id.map(|id| id.name().to_string()),
+    container,
+    is_lossless: codec_id.map(is_lossless_codec),
+    replaygain_track_db: find_tag_float(&tags, KEYS_REPLAYGAIN_TRACK_GAIN),
+    replaygain_album_db: find_tag_float(&tags, KEYS_REPLAYGAIN_ALBUM_GAIN),
+    replaygain_track_peak: find_tag_float(&tags, KEYS_REPLAYGAIN_TRACK_PEAK),
+    replaygain_album_peak: find_tag_float(&tags, KEYS_REPLAYGAIN_ALBUM_PEAK),
+  };
 
   let track = build_release_track(&song, &release, &tags, audio_details, file_details);
 
-  Ok(ExtractedMetadata { song, release: Some(release), track: Some(track) })
+  let raw_tags = if keep_raw_tags { tags } else { HashMap::new() };
+
+  Ok(ExtractedMetadata { song, release: Some(release), track: Some(track), artists, raw_tags })
 }
 
 // ----- helpers de alto nivel ------------
@@ -112,15 +561,138 @@ fn build_file_details(path: &Path) -> Result<FileDetails, MetadataError> {
     .unwrap_or_default()
     .as_secs();
 
-  Ok(FileDetails { path: path.to_path_buf(), size: fs_metadata.len(), modified: modified_timestamp })
+  // El hash de contenido lo aporta el scanner; aquí no se vuelve a leer el archivo.
+  Ok(FileDetails {
+    path: path.to_path_buf(),
+    size: fs_metadata.len(),
+    modified: modified_timestamp,
+    content_hash: None,
+  })
+}
+
+fn open_ffmpeg_input(path: &Path, limits: ProbeLimits) -> Result<ffmpeg::format::context::Input, MetadataError> {
+  open_input(path, limits).map_err(|e| MetadataError::Unsupported(format!("FFmpeg open failed: {e}")))
+}
+
+/// Abre `path` con `limits` y, si no aparece ningún stream de audio, lo reabre una vez con
+/// [`ProbeLimits::escalated`]. Si el reintento tampoco encuentra audio (o falla) se devuelve
+/// la primera apertura: los tags siguen sirviendo.
+pub(crate) fn open_input(path: &Path, limits: ProbeLimits) -> Result<ffmpeg::format::context::Input, ffmpeg::Error> {
+  let has_audio = |input: &ffmpeg::format::context::Input| input.streams().best(ffmpeg::media::Type::Audio).is_some();
+
+  let input = open_input_once(path, limits)?;
+  if has_audio(&input) {
+    return Ok(input);
+  }
+  let Some(larger) = limits.escalated() else {
+    return Ok(input);
+  };
+
+  debug!(path = %path.display(), "no audio stream found, probing again with larger limits");
+  match open_input_once(path, larger) {
+    Ok(retried) if has_audio(&retried) => Ok(retried),
+    _ => Ok(input),
+  }
 }
 
-fn open_ffmpeg_input(path: &Path) -> Result<ffmpeg::format::context::Input, MetadataError> {
-  ffmpeg::format::input(path).map_err(|e| MetadataError::Unsupported(format!("FFmpeg open failed: {e}")))
+/// Callback de interrupción de FFmpeg: corta la E/S cuando vence el plazo de
+/// [`with_extract_deadline`] en este hilo.
+unsafe extern "C" fn interrupt_on_deadline(_opaque: *mut c_void) -> c_int {
+  c_int::from(EXTRACT_DEADLINE.get().is_some_and(|deadline| Instant::now() >= deadline))
 }
 
+/// `ffmpeg::format::input_with_dictionary` e `input_with_interrupt` a la vez, que
+/// `ffmpeg-next` no ofrece: las opciones de `limits` y el plazo del hilo como callback de
+/// interrupción.
+///
+/// El callback es una función sin estado (lee el plazo del hilo), así que, a diferencia
+/// del closure de `input_with_interrupt`, no hay nada que liberar.
+fn open_input_once(path: &Path, limits: ProbeLimits) -> Result<ffmpeg::format::context::Input, ffmpeg::Error> {
+  let url = path.to_str().and_then(|p| CString::new(p).ok()).ok_or(ffmpeg::Error::InvalidData)?;
+
+  // SAFETY: la misma secuencia que `input_with_dictionary`. `avformat_open_input` libera
+  // el contexto si falla; si no, se cierra aquí o pasa a ser del `Input`. El diccionario
+  // se recupera después de abrir para liberar las opciones que FFmpeg no haya consumido.
+  unsafe {
+    let mut context = ffi::avformat_alloc_context();
+    if context.is_null() {
+      return Err(ffmpeg::Error::Other { errno: ffmpeg::error::ENOMEM });
+    }
+    (*context).interrupt_callback =
+      ffi::AVIOInterruptCB { callback: Some(interrupt_on_deadline), opaque: ptr::null_mut() };
+
+    let mut options = limits.to_dictionary().disown();
+    let opened = ffi::avformat_open_input(&mut context, url.as_ptr(), ptr::null(), &mut options);
+    drop(ffmpeg::Dictionary::own(options));
+    if opened != 0 {
+      return Err(ffmpeg::Error::from(opened));
+    }
+
+    match ffi::avformat_find_stream_info(context, ptr::null_mut()) {
+      found if found >= 0 => Ok(ffmpeg::format::context::Input::wrap(context)),
+      e => {
+        ffi::avformat_close_input(&mut context);
+        Err(ffmpeg::Error::from(e))
+      }
+    }
+  }
+}
+
+/// Tags del contenedor con las claves en minúsculas.
+///
+/// Ver [`normalize_tags`] para el tratamiento de codificaciones rotas y claves repetidas.
 fn collect_normalized_tags(context: &ffmpeg::format::context::Input) -> HashMap<String, String> {
-  context.metadata().iter().map(|(k, v)| (k.to_lowercase(), v.to_string())).collect()
+  normalize_tags(raw_dictionary_entries(&context.metadata()))
+}
+
+/// Pares clave/valor de un diccionario de FFmpeg, como bytes sin decodificar.
+///
+/// `DictionaryRef::iter` construye los `&str` con `from_utf8_unchecked`, y FFmpeg copia
+/// los tags tal cual vienen en el archivo (ID3v1 o RIFF INFO en Latin-1, mojibake…), así
+/// que con un tag mal codificado ya tendríamos un `&str` inválido. Se recorre a mano.
+fn raw_dictionary_entries(dict: &ffmpeg::DictionaryRef<'_>) -> Vec<(Vec<u8>, Vec<u8>)> {
+  let mut entries = Vec::new();
+
+  // SAFETY: `dict` sigue vivo durante todo el recorrido y no se modifica; `av_dict_get`
+  // devuelve entradas del propio diccionario (null al terminar), cuyas `key` y `value`
+  // son cadenas C terminadas en NUL que se copian antes de pedir la siguiente.
+  unsafe {
+    let dict_ptr = dict.as_ptr();
+    let mut entry: *mut ffi::AVDictionaryEntry = ptr::null_mut();
+    loop {
+      entry = ffi::av_dict_get(dict_ptr, c"".as_ptr(), entry, ffi::AV_DICT_IGNORE_SUFFIX);
+      if entry.is_null() {
+        break;
+      }
+      let key = CStr::from_ptr((*entry).key).to_bytes().to_vec();
+      let value = CStr::from_ptr((*entry).value).to_bytes().to_vec();
+      entries.push((key, value));
+    }
+  }
+
+  entries
+}
+
+/// Normaliza los tags crudos: claves en minúsculas y sin espacios alrededor, texto
+/// decodificado con pérdida (los bytes que no son UTF-8 pasan a `U+FFFD`).
+///
+/// Si una clave aparece varias veces gana el último valor no vacío; los valores vacíos o
+/// solo con espacios no pisan uno anterior.
+fn normalize_tags<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+  entries: impl IntoIterator<Item = (K, V)>,
+) -> HashMap<String, String> {
+  let mut tags = HashMap::new();
+
+  for (key, value) in entries {
+    let key = String::from_utf8_lossy(key.as_ref()).trim().to_lowercase();
+    let value = String::from_utf8_lossy(value.as_ref());
+    if key.is_empty() || value.trim().is_empty() {
+      continue;
+    }
+    tags.insert(key, value.into_owned());
+  }
+
+  tags
 }
 
 fn build_song(path: &Path, tags: &HashMap<String, String>) -> Song {
@@ -129,17 +701,57 @@ fn build_song(path: &Path, tags: &HashMap<String, String>) -> Song {
     .or_else(|| path.file_stem().and_then(|s| s.to_str()).map(|s| s.to_string()))
     .unwrap_or_else(|| "Unknown Title".to_string());
 
-  Song { id: SongId::new(), title, acoustid: None }
+  // La huella permite fusionar distintas codificaciones de la misma grabación al importar.
+  let acoustid = find_tag_value(tags, KEYS_ACOUSTID_FINGERPRINT).map(|s| s.to_string());
+
+  let comments = find_tag_value(tags, KEYS_COMMENT).map(|s| vec![s.to_string()]).unwrap_or_default();
+
+  Song {
+    id: SongId::new(),
+    title,
+    acoustid,
+    lyrics: build_lyrics(path, tags),
+    comments,
+    isrc: find_tag_value(tags, KEYS_ISRC).map(|s| s.to_uppercase()),
+    mbid: find_tag_value(tags, KEYS_MB_RECORDING_ID).map(|s| s.to_lowercase()),
+  }
+}
+
+/// Se queda con la primera letra no vacía; un archivo puede traer varias (una por idioma)
+/// y el dominio sólo guarda una, así que las demás se dejan en el log.
+fn build_lyrics(path: &Path, tags: &HashMap<String, String>) -> Option<String> {
+  let mut values = find_lyrics_values(tags).into_iter();
+  let (_, lyrics) = values.next()?;
+
+  for (key, _) in values.filter(|(_, other)| *other != lyrics) {
+    debug!(path = %path.display(), tag = key, "ignoring additional lyrics entry");
+  }
+
+  Some(lyrics.to_string())
+}
+
+/// Un artista por cada valor de la etiqueta de album artist, partida con [`TagSplitter`]
+/// (así "AC/DC" sigue siendo uno solo).
+fn build_album_artists(tags: &HashMap<String, String>) -> Vec<Artist> {
+  let Some(value) = find_tag_value(tags, KEYS_ALBUM_ARTIST) else {
+    return Vec::new();
+  };
+
+  TagSplitter::default()
+    .split_multi(value)
+    .into_iter()
+    .map(|name| Artist { id: ArtistId::new(), name, variations: Vec::new(), bio: None, sites: Vec::new() })
+    .collect()
 }
 
-fn build_release(tags: &HashMap<String, String>) -> Result<Release, MetadataError> {
+fn build_release(tags: &HashMap<String, String>, genre_map: &GenreMap) -> Result<Release, MetadataError> {
   let album_title =
     find_tag_value(tags, KEYS_ALBUM).map(|s| s.to_string()).unwrap_or_else(|| "Unknown Album".to_string());
 
   let date_str = find_tag_value(tags, KEYS_DATE).map(|s| s.to_string());
   let raw_genre = find_tag_value(tags, KEYS_GENRE).map(|s| s.to_string());
 
-  let (genres, styles) = parse_genre_and_style(raw_genre)?;
+  let (genres, styles) = parse_genre_and_style(raw_genre, genre_map)?;
 
   Ok(Release {
     id: ReleaseId::new(),
@@ -151,21 +763,89 @@ fn build_release(tags: &HashMap<String, String>) -> Result<Release, MetadataErro
     artworks: Vec::new(),
     genres,
     styles,
+    mbid: find_tag_value(tags, KEYS_MB_RELEASE_ID).map(|s| s.to_lowercase()),
   })
 }
 
-fn parse_genre_and_style(raw: Option<String>) -> Result<(Vec<Genre>, Vec<Style>), MetadataError> {
+/// Fragmentos de una etiqueta de género: `;` es el separador fuerte.
+static GENRE_SEGMENTS: LazyLock<TagSplitter> = LazyLock::new(|| TagSplitter::new([';']));
+
+/// Tokens de un fragmento que no es un género completo.
+static GENRE_TOKENS: LazyLock<TagSplitter> = LazyLock::new(|| TagSplitter::new(['/', ',']));
+
+/// Separa una etiqueta de género cruda en géneros y estilos.
+///
+/// `;` se trata como separador fuerte. Cada fragmento se prueba primero completo como
+/// [`Genre`] (para respetar nombres como "Funk / Soul" o "Folk, World, & Country") y, si
+/// no coincide, se divide además por `/` y `,`. Ambos cortes pasan por [`TagSplitter`], con
+/// sus excepciones. Cada token que no sea un género conocido
+/// se conserva como [`Style`]. Los duplicados se descartan manteniendo el orden.
+///
+/// Los alias de `genre_map` se consultan antes que el matching incorporado, tanto para el
+/// fragmento completo (p. ej. "Hip-Hop/Rap") como para cada token.
+fn parse_genre_and_style(raw: Option<String>, genre_map: &GenreMap) -> Result<(Vec<Genre>, Vec<Style>), MetadataError> {
   let Some(source) = raw else {
     return Ok((Vec::new(), Vec::new()));
   };
 
-  // Se permite que falle tanto Genre como Style sin abortar el análisis completo.
-  if let Ok(genre) = Genre::from_str(&source) {
-    Ok((vec![genre], Vec::new()))
-  } else {
-    let style = Style::from_str(&source).unwrap();
-    Ok((Vec::new(), vec![style]))
+  let mut genres: Vec<Genre> = Vec::new();
+  let mut styles: Vec<Style> = Vec::new();
+
+  for segment in GENRE_SEGMENTS.split_multi(&source) {
+    let segment = segment.as_str();
+    if let Some(genre) = resolve_genre(segment, genre_map) {
+      if !genres.contains(&genre) {
+        genres.push(genre);
+      }
+      continue;
+    }
+    if let Some(style) = resolve_style_alias(segment, genre_map) {
+      if !styles.contains(&style) {
+        styles.push(style);
+      }
+      continue;
+    }
+
+    for token in GENRE_TOKENS.split_multi(segment) {
+      let token = token.as_str();
+      match resolve_genre(token, genre_map) {
+        Some(genre) => {
+          if !genres.contains(&genre) {
+            genres.push(genre);
+          }
+        }
+        None => {
+          // `Style::from_str` es infalible: lo desconocido termina en `Style::Custom`.
+          let style = resolve_style_alias(token, genre_map).unwrap_or_else(|| {
+            let Ok(style) = Style::from_str(token);
+            style
+          });
+          if !styles.contains(&style) {
+            styles.push(style);
+          }
+        }
+      }
+    }
+  }
+
+  Ok((genres, styles))
+}
+
+/// Alias configurado primero y, si no hay o apunta a un nombre inválido, el matching incorporado.
+fn resolve_genre(raw: &str, genre_map: &GenreMap) -> Option<Genre> {
+  if let Some(canonical) = genre_map.genre_alias(raw) {
+    match Genre::from_str(canonical) {
+      Ok(genre) => return Some(genre),
+      Err(_) => warn!(alias = raw, canonical, "genre alias points to an unknown genre, ignoring"),
+    }
   }
+  Genre::from_str(raw).ok()
+}
+
+fn resolve_style_alias(raw: &str, genre_map: &GenreMap) -> Option<Style> {
+  let canonical = genre_map.style_alias(raw)?;
+  let Ok(style) = Style::from_str(canonical);
+  Some(style)
 }
 
 fn build_release_track(
@@ -202,38 +882,285 @@ fn extract_container_level_audio_info(context: &ffmpeg::format::context::Input)
   (duration, bitrate_kbps)
 }
 
-fn extract_stream_level_audio_info(context: &mut ffmpeg::format::context::Input) -> (Option<u32>, Option<u8>) {
+/// Nombre largo del formato de entrada (`"FLAC raw"`, `"QuickTime / MOV"`…).
+fn extract_container_name(context: &ffmpeg::format::context::Input) -> Option<String> {
+  let format = context.format();
+  let name = format.description().trim();
+  if name.is_empty() { None } else { Some(name.to_string()) }
+}
+
+/// Frecuencia de muestreo, canales y códec del mejor stream de audio.
+///
+/// El códec se devuelve aunque no haya decoder disponible para él: basta con los parámetros del stream.
+fn extract_stream_level_audio_info(
+  context: &mut ffmpeg::format::context::Input,
+) -> (Option<u32>, Option<u8>, Option<ffmpeg::codec::Id>) {
   let audio_stream = context.streams().best(ffmpeg::media::Type::Audio);
 
   if let Some(stream) = audio_stream {
     let params = stream.parameters();
-    if let Ok(ctx) = ffmpeg::codec::context::Context::from_parameters(params) {
-      if let Ok(audio_decoder) = ctx.decoder().audio() {
-        let rate = audio_decoder.rate();
-        let channels = audio_decoder.channels();
-        return (Some(rate), Some(channels as u8));
-      }
+    let codec_id = Some(params.id()).filter(|id| *id != ffmpeg::codec::Id::None);
+    if let Ok(ctx) = ffmpeg::codec::context::Context::from_parameters(params)
+      && let Ok(audio_decoder) = ctx.decoder().audio()
+    {
+      let rate = audio_decoder.rate();
+      let channels = audio_decoder.channels();
+      return (Some(rate), Some(channels as u8), codec_id);
+    }
+    return (None, None, codec_id);
+  }
+
+  (None, None, None)
+}
+
+/// Códecs de audio sin pérdida. PCM y DSD son familias con decenas de variantes en
+/// FFmpeg (`pcm_s16le`, `pcm_f32be`, `dsd_lsbf`…), así que se reconocen por prefijo.
+fn is_lossless_codec(id: ffmpeg::codec::Id) -> bool {
+  use ffmpeg::codec::Id;
+
+  match id {
+    Id::FLAC
+    | Id::ALAC
+    | Id::WAVPACK
+    | Id::APE
+    | Id::TTA
+    | Id::TAK
+    | Id::MLP
+    | Id::TRUEHD
+    | Id::SHORTEN
+    | Id::WMALOSSLESS => true,
+    other => {
+      let name = other.name();
+      name.starts_with("pcm_") || name.starts_with("dsd_")
     }
   }
+}
 
-  (None, None)
+/// Espera a uno de los `permits` de análisis.
+///
+/// Estamos en un hilo del pool, fuera del runtime: se espera bloqueando este hilo. El
+/// semáforo nunca se cierra; si lo estuviera, se analiza sin límite antes que fallar.
+fn analysis_permit(permits: &Semaphore) -> Option<SemaphorePermit<'_>> {
+  futures::executor::block_on(permits.acquire()).ok()
 }
 
+/// Análisis espectral de la entrada ya abierta. `lossless_codec` es el nombre del códec si
+/// es sin pérdida: con `LosslessPolicy::SkipAnalysis` el archivo no se decodifica ni ocupa
+/// un permiso de análisis.
 fn run_spectral_analysis(
   path: &Path,
-  analysis_config: Option<AnalysisConfig>,
+  context: &mut ffmpeg::format::context::Input,
+  analyzer: Option<&mut SpectralAnalyzer>,
+  permits: &Semaphore,
+  lossless_codec: Option<&str>,
 ) -> Result<Option<AudioQuality>, MetadataError> {
-  let Some(config) = analysis_config else {
+  let Some(analyzer) = analyzer else {
     return Ok(None);
   };
+  if let Some(quality) = lossless_codec.and_then(|codec| analyzer.lossless_shortcut(codec)) {
+    return Ok(Some(quality));
+  }
 
-  let mut analyzer = SpectralAnalyzer::new();
-  match analyzer.analyze_file(path) {
+  let _permit = analysis_permit(permits);
+
+  match analyzer.analyze_input(context) {
     Ok(result) => Ok(Some(result)),
     Err(e) => {
       // No queremos que un fallo de análisis cancele la extracción de metadatos.
-      eprintln!("Aviso: fallo en análisis espectral para {:?}: {e}", path);
+      warn!(path = %path.display(), error = %e, "fallo en análisis espectral");
       Ok(None)
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn invalid_utf8_is_decoded_lossily() {
+    let tags = normalize_tags([(&b"GENRE"[..], &b"Synth\xe9pop"[..]), (b"Title", b"Caf\xc3\xa9")]);
+
+    assert_eq!(tags["genre"], "Synth\u{FFFD}pop");
+    assert_eq!(tags["title"], "Café");
+  }
+
+  #[test]
+  fn duplicate_keys_keep_the_last_non_empty_value() {
+    let tags = normalize_tags([("artist", "First"), ("ARTIST", "Second"), ("Artist ", "  "), ("album", "")]);
+
+    assert_eq!(tags["artist"], "Second");
+    assert!(!tags.contains_key("album"));
+  }
+
+  #[tokio::test]
+  async fn analysis_permits_cap_pool_threads_without_deadlocking() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Más hilos que permisos: los que no consiguen uno se quedan bloqueados en el pool
+    // hasta que otro lo suelte, sin depender del runtime de Tokio.
+    let pool = DecodePool::new(4).unwrap();
+    let permits = Arc::new(Semaphore::new(2));
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+
+    let jobs = (0..12).map(|_| {
+      let (permits, running, peak) = (Arc::clone(&permits), Arc::clone(&running), Arc::clone(&peak));
+      pool.run(None, move || {
+        let _permit = analysis_permit(&permits);
+        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+        peak.fetch_max(now, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(10));
+        running.fetch_sub(1, Ordering::SeqCst);
+        Ok(())
+      })
+    });
+    let results = tokio::time::timeout(Duration::from_secs(10), futures::future::join_all(jobs))
+      .await
+      .expect("analysis jobs deadlocked");
+
+    assert!(results.into_iter().all(|r| r.is_ok()));
+    assert!(peak.load(Ordering::SeqCst) <= 2);
+    assert_eq!(permits.available_permits(), 2);
+  }
+
+  #[test]
+  fn probe_limits_only_escalate_upwards() {
+    assert_eq!(ProbeLimits::default().escalated(), Some(RETRY_PROBE_LIMITS));
+
+    let generous = ProbeLimits { probe_size: Some(u64::MAX), analyze_duration: None };
+    let escalated = generous.escalated().unwrap();
+    assert_eq!(escalated.probe_size, Some(u64::MAX));
+    assert_eq!(escalated.analyze_duration, RETRY_PROBE_LIMITS.analyze_duration);
+
+    assert_eq!(RETRY_PROBE_LIMITS.escalated(), None);
+  }
+
+  #[test]
+  fn garbage_genre_becomes_a_custom_style_instead_of_failing() {
+    let tags = normalize_tags([(&b"genre"[..], &b"Rock; \xff\xfe"[..])]);
+
+    let (genres, styles) = parse_genre_and_style(tags.get("genre").cloned(), &GenreMap::default()).unwrap();
+
+    assert_eq!(genres, vec![Genre::Rock]);
+    assert_eq!(styles, vec![Style::Custom("\u{FFFD}\u{FFFD}".into())]);
+  }
+
+  #[test]
+  fn album_artist_tags_are_split_but_exceptions_survive() {
+    let names = |value: &str| -> Vec<String> {
+      build_album_artists(&normalize_tags([("ALBUMARTIST", value)])).into_iter().map(|a| a.name).collect()
+    };
+
+    assert_eq!(names("AC/DC"), ["AC/DC"]);
+    assert_eq!(names("AC/DC; Metallica"), ["AC/DC", "Metallica"]);
+    assert!(build_album_artists(&HashMap::new()).is_empty());
+
+    let (genres, styles) = parse_genre_and_style(Some("Rock; AC/DC".into()), &GenreMap::default()).unwrap();
+    assert_eq!(genres, vec![Genre::Rock]);
+    assert_eq!(styles, vec![Style::Custom("AC/DC".into())]);
+  }
+
+  #[test]
+  fn aliases_are_consulted_before_the_built_in_matching() {
+    let genre_map = GenreMap::new(
+      HashMap::from([("Hip-Hop/Rap".into(), "Hip Hop".into()), ("Electronica".into(), "Not a genre".into())]),
+      HashMap::from([("Synthwave".into(), "Synth-pop".into())]),
+    );
+
+    let (genres, styles) =
+      parse_genre_and_style(Some("Hip-Hop/Rap; Electronica; Synthwave".into()), &genre_map).unwrap();
+
+    // Sin alias, "Hip-Hop/Rap" se partiría en el género y un estilo "Rap"; un alias a un
+    // género inexistente se ignora y el valor sigue el camino normal.
+    assert_eq!(genres, vec![Genre::HipHop]);
+    assert_eq!(styles, vec![Style::Custom("Electronica".into()), Style::SynthPop]);
+  }
+
+  #[test]
+  fn external_ids_are_read_when_tagged_and_absent_otherwise() {
+    let path = Path::new("/music/roygbiv.flac");
+    let tagged = normalize_tags([
+      ("ISRC", "gbbpw9800012"),
+      ("MUSICBRAINZ_TRACKID", "3A6C9BD8-5A2E-4B3F-9DF6-0C5B9F1EC7A1"),
+      ("MUSICBRAINZ_ALBUMID", "f4b3b7a4-6f1c-4f7a-9c55-4a1f43b9ed2a"),
+    ]);
+
+    let song = build_song(path, &tagged);
+    assert_eq!(song.isrc.as_deref(), Some("GBBPW9800012"));
+    assert_eq!(song.mbid.as_deref(), Some("3a6c9bd8-5a2e-4b3f-9df6-0c5b9f1ec7a1"));
+    let release = build_release(&tagged, &GenreMap::default()).unwrap();
+    assert_eq!(release.mbid.as_deref(), Some("f4b3b7a4-6f1c-4f7a-9c55-4a1f43b9ed2a"));
+
+    let untagged = normalize_tags([("TITLE", "Roygbiv")]);
+    let song = build_song(path, &untagged);
+    assert_eq!((song.isrc, song.mbid), (None, None));
+    assert_eq!(build_release(&untagged, &GenreMap::default()).unwrap().mbid, None);
+  }
+
+  /// WAV mínimo (PCM 16 bits mono) con un bloque `LIST/INFO` cuyo `IGNR` (género) no es UTF-8.
+  fn wav_with_genre(genre: &[u8]) -> Vec<u8> {
+    let mut ignr = genre.to_vec();
+    ignr.push(0);
+    if ignr.len() % 2 == 1 {
+      ignr.push(0);
+    }
+
+    let mut info = b"INFO".to_vec();
+    info.extend_from_slice(b"IGNR");
+    info.extend_from_slice(&(genre.len() as u32 + 1).to_le_bytes());
+    info.extend_from_slice(&ignr);
+
+    let samples = vec![0u8; 8_000 * 2];
+    let mut body = b"WAVE".to_vec();
+    body.extend_from_slice(b"fmt ");
+    body.extend_from_slice(&16u32.to_le_bytes());
+    body.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    body.extend_from_slice(&1u16.to_le_bytes()); // mono
+    body.extend_from_slice(&8_000u32.to_le_bytes());
+    body.extend_from_slice(&16_000u32.to_le_bytes());
+    body.extend_from_slice(&2u16.to_le_bytes());
+    body.extend_from_slice(&16u16.to_le_bytes());
+    body.extend_from_slice(b"LIST");
+    body.extend_from_slice(&(info.len() as u32).to_le_bytes());
+    body.extend_from_slice(&info);
+    body.extend_from_slice(b"data");
+    body.extend_from_slice(&(samples.len() as u32).to_le_bytes());
+    body.extend_from_slice(&samples);
+
+    let mut wav = b"RIFF".to_vec();
+    wav.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    wav.extend_from_slice(&body);
+    wav
+  }
+
+  #[test]
+  fn file_with_a_mis_encoded_genre_tag_is_still_extracted() {
+    ffmpeg::init().unwrap();
+    let path = std::env::temp_dir().join(format!("gamus-bad-genre-{}.wav", std::process::id()));
+    std::fs::write(&path, wav_with_genre(b"Electronic; Synth\xe9pop")).unwrap();
+
+    let extracted =
+      extract_sync(&path, None, &Semaphore::new(1), &GenreMap::default(), false, ProbeLimits::default(), None);
+    let _ = std::fs::remove_file(&path);
+
+    let release = extracted.unwrap().release.unwrap();
+    assert_eq!(release.genres, vec![Genre::Electronic]);
+    assert_eq!(release.styles, vec![Style::Custom("Synth\u{FFFD}pop".into())]);
+  }
+
+  #[test]
+  fn raw_tags_are_only_kept_when_asked_for() {
+    ffmpeg::init().unwrap();
+    let path = std::env::temp_dir().join(format!("gamus-raw-tags-{}.wav", std::process::id()));
+    std::fs::write(&path, wav_with_genre(b"Electronic")).unwrap();
+
+    let kept = extract_sync(&path, None, &Semaphore::new(1), &GenreMap::default(), true, ProbeLimits::default(), None);
+    let dropped =
+      extract_sync(&path, None, &Semaphore::new(1), &GenreMap::default(), false, ProbeLimits::default(), None);
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(kept.unwrap().raw_tags.get("genre").map(String::as_str), Some("Electronic"));
+    assert!(dropped.unwrap().raw_tags.is_empty());
+  }
+}
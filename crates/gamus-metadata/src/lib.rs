@@ -1,7 +1,11 @@
 pub mod config;
 pub mod ffmpeg_extractor;
 pub mod spectral_analyzer;
+pub mod tag_split;
+pub mod thumbnail;
 
+pub(crate) mod decode_pool;
 pub(crate) mod tag_keys;
 
-pub use ffmpeg_extractor::FfmpegProbe;
+pub use ffmpeg_extractor::{FfmpegProbe, ProbeLimits};
+pub use thumbnail::ThumbnailCache;
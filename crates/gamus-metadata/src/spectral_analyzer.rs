@@ -2,8 +2,9 @@
 //!
 //! Responsabilidades principales:
 //! - Leer audio de fichero usando FFmpeg.
-//! - Convertir a mono float32 y limitar duración de análisis.
-//! - Acumular espectros de ventanas FFT con ventana de Hann.
+//! - Convertir a mono float32 y limitar el análisis a un prefijo o a varios tramos repartidos.
+//! - Medir la correlación entre canales en fuentes estéreo (detección de dual mono).
+//! - Acumular espectros de ventanas FFT con ventana de Hann (opcionalmente solapadas, Welch).
 //! - Detectar cutoff en altas frecuencias.
 //! - Mapear resultado a `AudioQuality` + `AudioQualityReport`.
 
@@ -15,7 +16,12 @@ use rustfft::{Fft, FftPlanner, num_complex::Complex};
 use std::path::Path;
 use std::sync::Arc;
 
-use crate::config::AnalysisConfig;
+use crate::config::{AnalysisConfig, DownmixMode, LosslessPolicy, NoiseFloorMode};
+use crate::ffmpeg_extractor::{ProbeLimits, open_input};
+
+/// Tramos en que se divide el espectro para estimar la envolvente inferior
+/// (ver `SpectralAnalyzer::lower_envelope_line`).
+const ENVELOPE_SEGMENTS: usize = 32;
 
 /// Errores posibles durante el análisis espectral.
 ///
@@ -48,6 +54,12 @@ pub struct SpectralAnalyzer {
   window: Vec<f32>,
 }
 
+impl Default for SpectralAnalyzer {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 impl SpectralAnalyzer {
   /// Crea un analizador con `AnalysisConfig::default()`.
   pub fn new() -> Self {
@@ -82,22 +94,73 @@ impl SpectralAnalyzer {
   /// 1. Cálculo de espectro promedio (por ventanas FFT).
   /// 2. Detección de cutoff / full band.
   /// 3. Scoring + caps por bitrate + reporte de alto nivel.
+  ///
+  /// Abre el archivo con los límites de detección por defecto, reintentando con otros
+  /// mayores si no aparece audio (ver [`ProbeLimits`]).
   pub fn analyze_file(&mut self, path: &Path) -> Result<AudioQuality, AnalysisError> {
-    let (sample_rate, avg_spectrum, bitrate_opt) = self.compute_average_spectrum(path)?;
-    let outcome = self.detect_cutoff(&avg_spectrum, sample_rate);
-    Ok(self.score_outcome(outcome, bitrate_opt))
+    let mut ictx = open_input(path, ProbeLimits::default())?;
+    self.analyze_input(&mut ictx)
+  }
+
+  /// Igual que `analyze_file`, pero sobre una entrada FFmpeg ya abierta.
+  ///
+  /// Permite a quien ya abrió el archivo (p.ej. para leer tags) no abrirlo otra vez.
+  /// La entrada no debe haber leído paquetes todavía, y queda consumida (posición
+  /// indeterminada) al terminar.
+  pub fn analyze_input(&mut self, ictx: &mut ffmpeg::format::context::Input) -> Result<AudioQuality, AnalysisError> {
+    let spectrum = self.compute_average_spectrum(ictx)?;
+    let outcome = self.detect_cutoff(&spectrum.spectrum_db, spectrum.sample_rate);
+    Ok(self.score_outcome(outcome, spectrum.bitrate, spectrum.stereo_correlation))
+  }
+
+  /// Resultado sin decodificar nada para un archivo con códec sin pérdida `codec`, si
+  /// `lossless_policy` es `SkipAnalysis`; `None` si hay que analizarlo.
+  pub fn lossless_shortcut(&self, codec: &str) -> Option<AudioQuality> {
+    match self.config.lossless_policy {
+      LosslessPolicy::AlwaysVerify => None,
+      LosslessPolicy::SkipAnalysis => {
+        Some(self.score_outcome(AnalysisOutcome::AssumedLossless { codec: codec.to_string() }, None, None))
+      }
+    }
+  }
+
+  /// Adelanta la entrada hasta `analysis_start_secs` antes de empezar a decodificar.
+  ///
+  /// Si el offset no cabe en la duración conocida, o el demuxer no soporta seek,
+  /// se deja la entrada al principio (mismo comportamiento que sin offset).
+  fn seek_to_analysis_start(&self, ictx: &mut ffmpeg::format::context::Input) {
+    let start_secs = self.config.analysis_start_secs;
+    if start_secs <= 0.0 {
+      return;
+    }
+
+    // `Input::seek` trabaja en AV_TIME_BASE (microsegundos) cuando no se fija stream.
+    let start_ts = (f64::from(start_secs) * 1_000_000.0) as i64;
+    let duration = ictx.duration();
+    if duration > 0 && start_ts >= duration {
+      return;
+    }
+
+    // `..start_ts`: keyframe en o antes del objetivo, nunca después.
+    if ictx.seek(start_ts, ..start_ts).is_err() {
+      // Algunos formatos sin índice fallan aquí; analizar desde el inicio sigue siendo válido.
+      let _ = ictx.seek(0, ..0);
+    }
   }
 
   /// Calcula el espectro medio (en dB) del fichero.
   ///
   /// - Escoge el mejor stream de audio con FFmpeg.
-  /// - Re-muestrea a mono float32.
-  /// - Aplica ventanas FFT con Hann.
+  /// - Re-muestrea a float32 (estéreo si se mide correlación o el downmix no es la media, mono en otro caso).
+  /// - Aplica ventanas FFT con Hann sobre la mezcla mono.
   /// - Promedia el módulo del espectro en todas las ventanas.
   ///
-  /// Respeta `max_analysis_duration_secs` para acotar el trabajo.
-  fn compute_average_spectrum(&mut self, path: &Path) -> Result<(u32, Vec<f32>, Option<i64>), AnalysisError> {
-    let mut ictx = ffmpeg::format::input(path)?;
+  /// Respeta `max_analysis_duration_secs` para acotar el trabajo, contando desde el final
+  /// del silencio inicial si `silence_trim_db` está activo.
+  fn compute_average_spectrum(
+    &mut self,
+    ictx: &mut ffmpeg::format::context::Input,
+  ) -> Result<AverageSpectrum, AnalysisError> {
     let input_stream = ictx.streams().best(ffmpeg::media::Type::Audio).ok_or(AnalysisError::NoCompatibleTrack)?;
     let stream_index = input_stream.index();
 
@@ -112,34 +175,104 @@ impl SpectralAnalyzer {
     let decoder_bitrate = decoder.bit_rate();
     let bitrate_opt = if decoder_bitrate > 0 { Some(decoder_bitrate as i64) } else { None };
 
-    let mut magnitude_acc = vec![0.0f32; self.config.fft_window_size / 2];
-    let mut window_count = 0usize;
-    let mut samples_buffer = Vec::with_capacity(self.config.fft_window_size);
-
-    let dst_format = ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed);
-    let dst_layout = ffmpeg::util::channel_layout::ChannelLayout::MONO;
+    // Solo pedimos estéreo al resampler si la fuente lo es y algo necesita los canales por
+    // separado (correlación L/R o un downmix distinto de la media). La FFT siempre trabaja
+    // sobre la señal mono resultante.
+    let source_is_stereo = decoder.channels() >= 2;
+    let measure_stereo = self.config.stereo.measure_correlation && source_is_stereo;
+    let downmix = self.config.downmix;
+    let keep_stereo = source_is_stereo && (measure_stereo || downmix != DownmixMode::Average);
+
+    // Solo se re-muestrea hacia abajo (ver `AnalysisConfig::analysis_sample_rate`).
+    let target_rate = self.config.analysis_sample_rate.filter(|&rate| rate < sample_rate);
+    let analysis_rate = target_rate.unwrap_or(sample_rate);
+
+    let mut acc = SpectrumAccumulator::new(
+      self.config.fft_window_size,
+      self.config.fft_hop_size(),
+      keep_stereo,
+      measure_stereo,
+      downmix,
+    );
+    acc.target_rate = target_rate;
     let mut resampler: Option<ffmpeg::software::resampling::Context> = None;
 
-    let max_samples = if self.config.max_analysis_duration_secs > 0.0 {
-      Some((self.config.max_analysis_duration_secs * sample_rate as f32) as usize)
-    } else {
-      None
-    };
+    // `Input::duration` va en AV_TIME_BASE (microsegundos); `<= 0` es duración desconocida.
+    let duration = ictx.duration();
+    let duration_secs = if duration > 0 { duration as f64 / 1_000_000.0 } else { f64::NAN };
 
-    let mut total_samples_processed = 0usize;
-    let mut stop = false;
-
-    // Función local para procesar una tira de samples mono.
-    let mut process_plane = |plane: &[f32], analyzer: &mut SpectralAnalyzer| {
-      for &sample in plane {
-        samples_buffer.push(sample);
-        if samples_buffer.len() == analyzer.config.fft_window_size {
-          analyzer.process_fft_window(&samples_buffer, &mut magnitude_acc);
-          samples_buffer.clear();
-          window_count += 1;
+    let mut sampled = false;
+    if let Some(spans) = self.config.sampling.segment_spans(duration_secs) {
+      for span in spans {
+        let start_ts = (span.start * 1_000_000.0) as i64;
+        if ictx.seek(start_ts, ..start_ts).is_err() {
+          continue;
         }
+        // Tras un seek hay que descartar lo que el decoder tenga en cola, y una ventana FFT
+        // no debe mezclar muestras de dos tramos distintos.
+        decoder.flush();
+        acc.start_segment();
+
+        let max_samples = ((span.end - span.start) * f64::from(analysis_rate)) as usize;
+        self.decode_span(ictx, &mut decoder, stream_index, &mut resampler, &mut acc, Some(max_samples))?;
       }
-    };
+
+      sampled = acc.window_count > 0;
+      if !sampled {
+        // Ningún seek funcionó: volvemos al principio y seguimos como `Prefix`.
+        let _ = ictx.seek(0, ..0);
+        decoder.flush();
+        acc.start_segment();
+      }
+    }
+
+    if !sampled {
+      self.seek_to_analysis_start(ictx);
+      acc.trim_leading_silence(self.config.silence_trim_db);
+
+      let max_samples = if self.config.max_analysis_duration_secs > 0.0 {
+        Some((self.config.max_analysis_duration_secs * analysis_rate as f32) as usize)
+      } else {
+        None
+      };
+      self.decode_span(ictx, &mut decoder, stream_index, &mut resampler, &mut acc, max_samples)?;
+    }
+
+    if acc.window_count == 0 {
+      return Err(AnalysisError::InvalidAudioFormat);
+    }
+
+    let avg_spectrum_db: Vec<f32> = acc
+      .magnitude_acc
+      .iter()
+      .map(|mag_sum| {
+        let avg_mag = mag_sum / acc.window_count as f32;
+        20.0 * avg_mag.max(1e-10).log10()
+      })
+      .collect();
+
+    Ok(AverageSpectrum {
+      sample_rate: analysis_rate,
+      spectrum_db: avg_spectrum_db,
+      bitrate: bitrate_opt,
+      stereo_correlation: acc.correlation.coefficient(),
+    })
+  }
+
+  /// Decodifica desde la posición actual de `ictx` y acumula en `acc`.
+  ///
+  /// Para al alcanzar `max_samples` (por canal) o al final del stream; en el segundo
+  /// caso vacía decoder y resampler para no perder la cola.
+  fn decode_span(
+    &mut self,
+    ictx: &mut ffmpeg::format::context::Input,
+    decoder: &mut ffmpeg::decoder::Audio,
+    stream_index: usize,
+    resampler: &mut Option<ffmpeg::software::resampling::Context>,
+    acc: &mut SpectrumAccumulator,
+    max_samples: Option<usize>,
+  ) -> Result<(), AnalysisError> {
+    let mut total_samples_processed = 0usize;
 
     for (stream, packet) in ictx.packets() {
       if stream.index() != stream_index {
@@ -150,77 +283,53 @@ impl SpectralAnalyzer {
       let mut decoded = ffmpeg::util::frame::Audio::empty();
 
       while decoder.receive_frame(&mut decoded).is_ok() {
-        if resampler.is_none() || resampler.as_ref().unwrap().input().rate != decoded.rate() {
-          resampler = Some(ffmpeg::software::resampling::Context::get(
-            decoded.format(),
-            decoded.channel_layout(),
-            decoded.rate(),
-            dst_format,
-            dst_layout,
-            decoded.rate(),
-          )?);
-        }
-
-        let r = resampler.as_mut().unwrap();
-        let mut resampled = ffmpeg::util::frame::Audio::empty();
-        let _ = r.run(&decoded, &mut resampled)?;
+        total_samples_processed += self.accumulate_frame(acc, resampler, &decoded)?;
 
-        let plane = resampled.plane::<f32>(0);
-        if !plane.is_empty() {
-          process_plane(plane, self);
-          total_samples_processed += plane.len();
+        if let Some(max) = max_samples
+          && total_samples_processed >= max
+        {
+          return Ok(());
         }
-
-        if let Some(max) = max_samples {
-          if total_samples_processed >= max {
-            stop = true;
-            break;
-          }
-        }
-      }
-
-      if stop {
-        break;
       }
     }
 
     // Flush final para vaciar buffers de decoder / resampler.
-    if !stop {
-      decoder.send_eof()?;
-      let mut decoded = ffmpeg::util::frame::Audio::empty();
+    decoder.send_eof()?;
+    let mut decoded = ffmpeg::util::frame::Audio::empty();
 
-      while decoder.receive_frame(&mut decoded).is_ok() {
-        let r = resampler.as_mut().unwrap();
-        let mut resampled = ffmpeg::util::frame::Audio::empty();
-        let _ = r.run(&decoded, &mut resampled)?;
-        process_plane(resampled.plane::<f32>(0), self);
-      }
+    while decoder.receive_frame(&mut decoded).is_ok() {
+      self.accumulate_frame(acc, resampler, &decoded)?;
+    }
 
-      if let Some(ref mut r) = resampler {
-        let mut resampled = ffmpeg::util::frame::Audio::empty();
-        while r.flush(&mut resampled).is_ok() {
-          let plane = resampled.plane::<f32>(0);
-          if plane.is_empty() {
-            break;
-          }
-          process_plane(plane, self);
+    if let Some(r) = resampler {
+      let mut resampled = ffmpeg::util::frame::Audio::empty();
+      while r.flush(&mut resampled).is_ok() {
+        // Con el recorte de silencio activo `push_frame` puede devolver 0 aunque el frame traiga muestras.
+        if resampled.samples() == 0 {
+          break;
         }
+        acc.push_frame(&resampled, self);
       }
     }
 
-    if window_count == 0 {
-      return Err(AnalysisError::InvalidAudioFormat);
-    }
+    Ok(())
+  }
 
-    let avg_spectrum_db: Vec<f32> = magnitude_acc
-      .iter()
-      .map(|mag_sum| {
-        let avg_mag = mag_sum / window_count as f32;
-        20.0 * avg_mag.max(1e-10).log10()
-      })
-      .collect();
+  /// Acumula un frame decodificado, pasando por el resampler solo si hace falta.
+  ///
+  /// Devuelve el nº de samples (por canal) consumidos.
+  fn accumulate_frame(
+    &mut self,
+    acc: &mut SpectrumAccumulator,
+    resampler: &mut Option<ffmpeg::software::resampling::Context>,
+    decoded: &ffmpeg::util::frame::Audio,
+  ) -> Result<usize, AnalysisError> {
+    if acc.is_passthrough(decoded) {
+      return Ok(acc.process_plane(decoded.plane::<f32>(0), self));
+    }
 
-    Ok((sample_rate, avg_spectrum_db, bitrate_opt))
+    let resampled = acc.resample(resampler, decoded)?;
+    Ok(acc.push_frame(&resampled, self))
   }
 
   /// Media en dB del espectro en una banda [start, end] (Hz).
@@ -253,8 +362,8 @@ impl SpectralAnalyzer {
       self.fft_buffer[i] = Complex::new(sample * self.window[i], 0.0);
     }
     self.fft.process_with_scratch(&mut self.fft_buffer, &mut self.scratch_buffer);
-    for i in 0..acc.len() {
-      acc[i] += self.fft_buffer[i].norm();
+    for (slot, bin) in acc.iter_mut().zip(&self.fft_buffer) {
+      *slot += bin.norm();
     }
   }
 
@@ -263,57 +372,127 @@ impl SpectralAnalyzer {
   /// Detecta cutoff o espectro completo a partir del espectro medio.
   ///
   /// Estrategia:
-  /// - Calcula un noise floor (base + margen dinámico).
+  /// - Calcula un noise floor (base + margen dinámico, o la recta de
+  ///   [`NoiseFloorMode::Tilted`]).
   /// - Escanea en reversa desde Nyquist en bandas configurables.
   /// - La última banda con energía por encima del floor define `found_cutoff_freq`.
   /// - Si está suficientemente lejos de Nyquist (`margin_from_nyquist_hz`), se considera cutoff.
+  /// - Si ninguna banda supera el floor plano: con el máximo global por debajo de
+  ///   `digital_silence_db` es silencio (no concluyente); si no, es contenido real pero bajo
+  ///   o sin agudos, y se repite el barrido solo con el margen dinámico.
   fn detect_cutoff(&self, spectrum_db: &[f32], sample_rate: u32) -> AnalysisOutcome {
     let nyquist = sample_rate as f32 / 2.0;
 
     let global_max = spectrum_db.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    if !global_max.is_finite() || global_max < self.config.noise.digital_silence_db {
+      return AnalysisOutcome::Inconclusive("Audio silente".into());
+    }
 
-    let mut noise_floor = self.config.noise.base_floor_db;
+    // (frecuencia de la banda más alta con energía, su nivel, floor en esa frecuencia)
+    let highest = match self.config.noise.floor_mode {
+      NoiseFloorMode::Flat => {
+        let dyn_floor = global_max - self.config.noise.dynamic_margin_db;
+        let mut noise_floor = self.config.noise.base_floor_db.max(dyn_floor);
 
-    if global_max.is_finite() {
-      let dyn_floor = global_max - self.config.noise.dynamic_margin_db;
-      noise_floor = noise_floor.max(dyn_floor);
+        let mut highest = self.highest_band_above(spectrum_db, sample_rate, |_| noise_floor);
+        if highest.is_none() && dyn_floor < noise_floor {
+          noise_floor = dyn_floor;
+          highest = self.highest_band_above(spectrum_db, sample_rate, |_| noise_floor);
+        }
+        highest.map(|(freq, db)| (freq, db, noise_floor))
+      }
+      NoiseFloorMode::Tilted { margin_db } => {
+        let Some((intercept, slope)) = self.lower_envelope_line(spectrum_db, sample_rate) else {
+          return AnalysisOutcome::Inconclusive("Sin suelo de ruido medible".into());
+        };
+        let local_floor = |hz: f32| intercept + slope * hz + margin_db;
+        self.highest_band_above(spectrum_db, sample_rate, local_floor).map(|(freq, db)| (freq, db, local_floor(freq)))
+      }
+    };
+
+    let Some((found_cutoff_freq, max_db_found, cut_db)) = highest else {
+      return AnalysisOutcome::Inconclusive("Sin energía significativa en ninguna banda".into());
+    };
+
+    // Margen parametrizado
+    if nyquist - found_cutoff_freq > self.config.reverse_scan.margin_from_nyquist_hz {
+      AnalysisOutcome::CutoffDetected { freq: found_cutoff_freq, ref_db: max_db_found, cut_db }
+    } else {
+      AnalysisOutcome::NoCutoffDetected { ref_db: max_db_found, max_freq: found_cutoff_freq }
     }
+  }
 
+  /// Barrido en reversa desde Nyquist: extremo superior y nivel de la primera banda (la más
+  /// alta) cuya media supera `floor` evaluado en el centro de la banda.
+  fn highest_band_above(
+    &self,
+    spectrum_db: &[f32],
+    sample_rate: u32,
+    floor: impl Fn(f32) -> f32,
+  ) -> Option<(f32, f32)> {
+    let nyquist = sample_rate as f32 / 2.0;
     let step_hz = self.config.reverse_scan.band_width_hz.max(100.0);
 
-    let mut found_cutoff_freq = 0.0;
-    let mut max_db_found = -100.0;
-
     let mut f = (nyquist / step_hz).floor() * step_hz;
     while f >= step_hz {
-      let start = f - step_hz;
-      let end = f;
-
-      if let Some(db) = self.band_db(spectrum_db, sample_rate, start, end) {
-        if db > noise_floor {
-          found_cutoff_freq = end;
-          max_db_found = db;
-          break;
-        }
+      if let Some(db) = self.band_db(spectrum_db, sample_rate, f - step_hz, f)
+        && db > floor(f - step_hz / 2.0)
+      {
+        return Some((f, db));
       }
-
       f -= step_hz;
     }
+    None
+  }
 
-    if found_cutoff_freq <= 0.0 {
-      return AnalysisOutcome::Inconclusive("Audio silente o sin energía significativa en alta frecuencia".into());
+  /// Recta `(dB en 0 Hz, dB por Hz)` ajustada por mínimos cuadrados a la envolvente inferior
+  /// del espectro, para [`NoiseFloorMode::Tilted`].
+  ///
+  /// La envolvente es el percentil 10 de cada uno de [`ENVELOPE_SEGMENTS`] tramos iguales;
+  /// los tramos por debajo de `digital_silence_db` se descartan. `None` con menos de dos
+  /// tramos útiles.
+  fn lower_envelope_line(&self, spectrum_db: &[f32], sample_rate: u32) -> Option<(f32, f32)> {
+    let nyquist = sample_rate as f32 / 2.0;
+    let seg_len = spectrum_db.len() / ENVELOPE_SEGMENTS;
+    if seg_len == 0 {
+      return None;
     }
-
-    // Margen parametrizado
-    if nyquist - found_cutoff_freq > self.config.reverse_scan.margin_from_nyquist_hz {
-      AnalysisOutcome::CutoffDetected { freq: found_cutoff_freq, ref_db: max_db_found, cut_db: noise_floor }
-    } else {
-      AnalysisOutcome::NoCutoffDetected { ref_db: max_db_found, max_freq: found_cutoff_freq }
+    let bin_hz = nyquist / spectrum_db.len() as f32;
+
+    let points: Vec<(f32, f32)> = spectrum_db
+      .chunks_exact(seg_len)
+      .enumerate()
+      .filter_map(|(i, seg)| {
+        let mut sorted = seg.to_vec();
+        sorted.sort_by(f32::total_cmp);
+        let low = sorted[sorted.len() / 10];
+        let center_hz = (i as f32 + 0.5) * seg_len as f32 * bin_hz;
+        (low >= self.config.noise.digital_silence_db).then_some((center_hz, low))
+      })
+      .collect();
+    if points.len() < 2 {
+      return None;
     }
+
+    let n = points.len() as f32;
+    let mean_hz = points.iter().map(|p| p.0).sum::<f32>() / n;
+    let mean_db = points.iter().map(|p| p.1).sum::<f32>() / n;
+    let var: f32 = points.iter().map(|p| (p.0 - mean_hz).powi(2)).sum();
+    let cov: f32 = points.iter().map(|p| (p.0 - mean_hz) * (p.1 - mean_db)).sum();
+    let slope = cov / var;
+    Some((mean_db - slope * mean_hz, slope))
   }
 
   /// Asigna una puntuación al resultado del análisis y aplica caps por bitrate.
-  fn score_outcome(&self, outcome: AnalysisOutcome, bitrate: Option<i64>) -> AudioQuality {
+  ///
+  /// Una correlación L/R por encima de `pseudo_stereo_threshold` no penaliza la nota
+  /// (el espectro no cambia), pero se anota en el `assessment`.
+  fn score_outcome(
+    &self,
+    outcome: AnalysisOutcome,
+    bitrate: Option<i64>,
+    stereo_correlation: Option<f32>,
+  ) -> AudioQuality {
     let (mut score, mut assessment) = match &outcome {
       AnalysisOutcome::CutoffDetected { freq, .. } => {
         let s = self.config.scoring.score_for_cutoff(*freq);
@@ -323,6 +502,9 @@ impl SpectralAnalyzer {
         let s = self.config.scoring.score_for_full_band(*max_freq);
         (s, "Espectro completo".into())
       }
+      AnalysisOutcome::AssumedLossless { codec } => {
+        (self.config.scoring.full_band_scores.0, format!("Sin pérdida ({codec}), sin analizar"))
+      }
       AnalysisOutcome::Inconclusive(reason) => (0.0, format!("Error: {}", reason)),
     };
 
@@ -331,21 +513,18 @@ impl SpectralAnalyzer {
       self.config.bitrate_safety.apply_cap(br, &mut score, &mut assessment);
     }
 
-    let report = self.build_report(&outcome, score, &assessment);
+    if stereo_correlation.is_some_and(|c| c >= self.config.stereo.pseudo_stereo_threshold) {
+      assessment.push_str(" (Pseudo-estéreo / dual mono)");
+    }
+
+    let mut report = self.build_report(&outcome, score, &assessment);
+    report.stereo_correlation = stereo_correlation;
     AudioQuality { outcome, quality_score: score, assessment, report }
   }
 
   /// Construye el `AudioQualityReport` de alto nivel a partir del resultado.
   fn build_report(&self, outcome: &AnalysisOutcome, score: f32, assessment: &str) -> AudioQualityReport {
-    let level = if score >= 9.5 {
-      QualityLevel::Perfect
-    } else if score >= 8.0 {
-      QualityLevel::High
-    } else if score >= 5.5 {
-      QualityLevel::Medium
-    } else {
-      QualityLevel::Low
-    };
+    let level = self.config.scoring.level_thresholds.level_for(score);
 
     match outcome {
       AnalysisOutcome::CutoffDetected { freq, ref_db, .. } => AudioQualityReport {
@@ -361,6 +540,7 @@ impl SpectralAnalyzer {
         )),
         cutoff_freq_hz: Some(*freq),
         max_freq_hz: None,
+        stereo_correlation: None,
       },
       AnalysisOutcome::NoCutoffDetected { max_freq, ref_db } => AudioQualityReport {
         level,
@@ -375,6 +555,21 @@ impl SpectralAnalyzer {
         )),
         cutoff_freq_hz: None,
         max_freq_hz: Some(*max_freq),
+        stereo_correlation: None,
+      },
+      AnalysisOutcome::AssumedLossless { .. } => AudioQualityReport {
+        level,
+        score,
+        label: assessment.to_string(),
+        summary: "Códec sin pérdida; no se analizó el espectro.".into(),
+        details: Some(
+          "La configuración da por buenos los archivos sin pérdida (lossless_policy = skip_analysis). \
+                     Un transcode desde un formato con pérdida no se detectaría."
+            .into(),
+        ),
+        cutoff_freq_hz: None,
+        max_freq_hz: None,
+        stereo_correlation: None,
       },
       AnalysisOutcome::Inconclusive(r) => AudioQualityReport {
         level: QualityLevel::Inconclusive,
@@ -384,7 +579,426 @@ impl SpectralAnalyzer {
         details: Some(r.clone()),
         cutoff_freq_hz: None,
         max_freq_hz: None,
+        stereo_correlation: None,
       },
     }
   }
 }
+
+/// Resultado intermedio de `compute_average_spectrum`.
+struct AverageSpectrum {
+  sample_rate: u32,
+  spectrum_db: Vec<f32>,
+  bitrate: Option<i64>,
+  stereo_correlation: Option<f32>,
+}
+
+/// Estado acumulado de `compute_average_spectrum`, compartido entre tramos.
+struct SpectrumAccumulator {
+  magnitude_acc: Vec<f32>,
+  window_count: usize,
+  samples_buffer: Vec<f32>,
+  /// Muestras que se descartan del inicio de `samples_buffer` tras cada ventana.
+  hop: usize,
+  mono_scratch: Vec<f32>,
+  correlation: ChannelCorrelation,
+  keep_stereo: bool,
+  measure_stereo: bool,
+  downmix: DownmixMode,
+  /// Amplitud lineal bajo la cual se descartan muestras mientras dura el silencio inicial.
+  silence_threshold: Option<f32>,
+  /// Frecuencia de salida del resampler; `None` conserva la de cada frame.
+  target_rate: Option<u32>,
+}
+
+impl SpectrumAccumulator {
+  fn new(fft_window_size: usize, hop: usize, keep_stereo: bool, measure_stereo: bool, downmix: DownmixMode) -> Self {
+    Self {
+      magnitude_acc: vec![0.0; fft_window_size / 2],
+      window_count: 0,
+      samples_buffer: Vec::with_capacity(fft_window_size),
+      hop,
+      mono_scratch: Vec::new(),
+      correlation: ChannelCorrelation::default(),
+      keep_stereo,
+      measure_stereo,
+      downmix,
+      silence_threshold: None,
+      target_rate: None,
+    }
+  }
+
+  /// Descarta las muestras siguientes hasta la primera que llegue a `threshold_db` dBFS.
+  fn trim_leading_silence(&mut self, threshold_db: Option<f32>) {
+    self.silence_threshold = threshold_db.map(|db| 10f32.powf(db / 20.0));
+  }
+
+  /// Cuántas muestras del principio de `samples` son silencio inicial a descartar.
+  ///
+  /// En cuanto aparece una muestra audible el recorte se desactiva para el resto del análisis.
+  fn leading_silence(&mut self, samples: &[f32]) -> usize {
+    let Some(threshold) = self.silence_threshold else {
+      return 0;
+    };
+    match samples.iter().position(|s| s.abs() >= threshold) {
+      Some(start) => {
+        self.silence_threshold = None;
+        start
+      }
+      None => samples.len(),
+    }
+  }
+
+  /// Descarta la ventana FFT a medio llenar antes de saltar a otro punto de la pista.
+  fn start_segment(&mut self) {
+    self.samples_buffer.clear();
+  }
+
+  /// `true` si el frame ya es mono float32 packed y puede ir directo a `process_plane`.
+  ///
+  /// Sin `target_rate`, o si el frame ya viene a esa frecuencia, el resampler no la toca y
+  /// formato y canales son lo único que decide; ahorra la copia en el caso habitual de
+  /// WAV/FLAC mono.
+  fn is_passthrough(&self, decoded: &ffmpeg::util::frame::Audio) -> bool {
+    !self.keep_stereo
+      && decoded.channels() == 1
+      && decoded.format() == ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed)
+      && self.target_rate.is_none_or(|rate| rate == decoded.rate())
+  }
+
+  /// Convierte un frame decodificado a float32 packed (estéreo o mono según `keep_stereo`),
+  /// a `target_rate` si está fijada.
+  ///
+  /// El resampler se (re)crea si aún no existe o si cambia la frecuencia de entrada.
+  fn resample(
+    &self,
+    resampler: &mut Option<ffmpeg::software::resampling::Context>,
+    decoded: &ffmpeg::util::frame::Audio,
+  ) -> Result<ffmpeg::util::frame::Audio, AnalysisError> {
+    if resampler.as_ref().is_none_or(|r| r.input().rate != decoded.rate()) {
+      let dst_layout = if self.keep_stereo {
+        ffmpeg::util::channel_layout::ChannelLayout::STEREO
+      } else {
+        ffmpeg::util::channel_layout::ChannelLayout::MONO
+      };
+      *resampler = Some(ffmpeg::software::resampling::Context::get(
+        decoded.format(),
+        decoded.channel_layout(),
+        decoded.rate(),
+        ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed),
+        dst_layout,
+        self.target_rate.unwrap_or(decoded.rate()),
+      )?);
+    }
+
+    let mut resampled = ffmpeg::util::frame::Audio::empty();
+    if let Some(r) = resampler {
+      let _ = r.run(decoded, &mut resampled)?;
+    }
+    Ok(resampled)
+  }
+
+  /// Procesa un frame re-muestreado. Devuelve el nº de samples (por canal) consumidos.
+  fn push_frame(&mut self, frame: &ffmpeg::util::frame::Audio, analyzer: &mut SpectralAnalyzer) -> usize {
+    if frame.planes() == 0 {
+      return 0;
+    }
+
+    if !self.keep_stereo {
+      return self.process_plane(frame.plane::<f32>(0), analyzer);
+    }
+
+    let pairs = frame.plane::<(f32, f32)>(0);
+    let mut mono = std::mem::take(&mut self.mono_scratch);
+    mono.clear();
+    mono.extend(pairs.iter().map(|&(left, right)| self.downmix.mix(left, right)));
+
+    // El silencio inicial tampoco cuenta para la correlación.
+    let start = self.leading_silence(&mono);
+    if self.measure_stereo {
+      for &(left, right) in &pairs[start..] {
+        self.correlation.push(left, right);
+      }
+    }
+
+    let consumed = self.process_plane(&mono[start..], analyzer);
+    self.mono_scratch = mono;
+    consumed
+  }
+
+  /// Acumula muestras mono en ventanas FFT. Devuelve cuántas se consumieron, sin contar el
+  /// silencio inicial descartado.
+  fn process_plane(&mut self, samples: &[f32], analyzer: &mut SpectralAnalyzer) -> usize {
+    let samples = &samples[self.leading_silence(samples)..];
+    for &sample in samples {
+      self.samples_buffer.push(sample);
+      if self.samples_buffer.len() == analyzer.config.fft_window_size {
+        analyzer.process_fft_window(&self.samples_buffer, &mut self.magnitude_acc);
+        // Sin solapamiento `hop == fft_window_size` y esto vacía el buffer entero.
+        self.samples_buffer.drain(..self.hop);
+        self.window_count += 1;
+      }
+    }
+
+    samples.len()
+  }
+}
+
+/// Acumulador incremental del coeficiente de correlación de Pearson entre L y R.
+///
+/// Se usa `f64` porque se acumulan millones de productos y en `f32` la
+/// cancelación de `sum_xy - sum_x * sum_y / n` se come la precisión.
+#[derive(Debug, Default)]
+struct ChannelCorrelation {
+  n: u64,
+  sum_l: f64,
+  sum_r: f64,
+  sum_ll: f64,
+  sum_rr: f64,
+  sum_lr: f64,
+}
+
+impl ChannelCorrelation {
+  fn push(&mut self, left: f32, right: f32) {
+    let (l, r) = (left as f64, right as f64);
+    self.n += 1;
+    self.sum_l += l;
+    self.sum_r += r;
+    self.sum_ll += l * l;
+    self.sum_rr += r * r;
+    self.sum_lr += l * r;
+  }
+
+  /// Coeficiente en `[-1.0, 1.0]`, o `None` si no hay muestras o algún canal es constante
+  /// (p.ej. un canal muerto), donde la correlación no está definida.
+  fn coefficient(&self) -> Option<f32> {
+    if self.n == 0 {
+      return None;
+    }
+
+    let n = self.n as f64;
+    let cov = self.sum_lr - self.sum_l * self.sum_r / n;
+    let var_l = self.sum_ll - self.sum_l * self.sum_l / n;
+    let var_r = self.sum_rr - self.sum_r * self.sum_r / n;
+
+    if var_l <= f64::EPSILON || var_r <= f64::EPSILON {
+      return None;
+    }
+
+    Some((cov / (var_l.sqrt() * var_r.sqrt())).clamp(-1.0, 1.0) as f32)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn mono_f32_passthrough_matches_resampler_path() {
+    let mut analyzer = SpectralAnalyzer::new();
+    let window = analyzer.config.fft_window_size;
+    let hop = analyzer.config.fft_hop_size();
+
+    let samples: Vec<f32> =
+      (0..window * 4).map(|i| 0.5 * (i as f32 * 0.05).sin() + 0.25 * (i as f32 * 0.31).sin()).collect();
+    let mut frame = ffmpeg::util::frame::Audio::new(
+      ffmpeg::format::Sample::F32(ffmpeg::format::sample::Type::Packed),
+      samples.len(),
+      ffmpeg::util::channel_layout::ChannelLayout::MONO,
+    );
+    frame.set_rate(44_100);
+    frame.plane_mut::<f32>(0).copy_from_slice(&samples);
+
+    let mut direct = SpectrumAccumulator::new(window, hop, false, false, DownmixMode::Average);
+    assert!(direct.is_passthrough(&frame));
+    direct.process_plane(frame.plane::<f32>(0), &mut analyzer);
+
+    let mut via_resampler = SpectrumAccumulator::new(window, hop, false, false, DownmixMode::Average);
+    let mut resampler = None;
+    let resampled = via_resampler.resample(&mut resampler, &frame).unwrap();
+    via_resampler.push_frame(&resampled, &mut analyzer);
+
+    assert_eq!(direct.window_count, 4);
+    assert_eq!(direct.window_count, via_resampler.window_count);
+    assert_eq!(direct.magnitude_acc, via_resampler.magnitude_acc);
+  }
+
+  /// WAV PCM 16-bit mono a `rate` Hz: `silence_secs` de silencio y después `signal_secs` de
+  /// ruido de banda completa (un LCG, para que el test sea determinista).
+  fn wav_with_leading_silence(rate: u32, silence_secs: usize, signal_secs: usize) -> Vec<u8> {
+    let mut seed = 0x2545_f491u32;
+    let mut samples = vec![0i16; silence_secs * rate as usize];
+    samples.extend((0..signal_secs * rate as usize).map(|_| {
+      seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+      (seed >> 16) as i16 / 4
+    }));
+    let data: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+
+    let mut wav = b"RIFF".to_vec();
+    wav.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&rate.to_le_bytes());
+    wav.extend_from_slice(&(rate * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    wav.extend_from_slice(&data);
+    wav
+  }
+
+  #[test]
+  fn leading_silence_is_trimmed_before_the_analysis_window() {
+    let path = std::env::temp_dir().join(format!("gamus-silence-{}.wav", std::process::id()));
+    std::fs::write(&path, wav_with_leading_silence(22_050, 5, 2)).unwrap();
+    let config = |trim: Option<f32>| {
+      let mut builder = AnalysisConfig::builder().fft_window_size(1024).max_analysis_duration_secs(1.0);
+      if let Some(db) = trim {
+        builder = builder.silence_trim_db(db);
+      }
+      builder.build().unwrap()
+    };
+
+    let untrimmed = SpectralAnalyzer::new_with_config(config(None)).analyze_file(&path);
+    let trimmed = SpectralAnalyzer::new_with_config(config(Some(-60.0))).analyze_file(&path);
+    let _ = std::fs::remove_file(&path);
+
+    // El primer segundo es todo silencio; recortado, el segundo analizado es ya la señal.
+    assert_eq!(untrimmed.unwrap().report.level, QualityLevel::Inconclusive);
+    assert!(matches!(trimmed.unwrap().outcome, AnalysisOutcome::NoCutoffDetected { .. }));
+  }
+
+  #[test]
+  fn fixed_analysis_rate_caps_the_measured_band_at_its_nyquist() {
+    let path = std::env::temp_dir().join(format!("gamus-analysis-rate-{}.wav", std::process::id()));
+    std::fs::write(&path, wav_with_leading_silence(44_100, 0, 2)).unwrap();
+    let config = |rate: Option<u32>| {
+      let mut builder = AnalysisConfig::builder().fft_window_size(1024).max_analysis_duration_secs(1.0);
+      if let Some(rate) = rate {
+        builder = builder.analysis_sample_rate(rate);
+      }
+      builder.build().unwrap()
+    };
+    let top_freq = |quality: AudioQuality| match quality.outcome {
+      AnalysisOutcome::CutoffDetected { freq, .. } => freq,
+      AnalysisOutcome::NoCutoffDetected { max_freq, .. } => max_freq,
+      other => panic!("expected a measured spectrum, got {other:?}"),
+    };
+
+    let native = SpectralAnalyzer::new_with_config(config(None)).analyze_file(&path);
+    let fixed = SpectralAnalyzer::new_with_config(config(Some(22_050))).analyze_file(&path);
+    let _ = std::fs::remove_file(&path);
+
+    // Ruido de banda completa: a 44.1 kHz llega por encima de 11 kHz; re-muestreado a
+    // 22.05 kHz no puede pasar de su Nyquist.
+    assert!(top_freq(native.unwrap()) > 11_025.0);
+    assert!(top_freq(fixed.unwrap()) <= 11_025.0);
+  }
+
+  /// Espectro medio de 2048 bins a 44.1 kHz: `db` hasta `up_to_hz` y silencio (-200 dB) por encima.
+  fn flat_spectrum(db: f32, up_to_hz: f32) -> Vec<f32> {
+    let bin_hz = 22_050.0 / 2048.0;
+    (0..2048).map(|i| if (i as f32 + 1.0) * bin_hz <= up_to_hz { db } else { -200.0 }).collect()
+  }
+
+  #[test]
+  fn quiet_bandlimited_content_gets_a_cutoff_and_digital_silence_stays_inconclusive() {
+    let analyzer = SpectralAnalyzer::new();
+
+    // Todo por debajo del floor base (-65 dB), pero muy lejos del silencio.
+    match analyzer.detect_cutoff(&flat_spectrum(-80.0, 8_000.0), 44_100) {
+      AnalysisOutcome::CutoffDetected { freq, .. } => assert_eq!(freq, 8_000.0),
+      other => panic!("expected a cutoff, got {other:?}"),
+    }
+
+    let silence = vec![-200.0; 2048];
+    assert!(matches!(analyzer.detect_cutoff(&silence, 44_100), AnalysisOutcome::Inconclusive(_)));
+
+    // Con un umbral de silencio más alto, la misma pista baja pasa a no concluyente.
+    let strict =
+      SpectralAnalyzer::new_with_config(AnalysisConfig::builder().digital_silence_db(-70.0).build().unwrap());
+    assert!(matches!(strict.detect_cutoff(&flat_spectrum(-80.0, 8_000.0), 44_100), AnalysisOutcome::Inconclusive(_)));
+  }
+
+  /// Graves fuertes y agudos débiles pero presentes: ruido inclinado (-40 dB en 0 Hz,
+  /// -3.2 dB por kHz) con contenido 20 dB por encima en bins alternos, hasta `up_to_hz`.
+  fn bass_heavy_spectrum(up_to_hz: f32) -> Vec<f32> {
+    let bin_hz = 22_050.0 / 2048.0;
+    (0..2048)
+      .map(|i| {
+        let hz = (i as f32 + 0.5) * bin_hz;
+        let noise = -40.0 - 3.2 * hz / 1000.0;
+        if hz > up_to_hz {
+          -200.0
+        } else if i % 2 == 0 {
+          noise + 20.0
+        } else {
+          noise
+        }
+      })
+      .collect()
+  }
+
+  #[test]
+  fn tilted_floor_keeps_weak_highs_that_the_flat_floor_cuts() {
+    let full_band = bass_heavy_spectrum(f32::INFINITY);
+
+    // Floor plano: los agudos, muy por debajo del máximo de los graves, parecen un corte.
+    match SpectralAnalyzer::new().detect_cutoff(&full_band, 44_100) {
+      AnalysisOutcome::CutoffDetected { freq, .. } => assert!(freq < 15_000.0, "{freq}"),
+      other => panic!("expected a false cutoff with the flat floor, got {other:?}"),
+    }
+
+    let tilted = SpectralAnalyzer::new_with_config(
+      AnalysisConfig::builder().noise_floor_mode(NoiseFloorMode::Tilted { margin_db: 6.0 }).build().unwrap(),
+    );
+    assert!(matches!(tilted.detect_cutoff(&full_band, 44_100), AnalysisOutcome::NoCutoffDetected { .. }));
+
+    // Un corte real (codec con pérdida a 16 kHz) se sigue detectando.
+    match tilted.detect_cutoff(&bass_heavy_spectrum(16_000.0), 44_100) {
+      AnalysisOutcome::CutoffDetected { freq, .. } => assert_eq!(freq, 16_000.0),
+      other => panic!("expected a cutoff at 16 kHz, got {other:?}"),
+    }
+  }
+
+  #[test]
+  fn custom_perfect_threshold_reclassifies_a_borderline_score() {
+    let outcome = AnalysisOutcome::NoCutoffDetected { max_freq: 20_500.0, ref_db: -40.0 };
+
+    let default = SpectralAnalyzer::new();
+    assert_eq!(default.build_report(&outcome, 9.5, "").level, QualityLevel::Perfect);
+
+    let strict = SpectralAnalyzer::new_with_config(AnalysisConfig::builder().perfect_threshold(10.0).build().unwrap());
+    assert_eq!(strict.build_report(&outcome, 9.5, "").level, QualityLevel::High);
+    assert_eq!(strict.build_report(&outcome, 10.0, "").level, QualityLevel::Perfect);
+  }
+
+  #[test]
+  fn lossless_files_are_only_trusted_when_the_policy_says_so() {
+    assert_eq!(SpectralAnalyzer::new().lossless_shortcut("flac"), None);
+
+    let trusting = SpectralAnalyzer::new_with_config(
+      AnalysisConfig::builder().lossless_policy(crate::config::LosslessPolicy::SkipAnalysis).build().unwrap(),
+    );
+    let quality = trusting.lossless_shortcut("flac").unwrap();
+    assert_eq!(quality.outcome, AnalysisOutcome::AssumedLossless { codec: "flac".into() });
+    assert_eq!(quality.quality_score, 10.0);
+    assert_eq!(quality.report.level, QualityLevel::Perfect);
+  }
+
+  #[test]
+  fn unordered_level_thresholds_are_rejected() {
+    let err = AnalysisConfig::builder().high_threshold(9.8).build().unwrap_err();
+    assert!(matches!(err, crate::config::AnalysisConfigError::LevelThresholdsUnordered(_)));
+  }
+
+  #[test]
+  fn analysis_sample_rate_below_the_validated_minimum_is_rejected() {
+    let err = AnalysisConfig::builder().analysis_sample_rate(8_000).build().unwrap_err();
+    assert_eq!(err, crate::config::AnalysisConfigError::AnalysisSampleRateTooLow(8_000));
+    assert!(AnalysisConfig::builder().analysis_sample_rate(44_100).build().is_ok());
+  }
+}
@@ -3,11 +3,33 @@ use std::collections::HashMap;
 /// Claves normalizadas en minúsculas. Deben matchear lo que genera FFmpeg.
 pub const KEYS_TITLE: &[&str] = &["title", "tit2", "inam", "\u{a9}nam", "name"];
 pub const KEYS_ALBUM: &[&str] = &["album", "talb", "iprd", "\u{a9}alb"];
+/// Artista del álbum primero; si no existe se cae al artista de pista.
+pub const KEYS_ALBUM_ARTIST: &[&str] =
+  &["album_artist", "albumartist", "album artist", "tpe2", "aart", "artist", "tpe1", "iart", "\u{a9}art"];
 pub const KEYS_DATE: &[&str] =
   &["date", "year", "original_year", "originalyear", "releasedate", "tdrc", "tyer", "tdor", "\u{a9}day", "icrd"];
 pub const KEYS_GENRE: &[&str] = &["genre", "tcon", "ignr", "\u{a9}gen"];
 pub const KEYS_TRACK_NUMBER: &[&str] = &["track", "trck", "iprt", "itrk", "trkn"];
+/// Huella Chromaprint escrita por Picard/fpcalc (`ACOUSTID_FINGERPRINT`).
+pub const KEYS_ACOUSTID_FINGERPRINT: &[&str] = &["acoustid_fingerprint", "acoustid fingerprint"];
 pub const KEYS_DISC_NUMBER: &[&str] = &["disc", "tpos", "disk"];
+/// Letra sin sincronizar. Los frames USLT de ID3 llegan además como `lyrics-<idioma>`
+/// (ver [`LYRICS_LANGUAGE_PREFIX`]).
+pub const KEYS_LYRICS: &[&str] = &["lyrics", "unsyncedlyrics", "unsynced lyrics", "uslt", "\u{a9}lyr"];
+pub const KEYS_COMMENT: &[&str] = &["comment", "comm", "icmt", "\u{a9}cmt"];
+/// ReplayGain (`TXXX:REPLAYGAIN_*` en ID3, Vorbis comments, freeform de iTunes en MP4).
+pub const KEYS_REPLAYGAIN_TRACK_GAIN: &[&str] = &["replaygain_track_gain", "replaygain track gain"];
+pub const KEYS_REPLAYGAIN_ALBUM_GAIN: &[&str] = &["replaygain_album_gain", "replaygain album gain"];
+pub const KEYS_REPLAYGAIN_TRACK_PEAK: &[&str] = &["replaygain_track_peak", "replaygain track peak"];
+pub const KEYS_REPLAYGAIN_ALBUM_PEAK: &[&str] = &["replaygain_album_peak", "replaygain album peak"];
+pub const KEYS_ISRC: &[&str] = &["isrc", "tsrc"];
+/// Identificadores de MusicBrainz tal como los escribe Picard (Vorbis, `TXXX` de ID3, freeform de MP4).
+/// Pese al nombre, `MUSICBRAINZ_TRACKID` guarda el Recording ID; el de la pista del release es otro tag.
+pub const KEYS_MB_RECORDING_ID: &[&str] = &["musicbrainz_trackid", "musicbrainz track id"];
+pub const KEYS_MB_RELEASE_ID: &[&str] = &["musicbrainz_albumid", "musicbrainz album id"];
+
+/// Prefijo con el que FFmpeg expone la letra de ID3 etiquetada por idioma (`lyrics-eng`, `lyrics-spa`...).
+pub const LYRICS_LANGUAGE_PREFIX: &str = "lyrics-";
 
 /// Busca el primer valor no vacío asociado a una de las claves proporcionadas.
 ///
@@ -16,7 +38,55 @@ pub fn find_tag_value<'a>(tags: &'a HashMap<String, String>, keys: &[&str]) -> O
   keys.iter().find_map(|key| tags.get(*key).map(|v| v.trim())).filter(|v| !v.is_empty())
 }
 
+/// Todos los valores de letra no vacíos: primero las claves genéricas en el orden de
+/// [`KEYS_LYRICS`], después las etiquetadas por idioma ordenadas por clave para que el
+/// resultado no dependa del orden del `HashMap`.
+pub fn find_lyrics_values(tags: &HashMap<String, String>) -> Vec<(&str, &str)> {
+  let mut tagged: Vec<(&str, &str)> =
+    tags.iter().filter(|(k, _)| k.starts_with(LYRICS_LANGUAGE_PREFIX)).map(|(k, v)| (k.as_str(), v.trim())).collect();
+  tagged.sort_unstable_by_key(|(k, _)| *k);
+
+  KEYS_LYRICS
+    .iter()
+    .filter_map(|key| tags.get_key_value(*key).map(|(k, v)| (k.as_str(), v.trim())))
+    .chain(tagged)
+    .filter(|(_, v)| !v.is_empty())
+    .collect()
+}
+
 /// Intenta parsear un entero (track, disc, etc.) desde tags que pueden venir como "1/12".
 pub fn find_tag_number(tags: &HashMap<String, String>, keys: &[&str]) -> Option<u32> {
   find_tag_value(tags, keys).and_then(|raw| raw.split('/').next()).and_then(|token| token.trim().parse::<u32>().ok())
 }
+
+/// Parsea un decimal aceptando el sufijo `dB` de las ganancias ReplayGain: sirve para
+/// `"-6.54 dB"`, `"+1.20dB"`, `"-6.54"` y picos como `"0.988547"`. Los no finitos se descartan.
+pub fn find_tag_float(tags: &HashMap<String, String>, keys: &[&str]) -> Option<f32> {
+  let raw = find_tag_value(tags, keys)?;
+  let number = match raw.len().checked_sub(2) {
+    Some(split) if raw.is_char_boundary(split) && raw[split..].eq_ignore_ascii_case("db") => &raw[..split],
+    _ => raw,
+  };
+  number.trim().parse::<f32>().ok().filter(|v| v.is_finite())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn tags(value: &str) -> HashMap<String, String> {
+    HashMap::from([("replaygain_track_gain".to_string(), value.to_string())])
+  }
+
+  #[test]
+  fn replaygain_values_parse_with_and_without_the_db_suffix() {
+    let gain = |v: &str| find_tag_float(&tags(v), KEYS_REPLAYGAIN_TRACK_GAIN);
+
+    assert_eq!(gain("-6.54 dB"), Some(-6.54));
+    assert_eq!(gain("+1.20dB"), Some(1.2));
+    assert_eq!(gain(" -3.5 DB "), Some(-3.5));
+    assert_eq!(gain("0.988547"), Some(0.988547));
+    assert_eq!(gain("loud"), None);
+    assert_eq!(gain("NaN dB"), None);
+  }
+}
@@ -0,0 +1,315 @@
+//! Separación de etiquetas multivalor (artistas, géneros, estilos) y de colaboraciones "feat.".
+//!
+//! Las funciones libres usan [`TagSplitter::default`]; si hace falta otro conjunto de
+//! separadores o de excepciones se construye un [`TagSplitter`] propio.
+
+/// Separadores por defecto entre valores de una misma etiqueta.
+pub const DEFAULT_SEPARATORS: &[char] = &[';', '/', ','];
+
+/// Nombres que contienen un separador y no deben partirse (comparación sin mayúsculas).
+pub const DEFAULT_EXCEPTIONS: &[&str] = &[
+  "AC/DC",
+  "Au/Ra",
+  "Earth, Wind & Fire",
+  "Crosby, Stills, Nash & Young",
+  "Emerson, Lake & Palmer",
+  "Blood, Sweat & Tears",
+  "Tyler, the Creator",
+];
+
+/// Marcadores que introducen artistas invitados (comparación sin mayúsculas).
+pub const DEFAULT_FEATURING_MARKERS: &[&str] = &["featuring", "feat.", "feat", "ft.", "with"];
+
+/// Reglas de separación de etiquetas multivalor.
+#[derive(Debug, Clone)]
+pub struct TagSplitter {
+  separators: Vec<char>,
+  exceptions: Vec<String>,
+  featuring_markers: Vec<String>,
+}
+
+impl Default for TagSplitter {
+  fn default() -> Self {
+    Self {
+      separators: DEFAULT_SEPARATORS.to_vec(),
+      exceptions: DEFAULT_EXCEPTIONS.iter().map(|s| s.to_string()).collect(),
+      featuring_markers: DEFAULT_FEATURING_MARKERS.iter().map(|s| s.to_string()).collect(),
+    }
+  }
+}
+
+impl TagSplitter {
+  /// Crea un splitter con los separadores dados y las excepciones/marcadores por defecto.
+  pub fn new(separators: impl IntoIterator<Item = char>) -> Self {
+    Self { separators: separators.into_iter().collect(), ..Self::default() }
+  }
+
+  /// Sustituye la lista de nombres que nunca se parten.
+  pub fn with_exceptions<S: Into<String>>(mut self, exceptions: impl IntoIterator<Item = S>) -> Self {
+    self.exceptions = exceptions.into_iter().map(Into::into).collect();
+    self
+  }
+
+  /// Añade un nombre a la lista de excepciones.
+  pub fn add_exception(mut self, exception: impl Into<String>) -> Self {
+    self.exceptions.push(exception.into());
+    self
+  }
+
+  /// Sustituye los marcadores de colaboración ("feat.", "ft.", ...).
+  pub fn with_featuring_markers<S: Into<String>>(mut self, markers: impl IntoIterator<Item = S>) -> Self {
+    self.featuring_markers = markers.into_iter().map(Into::into).collect();
+    self
+  }
+
+  /// Parte `value` por los separadores configurados.
+  ///
+  /// Los valores se devuelven recortados y sin vacíos. Una excepción solo se respeta si
+  /// ocupa un valor completo: "AC/DC; Metallica" da `["AC/DC", "Metallica"]`, pero
+  /// "BAC/DCX" se parte con normalidad.
+  pub fn split_multi(&self, value: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut rest = value;
+
+    while let Some(ch) = rest.chars().next() {
+      if let Some(len) = self.exception_at(rest, &current) {
+        current.push_str(&rest[..len]);
+        rest = &rest[len..];
+        continue;
+      }
+
+      if self.separators.contains(&ch) {
+        push_trimmed(&mut parts, &current);
+        current.clear();
+      } else {
+        current.push(ch);
+      }
+      rest = &rest[ch.len_utf8()..];
+    }
+    push_trimmed(&mut parts, &current);
+
+    parts
+  }
+
+  /// Separa el artista principal de los invitados.
+  ///
+  /// Reconoce los marcadores configurados como palabra suelta, también entre paréntesis o
+  /// corchetes ("Song (feat. X)"). Los invitados se parten con [`Self::split_multi`] y
+  /// además por `&` y por marcadores adicionales ("A feat. B ft. C"). Sin marcador, el valor
+  /// entero (recortado) es el artista principal.
+  pub fn split_featuring(&self, value: &str) -> (String, Vec<String>) {
+    let Some((start, end)) = self.find_featuring(value) else {
+      return (value.trim().to_string(), Vec::new());
+    };
+
+    let main = value[..start].trim_end().trim_end_matches(['(', '[']).trim().to_string();
+    let mut featured = Vec::new();
+    let mut rest = &value[end..];
+
+    loop {
+      let (chunk, next) = match self.find_featuring(rest) {
+        Some((s, e)) => (&rest[..s], Some(&rest[e..])),
+        None => (rest, None),
+      };
+      let chunk = chunk.trim().trim_end_matches([')', ']']).trim_start_matches(['(', '[']);
+      for name in self.split_multi(chunk) {
+        if self.is_exception(&name) {
+          featured.push(name);
+        } else {
+          featured.extend(name.split('&').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string));
+        }
+      }
+      match next {
+        Some(next) => rest = next,
+        None => break,
+      }
+    }
+
+    (main, featured)
+  }
+
+  /// Longitud de la excepción que empieza en `rest`, si la hay y ocupa un valor completo.
+  ///
+  /// `current` es lo acumulado del valor en curso: debe estar vacío (salvo espacios) para
+  /// que la excepción empiece en una frontera.
+  fn exception_at(&self, rest: &str, current: &str) -> Option<usize> {
+    if !current.trim().is_empty() {
+      return None;
+    }
+
+    self.exceptions.iter().find_map(|exception| {
+      let candidate = rest.get(..exception.len())?;
+      if !candidate.eq_ignore_ascii_case(exception) {
+        return None;
+      }
+      let after = rest[exception.len()..].trim_start();
+      let at_boundary = after.is_empty() || after.starts_with(|c| self.separators.contains(&c));
+      at_boundary.then_some(exception.len())
+    })
+  }
+
+  fn is_exception(&self, name: &str) -> bool {
+    self.exceptions.iter().any(|exception| exception.eq_ignore_ascii_case(name))
+  }
+
+  /// Posición `(inicio, fin)` del primer marcador de colaboración en `value`.
+  fn find_featuring(&self, value: &str) -> Option<(usize, usize)> {
+    let lower = value.to_ascii_lowercase();
+
+    let mut best: Option<(usize, usize)> = None;
+    for marker in &self.featuring_markers {
+      let marker = marker.to_ascii_lowercase();
+      let mut from = 0;
+      while let Some(pos) = lower[from..].find(&marker) {
+        let start = from + pos;
+        let end = start + marker.len();
+        let before_ok = lower[..start].chars().next_back().is_none_or(|c| c.is_whitespace() || c == '(' || c == '[');
+        let after_ok = lower[end..].chars().next().is_none_or(|c| c.is_whitespace());
+        // Sin nada delante no hay artista principal: "With Honor" es un nombre, no una colaboración.
+        if before_ok && after_ok && !value[..start].trim().is_empty() {
+          if best.is_none_or(|(s, _)| start < s) {
+            best = Some((start, end));
+          }
+          break;
+        }
+        from = start + marker.len().max(1);
+      }
+    }
+
+    best
+  }
+}
+
+/// Parte una etiqueta multivalor con la configuración por defecto (`;`, `/`, `,`).
+pub fn split_multi(value: &str) -> Vec<String> {
+  TagSplitter::default().split_multi(value)
+}
+
+/// Separa artista principal e invitados con la configuración por defecto.
+pub fn split_featuring(value: &str) -> (String, Vec<String>) {
+  TagSplitter::default().split_featuring(value)
+}
+
+fn push_trimmed(parts: &mut Vec<String>, value: &str) {
+  let value = value.trim();
+  if !value.is_empty() {
+    parts.push(value.to_string());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|s| s.to_string()).collect()
+  }
+
+  #[test]
+  fn splits_on_every_default_separator() {
+    assert_eq!(split_multi("Rock; Pop / Jazz, Blues"), strings(&["Rock", "Pop", "Jazz", "Blues"]));
+  }
+
+  #[test]
+  fn drops_empty_and_blank_values() {
+    assert_eq!(split_multi(" ;; Rock ,, / "), strings(&["Rock"]));
+    assert!(split_multi("").is_empty());
+    assert!(split_multi("   ").is_empty());
+  }
+
+  #[test]
+  fn single_value_is_returned_trimmed() {
+    assert_eq!(split_multi("  Boards of Canada "), strings(&["Boards of Canada"]));
+  }
+
+  #[test]
+  fn exceptions_are_not_split() {
+    assert_eq!(split_multi("AC/DC"), strings(&["AC/DC"]));
+    assert_eq!(split_multi("ac/dc; Metallica"), strings(&["ac/dc", "Metallica"]));
+    assert_eq!(split_multi("Metallica / AC/DC"), strings(&["Metallica", "AC/DC"]));
+    assert_eq!(split_multi("Earth, Wind & Fire, Chic"), strings(&["Earth, Wind & Fire", "Chic"]));
+  }
+
+  #[test]
+  fn exceptions_only_match_whole_values() {
+    assert_eq!(split_multi("BAC/DCX"), strings(&["BAC", "DCX"]));
+    assert_eq!(split_multi("AC/DCX"), strings(&["AC", "DCX"]));
+  }
+
+  #[test]
+  fn custom_separators_and_exceptions() {
+    let splitter = TagSplitter::new(['|']).with_exceptions(["A|B"]);
+    assert_eq!(splitter.split_multi("A|B|C; D"), strings(&["A|B", "C; D"]));
+
+    let no_exceptions = TagSplitter::default().with_exceptions(Vec::<String>::new());
+    assert_eq!(no_exceptions.split_multi("AC/DC"), strings(&["AC", "DC"]));
+
+    let extended = TagSplitter::default().add_exception("Sunn O)))/Boris");
+    assert_eq!(extended.split_multi("Sunn O)))/Boris"), strings(&["Sunn O)))/Boris"]));
+  }
+
+  #[test]
+  fn non_ascii_values_are_kept_intact() {
+    assert_eq!(split_multi("Björk; Sigur Rós/坂本龍一"), strings(&["Björk", "Sigur Rós", "坂本龍一"]));
+  }
+
+  #[test]
+  fn featuring_markers_are_recognized() {
+    for value in [
+      "Daft Punk feat. Pharrell",
+      "Daft Punk ft. Pharrell",
+      "Daft Punk featuring Pharrell",
+      "Daft Punk with Pharrell",
+      "Daft Punk FEAT. Pharrell",
+      "Daft Punk feat Pharrell",
+    ] {
+      assert_eq!(split_featuring(value), ("Daft Punk".to_string(), strings(&["Pharrell"])), "{value}");
+    }
+  }
+
+  #[test]
+  fn featured_artists_are_split() {
+    assert_eq!(split_featuring("Artist feat. A, B & C"), ("Artist".to_string(), strings(&["A", "B", "C"])));
+    assert_eq!(split_featuring("Artist feat. A ft. B"), ("Artist".to_string(), strings(&["A", "B"])));
+  }
+
+  #[test]
+  fn bracketed_featuring_is_unwrapped() {
+    assert_eq!(split_featuring("Artist (feat. Guest)"), ("Artist".to_string(), strings(&["Guest"])));
+    assert_eq!(split_featuring("Artist [ft. Guest]"), ("Artist".to_string(), strings(&["Guest"])));
+  }
+
+  #[test]
+  fn no_marker_keeps_the_whole_value() {
+    assert_eq!(split_featuring("  Boards of Canada "), ("Boards of Canada".to_string(), Vec::new()));
+    assert_eq!(split_featuring(""), (String::new(), Vec::new()));
+  }
+
+  #[test]
+  fn markers_inside_words_are_ignored() {
+    assert_eq!(split_featuring("Withered Hand"), ("Withered Hand".to_string(), Vec::new()));
+    assert_eq!(split_featuring("Craft Spells"), ("Craft Spells".to_string(), Vec::new()));
+    assert_eq!(split_featuring("Lifeless Feather"), ("Lifeless Feather".to_string(), Vec::new()));
+  }
+
+  #[test]
+  fn leading_marker_is_part_of_the_name() {
+    assert_eq!(split_featuring("With Honor"), ("With Honor".to_string(), Vec::new()));
+  }
+
+  #[test]
+  fn featured_exceptions_survive() {
+    assert_eq!(
+      split_featuring("Chic feat. Earth, Wind & Fire"),
+      ("Chic".to_string(), strings(&["Earth, Wind & Fire"]))
+    );
+  }
+
+  #[test]
+  fn custom_featuring_markers() {
+    let splitter = TagSplitter::default().with_featuring_markers(["vs."]);
+    assert_eq!(splitter.split_featuring("A vs. B"), ("A".to_string(), strings(&["B"])));
+    assert_eq!(splitter.split_featuring("A feat. B"), ("A feat. B".to_string(), Vec::new()));
+  }
+}
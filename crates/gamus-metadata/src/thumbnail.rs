@@ -0,0 +1,228 @@
+//! Miniaturas de las portadas, cacheadas en disco por hash de contenido.
+//!
+//! Cada imagen se reduce una sola vez por tamaño: el nombre del archivo sale del hash del
+//! artwork y del lado máximo, así que dos releases con la misma portada comparten
+//! miniatura y una que ya existe no se vuelve a generar.
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use gamus_core::domain::release::Artwork;
+use image::codecs::jpeg::JpegEncoder;
+use image::{ImageError, ImageReader};
+use serde::Serialize;
+use thiserror::Error;
+
+/// Lado máximo por defecto de una miniatura, en píxeles.
+pub const DEFAULT_MAX_DIMENSION: u32 = 300;
+
+/// Calidad JPEG de las miniaturas (1-100).
+const JPEG_QUALITY: u8 = 85;
+
+#[derive(Debug, Error)]
+pub enum ThumbnailError {
+  #[error("artwork {0} has no content hash")]
+  MissingHash(PathBuf),
+  #[error("I/O error on {path}: {source}")]
+  Io { path: PathBuf, source: io::Error },
+  #[error("could not process image {path}: {source}")]
+  Image { path: PathBuf, source: ImageError },
+}
+
+/// Miniatura de un artwork ya presente en la caché.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Thumbnail {
+  /// Hash del artwork del que sale.
+  pub hash: String,
+  pub path: PathBuf,
+  /// `false` si ya estaba en la caché y no hizo falta generarla.
+  pub generated: bool,
+}
+
+/// Resultado de [`ThumbnailCache::ensure_all`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PrewarmSummary {
+  /// Una miniatura por hash distinto, generada o reutilizada.
+  pub thumbnails: Vec<Thumbnail>,
+  /// Artworks que no se pudieron reducir, con el error.
+  pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Directorio de miniaturas con un lado máximo fijo.
+#[derive(Debug, Clone)]
+pub struct ThumbnailCache {
+  dir: PathBuf,
+  max_dimension: u32,
+}
+
+impl ThumbnailCache {
+  /// Caché en `dir`, con [`DEFAULT_MAX_DIMENSION`]. El directorio se crea al generar la
+  /// primera miniatura.
+  pub fn new(dir: impl Into<PathBuf>) -> Self {
+    Self { dir: dir.into(), max_dimension: DEFAULT_MAX_DIMENSION }
+  }
+
+  /// Caché en `thumbnails/` dentro del directorio de caché de Gamus.
+  pub fn in_cache_dir() -> Self {
+    Self::new(gamus_config::PATHS.cache_dir.join("thumbnails"))
+  }
+
+  /// Lado máximo (ancho o alto) de las miniaturas; se conserva la proporción. Mínimo 1.
+  pub fn with_max_dimension(mut self, max_dimension: u32) -> Self {
+    self.max_dimension = max_dimension.max(1);
+    self
+  }
+
+  /// Ruta de la miniatura del artwork con ese hash, exista o no.
+  ///
+  /// El hash va en hexadecimal byte a byte: así prefijos como `xxh3p64:` no dan nombres
+  /// inválidos en Windows y dos hashes distintos nunca comparten archivo.
+  pub fn path_for(&self, hash: &str) -> PathBuf {
+    let name: String = hash.bytes().map(|b| format!("{b:02x}")).collect();
+    self.dir.join(format!("{name}_{}.jpg", self.max_dimension))
+  }
+
+  /// Devuelve la miniatura de `artwork`, generándola si aún no está en la caché.
+  ///
+  /// Se escribe primero a un archivo temporal y luego se renombra, así que una generación
+  /// interrumpida no deja una miniatura truncada que después se daría por buena. Si algo
+  /// falla, el temporal se borra.
+  pub fn ensure(&self, artwork: &Artwork) -> Result<Thumbnail, ThumbnailError> {
+    if artwork.hash.is_empty() {
+      return Err(ThumbnailError::MissingHash(artwork.path.clone()));
+    }
+    let path = self.path_for(&artwork.hash);
+    if path.is_file() {
+      return Ok(Thumbnail { hash: artwork.hash.clone(), path, generated: false });
+    }
+
+    let image_err = |source| ThumbnailError::Image { path: artwork.path.clone(), source };
+    let image = ImageReader::open(&artwork.path)
+      .map_err(|source| ThumbnailError::Io { path: artwork.path.clone(), source })?
+      .with_guessed_format()
+      .map_err(|source| ThumbnailError::Io { path: artwork.path.clone(), source })?
+      .decode()
+      .map_err(image_err)?;
+    // JPEG no admite canal alfa: se aplana a RGB.
+    let thumbnail = image.thumbnail(self.max_dimension, self.max_dimension).to_rgb8();
+
+    fs::create_dir_all(&self.dir).map_err(|source| ThumbnailError::Io { path: self.dir.clone(), source })?;
+    let tmp = path.with_extension("jpg.tmp");
+    let io_err = |source| ThumbnailError::Io { path: tmp.clone(), source };
+    let written = File::create(&tmp).map_err(io_err).and_then(|file| {
+      let mut writer = BufWriter::new(file);
+      JpegEncoder::new_with_quality(&mut writer, JPEG_QUALITY)
+        .encode_image(&thumbnail)
+        .map_err(|source| ThumbnailError::Image { path: tmp.clone(), source })?;
+      writer.flush().map_err(io_err)?;
+      drop(writer);
+      fs::rename(&tmp, &path).map_err(io_err)
+    });
+    if written.is_err() {
+      let _ = fs::remove_file(&tmp);
+    }
+    written?;
+
+    Ok(Thumbnail { hash: artwork.hash.clone(), path, generated: true })
+  }
+
+  /// [`Self::ensure`] para cada hash distinto de `artworks`. Un artwork que falla no detiene
+  /// al resto; si otro con el mismo hash se puede leer, se usa ese.
+  pub fn ensure_all<'a>(&self, artworks: impl IntoIterator<Item = &'a Artwork>) -> PrewarmSummary {
+    let mut done = HashSet::new();
+    let mut summary = PrewarmSummary::default();
+    for artwork in artworks {
+      if done.contains(&artwork.hash) {
+        continue;
+      }
+      match self.ensure(artwork) {
+        Ok(thumbnail) => {
+          done.insert(artwork.hash.clone());
+          summary.thumbnails.push(thumbnail);
+        }
+        Err(e) => summary.failed.push((artwork.path.clone(), e.to_string())),
+      }
+    }
+    summary
+  }
+
+  /// Directorio de la caché.
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use image::{GenericImageView, RgbImage};
+
+  use super::*;
+
+  #[test]
+  fn downscales_once_per_hash_and_reuses_the_cached_file() {
+    let dir = std::env::temp_dir().join(format!("gamus-thumbnails-{}", std::process::id()));
+    let source = dir.join("cover.png");
+    fs::create_dir_all(&dir).unwrap();
+    RgbImage::new(800, 400).save(&source).unwrap();
+
+    let artwork = |path: &Path| Artwork {
+      path: path.to_path_buf(),
+      mime_type: "image/png".into(),
+      description: None,
+      hash: "xxh3p64:cafe".into(),
+      credits: None,
+      thumbnail_path: None,
+    };
+    let cache = ThumbnailCache::new(dir.join("cache")).with_max_dimension(100);
+
+    let summary = cache.ensure_all([&artwork(&source), &artwork(&dir.join("missing.png"))]);
+    assert!(summary.failed.is_empty());
+    assert_eq!(summary.thumbnails.len(), 1);
+    let first = &summary.thumbnails[0];
+    assert!(first.generated);
+    assert_eq!(first.path, cache.dir().join("787868337036343a63616665_100.jpg"));
+    assert_eq!(image::open(&first.path).unwrap().dimensions(), (100, 50));
+
+    // Aunque el original ya no exista, la miniatura en caché basta.
+    fs::remove_file(&source).unwrap();
+    let again = cache.ensure(&artwork(&source)).unwrap();
+    assert!(!again.generated);
+    assert_eq!(again.path, first.path);
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn hashes_that_differ_only_in_punctuation_get_different_files() {
+    let cache = ThumbnailCache::new("/cache");
+    assert_ne!(cache.path_for("a:b"), cache.path_for("a/b"));
+    assert_ne!(cache.path_for("a:b"), cache.path_for("a_b"));
+  }
+
+  #[test]
+  fn a_failed_write_leaves_no_temporary_file_behind() {
+    let dir = std::env::temp_dir().join(format!("gamus-thumbnails-tmp-{}", std::process::id()));
+    let source = dir.join("cover.png");
+    fs::create_dir_all(&dir).unwrap();
+    RgbImage::new(40, 40).save(&source).unwrap();
+    let artwork = Artwork {
+      path: source,
+      mime_type: "image/png".into(),
+      description: None,
+      hash: "xxh3p64:beef".into(),
+      credits: None,
+      thumbnail_path: None,
+    };
+    let cache = ThumbnailCache::new(dir.join("cache"));
+    // Un directorio no vacío en la ruta final hace fallar el rename.
+    let target = cache.path_for(&artwork.hash);
+    fs::create_dir_all(target.join("blocker")).unwrap();
+
+    assert!(cache.ensure(&artwork).is_err());
+    assert!(!target.with_extension("jpg.tmp").exists());
+
+    let _ = fs::remove_dir_all(&dir);
+  }
+}
@@ -1,12 +1,20 @@
 use async_trait::async_trait;
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tracing::warn;
 
 use gamus_core::ports::scanner::{
-  ScanDevice, ScanError as CoreScanError, ScanGroup, ScannedFile as CoreScannedFile, Scanner,
+  ScanDevice, ScanError as CoreScanError, ScanGroup, ScanOutcome, ScanProgressFn, ScannedFile as CoreScannedFile,
+  Scanner,
 };
 
-use crate::fs_scanner::{FsScanGroup, FsScannedFile, ScannerError, scan_groups_async};
+use crate::config::DeviceSpeeds;
+use crate::fs_scanner::{
+  FsScanGroup, FsScannedFile, ScannerError, scan_groups_async_with_progress, scan_path_groups_async,
+};
 
 /// Implementation of the `Scanner` port for local filesystem interactions.
 ///
@@ -24,8 +32,20 @@ pub struct FsScanner {
 }
 
 impl FsScanner {
+  /// Creates a scanner seeded with the device speeds saved by previous runs.
+  ///
+  /// Entries older than `DEVICE_SPEED_MAX_AGE_SECS` are left out so those devices get
+  /// benchmarked again. A config that cannot be read only costs the benchmarks.
   pub fn new() -> Self {
-    Self { device_cache: Arc::new(Mutex::new(HashMap::new())) }
+    let speeds = match DeviceSpeeds::load() {
+      Ok(saved) => saved.fresh(unix_now()),
+      Err(e) => {
+        warn!(error = %e, "could not load saved device speeds");
+        HashMap::new()
+      }
+    };
+
+    Self { device_cache: Arc::new(Mutex::new(speeds)) }
   }
 }
 
@@ -35,62 +55,122 @@ impl Default for FsScanner {
   }
 }
 
-#[async_trait]
-impl Scanner for FsScanner {
-  /// Orchestrates the scanning of local storage devices.
+impl FsScanner {
+  /// Snapshot of known device speeds.
   ///
   /// # Concurrency Note
-  /// This method employs a "snapshot-then-update" locking strategy. We acquire the
-  /// cache lock strictly for reading/cloning initially, and release it *before*
-  /// starting the I/O heavy `scan_groups_async`. This prevents holding the mutex
-  /// during long-running asynchronous operations, avoiding potential contention.
-  async fn scan_library_files(&self) -> Result<Vec<ScanGroup>, CoreScanError> {
-    // 1. Snapshot known speeds.
+  /// Callers use a "snapshot-then-update" locking strategy: the lock is held only to
+  /// clone the map and is released *before* the I/O heavy scan. This prevents holding
+  /// the mutex during long-running asynchronous operations, avoiding potential contention.
+  fn known_speeds(&self) -> Result<HashMap<String, u64>, CoreScanError> {
     // Security: Handle poisoned mutexes gracefully by converting to an internal error.
-    let known_speeds = {
-      let guard =
-        self.device_cache.lock().map_err(|_| CoreScanError::Internal("Scanner mutex poisoned".to_string()))?;
-      guard.clone()
-    };
+    let guard = self.device_cache.lock().map_err(|_| CoreScanError::Internal("Scanner mutex poisoned".to_string()))?;
+    Ok(guard.clone())
+  }
 
-    // 2. Perform the heavy I/O scan.
-    // If a device is not in `known_speeds`, `scan_groups_async` will benchmark it.
-    let groups = scan_groups_async(&known_speeds).await.map_err(map_scanner_error)?;
-
-    // 3. Update cache with potential new benchmarks.
-    // We re-acquire the lock to merge new data.
-    {
-      if let Ok(mut guard) = self.device_cache.lock() {
-        for g in &groups {
-          if let Some(speed) = g.device.bandwidth_mb_s {
-            guard.insert(g.device.id.clone(), speed);
-          }
+  /// Merges any new benchmarks from `groups` back into the cache and the `[devices]` section.
+  ///
+  /// Only devices missing from `known` were benchmarked by this scan, so only those are
+  /// written; the config is re-read first to keep entries saved by other instances.
+  fn remember_speeds(&self, known: &HashMap<String, u64>, groups: &[FsScanGroup]) {
+    if let Ok(mut guard) = self.device_cache.lock() {
+      for g in groups {
+        if let Some(speed) = g.device.bandwidth_mb_s {
+          guard.insert(g.device.id.clone(), speed);
         }
       }
     }
 
-    // 4. Domain Adaptation.
-    // Map infrastructure-layer DTOs (`FsScanGroup`) to Core Domain entities (`ScanGroup`).
-    // This isolates the core from filesystem-specific implementation details (DTOs).
-    let mapped: Vec<ScanGroup> = groups
-      .into_iter()
-      .map(|g: FsScanGroup| {
-        let device = ScanDevice { id: g.device.id, bandwidth_mb_s: g.device.bandwidth_mb_s };
-
-        let files = g
-          .files
-          .into_iter()
-          .map(|f: FsScannedFile| CoreScannedFile { path: f.path, size_bytes: f.size, modified_unix: f.modified })
-          .collect();
-
-        ScanGroup { device, files }
-      })
+    let measured: Vec<(&str, u64)> = groups
+      .iter()
+      .filter(|g| !known.contains_key(&g.device.id))
+      .filter_map(|g| g.device.bandwidth_mb_s.map(|speed| (g.device.id.as_str(), speed)))
       .collect();
+    if measured.is_empty() {
+      return;
+    }
 
-    Ok(mapped)
+    let now = unix_now();
+    let result = DeviceSpeeds::load().and_then(|mut saved| {
+      for (id, speed) in measured {
+        saved.record(id, speed, now);
+      }
+      saved.save()
+    });
+    if let Err(e) = result {
+      warn!(error = %e, "could not save device speeds");
+    }
   }
 }
 
+#[async_trait]
+impl Scanner for FsScanner {
+  /// Orchestrates the scanning of local storage devices.
+  async fn scan_library_files(&self) -> Result<ScanOutcome, CoreScanError> {
+    self.scan_library_files_with_progress(Arc::new(|_| {})).await
+  }
+
+  async fn scan_library_files_with_progress(&self, on_progress: ScanProgressFn) -> Result<ScanOutcome, CoreScanError> {
+    // 1. Snapshot known speeds.
+    let known_speeds = self.known_speeds()?;
+
+    // 2. Perform the heavy I/O scan.
+    // If a device is not in `known_speeds`, `scan_groups_async_with_progress` will benchmark it.
+    let scan =
+      scan_groups_async_with_progress(&known_speeds, |found| on_progress(found)).await.map_err(map_scanner_error)?;
+
+    // 3. Update cache (and the saved speeds) with potential new benchmarks.
+    self.remember_speeds(&known_speeds, &scan.groups);
+
+    // 4. Domain Adaptation.
+    Ok(ScanOutcome {
+      groups: map_groups(scan.groups),
+      unavailable_roots: scan.unavailable_roots,
+      permission_denied: scan.permission_denied,
+    })
+  }
+
+  /// Scans a single subtree, sharing the device throughput cache with full scans.
+  async fn scan_path(&self, root: &Path) -> Result<Vec<ScanGroup>, CoreScanError> {
+    let known_speeds = self.known_speeds()?;
+
+    let groups = scan_path_groups_async(root, &known_speeds).await.map_err(map_scanner_error)?;
+
+    self.remember_speeds(&known_speeds, &groups);
+
+    Ok(map_groups(groups))
+  }
+}
+
+fn unix_now() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+/// Maps infrastructure-layer DTOs (`FsScanGroup`) to Core Domain entities (`ScanGroup`).
+///
+/// This isolates the core from filesystem-specific implementation details (DTOs).
+fn map_groups(groups: Vec<FsScanGroup>) -> Vec<ScanGroup> {
+  groups
+    .into_iter()
+    .map(|g: FsScanGroup| {
+      let device = ScanDevice { id: g.device.id, bandwidth_mb_s: g.device.bandwidth_mb_s };
+
+      let files = g
+        .files
+        .into_iter()
+        .map(|f: FsScannedFile| CoreScannedFile {
+          path: f.path,
+          size_bytes: f.size,
+          modified_unix: f.modified,
+          content_hash: f.content_hash,
+        })
+        .collect();
+
+      ScanGroup { device, files }
+    })
+    .collect()
+}
+
 /// Translates infrastructure-specific errors into domain-agnostic `CoreScanError`s.
 ///
 /// This prevents leaking implementation details (e.g., specific walker crate errors)
@@ -100,5 +180,6 @@ fn map_scanner_error(err: ScannerError) -> CoreScanError {
     ScannerError::Io(e) => CoreScanError::Io(e.to_string()),
     ScannerError::Walker(e) => CoreScanError::Internal(e),
     ScannerError::Config(e) => CoreScanError::Internal(e.to_string()),
+    ScannerError::RootsUnavailable(roots) => CoreScanError::RootsUnavailable(roots),
   }
 }
@@ -1,13 +1,18 @@
-use gamus_config::{CONFIG_BACKEND, ConfigBackend, ConfigError, PATHS};
+use gamus_config::{CONFIG_BACKEND, ConfigBackend, ConfigError, PATHS, SaveOutcome};
+use gamus_core::services::throttled_reporter::DEFAULT_SUCCESS_INTERVAL;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScannerConfig {
   /// Directorios raíz a escanear.
-  pub roots: Vec<PathBuf>,
+  pub roots: Vec<ScanRoot>,
 
-  /// Extensiones de audio a considerar.
+  /// Extensiones de audio a considerar en las raíces sin lista propia.
+  ///
+  /// El valor por defecto solo se usa si la clave falta: una lista guardada por el usuario,
+  /// aunque sea más corta, se respeta tal cual.
   #[serde(default = "default_audio_exts")]
   pub audio_exts: Vec<String>,
 
@@ -15,35 +20,238 @@ pub struct ScannerConfig {
   #[serde(default = "default_ignore_hidden")]
   pub ignore_hidden: bool,
 
+  /// Qué se considera oculto cuando `ignore_hidden` está activo.
+  #[serde(default)]
+  pub hidden_policy: HiddenPolicy,
+
   /// Profundidad máxima opcional.
   pub max_depth: Option<u32>,
+
+  /// Entrar en los directorios enlazados con symlinks (p. ej. una carpeta de música
+  /// sincronizada enlazada dentro de la raíz). Los ciclos se cortan al repetir directorio.
+  #[serde(default)]
+  pub follow_symlinks: bool,
+
+  /// Qué symlinks se siguen cuando `follow_symlinks` está activo.
+  #[serde(default)]
+  pub symlink_scope: SymlinkScope,
+
+  /// Hash de contenido para detectar cambios en la importación incremental.
+  #[serde(default)]
+  pub content_hash: ContentHashMode,
+
+  /// Cómo se mide la velocidad de lectura de un dispositivo nuevo.
+  #[serde(default)]
+  pub throughput: ThroughputConfig,
+
+  /// Duración mínima (segundos) para importar un archivo; los más cortos (tonos, efectos,
+  /// grabaciones sueltas) se saltan. `None` no filtra. El scanner no lo aplica: no abre los
+  /// archivos, así que lo usa la importación con la duración que ya extrae.
+  #[serde(default)]
+  pub min_duration_secs: Option<f64>,
+
+  /// Si la extensión no está en la lista, mirar los primeros KiB del archivo y aceptarlo
+  /// si sus bytes mágicos son de un contenedor de audio cuyo formato sí está en la lista
+  /// (archivos sin extensión de algunos NAS, un FLAC guardado como `.txt`). Desactivado
+  /// por defecto: abre y lee cada archivo que no pasa el filtro por extensión.
+  #[serde(default)]
+  pub sniff_unknown_extensions: bool,
+
+  /// Segundos durante los que la importación salta un archivo cuya extracción falló, salvo
+  /// que haya cambiado desde entonces. `0` lo reintenta siempre. Como `min_duration_secs`,
+  /// lo aplica la importación, no el scanner.
+  #[serde(default = "default_failure_cooldown_secs")]
+  pub failure_cooldown_secs: u64,
+
+  /// Milisegundos mínimos entre dos avisos de progreso de la importación a la interfaz: los
+  /// éxitos se agrupan en lotes (ver `ThrottledReporter`). `0` avisa de cada archivo. Como
+  /// `min_duration_secs`, lo aplica la importación, no el scanner.
+  #[serde(default = "default_progress_interval_ms")]
+  pub progress_interval_ms: u64,
 }
 
+/// Parámetros del micro-benchmark de lectura por dispositivo.
+///
+/// La muestra sale del archivo más grande del grupo, saltando su inicio: la cabecera y
+/// los primeros KiB ya los han leído el hash parcial y el extractor, y estarían en caché.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct ThroughputConfig {
+  /// Tamaño de la muestra en MiB. Si el archivo es más pequeño se lee entero.
+  pub sample_mb: u64,
+
+  /// MiB que se saltan desde el inicio antes de medir. En archivos que no dan para
+  /// salto + muestra, la ventana se desplaza hacia atrás hasta acabar en el final.
+  pub skip_mb: u64,
+
+  /// Pide al sistema que no sirva la muestra desde la caché de páginas
+  /// (`posix_fadvise(DONTNEED)` en Linux, `F_NOCACHE` en macOS). Sin efecto en el resto.
+  pub bypass_cache: bool,
+}
+
+impl Default for ThroughputConfig {
+  fn default() -> Self {
+    Self { sample_mb: 20, skip_mb: 4, bypass_cache: true }
+  }
+}
+
+impl ThroughputConfig {
+  pub fn sample_bytes(&self) -> u64 {
+    self.sample_mb.saturating_mul(1_048_576)
+  }
+
+  pub fn skip_bytes(&self) -> u64 {
+    self.skip_mb.saturating_mul(1_048_576)
+  }
+}
+
+/// Raíz de escaneo, opcionalmente con su propia lista de extensiones.
+///
+/// En el TOML acepta tanto la forma antigua, una ruta suelta (`"/music"`), como una tabla
+/// `{ path = "/masters", audio_exts = ["flac"] }`. Sin lista propia se guarda como ruta suelta.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(from = "ScanRootRepr", into = "ScanRootRepr")]
+pub struct ScanRoot {
+  pub path: PathBuf,
+  /// Sustituye a `ScannerConfig::audio_exts` dentro de esta raíz.
+  pub audio_exts: Option<Vec<String>>,
+}
+
+impl ScanRoot {
+  pub fn new(path: impl Into<PathBuf>) -> Self {
+    Self { path: path.into(), audio_exts: None }
+  }
+}
+
+impl From<PathBuf> for ScanRoot {
+  fn from(path: PathBuf) -> Self {
+    Self::new(path)
+  }
+}
+
+/// Formas serializadas de [`ScanRoot`].
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ScanRootRepr {
+  Path(PathBuf),
+  Table {
+    path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    audio_exts: Option<Vec<String>>,
+  },
+}
+
+impl From<ScanRootRepr> for ScanRoot {
+  fn from(repr: ScanRootRepr) -> Self {
+    match repr {
+      ScanRootRepr::Path(path) => Self::new(path),
+      ScanRootRepr::Table { path, audio_exts } => Self { path, audio_exts },
+    }
+  }
+}
+
+impl From<ScanRoot> for ScanRootRepr {
+  fn from(root: ScanRoot) -> Self {
+    match root.audio_exts {
+      None => ScanRootRepr::Path(root.path),
+      audio_exts => ScanRootRepr::Table { path: root.path, audio_exts },
+    }
+  }
+}
+
+/// Cómo se calcula el hash de contenido de cada archivo escaneado.
+///
+/// Con hash, la importación incremental lo usa como señal de cambio en vez de tamaño +
+/// fecha de modificación, que falla con herramientas de sincronización que conservan el
+/// `mtime` o que lo mueven sin tocar el archivo.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentHashMode {
+  /// Sin hash: solo tamaño + `mtime`.
+  Off,
+  /// Primeros y últimos 64 KiB más el tamaño. Barato y cubre los cambios de tags.
+  #[default]
+  Partial,
+  /// Archivo completo. Detecta cualquier cambio, pero lee toda la biblioteca en cada escaneo.
+  Full,
+}
+
+/// Criterio para decidir si una entrada del recorrido está oculta.
+///
+/// Solo se aplica a lo que hay *dentro* de cada raíz: una raíz configurada bajo una
+/// carpeta con punto (`~/.local/share/music`) se escanea igual.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HiddenPolicy {
+  /// Nombre que empieza por `.` (convención Unix), en cualquier plataforma.
+  #[default]
+  DotfilesOnly,
+  /// Lo que el sistema marca como oculto: atributo `FILE_ATTRIBUTE_HIDDEN` en Windows,
+  /// flag `UF_HIDDEN` además del punto en macOS, y el punto en el resto de Unix.
+  PlatformAttributes,
+  /// Nada se considera oculto.
+  None,
+}
+
+/// Hasta dónde pueden llevar los symlinks que se siguen.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SymlinkScope {
+  /// A cualquier parte, también fuera de la raíz.
+  #[default]
+  Anywhere,
+  /// Solo a directorios dentro de la propia raíz. Un enlace a `/` o a una carpeta del
+  /// sistema se salta en vez de recorrerse.
+  WithinRoot,
+}
+
+/// Formatos que FFmpeg decodifica y que aparecen habitualmente en bibliotecas musicales.
 fn default_audio_exts() -> Vec<String> {
-  vec!["mp3".into(), "flac".into(), "ogg".into()]
+  ["mp3", "flac", "ogg", "opus", "m4a", "aac", "wav", "aiff", "aif", "wv", "ape"]
+    .into_iter()
+    .map(String::from)
+    .collect()
 }
 
 fn default_ignore_hidden() -> bool {
   true
 }
 
+/// Un día.
+fn default_failure_cooldown_secs() -> u64 {
+  86_400
+}
+
+fn default_progress_interval_ms() -> u64 {
+  DEFAULT_SUCCESS_INTERVAL.as_millis() as u64
+}
+
 impl Default for ScannerConfig {
   fn default() -> Self {
     let mut roots = Vec::new();
 
     if let Some(audio_dir) = &PATHS.audio_dir {
-      roots.push(audio_dir.clone());
+      roots.push(ScanRoot::new(audio_dir.clone()));
     }
 
     if let Some(download_dir) = &PATHS.download_dir {
-      roots.push(download_dir.clone());
+      roots.push(ScanRoot::new(download_dir.clone()));
     }
 
     ScannerConfig {
       roots,
       audio_exts: default_audio_exts(),
       ignore_hidden: default_ignore_hidden(),
+      hidden_policy: HiddenPolicy::default(),
       max_depth: None,
+      follow_symlinks: false,
+      symlink_scope: SymlinkScope::default(),
+      content_hash: ContentHashMode::default(),
+      throughput: ThroughputConfig::default(),
+      min_duration_secs: None,
+      sniff_unknown_extensions: false,
+      failure_cooldown_secs: default_failure_cooldown_secs(),
+      progress_interval_ms: default_progress_interval_ms(),
     }
   }
 }
@@ -55,7 +263,153 @@ impl ScannerConfig {
     Ok(cfg)
   }
 
-  pub fn save(&self) -> Result<(), ConfigError> {
+  pub fn save(&self) -> Result<SaveOutcome, ConfigError> {
     CONFIG_BACKEND.save_section("scanner", self)
   }
+
+  /// Extensiones que se aceptan bajo `path`: las de la raíz configurada más específica que
+  /// lo contiene si tiene lista propia, y si no las globales.
+  pub fn audio_exts_for(&self, path: &Path) -> &[String] {
+    self
+      .roots
+      .iter()
+      .filter(|root| path.starts_with(&root.path))
+      .max_by_key(|root| root.path.components().count())
+      .and_then(|root| root.audio_exts.as_deref())
+      .unwrap_or(&self.audio_exts)
+  }
+}
+
+/// Antigüedad a partir de la cual una velocidad guardada se vuelve a medir (30 días).
+///
+/// Los discos cambian: un id de dispositivo puede pasar a otra unidad tras reconectarla.
+pub const DEVICE_SPEED_MAX_AGE_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// Velocidad de lectura medida para un dispositivo.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceSpeed {
+  /// Throughput en MB/s.
+  pub mb_s: u64,
+  /// Momento de la medición, en segundos Unix.
+  pub measured_at: u64,
+}
+
+/// Velocidades por id de dispositivo, persistidas en la sección `[devices]`.
+///
+/// Evita repetir el micro-benchmark de 20 MB de cada dispositivo en cada arranque.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct DeviceSpeeds {
+  pub entries: BTreeMap<String, DeviceSpeed>,
+}
+
+impl DeviceSpeeds {
+  pub fn load() -> Result<Self, ConfigError> {
+    CONFIG_BACKEND.load_section_with_default("devices")
+  }
+
+  pub fn save(&self) -> Result<SaveOutcome, ConfigError> {
+    CONFIG_BACKEND.save_section("devices", self)
+  }
+
+  /// Velocidades medidas hace menos de [`DEVICE_SPEED_MAX_AGE_SECS`] respecto a `now`.
+  pub fn fresh(&self, now: u64) -> HashMap<String, u64> {
+    self
+      .entries
+      .iter()
+      .filter(|(_, speed)| now.saturating_sub(speed.measured_at) < DEVICE_SPEED_MAX_AGE_SECS)
+      .map(|(id, speed)| (id.clone(), speed.mb_s))
+      .collect()
+  }
+
+  /// Registra (o sustituye) la medición de un dispositivo.
+  pub fn record(&mut self, device_id: impl Into<String>, mb_s: u64, now: u64) {
+    self.entries.insert(device_id.into(), DeviceSpeed { mb_s, measured_at: now });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn default_extensions_cover_common_formats() {
+    assert_eq!(default_audio_exts(), ["mp3", "flac", "ogg", "opus", "m4a", "aac", "wav", "aiff", "aif", "wv", "ape"]);
+  }
+
+  #[test]
+  fn saved_extension_list_is_kept_even_if_shorter() {
+    let saved: ScannerConfig = toml::from_str("roots = []\naudio_exts = [\"flac\"]\n").unwrap();
+    assert_eq!(saved.audio_exts, ["flac"]);
+
+    let missing: ScannerConfig = toml::from_str("roots = []\n").unwrap();
+    assert_eq!(missing.audio_exts, default_audio_exts());
+  }
+
+  #[test]
+  fn device_speeds_expire_after_max_age() {
+    let now = 10 * DEVICE_SPEED_MAX_AGE_SECS;
+    let mut speeds = DeviceSpeeds::default();
+    speeds.record("2049", 180, now - 60);
+    speeds.record("2050", 90, now - DEVICE_SPEED_MAX_AGE_SECS);
+
+    assert_eq!(speeds.fresh(now), HashMap::from([("2049".to_string(), 180)]));
+  }
+
+  #[test]
+  fn device_speeds_round_trip_through_toml() {
+    let mut speeds = DeviceSpeeds::default();
+    speeds.record("2049", 180, 1_760_000_000);
+    speeds.record("C:", 95, 1_760_000_100);
+
+    let encoded = toml::to_string(&speeds).unwrap();
+    assert_eq!(toml::from_str::<DeviceSpeeds>(&encoded).unwrap(), speeds);
+  }
+
+  #[test]
+  fn roots_accept_bare_paths_and_tables() {
+    let cfg: ScannerConfig = toml::from_str(
+      r#"
+roots = ["/music", { path = "/masters", audio_exts = ["flac"] }, { path = "/podcasts" }]
+audio_exts = ["mp3", "flac"]
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+      cfg.roots,
+      [
+        ScanRoot::new("/music"),
+        ScanRoot { path: "/masters".into(), audio_exts: Some(vec!["flac".into()]) },
+        ScanRoot::new("/podcasts"),
+      ]
+    );
+  }
+
+  #[test]
+  fn roots_without_override_serialize_as_bare_paths() {
+    let cfg = ScannerConfig {
+      roots: vec![ScanRoot::new("/music"), ScanRoot { path: "/masters".into(), audio_exts: Some(vec!["flac".into()]) }],
+      ..toml::from_str("roots = []").unwrap()
+    };
+
+    let encoded = toml::to_string(&cfg).unwrap();
+    assert!(encoded.contains(r#""/music""#), "{encoded}");
+    assert_eq!(toml::from_str::<ScannerConfig>(&encoded).unwrap().roots, cfg.roots);
+  }
+
+  #[test]
+  fn per_root_extensions_override_the_global_list() {
+    let cfg: ScannerConfig = toml::from_str(
+      r#"
+roots = ["/music", { path = "/music/masters", audio_exts = ["flac"] }]
+audio_exts = ["mp3", "flac"]
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(cfg.audio_exts_for(Path::new("/music/masters/a/b.flac")), ["flac"]);
+    assert_eq!(cfg.audio_exts_for(Path::new("/music/lossy/b.mp3")), ["mp3", "flac"]);
+    assert_eq!(cfg.audio_exts_for(Path::new("/elsewhere")), ["mp3", "flac"]);
+  }
 }
@@ -0,0 +1,68 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::config::ContentHashMode;
+
+/// Bytes hashed at each end of the file in [`ContentHashMode::Partial`].
+pub const PARTIAL_HASH_WINDOW: u64 = 64 * 1024;
+
+/// Computes the content hash of `path` according to `mode`.
+///
+/// The result is prefixed with the scheme (`xxh3p64:` / `xxh3f:`), so hashes computed
+/// under different modes are never compared as if they meant the same thing.
+/// Returns `Ok(None)` for [`ContentHashMode::Off`].
+pub fn content_hash(path: &Path, mode: ContentHashMode) -> io::Result<Option<String>> {
+  match mode {
+    ContentHashMode::Off => Ok(None),
+    ContentHashMode::Partial => {
+      partial_hash(path).map(|h| Some(format!("xxh3p{}:{h:032x}", PARTIAL_HASH_WINDOW / 1024)))
+    }
+    ContentHashMode::Full => full_hash(path).map(|h| Some(format!("xxh3f:{h:032x}"))),
+  }
+}
+
+/// Head + tail + size. Files smaller than two windows are hashed whole.
+fn partial_hash(path: &Path) -> io::Result<u128> {
+  let mut file = File::open(path)?;
+  let size = file.metadata()?.len();
+
+  let mut hasher = Xxh3::new();
+  hasher.update(&size.to_le_bytes());
+
+  if size <= PARTIAL_HASH_WINDOW * 2 {
+    hash_reader(&mut file, &mut hasher)?;
+    return Ok(hasher.digest128());
+  }
+
+  let mut buf = vec![0u8; PARTIAL_HASH_WINDOW as usize];
+  file.read_exact(&mut buf)?;
+  hasher.update(&buf);
+
+  file.seek(SeekFrom::Start(size - PARTIAL_HASH_WINDOW))?;
+  file.read_exact(&mut buf)?;
+  hasher.update(&buf);
+
+  Ok(hasher.digest128())
+}
+
+fn full_hash(path: &Path) -> io::Result<u128> {
+  let mut file = File::open(path)?;
+  let mut hasher = Xxh3::new();
+  hash_reader(&mut file, &mut hasher)?;
+  Ok(hasher.digest128())
+}
+
+fn hash_reader(reader: &mut impl Read, hasher: &mut Xxh3) -> io::Result<()> {
+  let mut buf = vec![0u8; 256 * 1024];
+  loop {
+    match reader.read(&mut buf) {
+      Ok(0) => return Ok(()),
+      Ok(n) => hasher.update(&buf[..n]),
+      Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+      Err(e) => return Err(e),
+    }
+  }
+}
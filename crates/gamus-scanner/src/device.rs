@@ -1,5 +1,15 @@
-use std::io::Read;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
+use std::time::Instant;
+
+use crate::config::ThroughputConfig;
+
+/// Below this, open/seek latency dominates and the figure says nothing about the device.
+const MIN_SAMPLE_BYTES: u64 = 256 * 1024;
+
+/// Read size per call; the sample itself can be much larger than what we want on the heap.
+const READ_CHUNK_BYTES: usize = 1_048_576;
 
 /// Identifies the physical device ID for a given file path.
 ///
@@ -43,37 +53,118 @@ pub fn device_id(_path: &Path) -> Result<String, std::io::Error> {
   Ok("UNKNOWN_DEVICE".into())
 }
 
-/// Performs a blocking micro-benchmark to estimate read throughput.
+/// Performs a blocking micro-benchmark to estimate read throughput in MB/s.
 ///
-/// Reads `sample_bytes` from the beginning of `sample_path` to calculate MB/s.
+/// Reads `cfg.sample_bytes()` starting `cfg.skip_bytes()` into `sample_path`. Files too short
+/// for skip + sample get a window shifted back to end at EOF, or are read whole if they are
+/// smaller than the sample; anything under 256 KiB is rejected rather than producing noise.
 ///
 /// # Performance Considerations
 /// * **Blocking:** This function blocks the thread. Do not call this directly from an async executor.
-/// * **Caching:** The OS page cache may skew results if the file was recently accessed.
-///   For the purpose of this application (ingestion throttling), cached speeds are an acceptable
-///   upper bound estimate.
-pub fn measure_device_throughput(sample_path: &Path, sample_bytes: usize) -> Result<f64, std::io::Error> {
-  let start = std::time::Instant::now();
-  let mut file = std::fs::File::open(sample_path)?;
-
-  // Allocate buffer on heap to avoid stack overflow for large sample sizes.
-  let mut buf = vec![0u8; sample_bytes];
-  let mut read_total = 0usize;
-
-  // Read loop ensures we fill the buffer even if the OS interrupts the read call.
-  while read_total < sample_bytes {
-    let n = file.read(&mut buf[read_total..])?;
+/// * **Caching:** With `cfg.bypass_cache` the sample range is evicted from (Linux) or kept out
+///   of (macOS) the page cache first. Elsewhere, or if the file is mapped by someone else, a
+///   recently read file can still report cache speed.
+///
+/// # Errors
+/// Besides I/O errors, fails with `InvalidInput` when the file is too small to sample and
+/// with `Other` when the read finishes too fast for the clock to measure.
+pub fn measure_device_throughput(sample_path: &Path, cfg: &ThroughputConfig) -> Result<f64, std::io::Error> {
+  let mut file = File::open(sample_path)?;
+  let len = file.metadata()?.len();
+
+  let Some((offset, sample)) = sample_window(len, cfg) else {
+    return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, "file too small for a throughput sample"));
+  };
+
+  if cfg.bypass_cache {
+    bypass_page_cache(&file, offset, sample);
+  }
+  file.seek(SeekFrom::Start(offset))?;
+
+  let mut buf = vec![0u8; READ_CHUNK_BYTES.min(sample as usize)];
+  let mut read_total = 0u64;
+  let start = Instant::now();
+
+  while read_total < sample {
+    let want = (sample - read_total).min(buf.len() as u64) as usize;
+    let n = file.read(&mut buf[..want])?;
     if n == 0 {
-      break; // EOF reached before sample size
+      break; // File shrank since we stat'ed it.
     }
-    read_total += n;
+    read_total += n as u64;
   }
 
   let secs = start.elapsed().as_secs_f64();
   if secs == 0.0 {
-    // Prevent division by zero if the read is instant (e.g., very small sample or fully cached).
-    Ok(0.0)
-  } else {
-    Ok((read_total as f64) / 1_048_576.0 / secs) // Convert bytes to MB/s
+    // Reporting 0 MB/s (or infinity) would be worse than admitting we don't know.
+    return Err(std::io::Error::other("throughput sample read too fast to measure"));
+  }
+  Ok((read_total as f64) / 1_048_576.0 / secs) // Convert bytes to MB/s
+}
+
+/// Byte range `(offset, length)` to sample from a file of `len` bytes, or `None` when the
+/// file is under [`MIN_SAMPLE_BYTES`]. See [`measure_device_throughput`] for the fallbacks.
+fn sample_window(len: u64, cfg: &ThroughputConfig) -> Option<(u64, u64)> {
+  let sample = cfg.sample_bytes().min(len);
+  if sample < MIN_SAMPLE_BYTES {
+    return None;
+  }
+  Some((cfg.skip_bytes().min(len - sample), sample))
+}
+
+/// Best-effort: drops the cached pages of `[offset, offset + len)` so the benchmark hits the device.
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+fn bypass_page_cache(file: &File, offset: u64, len: u64) {
+  use std::os::fd::AsRawFd;
+
+  // SAFETY: the descriptor is owned by `file` and stays open for the duration of the call.
+  unsafe {
+    libc::posix_fadvise(file.as_raw_fd(), offset as libc::off_t, len as libc::off_t, libc::POSIX_FADV_DONTNEED);
+  }
+}
+
+/// Best-effort: turns off data caching for reads through this descriptor.
+#[cfg(target_os = "macos")]
+fn bypass_page_cache(file: &File, _offset: u64, _len: u64) {
+  use std::os::fd::AsRawFd;
+
+  // SAFETY: the descriptor is owned by `file` and stays open for the duration of the call.
+  unsafe {
+    libc::fcntl(file.as_raw_fd(), libc::F_NOCACHE, 1);
+  }
+}
+
+/// No portable cache bypass elsewhere (Windows needs sector-aligned unbuffered reads).
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd", target_os = "macos")))]
+fn bypass_page_cache(_file: &File, _offset: u64, _len: u64) {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const MIB: u64 = 1_048_576;
+
+  #[test]
+  fn the_sample_window_shifts_back_or_shrinks_to_fit_the_file() {
+    let cfg = ThroughputConfig { sample_mb: 20, skip_mb: 4, bypass_cache: false };
+
+    // Room for skip + sample: the window starts after the skipped head.
+    assert_eq!(sample_window(100 * MIB, &cfg), Some((4 * MIB, 20 * MIB)));
+    // Not enough for both: the window is shifted back so it ends at EOF.
+    assert_eq!(sample_window(22 * MIB, &cfg), Some((2 * MIB, 20 * MIB)));
+    // Smaller than the sample: the whole file.
+    assert_eq!(sample_window(5 * MIB, &cfg), Some((0, 5 * MIB)));
+    // Too small to say anything about the device.
+    assert_eq!(sample_window(MIN_SAMPLE_BYTES - 1, &cfg), None);
+  }
+
+  #[test]
+  fn a_file_too_small_to_sample_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("tiny.flac");
+    std::fs::write(&path, vec![0u8; 1024]).unwrap();
+
+    let err = measure_device_throughput(&path, &ThroughputConfig::default()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
   }
 }
@@ -1,16 +1,18 @@
 use std::collections::HashMap;
-use std::fs;
+use std::fs::{self, File};
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
 use std::time::UNIX_EPOCH;
 
 use futures::StreamExt;
 use thiserror::Error;
 use tokio::task;
+use tracing::warn;
 
-use gamus_fs::async_walker::{Filtering, WalkConfig, walk_filtered};
+use gamus_fs::async_walker::{Filtering, SymlinkPolicy, WalkConfig, WalkEvent, walk_with_events};
 
-use crate::config::ScannerConfig;
+use crate::config::{ContentHashMode, HiddenPolicy, ScannerConfig, SymlinkScope, ThroughputConfig};
+use crate::content_hash::content_hash;
 use crate::device::{device_id, measure_device_throughput};
 
 #[derive(Debug, Error)]
@@ -23,6 +25,14 @@ pub enum ScannerError {
 
   #[error("config error: {0}")]
   Config(#[from] gamus_config::ConfigError),
+
+  /// None of the requested roots exists or can be read (e.g. every library lives on an unplugged drive).
+  #[error("scan roots unavailable: {}", display_paths(.0))]
+  RootsUnavailable(Vec<PathBuf>),
+}
+
+fn display_paths(paths: &[PathBuf]) -> String {
+  paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")
 }
 
 /// Lightweight DTO representing a file found during scanning.
@@ -32,6 +42,8 @@ pub struct FsScannedFile {
   pub path: PathBuf,
   pub size: u64,
   pub modified: u64,
+  /// Filled by [`fill_content_hashes`] according to [`ScannerConfig::content_hash`].
+  pub content_hash: Option<String>,
 }
 
 /// Represents a physical storage volume/partition.
@@ -52,15 +64,100 @@ pub struct FsScanGroup {
   pub files: Vec<FsScannedFile>,
 }
 
+/// Result of a multi-root scan: the files found plus the roots and folders that had to be skipped.
+#[derive(Debug, Clone, Default)]
+pub struct FsScanOutcome {
+  pub files: Vec<FsScannedFile>,
+  /// Configured roots that are missing, not directories, or unreadable for a reason other than
+  /// permissions (those go to `permission_denied`).
+  pub unavailable_roots: Vec<PathBuf>,
+  /// Roots and folders the OS refused to list (`PermissionDenied`), in the order found.
+  /// Unlike other read errors these need the user to act: fix the permissions, or grant
+  /// Full Disk Access on macOS.
+  pub permission_denied: Vec<PathBuf>,
+}
+
+impl FsScanOutcome {
+  /// Summarizes the scan for a dry run: totals, per-extension counts and up to
+  /// `sample_size` example paths (in path order, so repeated previews are stable).
+  pub fn preview(&self, sample_size: usize) -> ScanPreview {
+    let mut by_extension: HashMap<String, usize> = HashMap::new();
+    for file in &self.files {
+      let ext = file.path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+      *by_extension.entry(ext).or_default() += 1;
+    }
+
+    let mut sample_paths: Vec<PathBuf> = self.files.iter().map(|f| f.path.clone()).collect();
+    sample_paths.sort();
+    sample_paths.truncate(sample_size);
+
+    ScanPreview {
+      total: self.files.len(),
+      by_extension,
+      sample_paths,
+      unavailable_roots: self.unavailable_roots.clone(),
+      permission_denied: self.permission_denied.clone(),
+    }
+  }
+}
+
+/// What a scan would feed into an import, without reading tags or touching the database.
+#[derive(Debug, Clone, Default)]
+pub struct ScanPreview {
+  /// Number of audio files matched by the configuration.
+  pub total: usize,
+  /// Matched files per lowercase extension.
+  pub by_extension: HashMap<String, usize>,
+  /// A capped, sorted sample of the matched paths.
+  pub sample_paths: Vec<PathBuf>,
+  pub unavailable_roots: Vec<PathBuf>,
+  pub permission_denied: Vec<PathBuf>,
+}
+
+/// Same as [`FsScanOutcome`], with the files already grouped by device.
+#[derive(Debug, Clone, Default)]
+pub struct FsGroupedScan {
+  pub groups: Vec<FsScanGroup>,
+  pub unavailable_roots: Vec<PathBuf>,
+  pub permission_denied: Vec<PathBuf>,
+}
+
+/// Files found under one root, plus the folders in it that could not be listed for lack of permission.
+struct RootScan {
+  files: Vec<FsScannedFile>,
+  permission_denied: Vec<PathBuf>,
+}
+
 /// Checks if a file path corresponds to a supported audio format.
 /// Comparisons are case-insensitive.
-fn is_audio(path: &Path, cfg: &ScannerConfig) -> bool {
+fn is_audio(path: &Path, audio_exts: &[String]) -> bool {
   let ext = match path.extension().and_then(|e| e.to_str()) {
     Some(e) => e.to_lowercase(),
     None => return false,
   };
 
-  cfg.audio_exts.iter().any(|cfg_ext| cfg_ext.eq_ignore_ascii_case(&ext))
+  audio_exts.iter().any(|cfg_ext| cfg_ext.eq_ignore_ascii_case(&ext))
+}
+
+/// Bytes read from the start of a file when sniffing its content.
+const SNIFF_LEN: u64 = 8 * 1024;
+
+/// Content-based fallback for [`is_audio`]: checks the magic bytes of the first
+/// [`SNIFF_LEN`] bytes for an audio container whose usual extension is in `audio_exts`,
+/// so per-root extension lists still decide which formats are wanted.
+///
+/// Unreadable files are logged and treated as not audio.
+fn sniff_audio(path: &Path, audio_exts: &[String]) -> bool {
+  let mut head = Vec::with_capacity(SNIFF_LEN as usize);
+  if let Err(e) = File::open(path).and_then(|f| f.take(SNIFF_LEN).read_to_end(&mut head)) {
+    warn!(path = %path.display(), error = %e, "could not sniff file content");
+    return false;
+  }
+
+  infer::get(&head).is_some_and(|kind| {
+    kind.matcher_type() == infer::MatcherType::Audio
+      && audio_exts.iter().any(|cfg_ext| cfg_ext.eq_ignore_ascii_case(kind.extension()))
+  })
 }
 
 /// Safely extracts size and modification time.
@@ -74,7 +171,59 @@ fn file_metadata(path: &Path) -> Result<(u64, u64), ScannerError> {
   Ok((size, modified))
 }
 
-pub async fn scan_music_from_config() -> Result<Vec<FsScannedFile>, ScannerError> {
+/// Decides whether a walked entry is hidden under `policy`.
+fn is_hidden(path: &Path, policy: HiddenPolicy) -> bool {
+  let is_dotfile = || path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+
+  match policy {
+    HiddenPolicy::None => false,
+    HiddenPolicy::DotfilesOnly => is_dotfile(),
+    HiddenPolicy::PlatformAttributes => has_hidden_attribute(path) || (cfg!(unix) && is_dotfile()),
+  }
+}
+
+/// Windows `FILE_ATTRIBUTE_HIDDEN`. Entries whose metadata can't be read count as visible.
+#[cfg(windows)]
+fn has_hidden_attribute(path: &Path) -> bool {
+  use std::os::windows::fs::MetadataExt;
+
+  const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+  fs::symlink_metadata(path).is_ok_and(|m| m.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+}
+
+/// macOS `UF_HIDDEN` (set by `chflags hidden` and the Finder).
+#[cfg(target_os = "macos")]
+fn has_hidden_attribute(path: &Path) -> bool {
+  use std::os::macos::fs::MetadataExt;
+
+  const UF_HIDDEN: u32 = 0x8000;
+  fs::symlink_metadata(path).is_ok_and(|m| m.st_flags() & UF_HIDDEN != 0)
+}
+
+/// Other platforms have no hidden attribute beyond the dotfile convention.
+#[cfg(not(any(windows, target_os = "macos")))]
+fn has_hidden_attribute(_path: &Path) -> bool {
+  false
+}
+
+/// A root is usable when it is an existing directory we are allowed to list.
+///
+/// Checked up front because the walker only reports a missing root as a per-entry error,
+/// which would make an unplugged drive indistinguishable from an empty library.
+fn root_is_available(root: &Path) -> bool {
+  check_root(root).is_ok()
+}
+
+/// Same check as [`root_is_available`], keeping the error so a denied root can be told
+/// apart from a missing one.
+fn check_root(root: &Path) -> io::Result<()> {
+  if !root.is_dir() {
+    return Err(io::ErrorKind::NotADirectory.into());
+  }
+  fs::read_dir(root).map(drop)
+}
+
+pub async fn scan_music_from_config() -> Result<FsScanOutcome, ScannerError> {
   let cfg = ScannerConfig::load()?;
   scan_music_with_cfg(&cfg).await
 }
@@ -82,71 +231,171 @@ pub async fn scan_music_from_config() -> Result<Vec<FsScannedFile>, ScannerError
 /// Performs a recursive, asynchronous filesystem walk based on the provided configuration.
 ///
 /// # Logic
-/// * Uses `gamus_fs::async_walker` to stream directory entries without blocking the executor.
-/// * Applies filtering for hidden files (optional in config) and temporary files (`.tmp`).
-/// * Flattens the stream into a Vector.
+/// * Skips roots that are missing or unreadable, listing them in `unavailable_roots`, or in
+///   `permission_denied` when the OS refused to list them.
+/// * Walks every remaining root through [`scan_music_in_root`].
+/// * Lists roots and folders that could not be read for lack of permission in
+///   `permission_denied`; every other walker error is only logged.
+/// * Flattens the results into a Vector.
+///
+/// # Errors
+/// Returns [`ScannerError::RootsUnavailable`] when roots are configured but none of them is available.
 ///
 /// # Performance Note
 /// For libraries exceeding 100k files, the resulting `Vec` might cause a spike in heap allocation.
 /// If memory constraints become an issue, refactor this to return a `Stream`.
-pub async fn scan_music_with_cfg(cfg: &ScannerConfig) -> Result<Vec<FsScannedFile>, ScannerError> {
-  let walk_cfg =
-    WalkConfig { follow_symlinks: false, max_depth: cfg.max_depth.unwrap_or(50) as usize, dedup_dirs: true };
-
-  let mut all_files = Vec::new();
-  // Arc is required to share config across the stream's future boundary.
-  let cfg_arc = Arc::new(cfg.clone());
-
-  for root in &cfg_arc.roots {
-    let cfg_for_root = Arc::clone(&cfg_arc);
-
-    let entries = walk_filtered(root, walk_cfg.clone(), move |entry| {
-      let path = entry.path.clone();
-      let ignore_hidden = cfg_for_root.ignore_hidden;
-
-      async move {
-        // Security/UX: Skip hidden folders if configured to avoid scanning system directories.
-        if ignore_hidden {
-          if let Some(name) = path.file_name() {
-            if name.to_string_lossy().starts_with('.') {
-              return Filtering::IgnoreDir;
-            }
-          }
-        }
+pub async fn scan_music_with_cfg(cfg: &ScannerConfig) -> Result<FsScanOutcome, ScannerError> {
+  scan_music_with_cfg_progress(cfg, |_| {}).await
+}
 
-        // Ignore partial downloads or temp files common in sync folders.
-        if path.extension().map_or(false, |e| e == "tmp") {
-          return Filtering::Ignore;
-        }
+/// Same as [`scan_music_with_cfg`], calling `on_progress` with the running total of audio
+/// files found across all roots every time the walker enters a directory.
+///
+/// The callback runs inline on the scanning task: keep it cheap.
+pub async fn scan_music_with_cfg_progress(
+  cfg: &ScannerConfig,
+  mut on_progress: impl FnMut(usize),
+) -> Result<FsScanOutcome, ScannerError> {
+  let mut outcome = FsScanOutcome::default();
+
+  let mut skipped_roots = Vec::new();
+
+  for root in cfg.roots.iter().map(|r| r.path.as_path()) {
+    if let Err(e) = check_root(root) {
+      warn!(root = %root.display(), error = %e, "scan root unavailable, skipping");
+      if e.kind() == io::ErrorKind::PermissionDenied {
+        outcome.permission_denied.push(root.to_path_buf());
+      } else {
+        outcome.unavailable_roots.push(root.to_path_buf());
+      }
+      skipped_roots.push(root.to_path_buf());
+      continue;
+    }
+    let found_before = outcome.files.len();
+    let scan = scan_root(root, cfg, |p| on_progress(found_before + p.files_found)).await?;
+    outcome.files.extend(scan.files);
+    outcome.permission_denied.extend(scan.permission_denied);
+    on_progress(outcome.files.len());
+  }
+
+  if !cfg.roots.is_empty() && skipped_roots.len() == cfg.roots.len() {
+    return Err(ScannerError::RootsUnavailable(skipped_roots));
+  }
+
+  Ok(outcome)
+}
+
+/// Walks a single directory tree with the same filters as a full scan.
+///
+/// # Logic
+/// * Uses `gamus_fs::async_walker` to stream directory entries without blocking the executor.
+/// * Applies filtering for hidden files (optional in config) and temporary files (`.tmp`).
+/// * `root` does not need to be one of `cfg.roots`; the filters and depth limit are taken from `cfg`,
+///   and the extension list from the most specific configured root containing each file
+///   (see [`ScannerConfig::audio_exts_for`]).
+pub async fn scan_music_in_root(root: &Path, cfg: &ScannerConfig) -> Result<Vec<FsScannedFile>, ScannerError> {
+  scan_music_in_root_with_progress(root, cfg, |_| {}).await
+}
+
+/// Snapshot passed to the progress callback of [`scan_music_in_root_with_progress`].
+#[derive(Debug, Clone, Copy)]
+pub struct ScanProgress<'a> {
+  /// Directory the walker just entered.
+  pub current_dir: &'a Path,
+  /// Audio files accepted so far in this root (approximate: counted before the scan finishes).
+  pub files_found: usize,
+}
+
+/// Same as [`scan_music_in_root`], invoking `on_progress` every time the walker enters a directory.
+///
+/// The callback runs inline on the scanning task: keep it cheap (e.g. forward to a channel or throttle).
+pub async fn scan_music_in_root_with_progress(
+  root: &Path,
+  cfg: &ScannerConfig,
+  on_progress: impl FnMut(ScanProgress<'_>),
+) -> Result<Vec<FsScannedFile>, ScannerError> {
+  Ok(scan_root(root, cfg, on_progress).await?.files)
+}
+
+async fn scan_root(
+  root: &Path,
+  cfg: &ScannerConfig,
+  mut on_progress: impl FnMut(ScanProgress<'_>),
+) -> Result<RootScan, ScannerError> {
+  let symlink_policy = match (cfg.follow_symlinks, cfg.symlink_scope) {
+    (false, _) => SymlinkPolicy::None,
+    (true, SymlinkScope::Anywhere) => SymlinkPolicy::All,
+    (true, SymlinkScope::WithinRoot) => SymlinkPolicy::within_root(root).await,
+  };
+  // `dedup_dirs` is what keeps `follow_symlinks` from looping forever on `link -> ..`.
+  let walk_cfg = WalkConfig { symlink_policy, max_depth: cfg.max_depth.unwrap_or(50) as usize, dedup_dirs: true };
+  let ignore_hidden = cfg.ignore_hidden;
+  let hidden_policy = cfg.hidden_policy;
+
+  let mut files = Vec::new();
+  let mut permission_denied = Vec::new();
 
-        Filtering::Continue
+  let entries = walk_with_events(root, walk_cfg, move |entry| {
+    let path = entry.path.clone();
+
+    async move {
+      // Security/UX: Skip hidden folders if configured to avoid scanning system directories.
+      if ignore_hidden && is_hidden(&path, hidden_policy) {
+        return Filtering::IgnoreDir;
       }
-    });
 
-    tokio::pin!(entries);
+      // Ignore partial downloads or temp files common in sync folders.
+      if path.extension().is_some_and(|e| e == "tmp") {
+        return Filtering::Ignore;
+      }
 
-    while let Some(res) = entries.next().await {
-      let entry = match res {
-        Ok(e) => e,
-        Err(e) => {
-          // Log but do not abort the entire scan on single permission errors.
-          eprintln!("walker error: {e}");
-          continue;
-        }
-      };
+      Filtering::Continue
+    }
+  });
 
-      let path = entry.path;
+  tokio::pin!(entries);
 
-      if path.is_file() && is_audio(&path, &cfg_arc) {
-        match file_metadata(&path) {
-          Ok((size, modified)) => all_files.push(FsScannedFile { path, size, modified }),
-          Err(e) => eprintln!("metadata error: {e}"),
+  while let Some(res) = entries.next().await {
+    let entry = match res {
+      Ok(WalkEvent::Entry(e)) => e,
+      Ok(WalkEvent::DirEnter { path, .. }) => {
+        on_progress(ScanProgress { current_dir: &path, files_found: files.len() });
+        continue;
+      }
+      Ok(WalkEvent::DirLeave { .. }) => continue,
+      Ok(WalkEvent::DirSkipped { path, .. }) => {
+        warn!(path = %path.display(), "directory already scanned, skipping (symlink cycle or duplicate link)");
+        continue;
+      }
+      // Log but do not abort the entire scan on single unreadable folders.
+      Ok(WalkEvent::DirUnreadable { path, error, .. }) => {
+        warn!(path = %path.display(), error = %error, "could not read directory");
+        if error.kind() == io::ErrorKind::PermissionDenied {
+          permission_denied.push(path);
         }
+        continue;
+      }
+      Err(e) => {
+        warn!(error = %e, "walker error");
+        continue;
+      }
+    };
+
+    let path = entry.path;
+
+    // Extension first: sniffing opens the file, so it only runs for the ones that miss.
+    let audio_exts = cfg.audio_exts_for(&path);
+    if path.is_file()
+      && (is_audio(&path, audio_exts) || (cfg.sniff_unknown_extensions && sniff_audio(&path, audio_exts)))
+    {
+      match file_metadata(&path) {
+        Ok((size, modified)) => files.push(FsScannedFile { path, size, modified, content_hash: None }),
+        Err(e) => warn!(path = %path.display(), error = %e, "metadata error"),
       }
     }
   }
 
-  Ok(all_files)
+  Ok(RootScan { files, permission_denied })
 }
 
 /// Orchestrates the scanning process and groups files by their physical storage device.
@@ -159,10 +408,76 @@ pub async fn scan_music_with_cfg(cfg: &ScannerConfig) -> Result<Vec<FsScannedFil
 /// # Throughput Measurement
 /// If `known_speeds` is missing an entry for a device, a micro-benchmark is triggered.
 /// This IO operation is offloaded to `spawn_blocking` to prevent stalling the Tokio runtime.
-pub async fn scan_groups_async(known_speeds: &HashMap<String, u64>) -> Result<Vec<FsScanGroup>, ScannerError> {
+pub async fn scan_groups_async(known_speeds: &HashMap<String, u64>) -> Result<FsGroupedScan, ScannerError> {
+  scan_groups_async_with_progress(known_speeds, |_| {}).await
+}
+
+/// Same as [`scan_groups_async`], reporting the walk through `on_progress`
+/// (see [`scan_music_with_cfg_progress`]). Hashing and grouping are not reported.
+pub async fn scan_groups_async_with_progress(
+  known_speeds: &HashMap<String, u64>,
+  on_progress: impl FnMut(usize),
+) -> Result<FsGroupedScan, ScannerError> {
   let cfg = ScannerConfig::load()?;
-  let files = scan_music_with_cfg(&cfg).await?;
+  let outcome = scan_music_with_cfg_progress(&cfg, on_progress).await?;
+  let files = fill_content_hashes(outcome.files, cfg.content_hash).await?;
+
+  let groups = group_by_device(files, known_speeds, cfg.throughput).await?;
+  Ok(FsGroupedScan {
+    groups,
+    unavailable_roots: outcome.unavailable_roots,
+    permission_denied: outcome.permission_denied,
+  })
+}
+
+/// Same as [`scan_groups_async`], but only walks `root` instead of every configured root.
+///
+/// Used for targeted rescans (a single added folder, a filesystem watcher event).
+/// Filters and depth limits still come from the persisted [`ScannerConfig`].
+pub async fn scan_path_groups_async(
+  root: &Path,
+  known_speeds: &HashMap<String, u64>,
+) -> Result<Vec<FsScanGroup>, ScannerError> {
+  let cfg = ScannerConfig::load()?;
+  if !root_is_available(root) {
+    return Err(ScannerError::RootsUnavailable(vec![root.to_path_buf()]));
+  }
+  let files = scan_music_in_root(root, &cfg).await?;
+  let files = fill_content_hashes(files, cfg.content_hash).await?;
+
+  group_by_device(files, known_speeds, cfg.throughput).await
+}
+
+/// Computes `content_hash` for every file on a blocking thread.
+///
+/// A file that can't be read keeps `None`, so the importer falls back to size + mtime for it.
+pub async fn fill_content_hashes(
+  mut files: Vec<FsScannedFile>,
+  mode: ContentHashMode,
+) -> Result<Vec<FsScannedFile>, ScannerError> {
+  if mode == ContentHashMode::Off {
+    return Ok(files);
+  }
+
+  task::spawn_blocking(move || {
+    for f in &mut files {
+      match content_hash(&f.path, mode) {
+        Ok(hash) => f.content_hash = hash,
+        Err(e) => warn!(path = %f.path.display(), error = %e, "content hash error"),
+      }
+    }
+    files
+  })
+  .await
+  .map_err(|e| ScannerError::Walker(format!("join error: {e}")))
+}
 
+/// Groups scanned files by device and attaches a throughput figure to each group.
+async fn group_by_device(
+  files: Vec<FsScannedFile>,
+  known_speeds: &HashMap<String, u64>,
+  throughput: ThroughputConfig,
+) -> Result<Vec<FsScanGroup>, ScannerError> {
   // 1) Group by device_id to isolate I/O domains.
   let mut by_device: HashMap<String, Vec<FsScannedFile>> = HashMap::new();
 
@@ -171,7 +486,7 @@ pub async fn scan_groups_async(known_speeds: &HashMap<String, u64>) -> Result<Ve
       Ok(id) => id,
       Err(e) => {
         // Fallback strategy: Treat unknown devices as a single generic group.
-        eprintln!("device_id error for {}: {e}", f.path.display());
+        warn!(path = %f.path.display(), error = %e, "device_id error");
         "UNKNOWN_DEVICE".to_string()
       }
     };
@@ -179,7 +494,6 @@ pub async fn scan_groups_async(known_speeds: &HashMap<String, u64>) -> Result<Ve
     by_device.entry(dev_id).or_default().push(f);
   }
 
-  const SAMPLE_BYTES: u64 = 20 * 1_048_576; // 20 MB sample for throughput test
   let mut handles = Vec::new();
 
   for (dev_id, files) in by_device {
@@ -191,13 +505,20 @@ pub async fn scan_groups_async(known_speeds: &HashMap<String, u64>) -> Result<Ve
       let handle = tokio::spawn(async move { (dev_id_clone, Some(cached_speed), files_clone) });
       handles.push(handle);
     } else {
-      let sample_path = files.get(0).map(|f| f.path.clone());
+      // The largest file gives the best chance of fitting skip + sample.
+      let sample_path = files.iter().max_by_key(|f| f.size).map(|f| f.path.clone());
 
       // Slow path: Blocking I/O benchmark. Must be offloaded to thread pool.
       let handle = task::spawn_blocking(move || {
         let bw_opt = sample_path
           .as_ref()
-          .and_then(|p| measure_device_throughput(p, SAMPLE_BYTES as usize).ok())
+          .and_then(|p| match measure_device_throughput(p, &throughput) {
+            Ok(bw) => Some(bw),
+            Err(e) => {
+              warn!(path = %p.display(), error = %e, "throughput benchmark failed");
+              None
+            }
+          })
           .map(|bw| bw as u64);
 
         (dev_id, bw_opt, files)
@@ -215,5 +536,60 @@ pub async fn scan_groups_async(known_speeds: &HashMap<String, u64>) -> Result<Ve
     groups.push(FsScanGroup { device, files });
   }
 
+  sort_fastest_first(&mut groups);
   Ok(groups)
 }
+
+/// Orders groups by descending throughput, unknown speeds last, so the import starts with
+/// the fast internal drive that usually holds most of the library.
+///
+/// Ties go to the group with more files, then to the device id, to keep the order stable
+/// across scans (it comes out of a `HashMap`).
+fn sort_fastest_first(groups: &mut [FsScanGroup]) {
+  groups.sort_by(|a, b| {
+    b.device
+      .bandwidth_mb_s
+      .cmp(&a.device.bandwidth_mb_s)
+      .then_with(|| b.files.len().cmp(&a.files.len()))
+      .then_with(|| a.device.id.cmp(&b.device.id))
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn group(id: &str, bandwidth_mb_s: Option<u64>, files: usize) -> FsScanGroup {
+    let file = FsScannedFile { path: PathBuf::from("/x.flac"), size: 1, modified: 0, content_hash: None };
+    FsScanGroup { device: FsDevice { id: id.into(), bandwidth_mb_s }, files: vec![file; files] }
+  }
+
+  #[test]
+  fn hidden_entries_depend_on_the_policy() {
+    let (dotfile, plain) = (Path::new("/music/.cache"), Path::new("/music/album"));
+
+    assert!(is_hidden(dotfile, HiddenPolicy::DotfilesOnly));
+    assert!(!is_hidden(plain, HiddenPolicy::DotfilesOnly));
+    assert!(!is_hidden(dotfile, HiddenPolicy::None));
+    assert!(!is_hidden(plain, HiddenPolicy::None));
+    // Neither path exists, so there is no attribute to read: only the dot counts, on Unix.
+    assert_eq!(is_hidden(dotfile, HiddenPolicy::PlatformAttributes), cfg!(unix));
+    assert!(!is_hidden(plain, HiddenPolicy::PlatformAttributes));
+  }
+
+  #[test]
+  fn groups_are_sorted_fastest_first_with_unknown_speeds_last() {
+    let mut groups = vec![
+      group("usb", Some(40), 10),
+      group("nas", None, 5),
+      group("nvme", Some(2_000), 900),
+      group("mystery", None, 50),
+      group("sata", Some(450), 1),
+    ];
+
+    sort_fastest_first(&mut groups);
+
+    let order: Vec<&str> = groups.iter().map(|g| g.device.id.as_str()).collect();
+    assert_eq!(order, ["nvme", "sata", "usb", "mystery", "nas"]);
+  }
+}
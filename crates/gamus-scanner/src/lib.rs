@@ -1,8 +1,16 @@
 pub mod adapter;
 pub mod config;
+pub mod content_hash;
 pub mod device;
 pub mod fs_scanner;
 
 pub use adapter::FsScanner;
-pub use config::ScannerConfig;
-pub use fs_scanner::{FsDevice, FsScanGroup, FsScannedFile, ScannerError, scan_groups_async, scan_music_from_config};
+pub use config::{
+  ContentHashMode, DEVICE_SPEED_MAX_AGE_SECS, DeviceSpeed, DeviceSpeeds, HiddenPolicy, ScanRoot, ScannerConfig,
+  SymlinkScope, ThroughputConfig,
+};
+pub use fs_scanner::{
+  FsDevice, FsGroupedScan, FsScanGroup, FsScanOutcome, FsScannedFile, ScanPreview, ScanProgress, ScannerError,
+  fill_content_hashes, scan_groups_async, scan_groups_async_with_progress, scan_music_from_config, scan_music_in_root,
+  scan_music_in_root_with_progress, scan_music_with_cfg, scan_music_with_cfg_progress, scan_path_groups_async,
+};
@@ -0,0 +1,49 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use gamus_scanner::{ScanRoot, ScannerConfig, scan_music_with_cfg};
+
+/// Cabecera mínima que `infer` reconoce como FLAC.
+const FLAC_HEAD: &[u8] = b"fLaC\x00\x00\x00\x22";
+
+/// `root/{a.flac, 01 Intro, track.txt, notes.txt}`: los dos del medio son FLAC sin su extensión.
+fn library() -> tempfile::TempDir {
+  let dir = tempfile::tempdir().unwrap();
+  fs::write(dir.path().join("a.flac"), FLAC_HEAD).unwrap();
+  fs::write(dir.path().join("01 Intro"), FLAC_HEAD).unwrap();
+  fs::write(dir.path().join("track.txt"), FLAC_HEAD).unwrap();
+  fs::write(dir.path().join("notes.txt"), b"just some liner notes").unwrap();
+  dir
+}
+
+async fn found(root: &Path, audio_exts: &[&str], sniff_unknown_extensions: bool) -> BTreeSet<PathBuf> {
+  let cfg = ScannerConfig {
+    roots: vec![ScanRoot::new(root)],
+    audio_exts: audio_exts.iter().map(|e| e.to_string()).collect(),
+    ignore_hidden: false,
+    sniff_unknown_extensions,
+    ..ScannerConfig::default()
+  };
+  let outcome = scan_music_with_cfg(&cfg).await.unwrap();
+  outcome.files.iter().map(|f| f.path.strip_prefix(root).unwrap().to_path_buf()).collect()
+}
+
+fn paths(list: &[&str]) -> BTreeSet<PathBuf> {
+  list.iter().map(PathBuf::from).collect()
+}
+
+#[tokio::test]
+async fn extensionless_and_mislabeled_flac_are_found_only_when_sniffing() {
+  let dir = library();
+
+  assert_eq!(found(dir.path(), &["flac"], false).await, paths(&["a.flac"]));
+  assert_eq!(found(dir.path(), &["flac"], true).await, paths(&["a.flac", "01 Intro", "track.txt"]));
+}
+
+#[tokio::test]
+async fn sniffed_formats_must_still_be_in_the_extension_list() {
+  let dir = library();
+
+  assert_eq!(found(dir.path(), &["mp3"], true).await, paths(&[]));
+}
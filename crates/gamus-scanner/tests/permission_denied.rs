@@ -0,0 +1,55 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use gamus_scanner::{ScanRoot, ScannerConfig, scan_music_with_cfg};
+
+const FLAC_HEAD: &[u8] = b"fLaC\x00\x00\x00\x22";
+
+fn set_mode(path: &Path, mode: u32) {
+  fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+}
+
+#[tokio::test]
+async fn locked_roots_and_folders_are_reported_only_as_permission_denied() {
+  let dir = tempfile::tempdir().unwrap();
+  let (open, locked_root) = (dir.path().join("open"), dir.path().join("locked"));
+  let locked_folder = open.join("private");
+  fs::create_dir_all(&locked_folder).unwrap();
+  fs::create_dir(&locked_root).unwrap();
+  fs::write(open.join("a.flac"), FLAC_HEAD).unwrap();
+  fs::write(locked_folder.join("b.flac"), FLAC_HEAD).unwrap();
+  set_mode(&locked_root, 0o000);
+  set_mode(&locked_folder, 0o000);
+
+  // root ignores the mode bits: nothing is actually locked, so there is nothing to check.
+  if fs::read_dir(&locked_root).is_ok() {
+    set_mode(&locked_root, 0o755);
+    set_mode(&locked_folder, 0o755);
+    return;
+  }
+
+  let cfg = ScannerConfig {
+    roots: vec![ScanRoot::new(&open), ScanRoot::new(&locked_root)],
+    audio_exts: vec!["flac".into()],
+    ignore_hidden: false,
+    ..ScannerConfig::default()
+  };
+  let outcome = scan_music_with_cfg(&cfg).await;
+  let only_locked =
+    scan_music_with_cfg(&ScannerConfig { roots: vec![ScanRoot::new(&locked_root)], ..cfg.clone() }).await;
+
+  set_mode(&locked_root, 0o755);
+  set_mode(&locked_folder, 0o755);
+
+  let outcome = outcome.unwrap();
+  let found: Vec<_> = outcome.files.iter().map(|f| f.path.clone()).collect();
+  assert_eq!(found, [open.join("a.flac")]);
+  assert!(outcome.unavailable_roots.is_empty());
+  assert_eq!(outcome.permission_denied, [locked_folder, locked_root.clone()]);
+
+  // A library made only of locked roots is still unavailable as a whole.
+  assert!(matches!(only_locked, Err(gamus_scanner::ScannerError::RootsUnavailable(roots)) if roots == [locked_root]));
+}
@@ -0,0 +1,82 @@
+#![cfg(unix)]
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+
+use gamus_scanner::config::ThroughputConfig;
+use gamus_scanner::{ContentHashMode, HiddenPolicy, ScanRoot, ScannerConfig, SymlinkScope, scan_music_with_cfg};
+
+fn config(root: &Path, follow_symlinks: bool) -> ScannerConfig {
+  ScannerConfig {
+    roots: vec![ScanRoot::new(root)],
+    audio_exts: vec!["flac".into()],
+    ignore_hidden: false,
+    hidden_policy: HiddenPolicy::default(),
+    max_depth: None,
+    follow_symlinks,
+    symlink_scope: SymlinkScope::default(),
+    content_hash: ContentHashMode::default(),
+    throughput: ThroughputConfig::default(),
+    min_duration_secs: None,
+    sniff_unknown_extensions: false,
+    failure_cooldown_secs: 0,
+    progress_interval_ms: 0,
+  }
+}
+
+/// `root/{a.flac, album/b.flac, album/loop -> root, self -> root, synced -> <outside>/c.flac}`.
+fn library_with_cycles() -> (tempfile::TempDir, PathBuf) {
+  let dir = tempfile::tempdir().unwrap();
+  let root = dir.path().join("music");
+  let outside = dir.path().join("synced");
+  fs::create_dir_all(root.join("album")).unwrap();
+  fs::create_dir_all(&outside).unwrap();
+
+  fs::write(root.join("a.flac"), b"a").unwrap();
+  fs::write(root.join("album/b.flac"), b"b").unwrap();
+  fs::write(outside.join("c.flac"), b"c").unwrap();
+
+  symlink(&root, root.join("album/loop")).unwrap();
+  symlink(".", root.join("self")).unwrap();
+  symlink(&outside, root.join("synced")).unwrap();
+
+  (dir, root)
+}
+
+fn found(outcome: &gamus_scanner::FsScanOutcome, root: &Path) -> BTreeSet<PathBuf> {
+  outcome.files.iter().map(|f| f.path.strip_prefix(root).unwrap().to_path_buf()).collect()
+}
+
+#[tokio::test]
+async fn following_symlinks_terminates_on_cycles_and_finds_linked_files() {
+  let (_dir, root) = library_with_cycles();
+
+  let outcome = scan_music_with_cfg(&config(&root, true)).await.unwrap();
+
+  let expected: BTreeSet<PathBuf> =
+    ["a.flac", "album/b.flac", "synced/c.flac"].into_iter().map(PathBuf::from).collect();
+  assert_eq!(found(&outcome, &root), expected);
+}
+
+#[tokio::test]
+async fn symlinked_directories_are_skipped_by_default() {
+  let (_dir, root) = library_with_cycles();
+
+  let outcome = scan_music_with_cfg(&config(&root, false)).await.unwrap();
+
+  let expected: BTreeSet<PathBuf> = ["a.flac", "album/b.flac"].into_iter().map(PathBuf::from).collect();
+  assert_eq!(found(&outcome, &root), expected);
+}
+
+#[tokio::test]
+async fn within_root_scope_skips_links_that_leave_the_root() {
+  let (_dir, root) = library_with_cycles();
+  let cfg = ScannerConfig { symlink_scope: SymlinkScope::WithinRoot, ..config(&root, true) };
+
+  let outcome = scan_music_with_cfg(&cfg).await.unwrap();
+
+  let expected: BTreeSet<PathBuf> = ["a.flac", "album/b.flac"].into_iter().map(PathBuf::from).collect();
+  assert_eq!(found(&outcome, &root), expected);
+}
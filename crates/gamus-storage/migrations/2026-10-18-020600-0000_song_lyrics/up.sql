@@ -0,0 +1,7 @@
+-- Unsynced lyrics, one entry per song (the first non-empty tag wins on import).
+CREATE TABLE song_lyrics (
+  song_id TEXT PRIMARY KEY NOT NULL REFERENCES songs(id) ON DELETE CASCADE,
+  lyrics TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
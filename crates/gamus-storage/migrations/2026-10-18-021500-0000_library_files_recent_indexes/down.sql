@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_library_files_modified_unix;
+DROP INDEX IF EXISTS idx_library_files_added_at;
@@ -0,0 +1,3 @@
+-- "Recently added" listing and rescan change detection both scan library_files by time.
+CREATE INDEX idx_library_files_added_at ON library_files(added_at);
+CREATE INDEX idx_library_files_modified_unix ON library_files(modified_unix);
@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS idx_library_files_codec;
+
+ALTER TABLE library_files DROP COLUMN is_lossless;
+ALTER TABLE library_files DROP COLUMN container;
+ALTER TABLE library_files DROP COLUMN codec;
@@ -0,0 +1,5 @@
+ALTER TABLE library_files ADD COLUMN codec TEXT;
+ALTER TABLE library_files ADD COLUMN container TEXT;
+ALTER TABLE library_files ADD COLUMN is_lossless BOOLEAN;
+
+CREATE INDEX idx_library_files_codec ON library_files(codec);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_library_files_quality_level;
+
+ALTER TABLE library_files DROP COLUMN quality_level;
@@ -0,0 +1,16 @@
+-- QualityLevel identifier ("perfect", "high", ...) next to the raw score, so grouping and
+-- filtering by level don't re-bucket the float. NULL = the file was never analyzed.
+ALTER TABLE library_files ADD COLUMN quality_level TEXT;
+
+-- One-off backfill for rows written before this column existed. Thresholds mirror
+-- `QualityLevel::from_score`; new rows get the level from the analysis report itself.
+UPDATE library_files SET quality_level = CASE
+  WHEN quality_score IS NULL THEN 'inconclusive'
+  WHEN quality_score >= 9.5 THEN 'perfect'
+  WHEN quality_score >= 8.0 THEN 'high'
+  WHEN quality_score >= 5.5 THEN 'medium'
+  ELSE 'low'
+END
+WHERE quality_assessment IS NOT NULL;
+
+CREATE INDEX idx_library_files_quality_level ON library_files(quality_level);
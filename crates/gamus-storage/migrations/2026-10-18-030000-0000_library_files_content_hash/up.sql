@@ -0,0 +1,2 @@
+-- Scanner-computed content hash ("<scheme>:<hex>"), the change signal for incremental imports.
+ALTER TABLE library_files ADD COLUMN content_hash TEXT;
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_artists_name_normalized;
+ALTER TABLE artists DROP COLUMN name_normalized;
@@ -0,0 +1,7 @@
+-- Canonical form of artists.name (trimmed, lowercase, single spaces), written by the store
+-- on every insert/update. Reimports look artists up by it instead of minting a new id.
+--
+-- SQLite's lower() is ASCII-only and trim() does not collapse inner whitespace, so the
+-- backfill, the merge of duplicates and the unique index are done by the store right after
+-- migrating, with the same `normalize_artist_name` the writes use.
+ALTER TABLE artists ADD COLUMN name_normalized TEXT NOT NULL DEFAULT '';
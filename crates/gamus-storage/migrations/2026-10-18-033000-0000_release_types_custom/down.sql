@@ -0,0 +1 @@
+ALTER TABLE release_types DROP COLUMN custom;
@@ -0,0 +1,3 @@
+-- Literal value of ReleaseType::Custom; NULL for the standard types.
+-- `kind` keeps the Display string, which for a custom "CD" would parse back as Album.
+ALTER TABLE release_types ADD COLUMN custom TEXT;
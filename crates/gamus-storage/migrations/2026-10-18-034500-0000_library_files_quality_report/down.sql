@@ -0,0 +1,2 @@
+ALTER TABLE library_files DROP COLUMN quality_details;
+ALTER TABLE library_files DROP COLUMN quality_cutoff_hz;
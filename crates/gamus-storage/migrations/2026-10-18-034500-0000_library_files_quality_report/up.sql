@@ -0,0 +1,3 @@
+-- Parts of the spectral quality report that were computed but dropped on save.
+ALTER TABLE library_files ADD COLUMN quality_cutoff_hz REAL;
+ALTER TABLE library_files ADD COLUMN quality_details TEXT;
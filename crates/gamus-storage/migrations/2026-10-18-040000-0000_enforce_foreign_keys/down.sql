@@ -0,0 +1,2 @@
+-- The deleted rows were dangling references; there is nothing to restore.
+SELECT 1;
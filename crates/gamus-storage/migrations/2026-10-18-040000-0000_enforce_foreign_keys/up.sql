@@ -0,0 +1,23 @@
+-- Foreign keys were declared but never enforced (`PRAGMA foreign_keys` was off on every
+-- connection), so rows may point at parents that no longer exist. Drop them before the
+-- store starts enforcing; tracks go first so their files and credits follow.
+DELETE FROM release_tracks
+WHERE release_id NOT IN (SELECT id FROM releases) OR song_id NOT IN (SELECT id FROM songs);
+
+DELETE FROM library_files WHERE release_track_id NOT IN (SELECT id FROM release_tracks);
+DELETE FROM release_track_artists
+WHERE release_track_id NOT IN (SELECT id FROM release_tracks) OR artist_id NOT IN (SELECT id FROM artists);
+
+DELETE FROM artist_variations WHERE artist_id NOT IN (SELECT id FROM artists);
+DELETE FROM artist_sites WHERE artist_id NOT IN (SELECT id FROM artists);
+
+DELETE FROM song_comments WHERE song_id NOT IN (SELECT id FROM songs);
+DELETE FROM song_ratings WHERE song_id NOT IN (SELECT id FROM songs);
+DELETE FROM song_lyrics WHERE song_id NOT IN (SELECT id FROM songs);
+
+DELETE FROM release_types WHERE release_id NOT IN (SELECT id FROM releases);
+DELETE FROM release_main_artists
+WHERE release_id NOT IN (SELECT id FROM releases) OR artist_id NOT IN (SELECT id FROM artists);
+DELETE FROM release_genres WHERE release_id NOT IN (SELECT id FROM releases);
+DELETE FROM release_styles WHERE release_id NOT IN (SELECT id FROM releases);
+DELETE FROM artworks WHERE release_id NOT IN (SELECT id FROM releases);
@@ -0,0 +1,4 @@
+ALTER TABLE library_files DROP COLUMN replaygain_album_peak;
+ALTER TABLE library_files DROP COLUMN replaygain_track_peak;
+ALTER TABLE library_files DROP COLUMN replaygain_album_db;
+ALTER TABLE library_files DROP COLUMN replaygain_track_db;
@@ -0,0 +1,5 @@
+-- ReplayGain as read from the tags: gains in dB, peaks as linear sample amplitude (1.0 = full scale).
+ALTER TABLE library_files ADD COLUMN replaygain_track_db REAL;
+ALTER TABLE library_files ADD COLUMN replaygain_album_db REAL;
+ALTER TABLE library_files ADD COLUMN replaygain_track_peak REAL;
+ALTER TABLE library_files ADD COLUMN replaygain_album_peak REAL;
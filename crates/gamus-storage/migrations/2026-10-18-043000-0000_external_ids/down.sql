@@ -0,0 +1,7 @@
+DROP INDEX idx_releases_mbid;
+DROP INDEX idx_songs_mbid;
+DROP INDEX idx_songs_isrc;
+
+ALTER TABLE releases DROP COLUMN mbid;
+ALTER TABLE songs DROP COLUMN mbid;
+ALTER TABLE songs DROP COLUMN isrc;
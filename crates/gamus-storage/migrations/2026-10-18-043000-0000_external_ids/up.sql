@@ -0,0 +1,9 @@
+-- Cross-reference keys for external databases. NULLs don't collide in a SQLite unique index,
+-- so only files that actually carry the tag are constrained.
+ALTER TABLE songs ADD COLUMN isrc TEXT;
+ALTER TABLE songs ADD COLUMN mbid TEXT;
+ALTER TABLE releases ADD COLUMN mbid TEXT;
+
+CREATE UNIQUE INDEX idx_songs_isrc ON songs(isrc);
+CREATE UNIQUE INDEX idx_songs_mbid ON songs(mbid);
+CREATE UNIQUE INDEX idx_releases_mbid ON releases(mbid);
@@ -0,0 +1,6 @@
+-- At most one row: the resumable full import that started and hasn't finished yet.
+-- Files it already saved are found through library_files.updated_at >= started_at.
+CREATE TABLE import_checkpoint (
+  id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+  started_at BIGINT NOT NULL
+);
@@ -0,0 +1,9 @@
+-- Files whose metadata extraction failed (DRM, corrupt). Imports skip them for a cooldown
+-- unless modified_unix changes; a successful import removes the row.
+CREATE TABLE extraction_failures (
+  path TEXT PRIMARY KEY NOT NULL,
+  error TEXT NOT NULL,
+  failed_at BIGINT NOT NULL,
+  attempts INTEGER NOT NULL DEFAULT 1,
+  modified_unix BIGINT NOT NULL
+);
@@ -0,0 +1 @@
+DROP INDEX IF EXISTS idx_library_files_quality_score;
@@ -0,0 +1,2 @@
+-- The "re-rip candidates" view lists files by score range, lowest first.
+CREATE INDEX idx_library_files_quality_score ON library_files(quality_score);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_artworks_hash;
+ALTER TABLE artworks DROP COLUMN thumbnail_path;
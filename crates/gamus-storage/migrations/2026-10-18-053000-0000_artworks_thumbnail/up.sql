@@ -0,0 +1,3 @@
+-- Downscaled copy of the artwork in the thumbnail cache, shared by every row with the same hash.
+ALTER TABLE artworks ADD COLUMN thumbnail_path TEXT;
+CREATE INDEX idx_artworks_hash ON artworks(hash);
@@ -0,0 +1,58 @@
+-- Paths under a root are rebuilt as '<root>/<relative>'; rows whose root is unknown keep the
+-- relative path.
+CREATE TABLE library_files_old (
+  id TEXT PRIMARY KEY NOT NULL,
+  release_track_id TEXT NOT NULL REFERENCES release_tracks(id) ON DELETE CASCADE,
+  path TEXT NOT NULL UNIQUE,
+  size_bytes BIGINT NOT NULL,
+  modified_unix BIGINT NOT NULL,
+  duration_ms BIGINT NOT NULL,
+  bitrate_kbps INTEGER,
+  sample_rate_hz INTEGER,
+  channels INTEGER,
+  fingerprint TEXT,
+  bpm REAL,
+  quality_score REAL,
+  quality_assessment TEXT,
+  features BLOB,
+  added_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  codec TEXT,
+  container TEXT,
+  is_lossless BOOLEAN,
+  quality_level TEXT,
+  content_hash TEXT,
+  quality_cutoff_hz REAL,
+  quality_details TEXT,
+  replaygain_track_db REAL,
+  replaygain_album_db REAL,
+  replaygain_track_peak REAL,
+  replaygain_album_peak REAL,
+  UNIQUE(release_track_id)
+);
+
+INSERT INTO library_files_old (
+  id, release_track_id, path, size_bytes, modified_unix, duration_ms, bitrate_kbps, sample_rate_hz,
+  channels, fingerprint, bpm, quality_score, quality_assessment, features, added_at, updated_at, codec,
+  container, is_lossless, quality_level, content_hash, quality_cutoff_hz, quality_details,
+  replaygain_track_db, replaygain_album_db, replaygain_track_peak, replaygain_album_peak
+)
+SELECT
+  f.id, f.release_track_id, COALESCE(RTRIM(r.path, '/') || '/' || f.path, f.path), f.size_bytes,
+  f.modified_unix, f.duration_ms, f.bitrate_kbps, f.sample_rate_hz, f.channels, f.fingerprint, f.bpm,
+  f.quality_score, f.quality_assessment, f.features, f.added_at, f.updated_at, f.codec, f.container,
+  f.is_lossless, f.quality_level, f.content_hash, f.quality_cutoff_hz, f.quality_details,
+  f.replaygain_track_db, f.replaygain_album_db, f.replaygain_track_peak, f.replaygain_album_peak
+FROM library_files f
+LEFT JOIN library_roots r ON r.id = f.root_id;
+
+DROP TABLE library_files;
+ALTER TABLE library_files_old RENAME TO library_files;
+
+CREATE INDEX idx_library_files_added_at ON library_files(added_at);
+CREATE INDEX idx_library_files_modified_unix ON library_files(modified_unix);
+CREATE INDEX idx_library_files_codec ON library_files(codec);
+CREATE INDEX idx_library_files_quality_level ON library_files(quality_level);
+CREATE INDEX idx_library_files_quality_score ON library_files(quality_score);
+
+DROP TABLE library_roots;
@@ -0,0 +1,67 @@
+-- Folders library files are stored relative to. The id is what library_files references;
+-- the path is where the root lives on this machine and can be re-pointed after moving the
+-- database or remounting a drive without touching any file row.
+CREATE TABLE library_roots (
+  id TEXT PRIMARY KEY NOT NULL,
+  path TEXT NOT NULL UNIQUE,
+  added_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- library_files.path becomes relative to library_files.root_id, so the same relative path
+-- may appear under two roots: the column-level UNIQUE(path) has to go, which in SQLite
+-- means rebuilding the table. Existing rows keep their absolute paths under the 'absolute'
+-- sentinel root until a root containing them is registered.
+CREATE TABLE library_files_new (
+  id TEXT PRIMARY KEY NOT NULL,
+  release_track_id TEXT NOT NULL REFERENCES release_tracks(id) ON DELETE CASCADE,
+  path TEXT NOT NULL,
+  size_bytes BIGINT NOT NULL,
+  modified_unix BIGINT NOT NULL,
+  duration_ms BIGINT NOT NULL,
+  bitrate_kbps INTEGER,
+  sample_rate_hz INTEGER,
+  channels INTEGER,
+  fingerprint TEXT,
+  bpm REAL,
+  quality_score REAL,
+  quality_assessment TEXT,
+  features BLOB,
+  added_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  codec TEXT,
+  container TEXT,
+  is_lossless BOOLEAN,
+  quality_level TEXT,
+  content_hash TEXT,
+  quality_cutoff_hz REAL,
+  quality_details TEXT,
+  replaygain_track_db REAL,
+  replaygain_album_db REAL,
+  replaygain_track_peak REAL,
+  replaygain_album_peak REAL,
+  root_id TEXT NOT NULL DEFAULT 'absolute',
+  UNIQUE(release_track_id),
+  UNIQUE(root_id, path)
+);
+
+INSERT INTO library_files_new (
+  id, release_track_id, path, size_bytes, modified_unix, duration_ms, bitrate_kbps, sample_rate_hz,
+  channels, fingerprint, bpm, quality_score, quality_assessment, features, added_at, updated_at, codec,
+  container, is_lossless, quality_level, content_hash, quality_cutoff_hz, quality_details,
+  replaygain_track_db, replaygain_album_db, replaygain_track_peak, replaygain_album_peak
+)
+SELECT
+  id, release_track_id, path, size_bytes, modified_unix, duration_ms, bitrate_kbps, sample_rate_hz,
+  channels, fingerprint, bpm, quality_score, quality_assessment, features, added_at, updated_at, codec,
+  container, is_lossless, quality_level, content_hash, quality_cutoff_hz, quality_details,
+  replaygain_track_db, replaygain_album_db, replaygain_track_peak, replaygain_album_peak
+FROM library_files;
+
+DROP TABLE library_files;
+ALTER TABLE library_files_new RENAME TO library_files;
+
+CREATE INDEX idx_library_files_added_at ON library_files(added_at);
+CREATE INDEX idx_library_files_modified_unix ON library_files(modified_unix);
+CREATE INDEX idx_library_files_codec ON library_files(codec);
+CREATE INDEX idx_library_files_quality_level ON library_files(quality_level);
+CREATE INDEX idx_library_files_quality_score ON library_files(quality_score);
@@ -0,0 +1 @@
+ALTER TABLE library_files DROP COLUMN quality_json;
@@ -0,0 +1,3 @@
+-- The whole AudioQuality (outcome and report) as JSON, so reads give back what the analyser
+-- produced. The other quality_* columns stay as its queryable projection.
+ALTER TABLE library_files ADD COLUMN quality_json TEXT;
@@ -0,0 +1,2 @@
+DROP INDEX idx_songs_isrc;
+CREATE UNIQUE INDEX idx_songs_isrc ON songs(isrc);
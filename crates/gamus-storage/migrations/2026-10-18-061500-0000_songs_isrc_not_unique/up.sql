@@ -0,0 +1,4 @@
+-- An ISRC identifies a recording, but the same one ends up on distinct songs (remasters and
+-- compilations reusing it, mis-tagged files), so it is only an index for lookups.
+DROP INDEX idx_songs_isrc;
+CREATE INDEX idx_songs_isrc ON songs(isrc);
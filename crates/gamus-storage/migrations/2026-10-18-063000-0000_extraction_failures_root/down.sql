@@ -0,0 +1,17 @@
+-- Paths under a root are rebuilt as '<root>/<relative>'; rows whose root is unknown keep the
+-- relative path.
+CREATE TABLE extraction_failures_old (
+  path TEXT PRIMARY KEY NOT NULL,
+  error TEXT NOT NULL,
+  failed_at BIGINT NOT NULL,
+  attempts INTEGER NOT NULL DEFAULT 1,
+  modified_unix BIGINT NOT NULL
+);
+
+INSERT INTO extraction_failures_old (path, error, failed_at, attempts, modified_unix)
+SELECT COALESCE(RTRIM(r.path, '/') || '/' || f.path, f.path), f.error, f.failed_at, f.attempts, f.modified_unix
+FROM extraction_failures f
+LEFT JOIN library_roots r ON r.id = f.root_id;
+
+DROP TABLE extraction_failures;
+ALTER TABLE extraction_failures_old RENAME TO extraction_failures;
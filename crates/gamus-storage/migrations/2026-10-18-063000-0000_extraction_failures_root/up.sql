@@ -0,0 +1,19 @@
+-- extraction_failures.path becomes relative to a library root, like library_files.path, so
+-- the key grows to (root_id, path). SQLite can't change a primary key in place: rebuild.
+-- Existing rows keep their absolute paths under the 'absolute' sentinel root until a root
+-- containing them is registered.
+CREATE TABLE extraction_failures_new (
+  path TEXT NOT NULL,
+  error TEXT NOT NULL,
+  failed_at BIGINT NOT NULL,
+  attempts INTEGER NOT NULL DEFAULT 1,
+  modified_unix BIGINT NOT NULL,
+  root_id TEXT NOT NULL DEFAULT 'absolute',
+  PRIMARY KEY (root_id, path)
+);
+
+INSERT INTO extraction_failures_new (path, error, failed_at, attempts, modified_unix)
+SELECT path, error, failed_at, attempts, modified_unix FROM extraction_failures;
+
+DROP TABLE extraction_failures;
+ALTER TABLE extraction_failures_new RENAME TO extraction_failures;
@@ -1,28 +1,374 @@
-use gamus_config::{CONFIG_BACKEND, ConfigBackend, ConfigError, PATHS};
+use gamus_config::{CONFIG_BACKEND, ConfigBackend, ConfigError, PATHS, SaveOutcome};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StorageConfig {
+  /// Archivo SQLite de la biblioteca. Por defecto `gamus.db` dentro de `PATHS.data_dir`.
+  #[serde(default = "default_db_path")]
   pub db_path: PathBuf,
-  pub journal_mode: Option<String>,
+
+  /// Modo de journal de SQLite. En el TOML es un string (`"WAL"`, `"delete"`, ...);
+  /// los valores desconocidos hacen fallar `load()`.
+  #[serde(default)]
+  pub journal_mode: JournalMode,
+
+  /// Dimensionado del pool de conexiones.
+  #[serde(default)]
+  pub pool: PoolConfig,
+
+  /// Tiempo máximo (ms) que un escritor espera al lock de SQLite antes de fallar con `SQLITE_BUSY`.
+  #[serde(default = "default_busy_timeout_ms")]
+  pub busy_timeout_ms: u64,
+
+  /// Reintentos de escritura ante `SQLITE_BUSY`/`SQLITE_LOCKED` que sobreviven al `busy_timeout`.
+  #[serde(default)]
+  pub retry: RetryConfig,
+
+  /// PRAGMAs de conexión que se aplican a cada conexión del pool al abrirla.
+  #[serde(default)]
+  pub pragmas: PragmaConfig,
+}
+
+fn default_busy_timeout_ms() -> u64 {
+  5_000
+}
+
+fn default_db_path() -> PathBuf {
+  PATHS.data_dir.join("gamus.db")
 }
 
 impl Default for StorageConfig {
   fn default() -> Self {
-    let db_path = PATHS.data_dir.join("gamus.db");
-    StorageConfig { db_path, journal_mode: Some("WAL".to_string()) }
+    StorageConfig {
+      db_path: default_db_path(),
+      journal_mode: JournalMode::default(),
+      pool: PoolConfig::default(),
+      busy_timeout_ms: default_busy_timeout_ms(),
+      retry: RetryConfig::default(),
+      pragmas: PragmaConfig::default(),
+    }
+  }
+}
+
+/// Valores admitidos por `PRAGMA journal_mode`.
+///
+/// Tipado para no interpolar texto arbitrario en el PRAGMA y para detectar
+/// modos inválidos al cargar la configuración en vez de al abrir la base de datos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum JournalMode {
+  /// Write-Ahead Logging: lecturas concurrentes mientras se escribe.
+  #[default]
+  Wal,
+  Delete,
+  Truncate,
+  Persist,
+  Memory,
+  Off,
+}
+
+impl JournalMode {
+  /// Literal exacto que espera `PRAGMA journal_mode`.
+  pub fn as_pragma(&self) -> &'static str {
+    match self {
+      JournalMode::Wal => "WAL",
+      JournalMode::Delete => "DELETE",
+      JournalMode::Truncate => "TRUNCATE",
+      JournalMode::Persist => "PERSIST",
+      JournalMode::Memory => "MEMORY",
+      JournalMode::Off => "OFF",
+    }
+  }
+}
+
+impl fmt::Display for JournalMode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_pragma())
+  }
+}
+
+impl FromStr for JournalMode {
+  type Err = String;
+
+  /// Acepta el nombre del modo sin distinguir mayúsculas/minúsculas.
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_ascii_uppercase().as_str() {
+      "WAL" => Ok(JournalMode::Wal),
+      "DELETE" => Ok(JournalMode::Delete),
+      "TRUNCATE" => Ok(JournalMode::Truncate),
+      "PERSIST" => Ok(JournalMode::Persist),
+      "MEMORY" => Ok(JournalMode::Memory),
+      "OFF" => Ok(JournalMode::Off),
+      _ => Err(format!("invalid journal_mode '{s}' (expected one of: WAL, DELETE, TRUNCATE, PERSIST, MEMORY, OFF)")),
+    }
+  }
+}
+
+impl TryFrom<String> for JournalMode {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+impl From<JournalMode> for String {
+  fn from(mode: JournalMode) -> Self {
+    mode.as_pragma().to_string()
+  }
+}
+
+/// PRAGMAs que SQLite guarda por conexión y no en el archivo, así que cada conexión del
+/// pool los recibe al abrirse (junto con `busy_timeout`). `journal_mode` no está aquí:
+/// es de la base de datos y se fija una vez al abrir el store.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct PragmaConfig {
+  /// `PRAGMA foreign_keys`. Apagarlo deja de aplicar los `ON DELETE CASCADE` del esquema.
+  pub foreign_keys: bool,
+
+  /// `PRAGMA synchronous`. `None` deja el valor por defecto de SQLite (`FULL`); con WAL,
+  /// `NORMAL` es lo habitual: no corrompe, solo puede perder las últimas transacciones
+  /// ante un corte de luz.
+  pub synchronous: Option<Synchronous>,
+
+  /// `PRAGMA cache_size`: positivo en páginas, negativo en KiB. `None` deja el de SQLite.
+  pub cache_size: Option<i64>,
+}
+
+impl Default for PragmaConfig {
+  fn default() -> Self {
+    PragmaConfig { foreign_keys: true, synchronous: None, cache_size: None }
+  }
+}
+
+/// Valores admitidos por `PRAGMA synchronous`; tipado por lo mismo que [`JournalMode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Synchronous {
+  Off,
+  Normal,
+  Full,
+  Extra,
+}
+
+impl Synchronous {
+  /// Literal exacto que espera `PRAGMA synchronous`.
+  pub fn as_pragma(&self) -> &'static str {
+    match self {
+      Synchronous::Off => "OFF",
+      Synchronous::Normal => "NORMAL",
+      Synchronous::Full => "FULL",
+      Synchronous::Extra => "EXTRA",
+    }
+  }
+}
+
+impl FromStr for Synchronous {
+  type Err = String;
+
+  /// Acepta el nombre del modo sin distinguir mayúsculas/minúsculas.
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim().to_ascii_uppercase().as_str() {
+      "OFF" => Ok(Synchronous::Off),
+      "NORMAL" => Ok(Synchronous::Normal),
+      "FULL" => Ok(Synchronous::Full),
+      "EXTRA" => Ok(Synchronous::Extra),
+      _ => Err(format!("invalid synchronous '{s}' (expected one of: OFF, NORMAL, FULL, EXTRA)")),
+    }
+  }
+}
+
+impl TryFrom<String> for Synchronous {
+  type Error = String;
+
+  fn try_from(value: String) -> Result<Self, Self::Error> {
+    value.parse()
+  }
+}
+
+impl From<Synchronous> for String {
+  fn from(mode: Synchronous) -> Self {
+    mode.as_pragma().to_string()
+  }
+}
+
+/// Parámetros del pool `r2d2` que respalda a `LibraryStore`.
+///
+/// La importación puede lanzar decenas de tareas concurrentes (ver `decide_concurrency`),
+/// así que el tamaño por defecto de `r2d2` se queda corto y provoca errores de checkout.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PoolConfig {
+  /// Número máximo de conexiones abiertas simultáneamente.
+  pub max_size: u32,
+
+  /// Conexiones ociosas que el pool intenta mantener. `None` = igual a `max_size`.
+  pub min_idle: Option<u32>,
+
+  /// Tiempo máximo (segundos) esperando una conexión libre del pool.
+  pub connection_timeout_secs: u64,
+}
+
+impl Default for PoolConfig {
+  fn default() -> Self {
+    PoolConfig { max_size: 16, min_idle: Some(2), connection_timeout_secs: 30 }
+  }
+}
+
+/// Política de reintento para escrituras que fallan por contención transitoria.
+///
+/// El `busy_timeout` absorbe casi toda la contención, pero SQLite devuelve `SQLITE_BUSY`
+/// de inmediato en algunos casos (p. ej. al promocionar una transacción de lectura a
+/// escritura o durante un checkpoint), y ahí sólo sirve reintentar la transacción entera.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(default)]
+pub struct RetryConfig {
+  /// Intentos totales, incluido el primero. `1` desactiva los reintentos.
+  pub max_attempts: u32,
+
+  /// Espera (ms) antes del primer reintento; se duplica en cada intento.
+  pub initial_backoff_ms: u64,
+
+  /// Tope (ms) de la espera entre intentos.
+  pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+  fn default() -> Self {
+    RetryConfig { max_attempts: 5, initial_backoff_ms: 25, max_backoff_ms: 1_000 }
   }
 }
 
 impl StorageConfig {
+  /// Carga la sección `[storage]` y la valida (ver [`Self::validate`]). Solo si es válida se
+  /// reescribe completa en el archivo.
   pub fn load() -> Result<Self, ConfigError> {
-    let cfg = CONFIG_BACKEND.load_section_with_default("storage")?;
+    let cfg: Self = CONFIG_BACKEND.load_section_with_default("storage")?;
+    cfg.validate()?;
     CONFIG_BACKEND.save_section("storage", &cfg)?;
     Ok(cfg)
   }
 
-  pub fn save(&self) -> Result<(), ConfigError> {
+  /// Comprueba lo que de otro modo fallaría tarde y con peor mensaje al abrir el pool.
+  ///
+  /// `db_path` no puede estar vacío y su directorio padre se crea si falta, de modo que un
+  /// directorio no creable (permisos, disco desmontado) falla aquí. `r2d2` hace `panic` con
+  /// `max_size = 0` o `min_idle > max_size`, así que también se rechazan.
+  pub fn validate(&self) -> Result<(), ConfigError> {
+    if self.db_path.as_os_str().is_empty() {
+      return Err(ConfigError::Other("storage.db_path is empty".into()));
+    }
+
+    if let Some(parent) = self.db_path.parent()
+      && !parent.as_os_str().is_empty()
+    {
+      fs::create_dir_all(parent)?;
+    }
+
+    if self.pool.max_size == 0 {
+      return Err(ConfigError::Other("storage.pool.max_size must be at least 1".into()));
+    }
+
+    if let Some(min_idle) = self.pool.min_idle
+      && min_idle > self.pool.max_size
+    {
+      return Err(ConfigError::Other(format!(
+        "storage.pool.min_idle ({min_idle}) is larger than max_size ({})",
+        self.pool.max_size
+      )));
+    }
+
+    Ok(())
+  }
+
+  pub fn save(&self) -> Result<SaveOutcome, ConfigError> {
     CONFIG_BACKEND.save_section("storage", self)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::tempdir;
+
+  const FIXTURE: &str = r#"
+db_path = "library/gamus.db"
+journal_mode = "truncate"
+busy_timeout_ms = 250
+
+[pool]
+max_size = 4
+min_idle = 1
+connection_timeout_secs = 10
+
+[retry]
+max_attempts = 2
+
+[pragmas]
+foreign_keys = false
+synchronous = "normal"
+cache_size = -16000
+"#;
+
+  #[test]
+  fn parses_a_full_storage_section() {
+    let cfg: StorageConfig = toml::from_str(FIXTURE).unwrap();
+
+    assert_eq!(cfg.db_path, PathBuf::from("library/gamus.db"));
+    assert_eq!(cfg.journal_mode, JournalMode::Truncate);
+    assert_eq!(cfg.busy_timeout_ms, 250);
+    assert_eq!((cfg.pool.max_size, cfg.pool.min_idle, cfg.pool.connection_timeout_secs), (4, Some(1), 10));
+    assert_eq!(cfg.retry, RetryConfig { max_attempts: 2, ..RetryConfig::default() });
+    assert_eq!(
+      cfg.pragmas,
+      PragmaConfig { foreign_keys: false, synchronous: Some(Synchronous::Normal), cache_size: Some(-16_000) }
+    );
+  }
+
+  #[test]
+  fn missing_keys_fall_back_to_defaults() {
+    let cfg: StorageConfig = toml::from_str("").unwrap();
+
+    assert_eq!(cfg.db_path, PATHS.data_dir.join("gamus.db"));
+    assert_eq!(cfg.journal_mode, JournalMode::Wal);
+    assert_eq!(cfg.busy_timeout_ms, 5_000);
+    assert_eq!(cfg.pragmas, PragmaConfig::default());
+  }
+
+  #[test]
+  fn validate_creates_the_parent_directory() {
+    let dir = tempdir().unwrap();
+    let cfg = StorageConfig { db_path: dir.path().join("nested/dir/gamus.db"), ..toml::from_str(FIXTURE).unwrap() };
+
+    cfg.validate().unwrap();
+
+    assert!(dir.path().join("nested/dir").is_dir());
+  }
+
+  #[test]
+  fn validate_rejects_empty_path_and_bad_pool_sizes() {
+    let base: StorageConfig = toml::from_str(FIXTURE).unwrap();
+
+    let empty = StorageConfig { db_path: PathBuf::new(), ..base.clone() };
+    assert!(matches!(empty.validate(), Err(ConfigError::Other(_))));
+
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("gamus.db");
+    let no_conns =
+      StorageConfig { db_path: db_path.clone(), pool: PoolConfig { max_size: 0, ..base.pool.clone() }, ..base.clone() };
+    assert!(no_conns.validate().is_err());
+
+    let idle = StorageConfig { db_path, pool: PoolConfig { min_idle: Some(5), ..base.pool.clone() }, ..base };
+    assert!(idle.validate().is_err());
+  }
+
+  #[test]
+  fn unknown_journal_mode_is_rejected_when_parsing() {
+    assert!(toml::from_str::<StorageConfig>(r#"journal_mode = "fast""#).is_err());
+  }
+}
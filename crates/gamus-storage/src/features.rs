@@ -0,0 +1,135 @@
+//! Byte encoding of the `library_files.features` embedding column.
+//!
+//! Layout, all little-endian:
+//!
+//! | bytes     | content                              |
+//! |-----------|--------------------------------------|
+//! | 0         | format version ([`FEATURES_VERSION`]) |
+//! | 1..5      | number of values `n`, as `u32`       |
+//! | 5..5+4n   | the `n` values, as `f32`             |
+//!
+//! The explicit length lets a truncated or padded blob be rejected instead of silently
+//! yielding a shorter vector, and the version byte leaves room to change the layout
+//! (e.g. `f16` values) without guessing from the blob size.
+
+use gamus_core::errors::CoreError;
+
+/// Version written by [`encode_features`]; the only one [`decode_features`] accepts.
+pub const FEATURES_VERSION: u8 = 1;
+
+/// Version byte plus the `u32` value count.
+const HEADER_LEN: usize = 1 + 4;
+
+/// Encodes an embedding for the `features` column.
+///
+/// # Panics
+/// If `features` has more than `u32::MAX` values, which no real embedding gets near.
+pub fn encode_features(features: &[f32]) -> Vec<u8> {
+  let len = u32::try_from(features.len()).expect("feature vector longer than u32::MAX");
+  let mut bytes = Vec::with_capacity(HEADER_LEN + features.len() * 4);
+  bytes.push(FEATURES_VERSION);
+  bytes.extend_from_slice(&len.to_le_bytes());
+  bytes.extend(features.iter().flat_map(|v| v.to_le_bytes()));
+  bytes
+}
+
+/// Decodes a `features` blob written by [`encode_features`].
+///
+/// Fails with `CoreError::Repository` if the version is unknown or the payload size does
+/// not match the stored length.
+pub fn decode_features(bytes: &[u8]) -> Result<Vec<f32>, CoreError> {
+  let (header, payload) = bytes
+    .split_at_checked(HEADER_LEN)
+    .ok_or_else(|| CoreError::Repository(format!("features blob too short for its header ({} bytes)", bytes.len())))?;
+
+  let version = header[0];
+  if version != FEATURES_VERSION {
+    return Err(CoreError::Repository(format!("unsupported features encoding version {version}")));
+  }
+
+  let len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]) as usize;
+  if payload.len() != len * 4 {
+    return Err(CoreError::Repository(format!(
+      "features blob declares {len} values but carries {} bytes of payload",
+      payload.len()
+    )));
+  }
+
+  Ok(payload.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect())
+}
+
+/// Cosine similarity of two embeddings, in `[-1, 1]`.
+///
+/// `None` if the lengths differ (embeddings from different models or versions are not
+/// comparable) or either vector is all zeros.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> Option<f32> {
+  if a.len() != b.len() {
+    return None;
+  }
+  let (mut dot, mut norm_a, mut norm_b) = (0.0f64, 0.0f64, 0.0f64);
+  for (&x, &y) in a.iter().zip(b) {
+    let (x, y) = (f64::from(x), f64::from(y));
+    dot += x * y;
+    norm_a += x * x;
+    norm_b += y * y;
+  }
+  let norm = (norm_a * norm_b).sqrt();
+  (norm > 0.0).then(|| (dot / norm) as f32)
+}
+
+/// The `limit` candidates most similar to `query`, most similar first.
+///
+/// A plain linear scan: every candidate is scored, so the cost is O(n · d) for `n`
+/// candidates of `d` dimensions. Candidates that [`cosine_similarity`] cannot compare are
+/// skipped. Kept separate from the query that loads the candidates so an ANN index can
+/// replace it without touching the callers.
+pub fn nearest_by_cosine<K>(
+  query: &[f32],
+  candidates: impl IntoIterator<Item = (K, Vec<f32>)>,
+  limit: usize,
+) -> Vec<(K, f32)> {
+  let mut scored: Vec<(K, f32)> =
+    candidates.into_iter().filter_map(|(key, features)| Some((key, cosine_similarity(query, &features)?))).collect();
+  scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+  scored.truncate(limit);
+  scored
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_trips_and_rejects_malformed_blobs() {
+    let features = [0.25, -1.5, f32::MAX, 0.0];
+    let bytes = encode_features(&features);
+    assert_eq!(bytes.len(), HEADER_LEN + 16);
+    assert_eq!(decode_features(&bytes).unwrap(), features);
+    assert_eq!(decode_features(&encode_features(&[])).unwrap(), Vec::<f32>::new());
+
+    // Truncated payload, trailing garbage, unknown version and a bare header fragment.
+    assert!(decode_features(&bytes[..bytes.len() - 1]).is_err());
+    assert!(decode_features(&[bytes.as_slice(), &[0; 4]].concat()).is_err());
+    let mut future = bytes.clone();
+    future[0] = FEATURES_VERSION + 1;
+    assert!(decode_features(&future).is_err());
+    assert!(decode_features(&[FEATURES_VERSION, 0]).is_err());
+  }
+
+  #[test]
+  fn nearest_ranks_by_angle_and_skips_incomparable_vectors() {
+    let candidates = vec![
+      ("opposite", vec![-1.0, 0.0]),
+      ("scaled", vec![10.0, 0.5]),
+      ("orthogonal", vec![0.0, 3.0]),
+      ("zero", vec![0.0, 0.0]),
+      ("other model", vec![1.0, 0.0, 0.0]),
+    ];
+
+    let nearest = nearest_by_cosine(&[1.0, 0.0], candidates, 3);
+
+    let keys: Vec<&str> = nearest.iter().map(|(k, _)| *k).collect();
+    assert_eq!(keys, ["scaled", "orthogonal", "opposite"]);
+    assert!((nearest[2].1 + 1.0).abs() < 1e-6);
+  }
+}
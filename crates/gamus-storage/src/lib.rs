@@ -1,26 +1,229 @@
 pub mod config;
+pub mod features;
 pub mod models;
+pub mod paths;
+mod retry;
 pub mod schema;
 
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::slice;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::time::Duration;
 
+use async_trait::async_trait;
+use diesel::dsl::{AsExprOf, sql};
+use diesel::expression::{SqlLiteral, UncheckedBind};
 use diesel::prelude::*;
-use diesel::r2d2::{self, ConnectionManager, Pool};
+use diesel::r2d2::{self, ConnectionManager, CustomizeConnection, Pool};
+use diesel::sql_types::{BigInt, Bool, Double, Integer, Nullable, Text};
 use diesel::sqlite::SqliteConnection;
 use diesel_migrations::{MigrationHarness, embed_migrations};
 use uuid::Uuid;
 
-use gamus_core::domain::{ArtistId, ReleaseId, SongId, artist::Artist, release::Release, song::Song};
+use gamus_core::domain::artist::{Artist, ArtistPatch, normalize_artist_name};
+use gamus_core::domain::artist_role::{ArtistRole, ReleaseTrackArtistCredit};
+use gamus_core::domain::browse::{Page, Paged, ReleaseFilter, ReleaseSummary, SortBy};
+use gamus_core::domain::genre_styles::{Genre, Style};
+use gamus_core::domain::library_stats::LibraryStats;
+use gamus_core::domain::release_track::{
+  AudioAnalysis, AudioDetails, FileDetails, QualityLevel, ReleaseTrack, TrackQualitySummary,
+};
+use gamus_core::domain::release_type::ReleaseType;
+use gamus_core::domain::sync::{SyncCursor, Synced};
+use gamus_core::domain::{
+  ArtistId, ReleaseId, ReleaseTrackId, SongId,
+  release::{Artwork, Release, ReleasePatch},
+  song::{Song, SongPatch},
+};
 use gamus_core::errors::CoreError;
-use gamus_core::ports::Library;
+use gamus_core::ports::{AsyncLibrary, ExtractionFailure, ImportCheckpoint, Library, StoredFile};
 
-use crate::models::{ArtistRow, NewArtistRow, NewReleaseRow, NewSongRow, ReleaseRow, SongRow};
+use crate::config::{JournalMode, PoolConfig, PragmaConfig, RetryConfig};
+use crate::features::{decode_features, encode_features, nearest_by_cosine};
+use crate::models::{
+  ArtistChangeset, ArtistRow, ArtistSiteRow, ArtistVariationRow, ArtworkRow, LibraryFileAnalysisChangeset,
+  LibraryFileRow, LibraryRootRow, NewArtistRow, NewArtistSiteRow, NewArtistVariationRow, NewArtworkRow,
+  NewLibraryFileRow, NewReleaseGenreRow, NewReleaseMainArtistRow, NewReleaseRow, NewReleaseStyleRow,
+  NewReleaseTrackArtistRow, NewReleaseTrackRow, NewReleaseTypeRow, NewSongCommentRow, NewSongLyricsRow, NewSongRow,
+  ReleaseChangeset, ReleaseGenreRow, ReleaseMainArtistRow, ReleaseRow, ReleaseStyleRow, ReleaseTrackMetadataChangeset,
+  ReleaseTrackRow, ReleaseTypeRow, SongChangeset, SongCommentRow, SongLyricsRow, SongRow,
+};
+use crate::paths::{LibraryRoot, PathResolver};
+
+/// Rows per multi-row INSERT. Keeps each statement well under SQLite's bound-parameter limit.
+const INSERT_CHUNK_SIZE: usize = 500;
 
 /// Embeds migration SQL files into the compiled binary for self-contained execution.
 pub const MIGRATIONS: diesel_migrations::EmbeddedMigrations = embed_migrations!("migrations");
 
 type SqlitePool = Pool<ConnectionManager<SqliteConnection>>;
 
+/// Per-connection setup applied by `r2d2` every time it opens a new connection.
+///
+/// `busy_timeout`, `foreign_keys`, `synchronous` and `cache_size` are connection-scoped in
+/// SQLite, so running them once on the setup connection would leave every other pooled
+/// connection with SQLite's defaults (failing fast with `SQLITE_BUSY`, not enforcing
+/// foreign keys...).
+#[derive(Debug, Clone, Copy)]
+struct ConnectionPragmas {
+  busy_timeout_ms: u64,
+  pragmas: PragmaConfig,
+}
+
+impl ConnectionPragmas {
+  fn statements(&self) -> Vec<String> {
+    let mut statements = vec![
+      format!("PRAGMA busy_timeout = {}", self.busy_timeout_ms),
+      format!("PRAGMA foreign_keys = {}", if self.pragmas.foreign_keys { "ON" } else { "OFF" }),
+    ];
+    if let Some(synchronous) = self.pragmas.synchronous {
+      statements.push(format!("PRAGMA synchronous = {}", synchronous.as_pragma()));
+    }
+    if let Some(cache_size) = self.pragmas.cache_size {
+      statements.push(format!("PRAGMA cache_size = {cache_size}"));
+    }
+    statements
+  }
+
+  fn apply(&self, conn: &mut SqliteConnection) -> QueryResult<()> {
+    for statement in self.statements() {
+      diesel::sql_query(statement).execute(conn)?;
+    }
+    Ok(())
+  }
+}
+
+impl CustomizeConnection<SqliteConnection, r2d2::Error> for ConnectionPragmas {
+  fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), r2d2::Error> {
+    self.apply(conn).map_err(r2d2::Error::QueryError)
+  }
+}
+
+/// Applies pending migrations with foreign keys off, as SQLite recommends for schema changes,
+/// then restores the connection's pragmas.
+///
+/// A migration that rebuilds a table (create, copy, drop, rename) would otherwise cascade
+/// the `DROP` into every child table.
+fn run_migrations(conn: &mut SqliteConnection, pragmas: &ConnectionPragmas) -> Result<(), CoreError> {
+  diesel::sql_query("PRAGMA foreign_keys = OFF").execute(conn).map_err(|e| CoreError::Repository(e.to_string()))?;
+  let migrated = conn
+    .run_pending_migrations(MIGRATIONS)
+    .map(|_| ())
+    .map_err(|e| CoreError::Repository(format!("migration error: {e}")))
+    .and_then(|()| {
+      normalize_artist_names(conn).map_err(|e| CoreError::Repository(format!("artist name backfill error: {e}")))
+    });
+  pragmas.apply(conn).map_err(|e| CoreError::Repository(e.to_string()))?;
+  migrated
+}
+
+/// Unique index on `artists.name_normalized`, created by [`normalize_artist_names`].
+const ARTIST_NAME_INDEX: &str = "idx_artists_name_normalized";
+
+/// Fills `artists.name_normalized` with [`normalize_artist_name`], merges the artists that end
+/// up sharing it into the oldest one and creates the unique index on the column.
+///
+/// The migration adding the column can't do this in SQL (see its `up.sql`). Once the index
+/// exists every write keeps the column right, so later calls return straight away.
+fn normalize_artist_names(conn: &mut SqliteConnection) -> QueryResult<()> {
+  use crate::schema::artists;
+  use diesel::sql_types::Bool;
+
+  let indexed: bool = diesel::select(sql::<Bool>(&format!(
+    "EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'index' AND name = '{ARTIST_NAME_INDEX}')"
+  )))
+  .get_result(conn)?;
+  if indexed {
+    return Ok(());
+  }
+
+  conn.transaction(|conn| {
+    let rows: Vec<(String, String)> = artists::table
+      .select((artists::id, artists::name))
+      .order((artists::created_at.asc(), sql::<BigInt>("artists.rowid").asc()))
+      .load(conn)?;
+
+    let mut kept: HashMap<String, String> = HashMap::with_capacity(rows.len());
+    for (artist_id, artist_name) in rows {
+      let normalized = normalize_artist_name(&artist_name);
+      match kept.get(&normalized) {
+        Some(keep_id) => merge_artist_into(conn, &artist_id, keep_id)?,
+        None => {
+          diesel::update(artists::table.find(&artist_id))
+            .set(artists::name_normalized.eq(&normalized))
+            .execute(conn)?;
+          kept.insert(normalized, artist_id);
+        }
+      }
+    }
+
+    diesel::sql_query(format!("CREATE UNIQUE INDEX {ARTIST_NAME_INDEX} ON artists(name_normalized)")).execute(conn)?;
+    Ok(())
+  })
+}
+
+/// Repoints every child row of artist `dup_id` to `keep_id` and deletes `dup_id`. Children
+/// that would collide with one `keep_id` already has are dropped.
+fn merge_artist_into(conn: &mut SqliteConnection, dup_id: &str, keep_id: &str) -> QueryResult<()> {
+  use crate::schema::artists;
+
+  for table in ["artist_variations", "artist_sites", "release_main_artists", "release_track_artists"] {
+    diesel::sql_query(format!("UPDATE OR IGNORE {table} SET artist_id = ? WHERE artist_id = ?"))
+      .bind::<Text, _>(keep_id)
+      .bind::<Text, _>(dup_id)
+      .execute(conn)?;
+    diesel::sql_query(format!("DELETE FROM {table} WHERE artist_id = ?")).bind::<Text, _>(dup_id).execute(conn)?;
+  }
+  diesel::delete(artists::table.find(dup_id)).execute(conn)?;
+  Ok(())
+}
+
+/// Loads the registered library roots.
+fn load_path_resolver(conn: &mut SqliteConnection) -> Result<PathResolver, CoreError> {
+  use crate::schema::library_roots;
+
+  let rows = library_roots::table
+    .select((library_roots::id, library_roots::path))
+    .load::<LibraryRootRow>(conn)
+    .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+  Ok(PathResolver::new(rows.into_iter().map(|r| LibraryRoot { id: r.id, path: PathBuf::from(r.path) })))
+}
+
+/// Rewrites every `library_files` and `extraction_failures` row whose stored form under
+/// `paths` differs from the current one, i.e. files that now fall under a different (deeper)
+/// root.
+fn rebase_library_files(conn: &mut SqliteConnection, paths: &PathResolver) -> QueryResult<()> {
+  use crate::schema::{extraction_failures, library_files};
+
+  let rows: Vec<(String, String, String)> =
+    library_files::table.select((library_files::id, library_files::root_id, library_files::path)).load(conn)?;
+
+  for (id, root_id, path) in rows {
+    let stored = paths.to_stored(&paths.to_absolute(&root_id, &path));
+    if stored.root_id != root_id || stored.path != path {
+      diesel::update(library_files::table.find(&id))
+        .set((library_files::root_id.eq(&stored.root_id), library_files::path.eq(&stored.path)))
+        .execute(conn)?;
+    }
+  }
+
+  // The failure log is keyed by the same stored paths, so it follows the files.
+  let failures: Vec<(String, String)> =
+    extraction_failures::table.select((extraction_failures::root_id, extraction_failures::path)).load(conn)?;
+  for (root_id, path) in failures {
+    let stored = paths.to_stored(&paths.to_absolute(&root_id, &path));
+    if stored.root_id != root_id || stored.path != path {
+      diesel::update(extraction_failures::table.find((&root_id, &path)))
+        .set((extraction_failures::root_id.eq(&stored.root_id), extraction_failures::path.eq(&stored.path)))
+        .execute(conn)?;
+    }
+  }
+  Ok(())
+}
+
 /// Concrete implementation of the `Library` port backed by SQLite.
 ///
 /// Uses `r2d2` for connection pooling to manage file handles efficiently in a desktop environment.
@@ -28,6 +231,12 @@ type SqlitePool = Pool<ConnectionManager<SqliteConnection>>;
 #[derive(Clone)]
 pub struct LibraryStore {
   pool: SqlitePool,
+  /// Kept to open connections outside the pool (see [`LibraryStore::vacuum`]).
+  db_path: String,
+  pragmas: ConnectionPragmas,
+  retry: RetryConfig,
+  /// Registered library roots, shared by every clone (see [`crate::paths`]).
+  paths: Arc<RwLock<PathResolver>>,
 }
 
 impl LibraryStore {
@@ -37,21 +246,40 @@ impl LibraryStore {
   /// # Arguments
   ///
   /// * `db_path` - Filesystem path to the SQLite database.
-  /// * `journal_mode` - Optional PRAGMA journal_mode setting (defaults to WAL if passed).
+  /// * `journal_mode` - PRAGMA journal_mode to apply; `JournalMode::Wal` unless there is a reason not to.
+  /// * `pool_config` - Pool sizing; use `PoolConfig::default()` unless tuning for a specific workload.
+  /// * `busy_timeout_ms` - How long a writer waits on a locked database before giving up.
+  /// * `pragmas` - Other per-connection pragmas; `PragmaConfig::default()` enforces foreign keys.
+  /// * `retry` - Retry policy of write methods on a busy database; see [`RetryConfig`].
   ///
   /// # Security & Concurrency
   ///
   /// * Enables `test_on_check_out` to handle filesystem volatility common in desktop apps (e.g., file locks, deletion).
-  /// * Applies WAL mode to allow non-blocking concurrent reads while writing.
-  pub fn new(db_path: &PathBuf, journal_mode: &Option<String>) -> Result<Self, CoreError> {
+  /// * Applies the configured journal mode (WAL by default) to allow non-blocking concurrent reads while writing.
+  /// * Sets `busy_timeout` on every pooled connection so concurrent writers queue instead of failing.
+  /// * Applies `pragmas` to every pooled connection too (by default `foreign_keys`, so deletes
+  ///   cascade as the schema declares).
+  pub fn new(
+    db_path: &Path,
+    journal_mode: JournalMode,
+    pool_config: &PoolConfig,
+    busy_timeout_ms: u64,
+    pragmas: &PragmaConfig,
+    retry: &RetryConfig,
+  ) -> Result<Self, CoreError> {
     // Validate path encoding early to prevent runtime IO errors downstream
     let db_path = db_path.to_str().ok_or(CoreError::Repository("Invalid db path".to_string()))?;
     let manager = ConnectionManager::<SqliteConnection>::new(db_path);
+    let pragmas = ConnectionPragmas { busy_timeout_ms, pragmas: *pragmas };
 
     let pool = r2d2::Pool::builder()
       // Crucial for desktop context: verifies the connection is still alive and the file
       // is accessible before handing it to a thread. Slightly expensive but prevents "Database Locked" panics.
       .test_on_check_out(true)
+      .max_size(pool_config.max_size)
+      .min_idle(pool_config.min_idle)
+      .connection_timeout(Duration::from_secs(pool_config.connection_timeout_secs))
+      .connection_customizer(Box::new(pragmas))
       .build(manager)
       .map_err(|e| CoreError::Repository(format!("Pool error: {}", e)))?;
 
@@ -60,15 +288,48 @@ impl LibraryStore {
 
     // WAL (Write-Ahead Logging) is critical for concurrency in SQLite.
     // Without this, a write operation locks the entire database file against readers.
-    if let Some(mode) = journal_mode {
-      diesel::sql_query(format!("PRAGMA journal_mode = {}", mode))
-        .execute(&mut conn)
-        .map_err(|e| CoreError::Repository(format!("wal error: {}", e)))?;
-    }
+    diesel::sql_query(format!("PRAGMA journal_mode = {}", journal_mode.as_pragma()))
+      .execute(&mut conn)
+      .map_err(|e| CoreError::Repository(format!("wal error: {}", e)))?;
+
+    run_migrations(&mut conn, &pragmas)?;
+    let paths = load_path_resolver(&mut conn)?;
+
+    Ok(Self { pool, db_path: db_path.to_string(), pragmas, retry: *retry, paths: Arc::new(RwLock::new(paths)) })
+  }
+
+  /// Builds a store backed by a private in-memory database, with migrations applied.
+  ///
+  /// Meant for tests. Every SQLite connection to `:memory:` gets its own empty database,
+  /// so the pool is pinned to a single connection that is never recycled; the data lives
+  /// as long as the store (and its clones). [`LibraryStore::vacuum`] opens a separate
+  /// connection and therefore does nothing useful here.
+  pub fn in_memory() -> Result<Self, CoreError> {
+    const DB_PATH: &str = ":memory:";
+    let manager = ConnectionManager::<SqliteConnection>::new(DB_PATH);
+    let pragmas = ConnectionPragmas { busy_timeout_ms: 0, pragmas: PragmaConfig::default() };
+
+    let pool = r2d2::Pool::builder()
+      .max_size(1)
+      .min_idle(Some(1))
+      .idle_timeout(None)
+      .max_lifetime(None)
+      .connection_customizer(Box::new(pragmas))
+      .build(manager)
+      .map_err(|e| CoreError::Repository(format!("Pool error: {}", e)))?;
 
-    conn.run_pending_migrations(MIGRATIONS).map_err(|e| CoreError::Repository(format!("migration error: {e}")))?;
+    let mut conn = pool.get().map_err(|e| CoreError::Repository(e.to_string()))?;
+    run_migrations(&mut conn, &pragmas)?;
+    let paths = load_path_resolver(&mut conn)?;
+    drop(conn);
 
-    Ok(Self { pool })
+    Ok(Self {
+      pool,
+      db_path: DB_PATH.to_string(),
+      pragmas,
+      retry: RetryConfig::default(),
+      paths: Arc::new(RwLock::new(paths)),
+    })
   }
 
   /// Convenience constructor loading configuration from the environment/file.
@@ -77,7 +338,168 @@ impl LibraryStore {
 
     let cfg = StorageConfig::load().map_err(|e| CoreError::Repository(e.to_string()))?;
 
-    Self::new(&cfg.db_path, &cfg.journal_mode)
+    Self::new(&cfg.db_path, cfg.journal_mode, &cfg.pool, cfg.busy_timeout_ms, &cfg.pragmas, &cfg.retry)
+  }
+
+  /// Replaces the retry policy applied to write methods (the one given to `new`).
+  pub fn with_retry_config(mut self, retry: RetryConfig) -> Self {
+    self.retry = retry;
+    self
+  }
+
+  /// Moves the WAL contents into the main database file and truncates the `-wal` file.
+  ///
+  /// Readers still holding an old snapshot can prevent a full checkpoint; SQLite then
+  /// checkpoints what it can and the next call picks up the rest.
+  pub fn checkpoint(&self) -> Result<(), CoreError> {
+    let mut conn = self.get_conn()?;
+
+    diesel::sql_query("PRAGMA wal_checkpoint(TRUNCATE)")
+      .execute(&mut conn)
+      .map_err(|e| CoreError::Repository(format!("checkpoint error: {e}")))?;
+
+    Ok(())
+  }
+
+  /// Rebuilds the database file, reclaiming the space left by deleted rows.
+  ///
+  /// Runs on a dedicated connection opened outside the pool, so no pooled connection
+  /// is left mid-transaction or holding a statement while SQLite rewrites the file.
+  /// It takes an exclusive lock for its whole duration: do not run it concurrently with
+  /// an active import, writers would wait up to `busy_timeout` and then fail.
+  pub fn vacuum(&self) -> Result<(), CoreError> {
+    let mut conn = SqliteConnection::establish(&self.db_path)
+      .map_err(|e| CoreError::Repository(format!("connection error: {e}")))?;
+
+    self.pragmas.apply(&mut conn).map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    diesel::sql_query("VACUUM").execute(&mut conn).map_err(|e| CoreError::Repository(format!("vacuum error: {e}")))?;
+
+    Ok(())
+  }
+
+  /// Full maintenance pass: refreshes planner statistics, vacuums and truncates the WAL.
+  ///
+  /// The checkpoint runs last because `VACUUM` itself goes through the WAL in WAL mode.
+  /// Same restriction as [`LibraryStore::vacuum`]: call it while no import is running.
+  pub fn maintenance(&self) -> Result<(), CoreError> {
+    {
+      let mut conn = self.get_conn()?;
+      diesel::sql_query("PRAGMA optimize")
+        .execute(&mut conn)
+        .map_err(|e| CoreError::Repository(format!("optimize error: {e}")))?;
+    }
+
+    self.vacuum()?;
+    self.checkpoint()
+  }
+
+  /// Shutdown flush: refreshes planner statistics (`PRAGMA optimize`) and truncates the WAL,
+  /// so the next startup has nothing to replay and the `.db` file alone is a full backup.
+  ///
+  /// Consumes this handle; the pool closes once the last clone is dropped. Clones that are
+  /// still alive keep working, but writes made through them after `close` land in the WAL
+  /// again, so call it on the last handle in use (e.g. on application exit).
+  pub fn close(self) -> Result<(), CoreError> {
+    {
+      let mut conn = self.get_conn()?;
+      diesel::sql_query("PRAGMA optimize")
+        .execute(&mut conn)
+        .map_err(|e| CoreError::Repository(format!("optimize error: {e}")))?;
+    }
+
+    self.checkpoint()
+  }
+
+  /// Registered library roots, deepest first.
+  pub fn library_roots(&self) -> Vec<LibraryRoot> {
+    self.paths().roots().to_vec()
+  }
+
+  /// Registers `path` as a library root, or returns the existing root with that path.
+  ///
+  /// Files stored from now on under `path` are kept relative to it. Files already stored
+  /// that fall under it (absolute ones, or ones under a root containing it) are rebased onto
+  /// the new root in the same transaction.
+  ///
+  /// # Errors
+  /// `CoreError::InvalidInput` if `path` is not absolute.
+  pub fn add_library_root(&self, path: &Path) -> Result<LibraryRoot, CoreError> {
+    use crate::schema::library_roots;
+
+    if !path.is_absolute() {
+      return Err(CoreError::InvalidInput(format!("library root must be absolute: {}", path.display())));
+    }
+    if let Some(root) = self.paths().roots().iter().find(|r| r.path == path) {
+      return Ok(root.clone());
+    }
+
+    let root = LibraryRoot { id: Uuid::new_v4().to_string(), path: path.to_path_buf() };
+    let row = LibraryRootRow { id: root.id.clone(), path: path.to_string_lossy().into_owned() };
+    let resolver = PathResolver::new(self.paths().roots().iter().cloned().chain([root.clone()]));
+    let mut conn = self.get_conn()?;
+
+    retry::with_retry(&self.retry, || {
+      conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::insert_into(library_roots::table).values(&row).execute(conn)?;
+        rebase_library_files(conn, &resolver)
+      })
+    })
+    .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    *self.paths.write().unwrap_or_else(|e| e.into_inner()) = resolver;
+    Ok(root)
+  }
+
+  /// Points root `id` at `path`, e.g. after moving the database to another machine or
+  /// remounting the drive elsewhere. Files under the root keep their stored relative paths
+  /// and resolve under `path` from now on.
+  ///
+  /// # Errors
+  /// * `CoreError::NotFound` if there is no root `id`.
+  /// * `CoreError::InvalidInput` if `path` is not absolute or is already another root.
+  pub fn relocate_library_root(&self, id: &str, path: &Path) -> Result<(), CoreError> {
+    use crate::schema::library_roots;
+    use diesel::result::{DatabaseErrorKind, Error as DieselError};
+
+    if !path.is_absolute() {
+      return Err(CoreError::InvalidInput(format!("library root must be absolute: {}", path.display())));
+    }
+    let current = self.paths();
+    if current.root(id).is_none() {
+      return Err(CoreError::NotFound);
+    }
+    let resolver = PathResolver::new(
+      current
+        .roots()
+        .iter()
+        .map(|r| LibraryRoot { id: r.id.clone(), path: if r.id == id { path.to_path_buf() } else { r.path.clone() } }),
+    );
+    drop(current);
+    let path_str = path.to_string_lossy();
+    let mut conn = self.get_conn()?;
+
+    // Moving a root inside another one (or around one) changes which root is deepest for
+    // some files, so the rows are rebased like when a root is added.
+    retry::with_retry(&self.retry, || {
+      conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        diesel::update(library_roots::table.find(id)).set(library_roots::path.eq(path_str.as_ref())).execute(conn)?;
+        rebase_library_files(conn, &resolver)
+      })
+    })
+    .map_err(|e| match e {
+      DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+        CoreError::InvalidInput(format!("{} is already a library root", path.display()))
+      }
+      e => CoreError::Repository(e.to_string()),
+    })?;
+
+    *self.paths.write().unwrap_or_else(|e| e.into_inner()) = resolver;
+    Ok(())
+  }
+
+  fn paths(&self) -> RwLockReadGuard<'_, PathResolver> {
+    self.paths.read().unwrap_or_else(|e| e.into_inner())
   }
 
   /// Internal helper to retrieve a connection from the pool.
@@ -89,21 +511,105 @@ impl LibraryStore {
   }
 }
 
+/// Runs the work on Tokio's blocking pool so SQLite I/O never parks an async worker.
+///
+/// The work gets a clone of the store, so it takes its own connection from the pool like
+/// any other [`Library`] call. Outside a Tokio runtime (sync callers, plain executors)
+/// there is no blocking pool and the work runs on the calling thread.
+#[async_trait]
+impl AsyncLibrary for LibraryStore {
+  async fn offload<T, F>(&self, work: F) -> Result<T, CoreError>
+  where
+    F: FnOnce(&Self) -> Result<T, CoreError> + Send + 'static,
+    T: Send + 'static,
+  {
+    let store = self.clone();
+    match tokio::runtime::Handle::try_current() {
+      Ok(handle) => handle
+        .spawn_blocking(move || work(&store))
+        .await
+        .map_err(|e| CoreError::Repository(format!("blocking task failed: {}", e)))?,
+      Err(_) => work(&store),
+    }
+  }
+}
+
 impl Library for LibraryStore {
   fn save_artist(&self, artist: &Artist) -> Result<(), CoreError> {
     use crate::schema::artists::dsl::*;
 
     let new_row = artist_to_new_row(artist);
+    let ids = [new_row.id.clone()];
     let mut conn = self.get_conn()?;
 
-    // UPSERT semantics: Ensure idempotency by updating fields on conflict.
-    diesel::insert_into(artists)
-      .values(&new_row)
-      .on_conflict(id)
-      .do_update()
-      .set((name.eq(&artist.name), bio.eq(artist.bio.as_deref())))
-      .execute(&mut conn)
-      .map_err(|e| CoreError::Repository(e.to_string()))?;
+    retry::with_retry(&self.retry, || {
+      conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let before = load_artists_by_id(conn, &ids)?;
+
+        // UPSERT semantics: Ensure idempotency by updating fields on conflict.
+        // `updated_at` is left to `touch_changed_artists`, so an identical re-save isn't synced again.
+        diesel::insert_into(artists)
+          .values(&new_row)
+          .on_conflict(id)
+          .do_update()
+          .set((name.eq(&artist.name), bio.eq(artist.bio.as_deref()), name_normalized.eq(&new_row.name_normalized)))
+          .execute(conn)?;
+
+        replace_artist_children(conn, &[artist])?;
+        touch_changed_artists(conn, &ids, &before)
+      })
+    })
+    .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    Ok(())
+  }
+
+  fn save_artists_batch(&self, artists_in: &[Artist]) -> Result<(), CoreError> {
+    use crate::schema::artists::dsl::*;
+    use diesel::upsert::excluded;
+
+    // Deduplicate by id before hitting the DB: the last occurrence wins, mirroring
+    // what sequential `save_artist` calls would have produced.
+    let mut index_by_id: HashMap<ArtistId, usize> = HashMap::with_capacity(artists_in.len());
+    let mut unique: Vec<&Artist> = Vec::with_capacity(artists_in.len());
+    for artist in artists_in {
+      match index_by_id.get(&artist.id) {
+        Some(&i) => unique[i] = artist,
+        None => {
+          index_by_id.insert(artist.id, unique.len());
+          unique.push(artist);
+        }
+      }
+    }
+
+    if unique.is_empty() {
+      return Ok(());
+    }
+
+    let rows: Vec<NewArtistRow> = unique.iter().map(|a| artist_to_new_row(a)).collect();
+
+    let mut conn = self.get_conn()?;
+
+    retry::with_retry(&self.retry, || {
+      conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        for (row_chunk, artist_chunk) in rows.chunks(INSERT_CHUNK_SIZE).zip(unique.chunks(INSERT_CHUNK_SIZE)) {
+          let ids: Vec<String> = row_chunk.iter().map(|r| r.id.clone()).collect();
+          let before = load_artists_by_id(conn, &ids)?;
+
+          diesel::insert_into(artists)
+            .values(row_chunk)
+            .on_conflict(id)
+            .do_update()
+            .set((name.eq(excluded(name)), bio.eq(excluded(bio)), name_normalized.eq(excluded(name_normalized))))
+            .execute(conn)?;
+
+          replace_artist_children(conn, artist_chunk)?;
+          touch_changed_artists(conn, &ids, &before)?;
+        }
+        Ok(())
+      })
+    })
+    .map_err(|e| CoreError::Repository(e.to_string()))?;
 
     Ok(())
   }
@@ -112,15 +618,30 @@ impl Library for LibraryStore {
     use crate::schema::songs::dsl::*;
 
     let new_row = song_to_new_row(song);
+    let ids = [new_row.id.clone()];
     let mut conn = self.get_conn()?;
 
-    diesel::insert_into(songs)
-      .values(&new_row)
-      .on_conflict(id)
-      .do_update()
-      .set((title.eq(&song.title), acoustid.eq(song.acoustid.as_deref())))
-      .execute(&mut conn)
-      .map_err(|e| CoreError::Repository(e.to_string()))?;
+    retry::with_retry(&self.retry, || {
+      conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let before = load_songs_by_id(conn, &ids)?;
+
+        diesel::insert_into(songs)
+          .values(&new_row)
+          .on_conflict(id)
+          .do_update()
+          .set((
+            title.eq(&song.title),
+            acoustid.eq(song.acoustid.as_deref()),
+            isrc.eq(song.isrc.as_deref()),
+            mbid.eq(song.mbid.as_deref()),
+          ))
+          .execute(conn)?;
+
+        replace_song_texts(conn, song)?;
+        touch_changed_songs(conn, &ids, &before)
+      })
+    })
+    .map_err(|e| CoreError::Repository(e.to_string()))?;
 
     Ok(())
   }
@@ -129,146 +650,3246 @@ impl Library for LibraryStore {
     use crate::schema::releases::dsl::*;
 
     let new_row = release_to_new_row(release);
+    let ids = [new_row.id.clone()];
     let mut conn = self.get_conn()?;
 
-    diesel::insert_into(releases)
-      .values(&new_row)
-      .on_conflict(id)
-      .do_update()
-      .set((title.eq(&release.title), release_date.eq(release.release_date.as_deref())))
-      .execute(&mut conn)
-      .map_err(|e| CoreError::Repository(e.to_string()))?;
+    retry::with_retry(&self.retry, || {
+      conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let before = load_releases_by_id(conn, &ids)?;
+
+        diesel::insert_into(releases)
+          .values(&new_row)
+          .on_conflict(id)
+          .do_update()
+          .set((
+            title.eq(&release.title),
+            release_date.eq(release.release_date.as_deref()),
+            mbid.eq(release.mbid.as_deref()),
+          ))
+          .execute(conn)?;
+
+        replace_release_tags(conn, release)?;
+        replace_release_main_artists(conn, release)?;
+        replace_release_artworks(conn, release)?;
+        touch_changed_releases(conn, &ids, &before)
+      })
+    })
+    .map_err(|e| CoreError::Repository(e.to_string()))?;
 
     Ok(())
   }
 
-  fn find_artist(&self, artist_id: ArtistId) -> Result<Option<Artist>, CoreError> {
-    use crate::schema::artists::dsl::*;
+  fn save_track(&self, track: &ReleaseTrack) -> Result<(), CoreError> {
+    use crate::schema::{library_files, release_tracks};
     use diesel::OptionalExtension;
+    use diesel::upsert::excluded;
 
-    let id_str = artist_id.to_string();
+    let mut track_row = track_to_new_row(track);
+    let mut file_row = track_to_file_row(track, &self.paths());
+    let mut credit_rows = credits_to_rows(&track_row.id, &track.artist_credits)?;
     let mut conn = self.get_conn()?;
 
-    let row_opt = artists
-      .filter(id.eq(id_str))
-      .first::<ArtistRow>(&mut conn)
-      .optional()
-      .map_err(|e| CoreError::Repository(e.to_string()))?;
+    retry::with_retry(&self.retry, || {
+      conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        // A file maps to exactly one track. Imports mint a fresh id on every run, so the
+        // id already stored for this (root, path) wins and that track row is updated
+        // instead of leaving the old one behind as a duplicate.
+        let existing = library_files::table
+          .filter(library_files::root_id.eq(&file_row.root_id))
+          .filter(library_files::path.eq(&file_row.path))
+          .select(library_files::release_track_id)
+          .first::<String>(conn)
+          .optional()?;
+        let track_id = existing.unwrap_or_else(|| track.id.to_string());
+        track_row.id.clone_from(&track_id);
+        file_row.release_track_id.clone_from(&track_id);
+        for row in &mut credit_rows {
+          row.release_track_id.clone_from(&track_id);
+        }
+
+        diesel::insert_into(release_tracks::table)
+          .values(&track_row)
+          .on_conflict(release_tracks::id)
+          .do_update()
+          .set((
+            release_tracks::song_id.eq(excluded(release_tracks::song_id)),
+            release_tracks::release_id.eq(excluded(release_tracks::release_id)),
+            release_tracks::disc_number.eq(excluded(release_tracks::disc_number)),
+            release_tracks::track_number.eq(excluded(release_tracks::track_number)),
+            release_tracks::title_override.eq(excluded(release_tracks::title_override)),
+            release_tracks::updated_at.eq(sql::<Text>("CURRENT_TIMESTAMP")),
+          ))
+          .execute(conn)?;
 
-    Ok(row_opt.map(row_to_artist))
+        // The (root, path) pair is the natural key of a file. `added_at` is deliberately
+        // absent from the update set: it keeps the first-insert timestamp (column default)
+        // so "recently added" doesn't reshuffle on every rescan. `updated_at` is bumped even
+        // when nothing changed: it is how a resumed import finds the files it already saved.
+        diesel::insert_into(library_files::table)
+          .values(&file_row)
+          .on_conflict((library_files::root_id, library_files::path))
+          .do_update()
+          .set((
+            library_files::release_track_id.eq(excluded(library_files::release_track_id)),
+            library_files::size_bytes.eq(excluded(library_files::size_bytes)),
+            library_files::modified_unix.eq(excluded(library_files::modified_unix)),
+            library_files::duration_ms.eq(excluded(library_files::duration_ms)),
+            library_files::bitrate_kbps.eq(excluded(library_files::bitrate_kbps)),
+            library_files::sample_rate_hz.eq(excluded(library_files::sample_rate_hz)),
+            library_files::channels.eq(excluded(library_files::channels)),
+            library_files::fingerprint.eq(excluded(library_files::fingerprint)),
+            library_files::bpm.eq(excluded(library_files::bpm)),
+            library_files::quality_score.eq(excluded(library_files::quality_score)),
+            library_files::quality_assessment.eq(excluded(library_files::quality_assessment)),
+            library_files::features.eq(excluded(library_files::features)),
+            library_files::codec.eq(excluded(library_files::codec)),
+            library_files::container.eq(excluded(library_files::container)),
+            library_files::is_lossless.eq(excluded(library_files::is_lossless)),
+            library_files::quality_level.eq(excluded(library_files::quality_level)),
+            library_files::content_hash.eq(excluded(library_files::content_hash)),
+            library_files::quality_cutoff_hz.eq(excluded(library_files::quality_cutoff_hz)),
+            library_files::quality_details.eq(excluded(library_files::quality_details)),
+            library_files::quality_json.eq(excluded(library_files::quality_json)),
+            library_files::replaygain_track_db.eq(excluded(library_files::replaygain_track_db)),
+            library_files::replaygain_album_db.eq(excluded(library_files::replaygain_album_db)),
+            library_files::replaygain_track_peak.eq(excluded(library_files::replaygain_track_peak)),
+            library_files::replaygain_album_peak.eq(excluded(library_files::replaygain_album_peak)),
+            library_files::updated_at.eq(sql::<Text>("CURRENT_TIMESTAMP")),
+          ))
+          .execute(conn)?;
+
+        // No credits means the extractor found none, not that the track lost them.
+        if !credit_rows.is_empty() {
+          replace_track_credits(conn, &track_row.id, &credit_rows)?;
+        }
+        Ok(())
+      })
+    })
+    .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    Ok(())
   }
 
-  fn find_song(&self, song_id: SongId) -> Result<Option<Song>, CoreError> {
-    use crate::schema::songs::dsl::*;
-    use diesel::OptionalExtension;
+  fn save_track_credits(
+    &self,
+    track_id: ReleaseTrackId,
+    credits: &[ReleaseTrackArtistCredit],
+  ) -> Result<(), CoreError> {
+    let id_str = track_id.to_string();
+    let rows = credits_to_rows(&id_str, credits)?;
+    let mut conn = self.get_conn()?;
+
+    retry::with_retry(&self.retry, || {
+      conn.transaction::<_, diesel::result::Error, _>(|conn| replace_track_credits(conn, &id_str, &rows))
+    })
+    .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    Ok(())
+  }
+
+  fn update_track_analysis(&self, track_id: ReleaseTrackId, analysis: &AudioAnalysis) -> Result<(), CoreError> {
+    use crate::schema::library_files;
+
+    let changes = analysis_to_changeset(Some(analysis));
+    let id_str = track_id.to_string();
+    let mut conn = self.get_conn()?;
+
+    retry::with_retry(&self.retry, || {
+      diesel::update(library_files::table.filter(library_files::release_track_id.eq(&id_str)))
+        .set((&changes, library_files::updated_at.eq(sql::<Text>("CURRENT_TIMESTAMP"))))
+        .execute(&mut conn)
+    })
+    .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    Ok(())
+  }
+
+  fn update_track_metadata(
+    &self,
+    track_id: ReleaseTrackId,
+    track_number: Option<u32>,
+    disc_number: Option<u32>,
+    title_override: Option<Option<String>>,
+  ) -> Result<(), CoreError> {
+    use crate::schema::release_tracks;
 
+    let changes = ReleaseTrackMetadataChangeset {
+      track_number: track_number.map(|n| position_to_i32("track_number", n)).transpose()?,
+      disc_number: disc_number.map(|n| position_to_i32("disc_number", n)).transpose()?,
+      title_override,
+    };
+    let id_str = track_id.to_string();
+    let mut conn = self.get_conn()?;
+
+    let updated = retry::with_retry(&self.retry, || {
+      diesel::update(release_tracks::table.find(&id_str))
+        .set((&changes, release_tracks::updated_at.eq(sql::<Text>("CURRENT_TIMESTAMP"))))
+        .execute(&mut conn)
+    })
+    .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    if updated == 0 {
+      return Err(CoreError::NotFound);
+    }
+    Ok(())
+  }
+
+  fn update_song(&self, song_id: SongId, patch: &SongPatch) -> Result<(), CoreError> {
+    use crate::schema::songs;
+
+    let changes = SongChangeset {
+      title: patch.title.as_deref().map(|t| non_blank("title", t)).transpose()?,
+      acoustid: patch.acoustid.clone(),
+      isrc: patch.isrc.clone(),
+      mbid: patch.mbid.clone(),
+    };
     let id_str = song_id.to_string();
     let mut conn = self.get_conn()?;
 
-    let row_opt = songs
-      .filter(id.eq(id_str))
-      .first::<SongRow>(&mut conn)
-      .optional()
-      .map_err(|e| CoreError::Repository(e.to_string()))?;
+    let updated = retry::with_retry(&self.retry, || {
+      diesel::update(songs::table.find(&id_str))
+        .set((&changes, songs::updated_at.eq(sql::<Text>("CURRENT_TIMESTAMP"))))
+        .execute(&mut conn)
+    })
+    .map_err(|e| CoreError::Repository(e.to_string()))?;
 
-    Ok(row_opt.map(row_to_song))
+    if updated == 0 {
+      return Err(CoreError::NotFound);
+    }
+    Ok(())
   }
 
-  fn find_release(&self, release_id: ReleaseId) -> Result<Option<Release>, CoreError> {
-    use crate::schema::releases::dsl::*;
-    use diesel::OptionalExtension;
+  fn update_release(&self, release_id: ReleaseId, patch: &ReleasePatch) -> Result<(), CoreError> {
+    use crate::schema::releases;
 
+    let changes = ReleaseChangeset {
+      title: patch.title.as_deref().map(|t| non_blank("title", t)).transpose()?,
+      release_date: patch.release_date.clone(),
+      mbid: patch.mbid.clone(),
+    };
     let id_str = release_id.to_string();
     let mut conn = self.get_conn()?;
 
-    let row_opt = releases
-      .filter(id.eq(id_str))
-      .first::<ReleaseRow>(&mut conn)
-      .optional()
-      .map_err(|e| CoreError::Repository(e.to_string()))?;
+    let updated = retry::with_retry(&self.retry, || {
+      diesel::update(releases::table.find(&id_str))
+        .set((&changes, releases::updated_at.eq(sql::<Text>("CURRENT_TIMESTAMP"))))
+        .execute(&mut conn)
+    })
+    .map_err(|e| CoreError::Repository(e.to_string()))?;
 
-    Ok(row_opt.map(row_to_release))
+    if updated == 0 {
+      return Err(CoreError::NotFound);
+    }
+    Ok(())
   }
 
-  fn list_artists(&self) -> Result<Vec<Artist>, CoreError> {
-    use crate::schema::artists::dsl::*;
+  fn update_artist(&self, artist_id: ArtistId, patch: &ArtistPatch) -> Result<(), CoreError> {
+    use crate::schema::artists;
+    use diesel::result::{DatabaseErrorKind, Error as DieselError};
+
+    let name = patch.name.as_deref().map(|n| non_blank("name", n)).transpose()?;
+    let changes =
+      ArtistChangeset { name_normalized: name.as_deref().map(normalize_artist_name), name, bio: patch.bio.clone() };
+    let id_str = artist_id.to_string();
     let mut conn = self.get_conn()?;
 
-    // Note: Loading all rows without pagination may impact memory/performance on large libraries.
-    // Consider adding limits/offsets to the `Library` trait interface in the future.
-    let rows: Vec<ArtistRow> =
-      artists.load::<ArtistRow>(&mut conn).map_err(|e| CoreError::Repository(e.to_string()))?;
+    let updated = retry::with_retry(&self.retry, || {
+      diesel::update(artists::table.find(&id_str))
+        .set((&changes, artists::updated_at.eq(sql::<Text>("CURRENT_TIMESTAMP"))))
+        .execute(&mut conn)
+    })
+    .map_err(|e| match e {
+      DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => {
+        CoreError::InvalidInput(format!("another artist is already named {:?}", changes.name.unwrap_or_default()))
+      }
+      e => CoreError::Repository(e.to_string()),
+    })?;
 
-    Ok(rows.into_iter().map(row_to_artist).collect())
+    if updated == 0 {
+      return Err(CoreError::NotFound);
+    }
+    Ok(())
   }
 
-  fn list_songs(&self) -> Result<Vec<Song>, CoreError> {
-    use crate::schema::songs::dsl::*;
+  fn save_import_checkpoint(&self, checkpoint: &ImportCheckpoint) -> Result<(), CoreError> {
+    use crate::schema::import_checkpoint::dsl::*;
+
     let mut conn = self.get_conn()?;
 
-    let rows = songs.load::<SongRow>(&mut conn).map_err(|e| CoreError::Repository(e.to_string()))?;
+    retry::with_retry(&self.retry, || {
+      diesel::replace_into(import_checkpoint)
+        .values((id.eq(1), started_at.eq(checkpoint.started_at)))
+        .execute(&mut conn)
+    })
+    .map_err(|e| CoreError::Repository(e.to_string()))?;
 
-    Ok(rows.into_iter().map(row_to_song).collect())
+    Ok(())
   }
 
-  fn list_releases(&self) -> Result<Vec<Release>, CoreError> {
-    use crate::schema::releases::dsl::*;
+  fn clear_import_checkpoint(&self) -> Result<(), CoreError> {
+    use crate::schema::import_checkpoint::dsl::*;
+
     let mut conn = self.get_conn()?;
 
-    let rows = releases.load::<ReleaseRow>(&mut conn).map_err(|e| CoreError::Repository(e.to_string()))?;
+    retry::with_retry(&self.retry, || diesel::delete(import_checkpoint).execute(&mut conn))
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
 
-    Ok(rows.into_iter().map(row_to_release).collect())
+    Ok(())
   }
-}
 
-// --- DTO Mapping Helpers ---
-// Decouples Domain Entities (business logic) from Diesel Models (DB schema).
+  fn record_extraction_failure(
+    &self,
+    file_path: &Path,
+    message: &str,
+    mtime: u64,
+    unix_ts: i64,
+  ) -> Result<(), CoreError> {
+    use crate::schema::extraction_failures::dsl::*;
 
-fn artist_to_new_row(artist: &Artist) -> NewArtistRow {
-  NewArtistRow { id: artist.id.to_string(), name: artist.name.clone(), bio: artist.bio.clone() }
-}
+    let stored = self.paths().to_stored(file_path);
+    let mut conn = self.get_conn()?;
 
-fn song_to_new_row(song: &Song) -> NewSongRow {
-  NewSongRow { id: song.id.to_string(), title: song.title.clone(), acoustid: song.acoustid.clone() }
-}
+    // UPSERT: a repeat failure replaces the details and bumps the attempt count.
+    retry::with_retry(&self.retry, || {
+      diesel::insert_into(extraction_failures)
+        .values((
+          root_id.eq(&stored.root_id),
+          path.eq(&stored.path),
+          error.eq(message),
+          failed_at.eq(unix_ts),
+          modified_unix.eq(mtime as i64),
+        ))
+        .on_conflict((root_id, path))
+        .do_update()
+        .set((error.eq(message), failed_at.eq(unix_ts), modified_unix.eq(mtime as i64), attempts.eq(attempts + 1)))
+        .execute(&mut conn)
+    })
+    .map_err(|e| CoreError::Repository(e.to_string()))?;
 
-fn release_to_new_row(release: &Release) -> NewReleaseRow {
-  NewReleaseRow { id: release.id.to_string(), title: release.title.clone(), release_date: release.release_date.clone() }
-}
+    Ok(())
+  }
 
-// Inversion mappings (DB -> Domain)
-// Assumes DB integrity regarding UUID formatting.
-// NOTE: `expect` usage here relies on the invariant that IDs stored are valid UUIDs.
-// Database corruption could cause panics here.
+  fn forget_extraction_failure(&self, file_path: &Path) -> Result<(), CoreError> {
+    use crate::schema::extraction_failures::dsl::*;
 
-fn row_to_artist(row: ArtistRow) -> Artist {
-  Artist {
-    id: ArtistId::from_uuid(Uuid::parse_str(&row.id).expect("Invalid UUID in database")),
-    name: row.name,
-    variations: vec![],
-    bio: row.bio,
-    sites: vec![],
-  }
-}
+    let stored = self.paths().to_stored(file_path);
+    let mut conn = self.get_conn()?;
 
-fn row_to_song(row: SongRow) -> Song {
-  Song {
-    id: SongId::from_uuid(Uuid::parse_str(&row.id).expect("Invalid UUID in database")),
-    title: row.title,
-    acoustid: row.acoustid,
+    retry::with_retry(&self.retry, || {
+      diesel::delete(extraction_failures.find((&stored.root_id, &stored.path))).execute(&mut conn)
+    })
+    .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    Ok(())
   }
-}
 
-fn row_to_release(row: ReleaseRow) -> Release {
+  fn clear_extraction_failures(&self) -> Result<usize, CoreError> {
+    use crate::schema::extraction_failures::dsl::*;
+
+    let mut conn = self.get_conn()?;
+
+    retry::with_retry(&self.retry, || diesel::delete(extraction_failures).execute(&mut conn))
+      .map_err(|e| CoreError::Repository(e.to_string()))
+  }
+
+  fn set_artwork_thumbnail(&self, artwork_hash: &str, thumbnail: &Path) -> Result<usize, CoreError> {
+    use crate::schema::artworks::dsl::*;
+
+    let thumbnail = thumbnail.to_string_lossy();
+    let mut conn = self.get_conn()?;
+
+    retry::with_retry(&self.retry, || {
+      diesel::update(artworks.filter(hash.eq(artwork_hash)))
+        .set(thumbnail_path.eq(thumbnail.as_ref()))
+        .execute(&mut conn)
+    })
+    .map_err(|e| CoreError::Repository(e.to_string()))
+  }
+
+  fn find_artist(&self, artist_id: ArtistId) -> Result<Option<Artist>, CoreError> {
+    use crate::schema::artists::dsl::*;
+    use diesel::OptionalExtension;
+
+    let id_str = artist_id.to_string();
+    let mut conn = self.get_conn()?;
+
+    let row_opt = artists
+      .filter(id.eq(&id_str))
+      .first::<ArtistRow>(&mut conn)
+      .optional()
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    let Some(row) = row_opt else {
+      return Ok(None);
+    };
+
+    let mut children = load_artist_children(&mut conn, Some(slice::from_ref(&id_str)))
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+    let artist_children = children.remove(&row.id).unwrap_or_default();
+
+    Ok(Some(row_to_artist(row, artist_children)))
+  }
+
+  fn find_artist_by_name(&self, artist_name: &str) -> Result<Option<Artist>, CoreError> {
+    use crate::schema::artists::dsl::*;
+    use diesel::OptionalExtension;
+
+    let mut conn = self.get_conn()?;
+
+    let row_opt = artists
+      .filter(name_normalized.eq(normalize_artist_name(artist_name)))
+      .first::<ArtistRow>(&mut conn)
+      .optional()
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    let Some(row) = row_opt else {
+      return Ok(None);
+    };
+
+    let mut children = load_artist_children(&mut conn, Some(slice::from_ref(&row.id)))
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+    let artist_children = children.remove(&row.id).unwrap_or_default();
+
+    Ok(Some(row_to_artist(row, artist_children)))
+  }
+
+  fn find_song(&self, song_id: SongId) -> Result<Option<Song>, CoreError> {
+    use crate::schema::songs::dsl::*;
+    use diesel::OptionalExtension;
+
+    let id_str = song_id.to_string();
+    let mut conn = self.get_conn()?;
+
+    let row_opt = songs
+      .filter(id.eq(&id_str))
+      .first::<SongRow>(&mut conn)
+      .optional()
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    let Some(row) = row_opt else {
+      return Ok(None);
+    };
+
+    let mut texts =
+      load_song_texts(&mut conn, Some(slice::from_ref(&id_str))).map_err(|e| CoreError::Repository(e.to_string()))?;
+    let song_texts = texts.remove(&row.id).unwrap_or_default();
+
+    Ok(Some(row_to_song(row, song_texts)))
+  }
+
+  fn find_release(&self, release_id: ReleaseId) -> Result<Option<Release>, CoreError> {
+    use crate::schema::releases::dsl::*;
+    use diesel::OptionalExtension;
+
+    let id_str = release_id.to_string();
+    let mut conn = self.get_conn()?;
+
+    let row_opt = releases
+      .filter(id.eq(&id_str))
+      .first::<ReleaseRow>(&mut conn)
+      .optional()
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    let Some(row) = row_opt else {
+      return Ok(None);
+    };
+
+    let mut tags =
+      load_release_tags(&mut conn, Some(slice::from_ref(&id_str))).map_err(|e| CoreError::Repository(e.to_string()))?;
+    let release_tags = tags.remove(&row.id).unwrap_or_default();
+
+    Ok(Some(row_to_release(row, release_tags)))
+  }
+
+  fn find_track(&self, track_id: ReleaseTrackId) -> Result<Option<ReleaseTrack>, CoreError> {
+    use crate::schema::{library_files, release_tracks};
+    use diesel::OptionalExtension;
+
+    let mut conn = self.get_conn()?;
+
+    let row = library_files::table
+      .inner_join(release_tracks::table)
+      .filter(release_tracks::id.eq(track_id.to_string()))
+      .select((release_tracks::all_columns, library_files::all_columns))
+      .first::<(ReleaseTrackRow, LibraryFileRow)>(&mut conn)
+      .optional()
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    row.map(|(track, file)| row_to_release_track(track, file, &self.paths())).transpose()
+  }
+
+  fn find_song_by_fingerprint(&self, fingerprint: &str) -> Result<Option<Song>, CoreError> {
+    use crate::schema::{library_files, release_tracks, songs};
+    use diesel::OptionalExtension;
+
+    let mut conn = self.get_conn()?;
+
+    // Prefer the song-level AcoustID; fall back to the fingerprint of any file already linked to a song.
+    let by_acoustid = songs::table
+      .filter(songs::acoustid.eq(fingerprint))
+      .first::<SongRow>(&mut conn)
+      .optional()
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    let row_opt = match by_acoustid {
+      Some(row) => Some(row),
+      None => songs::table
+        .inner_join(release_tracks::table.inner_join(library_files::table))
+        .filter(library_files::fingerprint.eq(fingerprint))
+        .select(songs::all_columns)
+        .first::<SongRow>(&mut conn)
+        .optional()
+        .map_err(|e| CoreError::Repository(e.to_string()))?,
+    };
+
+    let Some(row) = row_opt else {
+      return Ok(None);
+    };
+
+    let mut texts =
+      load_song_texts(&mut conn, Some(slice::from_ref(&row.id))).map_err(|e| CoreError::Repository(e.to_string()))?;
+    let song_texts = texts.remove(&row.id).unwrap_or_default();
+
+    Ok(Some(row_to_song(row, song_texts)))
+  }
+
+  fn find_song_by_mbid(&self, mbid: &str) -> Result<Option<Song>, CoreError> {
+    use crate::schema::songs;
+    use diesel::OptionalExtension;
+
+    let mut conn = self.get_conn()?;
+    let row_opt = songs::table
+      .filter(songs::mbid.eq(mbid))
+      .first::<SongRow>(&mut conn)
+      .optional()
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    row_opt.map(|row| song_with_texts(&mut conn, row)).transpose()
+  }
+
+  fn find_song_by_isrc(&self, isrc: &str) -> Result<Option<Song>, CoreError> {
+    use crate::schema::songs;
+    use diesel::OptionalExtension;
+
+    let mut conn = self.get_conn()?;
+    let row_opt = songs::table
+      .filter(songs::isrc.eq(isrc))
+      .order((songs::created_at.asc(), sql::<BigInt>("songs.rowid").asc()))
+      .first::<SongRow>(&mut conn)
+      .optional()
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    row_opt.map(|row| song_with_texts(&mut conn, row)).transpose()
+  }
+
+  fn find_release_by_mbid(&self, mbid: &str) -> Result<Option<Release>, CoreError> {
+    use crate::schema::releases;
+    use diesel::OptionalExtension;
+
+    let mut conn = self.get_conn()?;
+    let row_opt = releases::table
+      .filter(releases::mbid.eq(mbid))
+      .first::<ReleaseRow>(&mut conn)
+      .optional()
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    let Some(row) = row_opt else {
+      return Ok(None);
+    };
+
+    let mut tags =
+      load_release_tags(&mut conn, Some(slice::from_ref(&row.id))).map_err(|e| CoreError::Repository(e.to_string()))?;
+    let release_tags = tags.remove(&row.id).unwrap_or_default();
+
+    Ok(Some(row_to_release(row, release_tags)))
+  }
+
+  fn release_thumbnail(&self, release: ReleaseId) -> Result<Option<PathBuf>, CoreError> {
+    use crate::schema::artworks::dsl::*;
+    use diesel::OptionalExtension;
+
+    let mut conn = self.get_conn()?;
+    let thumbnail = artworks
+      .filter(release_id.eq(release.to_string()))
+      .filter(thumbnail_path.is_not_null())
+      .order(path.asc())
+      .select(thumbnail_path.assume_not_null())
+      .first::<String>(&mut conn)
+      .optional()
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    Ok(thumbnail.map(PathBuf::from))
+  }
+
+  fn list_artists(&self) -> Result<Vec<Artist>, CoreError> {
+    use crate::schema::artists::dsl::*;
+    let mut conn = self.get_conn()?;
+
+    // Note: Loading all rows without pagination may impact memory/performance on large libraries.
+    // Consider adding limits/offsets to the `Library` trait interface in the future.
+    let rows: Vec<ArtistRow> =
+      artists.load::<ArtistRow>(&mut conn).map_err(|e| CoreError::Repository(e.to_string()))?;
+    let mut children = load_artist_children(&mut conn, None).map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| {
+          let artist_children = children.remove(&row.id).unwrap_or_default();
+          row_to_artist(row, artist_children)
+        })
+        .collect(),
+    )
+  }
+
+  fn list_songs(&self) -> Result<Vec<Song>, CoreError> {
+    use crate::schema::songs::dsl::*;
+    let mut conn = self.get_conn()?;
+
+    let rows = songs.load::<SongRow>(&mut conn).map_err(|e| CoreError::Repository(e.to_string()))?;
+    let mut texts = load_song_texts(&mut conn, None).map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| {
+          let song_texts = texts.remove(&row.id).unwrap_or_default();
+          row_to_song(row, song_texts)
+        })
+        .collect(),
+    )
+  }
+
+  fn list_releases(&self) -> Result<Vec<Release>, CoreError> {
+    use crate::schema::releases::dsl::*;
+    let mut conn = self.get_conn()?;
+
+    let rows = releases.load::<ReleaseRow>(&mut conn).map_err(|e| CoreError::Repository(e.to_string()))?;
+    let mut tags = load_release_tags(&mut conn, None).map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| {
+          let release_tags = tags.remove(&row.id).unwrap_or_default();
+          row_to_release(row, release_tags)
+        })
+        .collect(),
+    )
+  }
+
+  fn list_artists_updated_after(&self, after: &SyncCursor, limit: i64) -> Result<Vec<Synced<Artist>>, CoreError> {
+    use crate::schema::artists;
+    let mut conn = self.get_conn()?;
+
+    let rows = artists::table
+      .filter(after_cursor(after))
+      .order((artists::updated_at.asc(), artists::id.asc()))
+      .limit(limit)
+      .select((artists::all_columns, sql::<BigInt>(UPDATED_AT_UNIX_SQL)))
+      .load::<(ArtistRow, i64)>(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+    let ids: Vec<String> = rows.iter().map(|(row, _)| row.id.clone()).collect();
+    let mut children = load_artist_children(&mut conn, Some(&ids)).map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|(row, unix_ts)| {
+          let cursor = SyncCursor { updated_at: unix_ts, id: row.id.clone() };
+          let artist_children = children.remove(&row.id).unwrap_or_default();
+          Synced { item: row_to_artist(row, artist_children), cursor }
+        })
+        .collect(),
+    )
+  }
+
+  fn list_songs_updated_after(&self, after: &SyncCursor, limit: i64) -> Result<Vec<Synced<Song>>, CoreError> {
+    use crate::schema::songs;
+    let mut conn = self.get_conn()?;
+
+    let rows = songs::table
+      .filter(after_cursor(after))
+      .order((songs::updated_at.asc(), songs::id.asc()))
+      .limit(limit)
+      .select((songs::all_columns, sql::<BigInt>(UPDATED_AT_UNIX_SQL)))
+      .load::<(SongRow, i64)>(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+    let ids: Vec<String> = rows.iter().map(|(row, _)| row.id.clone()).collect();
+    let mut texts = load_song_texts(&mut conn, Some(&ids)).map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|(row, unix_ts)| {
+          let cursor = SyncCursor { updated_at: unix_ts, id: row.id.clone() };
+          let song_texts = texts.remove(&row.id).unwrap_or_default();
+          Synced { item: row_to_song(row, song_texts), cursor }
+        })
+        .collect(),
+    )
+  }
+
+  fn list_releases_updated_after(&self, after: &SyncCursor, limit: i64) -> Result<Vec<Synced<Release>>, CoreError> {
+    use crate::schema::releases;
+    let mut conn = self.get_conn()?;
+
+    let rows = releases::table
+      .filter(after_cursor(after))
+      .order((releases::updated_at.asc(), releases::id.asc()))
+      .limit(limit)
+      .select((releases::all_columns, sql::<BigInt>(UPDATED_AT_UNIX_SQL)))
+      .load::<(ReleaseRow, i64)>(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+    let ids: Vec<String> = rows.iter().map(|(row, _)| row.id.clone()).collect();
+    let mut tags = load_release_tags(&mut conn, Some(&ids)).map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|(row, unix_ts)| {
+          let cursor = SyncCursor { updated_at: unix_ts, id: row.id.clone() };
+          let release_tags = tags.remove(&row.id).unwrap_or_default();
+          Synced { item: row_to_release(row, release_tags), cursor }
+        })
+        .collect(),
+    )
+  }
+
+  fn list_recent_tracks(&self, limit: i64) -> Result<Vec<ReleaseTrack>, CoreError> {
+    use crate::schema::{library_files, release_tracks};
+
+    let mut conn = self.get_conn()?;
+
+    // `added_at` has second resolution; rowid breaks ties so a batch saved within the
+    // same second still comes back newest-first.
+    let rows = library_files::table
+      .inner_join(release_tracks::table)
+      .select((release_tracks::all_columns, library_files::all_columns))
+      .order((library_files::added_at.desc(), sql::<BigInt>("library_files.rowid").desc()))
+      .limit(limit.max(0))
+      .load::<(ReleaseTrackRow, LibraryFileRow)>(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    rows.into_iter().map(|(track, file)| row_to_release_track(track, file, &self.paths())).collect()
+  }
+
+  fn list_tracks_by_codec(&self, codec: &str) -> Result<Vec<ReleaseTrack>, CoreError> {
+    use crate::schema::{library_files, release_tracks};
+
+    let mut conn = self.get_conn()?;
+
+    // Codec names are stored as FFmpeg reports them, always lowercase.
+    let rows = library_files::table
+      .inner_join(release_tracks::table)
+      .filter(library_files::codec.eq(codec.trim().to_lowercase()))
+      .select((release_tracks::all_columns, library_files::all_columns))
+      .order(library_files::path.asc())
+      .load::<(ReleaseTrackRow, LibraryFileRow)>(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    rows.into_iter().map(|(track, file)| row_to_release_track(track, file, &self.paths())).collect()
+  }
+
+  fn list_track_credits(&self, track_id: ReleaseTrackId) -> Result<Vec<ReleaseTrackArtistCredit>, CoreError> {
+    use crate::schema::release_track_artists;
+
+    let mut conn = self.get_conn()?;
+
+    let rows: Vec<(String, String, Option<i32>)> = release_track_artists::table
+      .filter(release_track_artists::release_track_id.eq(track_id.to_string()))
+      .select((release_track_artists::artist_id, release_track_artists::role, release_track_artists::position))
+      .order((
+        release_track_artists::position.is_null(),
+        release_track_artists::position.asc(),
+        sql::<BigInt>("release_track_artists.rowid").asc(),
+      ))
+      .load(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    rows
+      .into_iter()
+      .map(|(artist_id, role, position)| {
+        Ok(ReleaseTrackArtistCredit {
+          release_track_id: track_id,
+          artist_id: ArtistId::from_uuid(Uuid::parse_str(&artist_id).expect("Invalid UUID in database")),
+          role: ArtistRole::from_str(&role).map_err(|e| CoreError::Repository(e.to_string()))?,
+          position: position.map(|p| p as u32),
+        })
+      })
+      .collect()
+  }
+
+  fn list_tracks_page(&self, offset: i64, limit: i64) -> Result<Vec<ReleaseTrack>, CoreError> {
+    use crate::schema::{library_files, release_tracks};
+
+    let mut conn = self.get_conn()?;
+
+    let rows = library_files::table
+      .inner_join(release_tracks::table)
+      .select((release_tracks::all_columns, library_files::all_columns))
+      .order(library_files::path.asc())
+      .offset(offset.max(0))
+      .limit(limit.max(0))
+      .load::<(ReleaseTrackRow, LibraryFileRow)>(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    rows.into_iter().map(|(track, file)| row_to_release_track(track, file, &self.paths())).collect()
+  }
+
+  fn list_tracks_by_quality(
+    &self,
+    min: f32,
+    max: f32,
+    limit: i64,
+    offset: i64,
+  ) -> Result<Vec<TrackQualitySummary>, CoreError> {
+    use crate::schema::{library_files, release_tracks, releases, songs};
+
+    let mut conn = self.get_conn()?;
+
+    // `BETWEEN` never matches NULL, so unanalysed and inconclusive files drop out.
+    let rows = library_files::table
+      .inner_join(release_tracks::table.inner_join(songs::table).inner_join(releases::table))
+      .filter(library_files::quality_score.between(min, max))
+      .select((
+        release_tracks::id,
+        library_files::root_id,
+        library_files::path,
+        library_files::quality_score.assume_not_null(),
+        library_files::quality_level,
+        release_tracks::title_override,
+        songs::title,
+        releases::title,
+      ))
+      .order((library_files::quality_score.asc(), library_files::path.asc()))
+      .offset(offset.max(0))
+      .limit(limit.max(0))
+      .load::<(String, String, String, f32, Option<String>, Option<String>, String, String)>(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    let paths = self.paths();
+    Ok(
+      rows
+        .into_iter()
+        .map(|(id, root_id, path, quality_score, level, title_override, song_title, release_title)| {
+          TrackQualitySummary {
+            track_id: ReleaseTrackId::from_uuid(Uuid::parse_str(&id).expect("Invalid UUID in database")),
+            path: paths.to_absolute(&root_id, &path),
+            quality_score,
+            quality_level: level.and_then(|l| QualityLevel::from_str(&l).ok()),
+            title: title_override.unwrap_or(song_title),
+            release_title,
+          }
+        })
+        .collect(),
+    )
+  }
+
+  fn list_tracks_pending_analysis(&self) -> Result<Vec<ReleaseTrack>, CoreError> {
+    use crate::schema::{library_files, release_tracks};
+
+    let mut conn = self.get_conn()?;
+
+    // Every analysis sets `quality_assessment`, inconclusive ones included, so it tells
+    // "never analysed" apart from "analysed without a verdict".
+    let rows = library_files::table
+      .inner_join(release_tracks::table)
+      .filter(library_files::quality_score.is_null())
+      .filter(library_files::quality_assessment.is_null())
+      .select((release_tracks::all_columns, library_files::all_columns))
+      .order(library_files::path.asc())
+      .load::<(ReleaseTrackRow, LibraryFileRow)>(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    rows.into_iter().map(|(track, file)| row_to_release_track(track, file, &self.paths())).collect()
+  }
+
+  fn list_orphan_songs(&self) -> Result<Vec<Song>, CoreError> {
+    use crate::schema::{release_tracks, songs};
+
+    let mut conn = self.get_conn()?;
+
+    let rows = songs::table
+      .left_join(release_tracks::table)
+      .filter(release_tracks::id.is_null())
+      .select(songs::all_columns)
+      .order(songs::title)
+      .load::<SongRow>(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+    let mut texts = load_song_texts(&mut conn, None).map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| {
+          let song_texts = texts.remove(&row.id).unwrap_or_default();
+          row_to_song(row, song_texts)
+        })
+        .collect(),
+    )
+  }
+
+  fn list_empty_releases(&self) -> Result<Vec<Release>, CoreError> {
+    use crate::schema::{release_tracks, releases};
+
+    let mut conn = self.get_conn()?;
+
+    let rows = releases::table
+      .left_join(release_tracks::table)
+      .filter(release_tracks::id.is_null())
+      .select(releases::all_columns)
+      .order(releases::title)
+      .load::<ReleaseRow>(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+    let mut tags = load_release_tags(&mut conn, None).map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| {
+          let release_tags = tags.remove(&row.id).unwrap_or_default();
+          row_to_release(row, release_tags)
+        })
+        .collect(),
+    )
+  }
+
+  fn list_file_states(&self) -> Result<Vec<StoredFile>, CoreError> {
+    use crate::schema::library_files;
+
+    let mut conn = self.get_conn()?;
+
+    let rows: Vec<(String, String, i64, i64, Option<String>)> = library_files::table
+      .select((
+        library_files::root_id,
+        library_files::path,
+        library_files::size_bytes,
+        library_files::modified_unix,
+        library_files::content_hash,
+      ))
+      .load(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    let paths = self.paths();
+    Ok(
+      rows
+        .into_iter()
+        .map(|(root_id, path, size, modified, content_hash)| StoredFile {
+          path: paths.to_absolute(&root_id, &path),
+          size_bytes: size as u64,
+          modified_unix: modified as u64,
+          content_hash,
+        })
+        .collect(),
+    )
+  }
+
+  fn load_import_checkpoint(&self) -> Result<Option<ImportCheckpoint>, CoreError> {
+    use crate::schema::import_checkpoint::dsl::*;
+    use diesel::OptionalExtension;
+
+    let mut conn = self.get_conn()?;
+
+    let started = import_checkpoint
+      .select(started_at)
+      .first::<i64>(&mut conn)
+      .optional()
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    Ok(started.map(|ts| ImportCheckpoint { started_at: ts }))
+  }
+
+  fn list_paths_saved_since(&self, unix_ts: i64) -> Result<Vec<PathBuf>, CoreError> {
+    use crate::schema::library_files;
+
+    let mut conn = self.get_conn()?;
+
+    let rows: Vec<(String, String)> = library_files::table
+      .filter(library_files::updated_at.ge(sqlite_datetime(unix_ts)))
+      .select((library_files::root_id, library_files::path))
+      .load(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    let paths = self.paths();
+    Ok(rows.into_iter().map(|(root_id, path)| paths.to_absolute(&root_id, &path)).collect())
+  }
+
+  fn list_extraction_failures(&self) -> Result<Vec<ExtractionFailure>, CoreError> {
+    use crate::schema::extraction_failures::dsl::*;
+
+    let mut conn = self.get_conn()?;
+
+    let rows: Vec<(String, String, String, i64, i32, i64)> = extraction_failures
+      .select((root_id, path, error, failed_at, attempts, modified_unix))
+      .load(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    let paths = self.paths();
+    let mut failures: Vec<ExtractionFailure> = rows
+      .into_iter()
+      .map(|(root, p, message, ts, n, mtime)| ExtractionFailure {
+        path: paths.to_absolute(&root, &p),
+        error: message,
+        failed_at: ts,
+        attempts: n as u32,
+        modified_unix: mtime as u64,
+      })
+      .collect();
+    // Stored paths are relative to different roots; sort by the absolute ones.
+    failures.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(failures)
+  }
+
+  fn list_artworks(&self) -> Result<Vec<Artwork>, CoreError> {
+    use crate::schema::artworks::dsl::*;
+
+    let mut conn = self.get_conn()?;
+    let rows =
+      artworks.order(path.asc()).load::<ArtworkRow>(&mut conn).map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    Ok(rows.into_iter().map(row_to_artwork).collect())
+  }
+
+  /// Linear scan: every stored embedding is loaded, decoded and scored on each call, so
+  /// the cost grows as O(n) with the number of analysed tracks (tracks without an
+  /// embedding are filtered out in SQL and cost nothing). Fine for a "similar tracks"
+  /// panel on a personal library; callers issuing many queries should restrict themselves
+  /// to analysed tracks or wait for an index, which would only replace [`nearest_by_cosine`].
+  fn find_similar(&self, track_id: ReleaseTrackId, limit: usize) -> Result<Vec<(ReleaseTrackId, f32)>, CoreError> {
+    use crate::schema::library_files;
+
+    let mut conn = self.get_conn()?;
+    let id = track_id.to_string();
+
+    let query = library_files::table
+      .filter(library_files::release_track_id.eq(&id))
+      .select(library_files::features)
+      .first::<Option<Vec<u8>>>(&mut conn)
+      .optional()
+      .map_err(|e| CoreError::Repository(e.to_string()))?
+      .ok_or(CoreError::NotFound)?
+      .ok_or_else(|| CoreError::InvalidInput(format!("track {track_id} has no feature embedding")))?;
+    let query = decode_features(&query)?;
+
+    let rows: Vec<(String, Vec<u8>)> = library_files::table
+      .filter(library_files::release_track_id.ne(&id))
+      .filter(library_files::features.is_not_null())
+      .select((library_files::release_track_id, library_files::features.assume_not_null()))
+      .load(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    let candidates = rows
+      .into_iter()
+      .map(|(id, bytes)| {
+        let id = ReleaseTrackId::from_uuid(Uuid::parse_str(&id).expect("Invalid UUID in database"));
+        Ok((id, decode_features(&bytes)?))
+      })
+      .collect::<Result<Vec<_>, CoreError>>()?;
+
+    Ok(nearest_by_cosine(&query, candidates, limit))
+  }
+
+  fn find_duplicate_files(&self) -> Result<Vec<Vec<ReleaseTrackId>>, CoreError> {
+    use crate::schema::library_files;
+    use diesel::dsl::count_star;
+
+    let mut conn = self.get_conn()?;
+
+    let hashes: Vec<String> = library_files::table
+      .filter(library_files::content_hash.is_not_null())
+      .group_by((library_files::size_bytes, library_files::content_hash))
+      .having(count_star().gt(1))
+      .select(library_files::content_hash.assume_not_null())
+      .load(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+    if hashes.is_empty() {
+      return Ok(Vec::new());
+    }
+
+    // Same hash with a different size can only happen with full hashes (partial ones mix
+    // in the size) and would be a collision, so rows are grouped by both again.
+    let rows: Vec<(i64, String, String)> = library_files::table
+      .filter(library_files::content_hash.eq_any(&hashes))
+      .select((
+        library_files::size_bytes,
+        library_files::content_hash.assume_not_null(),
+        library_files::release_track_id,
+      ))
+      .order((library_files::size_bytes.desc(), library_files::content_hash.asc(), library_files::path.asc()))
+      .load(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    let mut clusters: Vec<Vec<ReleaseTrackId>> = Vec::new();
+    let mut current_key = None;
+    for (size, hash, id) in rows {
+      let id = ReleaseTrackId::from_uuid(Uuid::parse_str(&id).expect("Invalid UUID in database"));
+      let key = Some((size, hash));
+      if key == current_key {
+        clusters.last_mut().expect("a cluster per key").push(id);
+      } else {
+        clusters.push(vec![id]);
+        current_key = key;
+      }
+    }
+    clusters.retain(|ids| ids.len() > 1);
+    Ok(clusters)
+  }
+
+  fn stats(&self) -> Result<LibraryStats, CoreError> {
+    use crate::schema::{artists, library_files, release_genres, releases, songs};
+    use diesel::dsl::{count_star, sql};
+    use diesel::sql_types::{BigInt, Nullable};
+
+    let mut conn = self.get_conn()?;
+
+    // Single read transaction so every figure comes from the same snapshot.
+    let (songs_n, releases_n, artists_n, files_n, duration_ms, size_bytes, genre_counts, level_counts) = conn
+      .transaction::<_, diesel::result::Error, _>(|conn| {
+        let songs_n: i64 = songs::table.count().get_result(conn)?;
+        let releases_n: i64 = releases::table.count().get_result(conn)?;
+        let artists_n: i64 = artists::table.count().get_result(conn)?;
+        let files_n: i64 = library_files::table.count().get_result(conn)?;
+
+        let (duration_ms, size_bytes): (Option<i64>, Option<i64>) = library_files::table
+          .select((sql::<Nullable<BigInt>>("SUM(duration_ms)"), sql::<Nullable<BigInt>>("SUM(size_bytes)")))
+          .get_result(conn)?;
+
+        let genre_counts: Vec<(String, i64)> = release_genres::table
+          .group_by(release_genres::genre)
+          .select((release_genres::genre, count_star()))
+          .load(conn)?;
+
+        let level_counts: Vec<(Option<String>, i64)> = library_files::table
+          .group_by(library_files::quality_level)
+          .select((library_files::quality_level, count_star()))
+          .load(conn)?;
+
+        Ok((songs_n, releases_n, artists_n, files_n, duration_ms, size_bytes, genre_counts, level_counts))
+      })
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    let mut by_genre = HashMap::new();
+    for (raw, n) in genre_counts {
+      if let Ok(g) = Genre::from_str(&raw) {
+        *by_genre.entry(g).or_insert(0) += n as usize;
+      }
+    }
+
+    let mut by_quality_level = HashMap::new();
+    for (raw, n) in level_counts {
+      // Files never analyzed have no level; they count as inconclusive, same as before the column existed.
+      let level = raw.and_then(|r| QualityLevel::from_str(&r).ok()).unwrap_or(QualityLevel::Inconclusive);
+      *by_quality_level.entry(level).or_insert(0) += n as usize;
+    }
+
+    Ok(LibraryStats {
+      songs: songs_n as usize,
+      releases: releases_n as usize,
+      artists: artists_n as usize,
+      files: files_n as usize,
+      total_duration_ms: duration_ms.unwrap_or(0).max(0) as u64,
+      total_size_bytes: size_bytes.unwrap_or(0).max(0) as u64,
+      by_genre,
+      by_quality_level,
+    })
+  }
+
+  fn count_songs(&self) -> Result<i64, CoreError> {
+    use crate::schema::songs;
+    let mut conn = self.get_conn()?;
+    songs::table.count().get_result(&mut conn).map_err(|e| CoreError::Repository(e.to_string()))
+  }
+
+  fn count_releases(&self) -> Result<i64, CoreError> {
+    use crate::schema::releases;
+    let mut conn = self.get_conn()?;
+    releases::table.count().get_result(&mut conn).map_err(|e| CoreError::Repository(e.to_string()))
+  }
+
+  fn count_artists(&self) -> Result<i64, CoreError> {
+    use crate::schema::artists;
+    let mut conn = self.get_conn()?;
+    artists::table.count().get_result(&mut conn).map_err(|e| CoreError::Repository(e.to_string()))
+  }
+
+  /// Same join as `list_tracks_page`, so the total always matches what the pages return.
+  fn count_tracks(&self) -> Result<i64, CoreError> {
+    use crate::schema::{library_files, release_tracks};
+    let mut conn = self.get_conn()?;
+    library_files::table
+      .inner_join(release_tracks::table)
+      .count()
+      .get_result(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))
+  }
+
+  fn distinct_genres(&self) -> Result<Vec<(Genre, usize)>, CoreError> {
+    use crate::schema::release_genres;
+
+    let mut conn = self.get_conn()?;
+    let rows: Vec<(String, String)> = release_genres::table
+      .select((release_genres::genre, release_genres::release_id))
+      .distinct()
+      .load(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    // Several spellings can parse to the same genre ("Hip Hop", "hip-hop"), so merge after
+    // parsing, counting a release tagged with two of them once.
+    let mut releases: HashMap<Genre, HashSet<String>> = HashMap::new();
+    for (raw, release_id) in rows {
+      if let Ok(genre) = Genre::from_str(&raw) {
+        releases.entry(genre).or_default().insert(release_id);
+      }
+    }
+    Ok(ranked(releases.into_iter().map(|(genre, ids)| (genre, ids.len())).collect()))
+  }
+
+  fn distinct_styles(&self) -> Result<Vec<(Style, usize)>, CoreError> {
+    use crate::schema::release_styles;
+
+    let mut conn = self.get_conn()?;
+    let rows: Vec<(String, String)> = release_styles::table
+      .select((release_styles::style, release_styles::release_id))
+      .distinct()
+      .load(&mut conn)
+      .map_err(|e| CoreError::Repository(e.to_string()))?;
+
+    // As with genres, a release tagged with two spellings of one style counts once.
+    let mut releases: HashMap<Style, HashSet<String>> = HashMap::new();
+    for (raw, release_id) in rows {
+      let Ok(style) = Style::from_str(&raw);
+      releases.entry(style).or_default().insert(release_id);
+    }
+    Ok(ranked(releases.into_iter().map(|(style, ids)| (style, ids.len())).collect()))
+  }
+
+  fn browse_releases(
+    &self,
+    filter: ReleaseFilter,
+    sort: SortBy,
+    page: Page,
+  ) -> Result<Paged<ReleaseSummary>, CoreError> {
+    use crate::schema::{artists, release_genres, release_main_artists, release_styles, releases};
+
+    let mut conn = self.get_conn()?;
+    let repo_err = |e: diesel::result::Error| CoreError::Repository(e.to_string());
+
+    // Stored spellings that parse to the requested genre/style, so the filter agrees with the facets.
+    let genre_spellings = match filter.genre {
+      Some(genre) => {
+        let raws: Vec<String> =
+          release_genres::table.select(release_genres::genre).distinct().load(&mut conn).map_err(repo_err)?;
+        Some(raws.into_iter().filter(|raw| Genre::from_str(raw).is_ok_and(|g| g == genre)).collect::<Vec<_>>())
+      }
+      None => None,
+    };
+    let style_spellings = match &filter.style {
+      Some(style) => {
+        let raws: Vec<String> =
+          release_styles::table.select(release_styles::style).distinct().load(&mut conn).map_err(repo_err)?;
+        Some(raws.into_iter().filter(|raw| Style::from_str(raw).is_ok_and(|s| &s == style)).collect::<Vec<_>>())
+      }
+      None => None,
+    };
+
+    // Built twice, for the total and for the page; filters left unset add no clause.
+    let filtered = || {
+      let mut query = releases::table.into_boxed();
+      if let Some(spellings) = &genre_spellings {
+        let tagged = release_genres::table.filter(release_genres::genre.eq_any(spellings.clone()));
+        query = query.filter(releases::id.eq_any(tagged.select(release_genres::release_id)));
+      }
+      if let Some(spellings) = &style_spellings {
+        let tagged = release_styles::table.filter(release_styles::style.eq_any(spellings.clone()));
+        query = query.filter(releases::id.eq_any(tagged.select(release_styles::release_id)));
+      }
+      if let Some(from) = filter.year_from {
+        query = query.filter(sql::<Nullable<Integer>>(RELEASE_YEAR_SQL).ge(from));
+      }
+      if let Some(to) = filter.year_to {
+        query = query.filter(sql::<Nullable<Integer>>(RELEASE_YEAR_SQL).le(to));
+      }
+      if let Some(min) = filter.min_quality {
+        query = query.filter(sql::<Nullable<Double>>(RELEASE_AVG_QUALITY_SQL).ge(f64::from(min)));
+      }
+      if let Some(artist) = filter.artist {
+        let credited = release_main_artists::table.filter(release_main_artists::artist_id.eq(artist.to_string()));
+        query = query.filter(releases::id.eq_any(credited.select(release_main_artists::release_id)));
+      }
+      query
+    };
+
+    let total = filtered().count().get_result::<i64>(&mut conn).map_err(repo_err)?;
+
+    let title = || sql::<Text>("releases.title COLLATE NOCASE");
+    let query = filtered().select((
+      releases::id,
+      releases::title,
+      sql::<Nullable<Integer>>(RELEASE_YEAR_SQL),
+      sql::<BigInt>(RELEASE_TRACK_COUNT_SQL),
+      sql::<Nullable<Double>>(RELEASE_AVG_QUALITY_SQL),
+    ));
+    // SQLite sorts NULL lowest, so descending orders leave releases without a value last.
+    let query = match sort {
+      SortBy::Title => query.order((title().asc(), releases::id.asc())),
+      SortBy::Year => {
+        query.order((sql::<Nullable<Integer>>(RELEASE_YEAR_SQL).desc(), title().asc(), releases::id.asc()))
+      }
+      SortBy::RecentlyAdded => query.order((releases::created_at.desc(), title().asc(), releases::id.asc())),
+      SortBy::Quality => {
+        query.order((sql::<Nullable<Double>>(RELEASE_AVG_QUALITY_SQL).desc(), title().asc(), releases::id.asc()))
+      }
+    };
+    let rows: Vec<ReleaseSummaryRow> =
+      query.offset(page.offset.max(0)).limit(page.limit.max(0)).load(&mut conn).map_err(repo_err)?;
+
+    let ids: Vec<String> = rows.iter().map(|row| row.0.clone()).collect();
+    let credited: Vec<(String, String)> = release_main_artists::table
+      .inner_join(artists::table)
+      .filter(release_main_artists::release_id.eq_any(ids))
+      .order(sql::<BigInt>("release_main_artists.rowid").asc())
+      .select((release_main_artists::release_id, artists::name))
+      .load(&mut conn)
+      .map_err(repo_err)?;
+    let mut artist_names: HashMap<String, Vec<String>> = HashMap::new();
+    for (release_id, name) in credited {
+      artist_names.entry(release_id).or_default().push(name);
+    }
+
+    let items = rows
+      .into_iter()
+      .map(|(release_id, title, year, track_count, avg_quality)| ReleaseSummary {
+        id: ReleaseId::from_uuid(Uuid::parse_str(&release_id).expect("Invalid UUID in database")),
+        artist_names: artist_names.remove(&release_id).unwrap_or_default(),
+        title,
+        year,
+        track_count: track_count as usize,
+        avg_quality: avg_quality.map(|q| q as f32),
+      })
+      .collect();
+    Ok(Paged { items, total, page })
+  }
+}
+
+/// `(id, title, year, track count, average quality)` selected by `browse_releases`.
+type ReleaseSummaryRow = (String, String, Option<i32>, i64, Option<f64>);
+
+/// Year of `releases.release_date`, NULL unless the date starts with four digits.
+const RELEASE_YEAR_SQL: &str = "(CASE WHEN releases.release_date GLOB '[0-9][0-9][0-9][0-9]*' \
+   THEN CAST(substr(releases.release_date, 1, 4) AS INTEGER) END)";
+
+/// Tracks of the current `releases` row.
+const RELEASE_TRACK_COUNT_SQL: &str =
+  "(SELECT COUNT(*) FROM release_tracks WHERE release_tracks.release_id = releases.id)";
+
+/// Mean quality score of the current `releases` row's files; `AVG` skips unscored ones and
+/// is NULL when none is scored.
+const RELEASE_AVG_QUALITY_SQL: &str = "(SELECT AVG(library_files.quality_score) FROM library_files \
+   JOIN release_tracks ON release_tracks.id = library_files.release_track_id \
+   WHERE release_tracks.release_id = releases.id)";
+
+/// Facet counts from most to least frequent; ties ordered by display name so the list is stable.
+fn ranked<T: std::fmt::Display>(counts: HashMap<T, usize>) -> Vec<(T, usize)> {
+  let mut ranked: Vec<(T, usize)> = counts.into_iter().collect();
+  ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.to_string().cmp(&b.0.to_string())));
+  ranked
+}
+
+/// `unix_ts` in the `YYYY-MM-DD HH:MM:SS` (UTC) text form that `CURRENT_TIMESTAMP` writes,
+/// so the `*_at` columns compare against it as plain strings.
+fn sqlite_datetime(unix_ts: i64) -> SqlLiteral<Text, UncheckedBind<SqlLiteral<Text>, AsExprOf<i64, BigInt>>> {
+  sql::<Text>("datetime(").bind::<BigInt, _>(unix_ts).sql(", 'unixepoch')")
+}
+
+/// Filter produced by [`after_cursor`].
+type AfterCursor = SqlLiteral<
+  Bool,
+  UncheckedBind<SqlLiteral<Bool, UncheckedBind<SqlLiteral<Bool>, AsExprOf<i64, BigInt>>>, AsExprOf<String, Text>>,
+>;
+
+/// `(updated_at, id) > cursor` on the queried table. Compared as a row value, so rows saved in
+/// the cursor's own second still come after it when their id sorts later.
+fn after_cursor(cursor: &SyncCursor) -> AfterCursor {
+  sql::<Bool>("(updated_at, id) > (datetime(")
+    .bind::<BigInt, _>(cursor.updated_at)
+    .sql(", 'unixepoch'), ")
+    .bind::<Text, _>(cursor.id.clone())
+    .sql(")")
+}
+
+/// `updated_at` of the queried table as UNIX seconds, for the cursor of each synced row.
+const UPDATED_AT_UNIX_SQL: &str = "CAST(strftime('%s', updated_at) AS INTEGER)";
+
+/// Ids whose stored entity no longer matches the snapshot taken before a save. Ids missing
+/// from `before` were just inserted and already got `updated_at` from the column default.
+fn changed_ids<T: PartialEq>(before: &HashMap<String, T>, after: &HashMap<String, T>) -> Vec<String> {
+  before.iter().filter(|(id, old)| after.get(*id) != Some(*old)).map(|(id, _)| id.clone()).collect()
+}
+
+/// Track/disc numbers are 1-based and stored as `INTEGER`.
+fn position_to_i32(field: &str, n: u32) -> Result<i32, CoreError> {
+  match i32::try_from(n) {
+    Ok(n) if n >= 1 => Ok(n),
+    _ => Err(CoreError::InvalidInput(format!("{field} must be between 1 and {}, got {n}", i32::MAX))),
+  }
+}
+
+/// Trims `value`, rejecting it if nothing is left: a blank title or name can't be shown.
+fn non_blank(field: &str, value: &str) -> Result<String, CoreError> {
+  match value.trim() {
+    "" => Err(CoreError::InvalidInput(format!("{field} must not be blank"))),
+    trimmed => Ok(trimmed.to_string()),
+  }
+}
+
+// --- Artist child tables ---
+
+/// Name variations and sites attached to an artist (`artist_variations` / `artist_sites`).
+#[derive(Debug, Default)]
+struct ArtistChildren {
+  variations: Vec<String>,
+  sites: Vec<String>,
+}
+
+/// Rewrites the variation/site rows of `artists_in` (delete-then-insert), so entries
+/// removed from the domain object disappear from the DB.
+/// Must run inside the caller's transaction.
+fn replace_artist_children(conn: &mut SqliteConnection, artists_in: &[&Artist]) -> QueryResult<()> {
+  use crate::schema::{artist_sites, artist_variations};
+
+  let ids: Vec<String> = artists_in.iter().map(|a| a.id.to_string()).collect();
+
+  diesel::delete(artist_variations::table.filter(artist_variations::artist_id.eq_any(&ids))).execute(conn)?;
+  diesel::delete(artist_sites::table.filter(artist_sites::artist_id.eq_any(&ids))).execute(conn)?;
+
+  let mut variation_rows: Vec<NewArtistVariationRow> = Vec::new();
+  let mut site_rows: Vec<NewArtistSiteRow> = Vec::new();
+
+  for (artist, artist_id) in artists_in.iter().zip(&ids) {
+    // UNIQUE(artist_id, variation|url): skip repeats instead of failing the whole save.
+    let mut seen_variations = HashSet::new();
+    for v in artist.variations.iter().filter(|v| seen_variations.insert(v.as_str())) {
+      variation_rows.push(NewArtistVariationRow {
+        id: Uuid::new_v4().to_string(),
+        artist_id: artist_id.clone(),
+        variation: v.clone(),
+      });
+    }
+
+    let mut seen_sites = HashSet::new();
+    for url in artist.sites.iter().filter(|u| seen_sites.insert(u.as_str())) {
+      site_rows.push(NewArtistSiteRow {
+        id: Uuid::new_v4().to_string(),
+        artist_id: artist_id.clone(),
+        url: url.clone(),
+      });
+    }
+  }
+
+  for chunk in variation_rows.chunks(INSERT_CHUNK_SIZE) {
+    diesel::insert_into(artist_variations::table).values(chunk).execute(conn)?;
+  }
+  for chunk in site_rows.chunks(INSERT_CHUNK_SIZE) {
+    diesel::insert_into(artist_sites::table).values(chunk).execute(conn)?;
+  }
+
+  Ok(())
+}
+
+/// Loads variations and sites grouped by artist id. `None` loads every artist.
+fn load_artist_children(
+  conn: &mut SqliteConnection,
+  only_artists: Option<&[String]>,
+) -> QueryResult<HashMap<String, ArtistChildren>> {
+  use crate::schema::{artist_sites, artist_variations};
+
+  let mut variations_query = artist_variations::table.into_boxed();
+  let mut sites_query = artist_sites::table.into_boxed();
+  if let Some(ids) = only_artists {
+    variations_query = variations_query.filter(artist_variations::artist_id.eq_any(ids));
+    sites_query = sites_query.filter(artist_sites::artist_id.eq_any(ids));
+  }
+
+  let variation_rows = variations_query.load::<ArtistVariationRow>(conn)?;
+  let site_rows = sites_query.load::<ArtistSiteRow>(conn)?;
+
+  let mut children: HashMap<String, ArtistChildren> = HashMap::new();
+  for row in variation_rows {
+    children.entry(row.artist_id).or_default().variations.push(row.variation);
+  }
+  for row in site_rows {
+    children.entry(row.artist_id).or_default().sites.push(row.url);
+  }
+
+  Ok(children)
+}
+
+/// Stored artists among `ids`, child rows included, keyed by id.
+fn load_artists_by_id(conn: &mut SqliteConnection, ids: &[String]) -> QueryResult<HashMap<String, Artist>> {
+  use crate::schema::artists;
+
+  let rows = artists::table.filter(artists::id.eq_any(ids)).load::<ArtistRow>(conn)?;
+  let mut children = load_artist_children(conn, Some(ids))?;
+  Ok(
+    rows
+      .into_iter()
+      .map(|row| {
+        let artist_children = children.remove(&row.id).unwrap_or_default();
+        (row.id.clone(), row_to_artist(row, artist_children))
+      })
+      .collect(),
+  )
+}
+
+/// Bumps `updated_at` of the artists among `ids` that a save changed, `before` being what
+/// [`load_artists_by_id`] returned ahead of it. Same transaction rule as [`replace_artist_children`].
+fn touch_changed_artists(
+  conn: &mut SqliteConnection,
+  ids: &[String],
+  before: &HashMap<String, Artist>,
+) -> QueryResult<()> {
+  use crate::schema::artists;
+
+  let changed = changed_ids(before, &load_artists_by_id(conn, ids)?);
+  if !changed.is_empty() {
+    diesel::update(artists::table.filter(artists::id.eq_any(&changed)))
+      .set(artists::updated_at.eq(sql::<Text>("CURRENT_TIMESTAMP")))
+      .execute(conn)?;
+  }
+  Ok(())
+}
+
+// --- Song child tables ---
+
+/// Lyrics and comments attached to a song (`song_lyrics` / `song_comments`).
+#[derive(Debug, Default)]
+struct SongTexts {
+  lyrics: Option<String>,
+  comments: Vec<String>,
+}
+
+/// Rows for `release_track_artists`, in input order. A repeated `(artist, role)` pair keeps
+/// only its first entry, since the table makes it unique.
+fn credits_to_rows(
+  track_id: &str,
+  credits: &[ReleaseTrackArtistCredit],
+) -> Result<Vec<NewReleaseTrackArtistRow>, CoreError> {
+  let mut seen = HashSet::new();
+  credits
+    .iter()
+    .filter(|c| seen.insert((c.artist_id, c.role)))
+    .map(|c| {
+      let position = c
+        .position
+        .map(|n| i32::try_from(n).map_err(|_| CoreError::InvalidInput(format!("credit position {n} is too large"))))
+        .transpose()?;
+      Ok(NewReleaseTrackArtistRow {
+        id: Uuid::new_v4().to_string(),
+        release_track_id: track_id.to_string(),
+        artist_id: c.artist_id.to_string(),
+        role: c.role.to_string(),
+        position,
+      })
+    })
+    .collect()
+}
+
+/// Rewrites the credit rows of the track `track_id` (delete-then-insert).
+/// Must run inside the caller's transaction, same as [`replace_artist_children`].
+fn replace_track_credits(
+  conn: &mut SqliteConnection,
+  track_id: &str,
+  rows: &[NewReleaseTrackArtistRow],
+) -> QueryResult<()> {
+  use crate::schema::release_track_artists;
+
+  diesel::delete(release_track_artists::table.filter(release_track_artists::release_track_id.eq(track_id)))
+    .execute(conn)?;
+  for chunk in rows.chunks(INSERT_CHUNK_SIZE) {
+    diesel::insert_into(release_track_artists::table).values(chunk).execute(conn)?;
+  }
+  Ok(())
+}
+
+/// Rewrites the lyrics/comment rows of `song` (delete-then-insert).
+/// Must run inside the caller's transaction, same as [`replace_artist_children`].
+fn replace_song_texts(conn: &mut SqliteConnection, song: &Song) -> QueryResult<()> {
+  use crate::schema::{song_comments, song_lyrics};
+
+  let song_id = song.id.to_string();
+
+  diesel::delete(song_lyrics::table.filter(song_lyrics::song_id.eq(&song_id))).execute(conn)?;
+  diesel::delete(song_comments::table.filter(song_comments::song_id.eq(&song_id))).execute(conn)?;
+
+  if let Some(lyrics) = &song.lyrics {
+    diesel::insert_into(song_lyrics::table)
+      .values(NewSongLyricsRow { song_id: song_id.clone(), lyrics: lyrics.clone() })
+      .execute(conn)?;
+  }
+
+  let comment_rows: Vec<NewSongCommentRow> = song
+    .comments
+    .iter()
+    .map(|c| NewSongCommentRow { id: Uuid::new_v4().to_string(), song_id: song_id.clone(), comment: c.clone() })
+    .collect();
+  for chunk in comment_rows.chunks(INSERT_CHUNK_SIZE) {
+    diesel::insert_into(song_comments::table).values(chunk).execute(conn)?;
+  }
+
+  Ok(())
+}
+
+/// Loads lyrics and comments keyed by song id. `only_songs` restricts the query to those songs.
+fn load_song_texts(
+  conn: &mut SqliteConnection,
+  only_songs: Option<&[String]>,
+) -> QueryResult<HashMap<String, SongTexts>> {
+  use crate::schema::{song_comments, song_lyrics};
+
+  let mut lyrics_query = song_lyrics::table.into_boxed();
+  let mut comments_query = song_comments::table.order(song_comments::created_at.asc()).into_boxed();
+  if let Some(ids) = only_songs {
+    lyrics_query = lyrics_query.filter(song_lyrics::song_id.eq_any(ids));
+    comments_query = comments_query.filter(song_comments::song_id.eq_any(ids));
+  }
+
+  let lyrics_rows = lyrics_query.load::<SongLyricsRow>(conn)?;
+  let comment_rows = comments_query.load::<SongCommentRow>(conn)?;
+
+  let mut texts: HashMap<String, SongTexts> = HashMap::new();
+  for row in lyrics_rows {
+    texts.entry(row.song_id).or_default().lyrics = Some(row.lyrics);
+  }
+  for row in comment_rows {
+    texts.entry(row.song_id).or_default().comments.push(row.comment);
+  }
+
+  Ok(texts)
+}
+
+/// Stored songs among `ids`, lyrics and comments included, keyed by id.
+fn load_songs_by_id(conn: &mut SqliteConnection, ids: &[String]) -> QueryResult<HashMap<String, Song>> {
+  use crate::schema::songs;
+
+  let rows = songs::table.filter(songs::id.eq_any(ids)).load::<SongRow>(conn)?;
+  let mut texts = load_song_texts(conn, Some(ids))?;
+  Ok(
+    rows
+      .into_iter()
+      .map(|row| {
+        let song_texts = texts.remove(&row.id).unwrap_or_default();
+        (row.id.clone(), row_to_song(row, song_texts))
+      })
+      .collect(),
+  )
+}
+
+/// Like [`touch_changed_artists`], for songs.
+fn touch_changed_songs(conn: &mut SqliteConnection, ids: &[String], before: &HashMap<String, Song>) -> QueryResult<()> {
+  use crate::schema::songs;
+
+  let changed = changed_ids(before, &load_songs_by_id(conn, ids)?);
+  if !changed.is_empty() {
+    diesel::update(songs::table.filter(songs::id.eq_any(&changed)))
+      .set(songs::updated_at.eq(sql::<Text>("CURRENT_TIMESTAMP")))
+      .execute(conn)?;
+  }
+  Ok(())
+}
+
+// --- Release child tables ---
+
+/// Types, genres, styles, main artists and artworks attached to a release, as stored in
+/// `release_types` / `release_genres` / `release_styles` / `release_main_artists` / `artworks`.
+#[derive(Debug, Default)]
+struct ReleaseTags {
+  types: Vec<ReleaseType>,
+  main_artist_ids: Vec<ArtistId>,
+  genres: Vec<Genre>,
+  styles: Vec<Style>,
+  artworks: Vec<Artwork>,
+}
+
+/// Rewrites the type/genre/style rows of `release` (delete-then-insert).
+/// Must run inside the caller's transaction so a release never ends up half-tagged.
+fn replace_release_tags(conn: &mut SqliteConnection, release: &Release) -> QueryResult<()> {
+  use crate::schema::{release_genres, release_styles, release_types};
+
+  let release_id = release.id.to_string();
+
+  diesel::delete(release_types::table.filter(release_types::release_id.eq(&release_id))).execute(conn)?;
+  diesel::delete(release_genres::table.filter(release_genres::release_id.eq(&release_id))).execute(conn)?;
+  diesel::delete(release_styles::table.filter(release_styles::release_id.eq(&release_id))).execute(conn)?;
+
+  // `(release_id, kind)` is unique, so a Custom spelled like a standard type keeps only the first entry.
+  let mut seen_kinds = HashSet::new();
+  let type_rows: Vec<NewReleaseTypeRow> = release
+    .release_type
+    .iter()
+    .filter(|t| seen_kinds.insert(t.to_string()))
+    .map(|t| NewReleaseTypeRow {
+      id: Uuid::new_v4().to_string(),
+      release_id: release_id.clone(),
+      kind: t.to_string(),
+      custom: match t {
+        ReleaseType::Custom(value) => Some(value.clone()),
+        _ => None,
+      },
+    })
+    .collect();
+
+  let genre_rows: Vec<NewReleaseGenreRow> = release
+    .genres
+    .iter()
+    .map(|g| NewReleaseGenreRow {
+      id: Uuid::new_v4().to_string(),
+      release_id: release_id.clone(),
+      genre: g.to_string(),
+    })
+    .collect();
+
+  let style_rows: Vec<NewReleaseStyleRow> = release
+    .styles
+    .iter()
+    .map(|s| NewReleaseStyleRow {
+      id: Uuid::new_v4().to_string(),
+      release_id: release_id.clone(),
+      style: s.to_string(),
+    })
+    .collect();
+
+  if !type_rows.is_empty() {
+    diesel::insert_into(release_types::table).values(&type_rows).execute(conn)?;
+  }
+  if !genre_rows.is_empty() {
+    diesel::insert_into(release_genres::table).values(&genre_rows).execute(conn)?;
+  }
+  if !style_rows.is_empty() {
+    diesel::insert_into(release_styles::table).values(&style_rows).execute(conn)?;
+  }
+
+  Ok(())
+}
+
+/// Rewrites the `release_main_artists` rows of `release` (delete-then-insert), keeping the
+/// order of `main_artist_ids`. Every artist must already be stored: the table references
+/// `artists`. Same transaction rule as [`replace_release_tags`].
+fn replace_release_main_artists(conn: &mut SqliteConnection, release: &Release) -> QueryResult<()> {
+  use crate::schema::release_main_artists;
+
+  let release_id = release.id.to_string();
+  diesel::delete(release_main_artists::table.filter(release_main_artists::release_id.eq(&release_id))).execute(conn)?;
+
+  // `(release_id, artist_id)` is unique: an artist listed twice keeps only its first entry.
+  let mut seen = HashSet::new();
+  let rows: Vec<NewReleaseMainArtistRow> = release
+    .main_artist_ids
+    .iter()
+    .filter(|id| seen.insert(**id))
+    .map(|id| NewReleaseMainArtistRow {
+      id: Uuid::new_v4().to_string(),
+      release_id: release_id.clone(),
+      artist_id: id.to_string(),
+    })
+    .collect();
+
+  if !rows.is_empty() {
+    diesel::insert_into(release_main_artists::table).values(&rows).execute(conn)?;
+  }
+  Ok(())
+}
+
+/// Rewrites the artwork rows of `release` (delete-then-insert).
+///
+/// A thumbnail already recorded for the same hash is kept, so re-saving a release doesn't
+/// send its covers back through the thumbnail generator.
+fn replace_release_artworks(conn: &mut SqliteConnection, release: &Release) -> QueryResult<()> {
+  use crate::schema::artworks;
+
+  let release_id = release.id.to_string();
+  let hashes: Vec<&str> = release.artworks.iter().map(|a| a.hash.as_str()).collect();
+  let known_thumbnails: HashMap<String, String> = artworks::table
+    .filter(artworks::hash.eq_any(&hashes))
+    .filter(artworks::thumbnail_path.is_not_null())
+    .select((artworks::hash.assume_not_null(), artworks::thumbnail_path.assume_not_null()))
+    .load::<(String, String)>(conn)?
+    .into_iter()
+    .collect();
+
+  diesel::delete(artworks::table.filter(artworks::release_id.eq(&release_id))).execute(conn)?;
+
+  // `(release_id, path)` is unique: the same image listed twice keeps only its first entry.
+  let mut seen_paths = HashSet::new();
+  let rows: Vec<NewArtworkRow> = release
+    .artworks
+    .iter()
+    .filter(|a| seen_paths.insert(&a.path))
+    .map(|a| NewArtworkRow {
+      id: Uuid::new_v4().to_string(),
+      release_id: release_id.clone(),
+      path: a.path.to_string_lossy().into_owned(),
+      mime_type: a.mime_type.clone(),
+      description: a.description.clone(),
+      hash: Some(a.hash.clone()),
+      credits: a.credits.clone(),
+      thumbnail_path: a
+        .thumbnail_path
+        .as_ref()
+        .map(|p| p.to_string_lossy().into_owned())
+        .or_else(|| known_thumbnails.get(&a.hash).cloned()),
+    })
+    .collect();
+
+  if !rows.is_empty() {
+    diesel::insert_into(artworks::table).values(&rows).execute(conn)?;
+  }
+  Ok(())
+}
+
+fn row_to_artwork(row: ArtworkRow) -> Artwork {
+  Artwork {
+    path: PathBuf::from(row.path),
+    mime_type: row.mime_type,
+    description: row.description,
+    hash: row.hash.unwrap_or_default(),
+    credits: row.credits,
+    thumbnail_path: row.thumbnail_path.map(PathBuf::from),
+  }
+}
+
+/// Loads types, genres, styles, main artists and artworks grouped by release id. `None` loads
+/// every release.
+///
+/// Genre strings that no longer parse are skipped rather than failing the whole read.
+/// Custom types come back from their literal column so they never get re-normalized.
+fn load_release_tags(
+  conn: &mut SqliteConnection,
+  only_releases: Option<&[String]>,
+) -> QueryResult<HashMap<String, ReleaseTags>> {
+  use crate::schema::{artworks, release_genres, release_main_artists, release_styles, release_types};
+
+  // Insertion order: the first type listed is the release's primary one.
+  let mut types_query = release_types::table.order(sql::<BigInt>("release_types.rowid")).into_boxed();
+  let mut main_artists_query =
+    release_main_artists::table.order(sql::<BigInt>("release_main_artists.rowid")).into_boxed();
+  let mut genres_query = release_genres::table.into_boxed();
+  let mut styles_query = release_styles::table.into_boxed();
+  let mut artworks_query = artworks::table.order(artworks::path).into_boxed();
+  if let Some(ids) = only_releases {
+    types_query = types_query.filter(release_types::release_id.eq_any(ids));
+    main_artists_query = main_artists_query.filter(release_main_artists::release_id.eq_any(ids));
+    genres_query = genres_query.filter(release_genres::release_id.eq_any(ids));
+    styles_query = styles_query.filter(release_styles::release_id.eq_any(ids));
+    artworks_query = artworks_query.filter(artworks::release_id.eq_any(ids));
+  }
+
+  let type_rows = types_query.load::<ReleaseTypeRow>(conn)?;
+  let main_artist_rows = main_artists_query.load::<ReleaseMainArtistRow>(conn)?;
+  let genre_rows = genres_query.load::<ReleaseGenreRow>(conn)?;
+  let style_rows = styles_query.load::<ReleaseStyleRow>(conn)?;
+  let artwork_rows = artworks_query.load::<ArtworkRow>(conn)?;
+
+  let mut tags: HashMap<String, ReleaseTags> = HashMap::new();
+  for row in type_rows {
+    let release_type = match row.custom {
+      Some(value) => ReleaseType::Custom(value),
+      None => {
+        let Ok(parsed) = ReleaseType::from_str(&row.kind);
+        parsed
+      }
+    };
+    tags.entry(row.release_id).or_default().types.push(release_type);
+  }
+  for row in main_artist_rows {
+    let artist_id = ArtistId::from_uuid(Uuid::parse_str(&row.artist_id).expect("Invalid UUID in database"));
+    tags.entry(row.release_id).or_default().main_artist_ids.push(artist_id);
+  }
+  for row in genre_rows {
+    if let Ok(genre) = Genre::from_str(&row.genre) {
+      tags.entry(row.release_id).or_default().genres.push(genre);
+    }
+  }
+  for row in style_rows {
+    let Ok(style) = Style::from_str(&row.style);
+    tags.entry(row.release_id).or_default().styles.push(style);
+  }
+  for row in artwork_rows {
+    let release_id = row.release_id.clone();
+    tags.entry(release_id).or_default().artworks.push(row_to_artwork(row));
+  }
+
+  Ok(tags)
+}
+
+/// Stored releases among `ids`, types, genres, styles, main artists and artworks included,
+/// keyed by id.
+fn load_releases_by_id(conn: &mut SqliteConnection, ids: &[String]) -> QueryResult<HashMap<String, Release>> {
+  use crate::schema::releases;
+
+  let rows = releases::table.filter(releases::id.eq_any(ids)).load::<ReleaseRow>(conn)?;
+  let mut tags = load_release_tags(conn, Some(ids))?;
+  Ok(
+    rows
+      .into_iter()
+      .map(|row| {
+        let release_tags = tags.remove(&row.id).unwrap_or_default();
+        (row.id.clone(), row_to_release(row, release_tags))
+      })
+      .collect(),
+  )
+}
+
+/// Like [`touch_changed_artists`], for releases.
+fn touch_changed_releases(
+  conn: &mut SqliteConnection,
+  ids: &[String],
+  before: &HashMap<String, Release>,
+) -> QueryResult<()> {
+  use crate::schema::releases;
+
+  let changed = changed_ids(before, &load_releases_by_id(conn, ids)?);
+  if !changed.is_empty() {
+    diesel::update(releases::table.filter(releases::id.eq_any(&changed)))
+      .set(releases::updated_at.eq(sql::<Text>("CURRENT_TIMESTAMP")))
+      .execute(conn)?;
+  }
+  Ok(())
+}
+
+// --- DTO Mapping Helpers ---
+// Decouples Domain Entities (business logic) from Diesel Models (DB schema).
+
+fn artist_to_new_row(artist: &Artist) -> NewArtistRow {
+  NewArtistRow {
+    id: artist.id.to_string(),
+    name: artist.name.clone(),
+    bio: artist.bio.clone(),
+    name_normalized: normalize_artist_name(&artist.name),
+  }
+}
+
+fn song_to_new_row(song: &Song) -> NewSongRow {
+  NewSongRow {
+    id: song.id.to_string(),
+    title: song.title.clone(),
+    acoustid: song.acoustid.clone(),
+    isrc: song.isrc.clone(),
+    mbid: song.mbid.clone(),
+  }
+}
+
+fn release_to_new_row(release: &Release) -> NewReleaseRow {
+  NewReleaseRow {
+    id: release.id.to_string(),
+    title: release.title.clone(),
+    release_date: release.release_date.clone(),
+    mbid: release.mbid.clone(),
+  }
+}
+
+fn track_to_new_row(track: &ReleaseTrack) -> NewReleaseTrackRow {
+  NewReleaseTrackRow {
+    id: track.id.to_string(),
+    release_id: track.release_id.to_string(),
+    song_id: track.song_id.to_string(),
+    disc_number: track.disc_number as i32,
+    track_number: track.track_number as i32,
+    title_override: track.title_override.clone(),
+  }
+}
+
+fn track_to_file_row(track: &ReleaseTrack, paths: &PathResolver) -> NewLibraryFileRow {
+  let audio = &track.audio_details;
+  let file = &track.file_details;
+  let analysis = analysis_to_changeset(audio.analysis.as_ref());
+  let stored = paths.to_stored(&file.path);
+
+  NewLibraryFileRow {
+    id: Uuid::new_v4().to_string(),
+    release_track_id: track.id.to_string(),
+    path: stored.path,
+    size_bytes: file.size as i64,
+    modified_unix: file.modified as i64,
+    duration_ms: audio.duration.as_millis() as i64,
+    bitrate_kbps: audio.bitrate_kbps.map(|v| v as i32),
+    sample_rate_hz: audio.sample_rate_hz.map(|v| v as i32),
+    channels: audio.channels.map(i32::from),
+    fingerprint: audio.fingerprint.clone(),
+    bpm: analysis.bpm,
+    quality_score: analysis.quality_score,
+    quality_assessment: analysis.quality_assessment,
+    quality_level: analysis.quality_level,
+    content_hash: file.content_hash.clone(),
+    features: analysis.features,
+    codec: audio.codec.clone(),
+    container: audio.container.clone(),
+    is_lossless: audio.is_lossless,
+    quality_cutoff_hz: analysis.quality_cutoff_hz,
+    quality_details: analysis.quality_details,
+    replaygain_track_db: audio.replaygain_track_db,
+    replaygain_album_db: audio.replaygain_album_db,
+    replaygain_track_peak: audio.replaygain_track_peak,
+    replaygain_album_peak: audio.replaygain_album_peak,
+    root_id: stored.root_id,
+    quality_json: analysis.quality_json,
+  }
+}
+
+fn analysis_to_changeset(analysis: Option<&AudioAnalysis>) -> LibraryFileAnalysisChangeset {
+  let quality = analysis.and_then(|a| a.quality.as_ref());
+
+  LibraryFileAnalysisChangeset {
+    bpm: analysis.and_then(|a| a.bpm),
+    quality_score: quality.map(|q| q.quality_score),
+    quality_assessment: quality.map(|q| q.assessment.clone()),
+    features: analysis.and_then(|a| a.features.as_deref()).map(encode_features),
+    quality_level: quality.map(|q| q.report.level.to_string()),
+    quality_cutoff_hz: quality.and_then(|q| q.report.cutoff_freq_hz),
+    quality_details: quality.and_then(|q| q.report.details.clone()),
+    quality_json: quality.and_then(|q| serde_json::to_string(q).ok()),
+  }
+}
+
+// Inversion mappings (DB -> Domain)
+// Assumes DB integrity regarding UUID formatting.
+// NOTE: `expect` usage here relies on the invariant that IDs stored are valid UUIDs.
+// Database corruption could cause panics here.
+
+fn row_to_artist(row: ArtistRow, children: ArtistChildren) -> Artist {
+  Artist {
+    id: ArtistId::from_uuid(Uuid::parse_str(&row.id).expect("Invalid UUID in database")),
+    name: row.name,
+    variations: children.variations,
+    bio: row.bio,
+    sites: children.sites,
+  }
+}
+
+/// Loads the lyrics and comments of a single song row and assembles the domain `Song`.
+fn song_with_texts(conn: &mut SqliteConnection, row: SongRow) -> Result<Song, CoreError> {
+  let mut texts =
+    load_song_texts(conn, Some(slice::from_ref(&row.id))).map_err(|e| CoreError::Repository(e.to_string()))?;
+  let song_texts = texts.remove(&row.id).unwrap_or_default();
+  Ok(row_to_song(row, song_texts))
+}
+
+fn row_to_song(row: SongRow, texts: SongTexts) -> Song {
+  Song {
+    id: SongId::from_uuid(Uuid::parse_str(&row.id).expect("Invalid UUID in database")),
+    title: row.title,
+    acoustid: row.acoustid,
+    lyrics: texts.lyrics,
+    comments: texts.comments,
+    isrc: row.isrc,
+    mbid: row.mbid,
+  }
+}
+
+fn row_to_release(row: ReleaseRow, tags: ReleaseTags) -> Release {
   Release {
     id: ReleaseId::from_uuid(Uuid::parse_str(&row.id).expect("Invalid UUID in database")),
     title: row.title,
-    release_type: vec![],
-    main_artist_ids: vec![],
+    release_type: tags.types,
+    main_artist_ids: tags.main_artist_ids,
     release_tracks: vec![],
     release_date: row.release_date,
-    artworks: vec![],
-    genres: vec![],
-    styles: vec![],
+    artworks: tags.artworks,
+    genres: tags.genres,
+    styles: tags.styles,
+    mbid: row.mbid,
+  }
+}
+
+/// Rebuilds a track from its `release_tracks` / `library_files` rows.
+///
+/// `analysis.quality` is rebuilt from `quality_json`; the flat quality columns only serve
+/// queries. BPM and features are restored too.
+/// Artist credits are left empty; they are loaded on demand with `list_track_credits`.
+///
+/// Fails only if the stored `features` blob is malformed (see [`decode_features`]).
+fn row_to_release_track(
+  track: ReleaseTrackRow,
+  file: LibraryFileRow,
+  paths: &PathResolver,
+) -> Result<ReleaseTrack, CoreError> {
+  let features = file.features.as_deref().map(decode_features).transpose()?;
+  // Files analysed before `quality_json` existed only have the flat columns: no quality.
+  let quality = file.quality_json.as_deref().and_then(|json| serde_json::from_str(json).ok());
+
+  Ok(ReleaseTrack {
+    id: ReleaseTrackId::from_uuid(Uuid::parse_str(&track.id).expect("Invalid UUID in database")),
+    song_id: SongId::from_uuid(Uuid::parse_str(&track.song_id).expect("Invalid UUID in database")),
+    release_id: ReleaseId::from_uuid(Uuid::parse_str(&track.release_id).expect("Invalid UUID in database")),
+    track_number: track.track_number as u32,
+    disc_number: track.disc_number as u32,
+    title_override: track.title_override,
+    artist_credits: vec![],
+    audio_details: AudioDetails {
+      duration: Duration::from_millis(file.duration_ms as u64),
+      bitrate_kbps: file.bitrate_kbps.map(|v| v as u32),
+      sample_rate_hz: file.sample_rate_hz.map(|v| v as u32),
+      channels: file.channels.map(|v| v as u8),
+      analysis: Some(AudioAnalysis { quality, features, bpm: file.bpm }),
+      fingerprint: file.fingerprint,
+      codec: file.codec,
+      container: file.container,
+      is_lossless: file.is_lossless,
+      replaygain_track_db: file.replaygain_track_db,
+      replaygain_album_db: file.replaygain_album_db,
+      replaygain_track_peak: file.replaygain_track_peak,
+      replaygain_album_peak: file.replaygain_album_peak,
+    },
+    file_details: FileDetails {
+      path: paths.to_absolute(&file.root_id, &file.path),
+      size: file.size_bytes as u64,
+      modified: file.modified_unix as u64,
+      content_hash: file.content_hash,
+    },
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use gamus_core::domain::release_track::AnalysisOutcome;
+  use tempfile::{TempDir, tempdir};
+
+  fn temp_store() -> (TempDir, LibraryStore) {
+    let dir = tempdir().unwrap();
+    let store = LibraryStore::new(
+      &dir.path().join("gamus.db"),
+      JournalMode::Wal,
+      &PoolConfig::default(),
+      5_000,
+      &PragmaConfig::default(),
+      &RetryConfig::default(),
+    )
+    .unwrap();
+    (dir, store)
+  }
+
+  #[test]
+  fn every_pooled_connection_gets_the_configured_pragmas() {
+    use crate::config::Synchronous;
+
+    #[derive(QueryableByName)]
+    struct Pragmas {
+      #[diesel(sql_type = BigInt)]
+      foreign_keys: i64,
+      #[diesel(sql_type = BigInt)]
+      synchronous: i64,
+      #[diesel(sql_type = BigInt)]
+      cache_size: i64,
+    }
+
+    let dir = tempdir().unwrap();
+    let pragmas = PragmaConfig { foreign_keys: true, synchronous: Some(Synchronous::Normal), cache_size: Some(-4_000) };
+    let pool = PoolConfig { max_size: 3, min_idle: Some(3), ..PoolConfig::default() };
+    let store = LibraryStore::new(
+      &dir.path().join("gamus.db"),
+      JournalMode::Wal,
+      &pool,
+      1_000,
+      &pragmas,
+      &RetryConfig::default(),
+    )
+    .unwrap();
+
+    // Hold every connection at once, so most of them are not the one `new` set up.
+    let mut conns: Vec<_> = (0..3).map(|_| store.get_conn().unwrap()).collect();
+    for conn in &mut conns {
+      let row: Pragmas = diesel::sql_query(
+        "SELECT (SELECT foreign_keys FROM pragma_foreign_keys) AS foreign_keys, \
+         (SELECT synchronous FROM pragma_synchronous) AS synchronous, \
+         (SELECT cache_size FROM pragma_cache_size) AS cache_size",
+      )
+      .get_result(&mut **conn)
+      .unwrap();
+      // `synchronous = NORMAL` reads back as 1.
+      assert_eq!((row.foreign_keys, row.synchronous, row.cache_size), (1, 1, -4_000));
+    }
+  }
+
+  #[test]
+  fn artist_variations_and_sites_round_trip() {
+    let (_dir, store) = temp_store();
+
+    let mut artist = Artist {
+      id: ArtistId::new(),
+      name: "Hatsune Miku".into(),
+      variations: vec!["初音ミク".into(), "Miku Hatsune".into()],
+      bio: None,
+      sites: vec!["https://piapro.jp".into(), "https://ec.crypton.co.jp".into()],
+    };
+    store.save_artist(&artist).unwrap();
+
+    let mut loaded = store.find_artist(artist.id).unwrap().unwrap();
+    loaded.variations.sort();
+    loaded.sites.sort();
+    let mut expected = artist.clone();
+    expected.variations.sort();
+    expected.sites.sort();
+    assert_eq!(loaded, expected);
+
+    // Removed children must disappear on update.
+    artist.variations.truncate(1);
+    artist.sites.clear();
+    store.save_artist(&artist).unwrap();
+
+    let listed = store.list_artists().unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].variations, artist.variations);
+    assert!(listed[0].sites.is_empty());
+  }
+
+  #[test]
+  fn writes_retry_while_another_connection_holds_the_lock() {
+    let dir = tempdir().unwrap();
+    let db_path = dir.path().join("gamus.db");
+    // busy_timeout = 0: every lock conflict surfaces as SQLITE_BUSY right away, so only the retry can help.
+    let retry = RetryConfig { max_attempts: 10, initial_backoff_ms: 20, max_backoff_ms: 200 };
+    let store =
+      LibraryStore::new(&db_path, JournalMode::Wal, &PoolConfig::default(), 0, &PragmaConfig::default(), &retry)
+        .unwrap();
+    let artist =
+      Artist { id: ArtistId::new(), name: "Boards of Canada".into(), variations: vec![], bio: None, sites: vec![] };
+
+    let mut holder = SqliteConnection::establish(db_path.to_str().unwrap()).unwrap();
+    diesel::sql_query("BEGIN IMMEDIATE").execute(&mut holder).unwrap();
+
+    let no_retry = store.clone().with_retry_config(RetryConfig { max_attempts: 1, ..RetryConfig::default() });
+    assert!(no_retry.save_artist(&artist).is_err(), "write should fail fast while the lock is held");
+
+    let release = std::thread::spawn(move || {
+      std::thread::sleep(Duration::from_millis(150));
+      diesel::sql_query("COMMIT").execute(&mut holder).unwrap();
+    });
+
+    store.save_artist(&artist).unwrap();
+    release.join().unwrap();
+
+    assert_eq!(store.find_artist(artist.id).unwrap().unwrap().name, artist.name);
+  }
+
+  #[test]
+  fn close_leaves_an_empty_wal_and_the_data_in_the_main_file() {
+    let (dir, store) = temp_store();
+    let artist = Artist { id: ArtistId::new(), name: "Autechre".into(), variations: vec![], bio: None, sites: vec![] };
+    store.save_artist(&artist).unwrap();
+
+    store.close().unwrap();
+
+    let wal = dir.path().join("gamus.db-wal");
+    assert!(!wal.exists() || std::fs::metadata(&wal).unwrap().len() == 0);
+    let reopened = LibraryStore::new(
+      &dir.path().join("gamus.db"),
+      JournalMode::Wal,
+      &PoolConfig::default(),
+      5_000,
+      &PragmaConfig::default(),
+      &RetryConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(reopened.find_artist(artist.id).unwrap().unwrap().name, artist.name);
+  }
+
+  fn track_at(path: &str) -> ReleaseTrack {
+    ReleaseTrack {
+      id: ReleaseTrackId::new(),
+      song_id: SongId::new(),
+      release_id: ReleaseId::new(),
+      track_number: 1,
+      disc_number: 1,
+      title_override: None,
+      artist_credits: vec![],
+      audio_details: AudioDetails {
+        duration: Duration::from_millis(215_000),
+        bitrate_kbps: Some(320),
+        sample_rate_hz: Some(44_100),
+        channels: Some(2),
+        analysis: Some(AudioAnalysis { quality: None, features: Some(vec![0.25, -1.5]), bpm: Some(128.0) }),
+        fingerprint: Some("AQAA".into()),
+        codec: Some("flac".into()),
+        container: Some("raw FLAC".into()),
+        is_lossless: Some(true),
+        replaygain_track_db: Some(-7.25),
+        replaygain_album_db: Some(-6.5),
+        replaygain_track_peak: Some(0.988),
+        replaygain_album_peak: Some(1.0),
+      },
+      file_details: FileDetails {
+        path: path.into(),
+        size: 8_000_000,
+        modified: 1_700_000_000,
+        content_hash: Some("xxh3p64:0123".into()),
+      },
+    }
+  }
+
+  /// Saves placeholder song and release rows for `track`, then the track: foreign keys are enforced.
+  fn save_with_parents(store: &LibraryStore, track: &ReleaseTrack) {
+    store
+      .save_song(&Song {
+        id: track.song_id,
+        acoustid: None,
+        title: "Song".into(),
+        lyrics: None,
+        comments: vec![],
+        isrc: None,
+        mbid: None,
+      })
+      .unwrap();
+    store
+      .save_release(&Release {
+        id: track.release_id,
+        title: "Release".into(),
+        release_type: vec![],
+        main_artist_ids: vec![],
+        release_tracks: vec![],
+        release_date: None,
+        artworks: vec![],
+        genres: vec![],
+        styles: vec![],
+        mbid: None,
+      })
+      .unwrap();
+    store.save_track(track).unwrap();
+  }
+
+  #[test]
+  fn deleting_a_release_cascades_to_its_tracks_and_files() {
+    use crate::schema::{library_files, release_tracks, releases};
+
+    let store = LibraryStore::in_memory().unwrap();
+    let track = track_at("/music/gone.flac");
+    save_with_parents(&store, &track);
+
+    {
+      let mut conn = store.get_conn().unwrap();
+      diesel::delete(releases::table.find(track.release_id.to_string())).execute(&mut conn).unwrap();
+      let tracks: i64 = release_tracks::table.count().get_result(&mut conn).unwrap();
+      let files: i64 = library_files::table.count().get_result(&mut conn).unwrap();
+      assert_eq!((tracks, files), (0, 0));
+    }
+
+    // A track pointing at a release that does not exist is rejected.
+    assert!(store.save_track(&track_at("/music/dangling.flac")).is_err());
+  }
+
+  #[test]
+  fn recent_tracks_are_newest_first_and_round_trip() {
+    let store = LibraryStore::in_memory().unwrap();
+
+    let first = track_at("/music/a.flac");
+    let second = track_at("/music/b.flac");
+    save_with_parents(&store, &first);
+    save_with_parents(&store, &second);
+    // Re-saving an existing file must not move it to the top.
+    save_with_parents(&store, &first);
+
+    let recent = store.list_recent_tracks(10).unwrap();
+    assert_eq!(recent, vec![second.clone(), first]);
+    assert_eq!(store.list_recent_tracks(1).unwrap(), vec![second]);
+  }
+
+  #[test]
+  fn tracks_are_listed_by_codec_case_insensitively() {
+    let store = LibraryStore::in_memory().unwrap();
+
+    let flac = track_at("/music/a.flac");
+    let mut mp3 = track_at("/music/b.mp3");
+    mp3.audio_details.codec = Some("mp3".into());
+    mp3.audio_details.container = Some("MP2/3 (MPEG audio layer 2/3)".into());
+    mp3.audio_details.is_lossless = Some(false);
+    save_with_parents(&store, &flac);
+    save_with_parents(&store, &mp3);
+
+    assert_eq!(store.list_tracks_by_codec(" FLAC ").unwrap(), vec![flac]);
+    assert_eq!(store.list_tracks_by_codec("mp3").unwrap(), vec![mp3]);
+    assert!(store.list_tracks_by_codec("opus").unwrap().is_empty());
+  }
+
+  #[test]
+  fn analysis_updates_clear_pending_tracks() {
+    use gamus_core::domain::release_track::{AudioQuality, AudioQualityReport};
+
+    let store = LibraryStore::in_memory().unwrap();
+    let (done, pending) = (track_at("/music/a.flac"), track_at("/music/b.flac"));
+    save_with_parents(&store, &done);
+    save_with_parents(&store, &pending);
+    assert_eq!(store.list_tracks_pending_analysis().unwrap().len(), 2);
+
+    let analysis = AudioAnalysis {
+      quality: Some(AudioQuality {
+        outcome: AnalysisOutcome::Inconclusive("too short".into()),
+        quality_score: 0.0,
+        assessment: "inconclusive".into(),
+        report: AudioQualityReport {
+          level: QualityLevel::Medium,
+          score: 0.0,
+          label: "Unknown".into(),
+          summary: "too short".into(),
+          details: None,
+          cutoff_freq_hz: None,
+          max_freq_hz: None,
+          stereo_correlation: None,
+        },
+      }),
+      features: None,
+      bpm: Some(90.0),
+    };
+    store.update_track_analysis(done.id, &analysis).unwrap();
+
+    // Inconclusive still counts as analysed; only `b.flac` is left.
+    assert_eq!(store.list_tracks_pending_analysis().unwrap(), vec![pending]);
+    let updated = store.list_tracks_page(0, 1).unwrap().remove(0);
+    assert_eq!(updated.id, done.id);
+    assert_eq!(updated.audio_details.analysis, Some(analysis));
+  }
+
+  #[test]
+  fn track_credits_round_trip_with_roles_and_order() {
+    let store = LibraryStore::in_memory().unwrap();
+    let track = track_at("/music/collab.flac");
+    save_with_parents(&store, &track);
+
+    let artist = |name: &str| {
+      let artist = Artist { id: ArtistId::new(), name: name.into(), variations: vec![], bio: None, sites: vec![] };
+      store.save_artist(&artist).unwrap();
+      artist.id
+    };
+    let credit =
+      |artist_id, role, position| ReleaseTrackArtistCredit { release_track_id: track.id, artist_id, role, position };
+    let (main, guest, other_guest, producer) = (artist("Main"), artist("Guest"), artist("Other"), artist("Prod"));
+    let credits = vec![
+      credit(main, ArtistRole::Performer, Some(1)),
+      credit(guest, ArtistRole::Featured, Some(2)),
+      credit(other_guest, ArtistRole::Featured, Some(3)),
+      credit(producer, ArtistRole::Producer, None),
+    ];
+
+    // Stored out of order and with a repeat: listing follows `position`, not insertion.
+    let shuffled = [credits[3].clone(), credits[2].clone(), credits[0].clone(), credits[1].clone(), credits[0].clone()];
+    store.save_track_credits(track.id, &shuffled).unwrap();
+    assert_eq!(store.list_track_credits(track.id).unwrap(), credits);
+
+    // Saving the track with no credits keeps them; with credits, replaces them.
+    store.save_track(&track).unwrap();
+    assert_eq!(store.list_track_credits(track.id).unwrap().len(), 4);
+    let solo = ReleaseTrack { artist_credits: vec![credit(main, ArtistRole::Remixer, None)], ..track.clone() };
+    store.save_track(&solo).unwrap();
+    assert_eq!(store.list_track_credits(track.id).unwrap(), solo.artist_credits);
+  }
+
+  #[test]
+  fn a_backup_restores_quality_and_credits_into_an_empty_store() {
+    use gamus_core::domain::release_track::{AudioQuality, AudioQualityReport};
+    use gamus_core::services::{export_library_json, import_library_json};
+
+    let store = LibraryStore::in_memory().unwrap();
+    let artist = Artist { id: ArtistId::new(), name: "Autechre".into(), variations: vec![], bio: None, sites: vec![] };
+    store.save_artist(&artist).unwrap();
+    let mut track = track_at("/music/gantz_graf.flac");
+    track.artist_credits = vec![ReleaseTrackArtistCredit {
+      release_track_id: track.id,
+      artist_id: artist.id,
+      role: ArtistRole::Performer,
+      position: Some(1),
+    }];
+    track.audio_details.analysis = Some(AudioAnalysis {
+      quality: Some(AudioQuality {
+        outcome: AnalysisOutcome::NoCutoffDetected { ref_db: -24.0, max_freq: 22_050.0 },
+        quality_score: 10.0,
+        assessment: "full band".into(),
+        report: AudioQualityReport {
+          level: QualityLevel::Perfect,
+          score: 10.0,
+          label: "Lossless".into(),
+          summary: "No cutoff".into(),
+          details: None,
+          cutoff_freq_hz: None,
+          max_freq_hz: Some(22_050.0),
+          stereo_correlation: Some(0.4),
+        },
+      }),
+      features: None,
+      bpm: Some(140.0),
+    });
+    save_with_parents(&store, &track);
+
+    let mut backup = Vec::new();
+    export_library_json(&store, &mut backup).unwrap();
+    let restored = LibraryStore::in_memory().unwrap();
+    let summary = import_library_json(&restored, backup.as_slice()).unwrap();
+
+    assert_eq!(summary.failed, 0);
+    assert_eq!(restored.list_tracks_page(0, 10).unwrap(), store.list_tracks_page(0, 10).unwrap());
+    assert_eq!(restored.list_track_credits(track.id).unwrap(), track.artist_credits);
+  }
+
+  #[test]
+  fn duplicate_files_need_the_same_size_and_hash() {
+    let store = LibraryStore::in_memory().unwrap();
+    let file = |path: &str, size: u64, hash: Option<&str>| {
+      let mut track = track_at(path);
+      track.file_details.size = size;
+      track.file_details.content_hash = hash.map(Into::into);
+      save_with_parents(&store, &track);
+      track.id
+    };
+    let small = [file("/a/song.mp3", 100, Some("xxh3p64:aa")), file("/b/song.mp3", 100, Some("xxh3p64:aa"))];
+    file("/c/other.mp3", 100, Some("xxh3p64:bb"));
+    file("/c/unhashed.mp3", 100, None);
+    file("/c/unhashed-copy.mp3", 100, None);
+    let large = [file("/a/album.flac", 900, Some("xxh3f:cc")), file("/b/album.flac", 900, Some("xxh3f:cc"))];
+
+    assert_eq!(store.find_duplicate_files().unwrap(), vec![large.to_vec(), small.to_vec()]);
+  }
+
+  #[test]
+  fn artwork_thumbnails_are_shared_by_hash_and_survive_a_resave() {
+    use gamus_core::domain::release::Artwork;
+
+    let store = LibraryStore::in_memory().unwrap();
+    let cover = |path: &str, hash: &str| Artwork {
+      path: path.into(),
+      mime_type: "image/jpeg".into(),
+      description: None,
+      hash: hash.into(),
+      credits: None,
+      thumbnail_path: None,
+    };
+    let release = |artworks: Vec<Artwork>| Release {
+      id: ReleaseId::new(),
+      title: "Release".into(),
+      release_type: vec![],
+      main_artist_ids: vec![],
+      release_tracks: vec![],
+      release_date: None,
+      artworks,
+      genres: vec![],
+      styles: vec![],
+      mbid: None,
+    };
+    let mut first = release(vec![cover("/a/front.jpg", "xxh3p64:aa"), cover("/a/back.jpg", "xxh3p64:bb")]);
+    let second = release(vec![cover("/b/front.jpg", "xxh3p64:aa")]);
+    store.save_release(&first).unwrap();
+    store.save_release(&second).unwrap();
+    assert_eq!(store.release_thumbnail(first.id).unwrap(), None);
+
+    assert_eq!(store.set_artwork_thumbnail("xxh3p64:aa", Path::new("/cache/aa.jpg")).unwrap(), 2);
+    assert_eq!(store.release_thumbnail(second.id).unwrap(), Some(PathBuf::from("/cache/aa.jpg")));
+    assert_eq!(store.list_artworks().unwrap().len(), 3);
+
+    // Back sorts before front, but has no thumbnail yet.
+    assert_eq!(store.release_thumbnail(first.id).unwrap(), Some(PathBuf::from("/cache/aa.jpg")));
+    first.title = "Retitled".into();
+    store.save_release(&first).unwrap();
+    let loaded = store.find_release(first.id).unwrap().unwrap();
+    let paths: Vec<_> = loaded.artworks.iter().map(|a| (a.path.clone(), a.thumbnail_path.clone())).collect();
+    assert_eq!(
+      paths,
+      [(PathBuf::from("/a/back.jpg"), None), (PathBuf::from("/a/front.jpg"), Some(PathBuf::from("/cache/aa.jpg")))]
+    );
+
+    // The saved list is authoritative: an empty one removes the last covers too.
+    first.artworks.clear();
+    store.save_release(&first).unwrap();
+    assert!(store.find_release(first.id).unwrap().unwrap().artworks.is_empty());
+  }
+
+  #[test]
+  fn quality_range_lists_scored_tracks_worst_first() {
+    use gamus_core::domain::release_track::{AudioQuality, AudioQualityReport};
+
+    let store = LibraryStore::in_memory().unwrap();
+    let scored = |path: &str, score: f32| {
+      let mut track = track_at(path);
+      track.audio_details.analysis = Some(AudioAnalysis {
+        quality: Some(AudioQuality {
+          outcome: AnalysisOutcome::NoCutoffDetected { ref_db: -30.0, max_freq: 22_050.0 },
+          quality_score: score,
+          assessment: String::new(),
+          report: AudioQualityReport {
+            level: QualityLevel::from_score(score),
+            score,
+            label: String::new(),
+            summary: String::new(),
+            details: None,
+            cutoff_freq_hz: None,
+            max_freq_hz: None,
+            stereo_correlation: None,
+          },
+        }),
+        features: None,
+        bpm: None,
+      });
+      track
+    };
+    for track in [scored("/music/a.flac", 9.5), scored("/music/b.mp3", 3.0), scored("/music/c.mp3", 5.0)] {
+      save_with_parents(&store, &track);
+    }
+    save_with_parents(&store, &track_at("/music/unanalysed.flac"));
+
+    let paths = |rows: Vec<TrackQualitySummary>| -> Vec<PathBuf> { rows.into_iter().map(|r| r.path).collect() };
+    let worst = store.list_tracks_by_quality(0.0, 6.0, 10, 0).unwrap();
+    assert_eq!((worst[0].quality_score, worst[0].quality_level), (3.0, Some(QualityLevel::from_score(3.0))));
+    assert_eq!((worst[0].title.as_str(), worst[0].release_title.as_str()), ("Song", "Release"));
+    assert_eq!(paths(worst), [PathBuf::from("/music/b.mp3"), PathBuf::from("/music/c.mp3")]);
+    assert_eq!(paths(store.list_tracks_by_quality(0.0, 10.0, 1, 1).unwrap()), [PathBuf::from("/music/c.mp3")]);
+  }
+
+  #[test]
+  fn quality_report_cutoff_and_details_are_persisted() {
+    use crate::schema::library_files;
+    use gamus_core::domain::release_track::{AudioQuality, AudioQualityReport};
+
+    let store = LibraryStore::in_memory().unwrap();
+    let mut track = track_at("/music/lossy.flac");
+    track.audio_details.analysis = Some(AudioAnalysis {
+      quality: Some(AudioQuality {
+        outcome: AnalysisOutcome::CutoffDetected { freq: 16_000.0, ref_db: -30.0, cut_db: -90.0 },
+        quality_score: 4.0,
+        assessment: "MP3 128 kbps transcode".into(),
+        report: AudioQualityReport {
+          level: QualityLevel::Low,
+          score: 4.0,
+          label: "Lossy".into(),
+          summary: "Cutoff at 16 kHz".into(),
+          details: Some("sharp drop of 60 dB".into()),
+          cutoff_freq_hz: Some(16_000.0),
+          max_freq_hz: Some(22_050.0),
+          stereo_correlation: None,
+        },
+      }),
+      features: None,
+      bpm: None,
+    });
+    save_with_parents(&store, &track);
+
+    {
+      let mut conn = store.get_conn().unwrap();
+      let stored: (Option<String>, Option<f32>, Option<String>) = library_files::table
+        .select((library_files::quality_level, library_files::quality_cutoff_hz, library_files::quality_details))
+        .first(&mut conn)
+        .unwrap();
+      assert_eq!(stored, (Some("low".into()), Some(16_000.0), Some("sharp drop of 60 dB".into())));
+    }
+
+    // Outcome and report come back whole, not just the flat columns.
+    assert_eq!(store.list_tracks_page(0, 1).unwrap(), vec![track]);
+  }
+
+  #[test]
+  fn track_metadata_corrections_and_clearing_the_title_override() {
+    let store = LibraryStore::in_memory().unwrap();
+    let mut track = track_at("/music/mislabeled.flac");
+    track.title_override = Some("Dayvan Cowboy (Radio Edit)".into());
+    save_with_parents(&store, &track);
+
+    store.update_track_metadata(track.id, Some(7), None, None).unwrap();
+    let stored = store.list_recent_tracks(1).unwrap().remove(0);
+    assert_eq!((stored.track_number, stored.disc_number), (7, 1));
+    assert_eq!(stored.title_override.as_deref(), Some("Dayvan Cowboy (Radio Edit)"));
+
+    store.update_track_metadata(track.id, None, Some(2), Some(None)).unwrap();
+    let stored = store.list_recent_tracks(1).unwrap().remove(0);
+    assert_eq!((stored.track_number, stored.disc_number), (7, 2));
+    assert_eq!(stored.title_override, None);
+
+    assert!(matches!(store.update_track_metadata(track.id, Some(0), None, None), Err(CoreError::InvalidInput(_))));
+    assert!(matches!(
+      store.update_track_metadata(ReleaseTrackId::new(), Some(1), None, None),
+      Err(CoreError::NotFound)
+    ));
+  }
+
+  #[test]
+  fn patches_only_touch_the_fields_they_carry() {
+    use gamus_core::domain::artist::ArtistPatch;
+    use gamus_core::domain::song::SongPatch;
+
+    let store = LibraryStore::in_memory().unwrap();
+    let song = Song {
+      id: SongId::new(),
+      acoustid: Some("acoustid-1".into()),
+      title: "Roygbiv".into(),
+      lyrics: Some("no lyrics".into()),
+      comments: vec!["rip".into()],
+      isrc: Some("GBBPW9800012".into()),
+      mbid: None,
+    };
+    store.save_song(&song).unwrap();
+
+    let retitle = SongPatch { title: Some(" Roygbiv (Remastered) ".into()), ..Default::default() };
+    store.update_song(song.id, &retitle).unwrap();
+    let stored = store.find_song(song.id).unwrap().unwrap();
+    assert_eq!(stored, Song { title: "Roygbiv (Remastered)".into(), ..song.clone() });
+
+    store.update_song(song.id, &SongPatch { isrc: Some(None), ..Default::default() }).unwrap();
+    assert_eq!(store.find_song(song.id).unwrap().unwrap().isrc, None);
+
+    let blank = SongPatch { title: Some("  ".into()), ..Default::default() };
+    assert!(matches!(store.update_song(song.id, &blank), Err(CoreError::InvalidInput(_))));
+    assert!(matches!(store.update_song(SongId::new(), &retitle), Err(CoreError::NotFound)));
+
+    let artist =
+      |name: &str| Artist { id: ArtistId::new(), name: name.into(), variations: vec![], bio: None, sites: vec![] };
+    let (boc, autechre) = (artist("Boards of Canada"), artist("Autechre"));
+    store.save_artists_batch(&[boc.clone(), autechre.clone()]).unwrap();
+    store.update_artist(boc.id, &ArtistPatch { bio: Some(Some("Scottish duo".into())), ..Default::default() }).unwrap();
+    let stored = store.find_artist(boc.id).unwrap().unwrap();
+    assert_eq!((stored.name.as_str(), stored.bio.as_deref()), ("Boards of Canada", Some("Scottish duo")));
+
+    let clash = ArtistPatch { name: Some("autechre".into()), ..Default::default() };
+    assert!(matches!(store.update_artist(boc.id, &clash), Err(CoreError::InvalidInput(_))));
+  }
+
+  #[test]
+  fn release_patch_only_touches_the_fields_it_carries() {
+    let store = LibraryStore::in_memory().unwrap();
+    let release = Release {
+      id: ReleaseId::new(),
+      title: "Music Has the Right to Children".into(),
+      release_type: vec![],
+      main_artist_ids: vec![],
+      release_tracks: vec![],
+      release_date: Some("1998-04-20".into()),
+      artworks: vec![],
+      genres: vec![Genre::Electronic],
+      styles: vec![],
+      mbid: Some("mbid-1".into()),
+    };
+    store.save_release(&release).unwrap();
+
+    let retitle = ReleasePatch { title: Some(" MHTRTC ".into()), ..Default::default() };
+    store.update_release(release.id, &retitle).unwrap();
+    let stored = store.find_release(release.id).unwrap().unwrap();
+    assert_eq!(stored, Release { title: "MHTRTC".into(), ..release.clone() });
+
+    store.update_release(release.id, &ReleasePatch { release_date: Some(None), ..Default::default() }).unwrap();
+    let stored = store.find_release(release.id).unwrap().unwrap();
+    assert_eq!((stored.release_date, stored.mbid.as_deref()), (None, Some("mbid-1")));
+
+    let blank = ReleasePatch { title: Some("  ".into()), ..Default::default() };
+    assert!(matches!(store.update_release(release.id, &blank), Err(CoreError::InvalidInput(_))));
+    assert!(matches!(store.update_release(ReleaseId::new(), &retitle), Err(CoreError::NotFound)));
+  }
+
+  #[test]
+  fn songs_and_releases_are_found_by_external_ids_and_only_mbids_must_be_unique() {
+    let store = LibraryStore::in_memory().unwrap();
+    let song = Song {
+      id: SongId::new(),
+      acoustid: None,
+      title: "Roygbiv".into(),
+      lyrics: None,
+      comments: vec![],
+      isrc: Some("GBBPW9800012".into()),
+      mbid: Some("3a6c9bd8-5a2e-4b3f-9df6-0c5b9f1ec7a1".into()),
+    };
+    store.save_song(&song).unwrap();
+
+    assert_eq!(store.find_song_by_isrc("GBBPW9800012").unwrap(), Some(song.clone()));
+    assert_eq!(store.find_song_by_mbid("3a6c9bd8-5a2e-4b3f-9df6-0c5b9f1ec7a1").unwrap(), Some(song.clone()));
+    assert_eq!(store.find_song_by_mbid("unknown").unwrap(), None);
+    assert_eq!(store.find_release_by_mbid("3a6c9bd8-5a2e-4b3f-9df6-0c5b9f1ec7a1").unwrap(), None);
+
+    let same_isrc = Song { id: SongId::new(), mbid: None, ..song.clone() };
+    store.save_song(&same_isrc).unwrap();
+    assert_eq!(store.find_song_by_isrc("GBBPW9800012").unwrap(), Some(song.clone()), "the oldest one wins");
+
+    let duplicate = Song { id: SongId::new(), isrc: None, ..song };
+    assert!(store.save_song(&duplicate).is_err());
+  }
+
+  #[test]
+  fn import_checkpoint_round_trip_and_paths_saved_since() {
+    use crate::schema::library_files;
+
+    let store = LibraryStore::in_memory().unwrap();
+    assert_eq!(store.load_import_checkpoint().unwrap(), None);
+
+    let old = track_at("/music/old.flac");
+    save_with_parents(&store, &old);
+    {
+      let mut conn = store.get_conn().unwrap();
+      diesel::update(library_files::table)
+        .set(library_files::updated_at.eq("2000-01-01 00:00:00"))
+        .execute(&mut conn)
+        .unwrap();
+    }
+    let checkpoint = ImportCheckpoint { started_at: 1_000_000_000 }; // 2001-09-09
+    store.save_import_checkpoint(&checkpoint).unwrap();
+    store.save_import_checkpoint(&checkpoint).unwrap();
+    save_with_parents(&store, &track_at("/music/new.flac"));
+
+    assert_eq!(store.load_import_checkpoint().unwrap(), Some(checkpoint));
+    assert_eq!(store.list_paths_saved_since(checkpoint.started_at).unwrap(), vec![PathBuf::from("/music/new.flac")]);
+
+    store.clear_import_checkpoint().unwrap();
+    assert_eq!(store.load_import_checkpoint().unwrap(), None);
+  }
+
+  #[test]
+  fn reimporting_a_file_updates_its_track_instead_of_adding_one() {
+    use crate::schema::{library_files, release_tracks};
+
+    let store = LibraryStore::in_memory().unwrap();
+    let first = track_at("/music/a.flac");
+    save_with_parents(&store, &first);
+    // Same file on the next import: new ids everywhere, a different track number.
+    let again = ReleaseTrack { track_number: 7, ..track_at("/music/a.flac") };
+    save_with_parents(&store, &again);
+
+    let mut conn = store.get_conn().unwrap();
+    assert_eq!(release_tracks::table.count().get_result::<i64>(&mut conn).unwrap(), 1);
+    assert_eq!(library_files::table.count().get_result::<i64>(&mut conn).unwrap(), 1);
+    drop(conn);
+    let stored = store.find_track(first.id).unwrap().unwrap();
+    assert_eq!((stored.track_number, stored.release_id), (7, again.release_id));
+  }
+
+  #[test]
+  fn files_follow_their_root_when_it_is_relocated() {
+    use crate::schema::library_files;
+
+    let store = LibraryStore::in_memory().unwrap();
+    let before = track_at("/music/Artist/01.flac");
+    save_with_parents(&store, &before);
+    store.record_extraction_failure(Path::new("/music/Artist/02.m4p"), "encrypted", 10, 1_000).unwrap();
+
+    // Registering the root rebases the file saved before it existed.
+    let root = store.add_library_root(Path::new("/music")).unwrap();
+    assert_eq!(store.add_library_root(Path::new("/music")).unwrap(), root);
+    let stored = |store: &LibraryStore| -> Vec<(String, String)> {
+      let mut conn = store.get_conn().unwrap();
+      library_files::table.select((library_files::root_id, library_files::path)).load(&mut conn).unwrap()
+    };
+    assert_eq!(stored(&store), [(root.id.clone(), "Artist/01.flac".to_string())]);
+
+    // Re-saving from the same absolute path hits the same row.
+    save_with_parents(&store, &track_at("/music/Artist/01.flac"));
+    assert_eq!(stored(&store).len(), 1);
+
+    store.relocate_library_root(&root.id, Path::new("/mnt/usb/music")).unwrap();
+    assert_eq!(store.list_file_states().unwrap()[0].path, PathBuf::from("/mnt/usb/music/Artist/01.flac"));
+    // The failure log follows its root too.
+    let failed = Path::new("/mnt/usb/music/Artist/02.m4p");
+    assert_eq!(store.list_extraction_failures().unwrap()[0].path, failed);
+    store.forget_extraction_failure(failed).unwrap();
+    assert!(store.list_extraction_failures().unwrap().is_empty());
+    assert!(matches!(store.relocate_library_root("nope", Path::new("/x")), Err(CoreError::NotFound)));
+    assert!(matches!(store.add_library_root(Path::new("relative")), Err(CoreError::InvalidInput(_))));
+  }
+
+  #[test]
+  fn orphan_songs_and_empty_releases_have_no_tracks() {
+    let store = LibraryStore::in_memory().unwrap();
+
+    let song = |title: &str| Song {
+      id: SongId::new(),
+      acoustid: None,
+      title: title.into(),
+      lyrics: None,
+      comments: vec![],
+      isrc: None,
+      mbid: None,
+    };
+    let release = |title: &str| Release {
+      id: ReleaseId::new(),
+      title: title.into(),
+      release_type: vec![],
+      main_artist_ids: vec![],
+      release_tracks: vec![],
+      release_date: None,
+      artworks: vec![],
+      genres: vec![],
+      styles: vec![],
+      mbid: None,
+    };
+
+    let (used_song, orphan) = (song("Roygbiv"), song("Aquarius"));
+    let (used_release, empty) = (release("Music Has the Right to Children"), release("Twoism"));
+    for s in [&used_song, &orphan] {
+      store.save_song(s).unwrap();
+    }
+    for r in [&used_release, &empty] {
+      store.save_release(r).unwrap();
+    }
+
+    let mut track = track_at("/music/roygbiv.flac");
+    track.song_id = used_song.id;
+    track.release_id = used_release.id;
+    store.save_track(&track).unwrap();
+
+    assert_eq!(store.list_orphan_songs().unwrap(), vec![orphan]);
+    assert_eq!(store.list_empty_releases().unwrap(), vec![empty]);
+  }
+
+  #[test]
+  fn sync_queries_page_by_cursor_and_skip_identical_resaves() {
+    use crate::schema::{artists, releases, songs};
+
+    let store = LibraryStore::in_memory().unwrap();
+    let song = |title: &str| Song {
+      id: SongId::new(),
+      acoustid: None,
+      title: title.into(),
+      lyrics: None,
+      comments: vec![],
+      isrc: None,
+      mbid: None,
+    };
+    let (changed, untouched) = (song("Roygbiv"), song("Aquarius"));
+    let release = Release {
+      id: ReleaseId::new(),
+      title: "Geogaddi".into(),
+      release_type: vec![],
+      main_artist_ids: vec![],
+      release_tracks: vec![],
+      release_date: None,
+      artworks: vec![],
+      genres: vec![],
+      styles: vec![],
+      mbid: None,
+    };
+    let artist =
+      Artist { id: ArtistId::new(), name: "Boards of Canada".into(), variations: vec![], bio: None, sites: vec![] };
+    store.save_song(&changed).unwrap();
+    store.save_song(&untouched).unwrap();
+    store.save_release(&release).unwrap();
+    store.save_artist(&artist).unwrap();
+
+    // Backdate everything to the same second so the re-saves below are observable without
+    // sleeping. The in-memory pool has a single connection: release it before using the store again.
+    const EPOCH_2000: i64 = 946_684_800;
+    {
+      let mut conn = store.get_conn().unwrap();
+      diesel::update(songs::table).set(songs::updated_at.eq("2000-01-01 00:00:00")).execute(&mut conn).unwrap();
+      diesel::update(releases::table).set(releases::updated_at.eq("2000-01-01 00:00:00")).execute(&mut conn).unwrap();
+      diesel::update(artists::table).set(artists::updated_at.eq("2000-01-01 00:00:00")).execute(&mut conn).unwrap();
+    }
+
+    // Both songs share a second: the id in the cursor still splits them across pages.
+    let first = store.list_songs_updated_after(&SyncCursor::default(), 1).unwrap();
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].cursor.updated_at, EPOCH_2000);
+    let second = store.list_songs_updated_after(&first[0].cursor, 1).unwrap();
+    assert_eq!(second.len(), 1);
+    assert_ne!(second[0].item.id, first[0].item.id);
+    let songs_seen = second[0].cursor.clone();
+    assert!(store.list_songs_updated_after(&songs_seen, 10).unwrap().is_empty());
+
+    let releases_seen = store.list_releases_updated_after(&SyncCursor::default(), 10).unwrap()[0].cursor.clone();
+    let artists_seen = store.list_artists_updated_after(&SyncCursor::default(), 10).unwrap()[0].cursor.clone();
+
+    // A changed column, nothing at all, and a changed child row.
+    store.save_song(&Song { title: "Roygbiv (remaster)".into(), ..changed.clone() }).unwrap();
+    store.save_release(&release).unwrap();
+    let renamed = Artist { variations: vec!["BoC".into()], ..artist.clone() };
+    store.save_artists_batch(std::slice::from_ref(&renamed)).unwrap();
+
+    let songs_after = store.list_songs_updated_after(&songs_seen, 10).unwrap();
+    assert_eq!(songs_after.iter().map(|s| s.item.id).collect::<Vec<_>>(), vec![changed.id]);
+    assert!(store.list_releases_updated_after(&releases_seen, 10).unwrap().is_empty());
+    let artists_after = store.list_artists_updated_after(&artists_seen, 10).unwrap();
+    assert_eq!(artists_after.into_iter().map(|a| a.item).collect::<Vec<_>>(), vec![renamed]);
+  }
+
+  #[test]
+  fn release_types_round_trip_including_custom_values() {
+    let store = LibraryStore::in_memory().unwrap();
+
+    let release = Release {
+      id: ReleaseId::new(),
+      title: "Geogaddi".into(),
+      release_type: vec![
+        ReleaseType::Album,
+        ReleaseType::EP,
+        ReleaseType::Compilation,
+        ReleaseType::Mix,
+        ReleaseType::Custom("CD".into()),
+        ReleaseType::Custom("Live / Bootleg (ñ, 日本) 100% \"rare\"".into()),
+      ],
+      main_artist_ids: vec![],
+      release_tracks: vec![],
+      release_date: None,
+      artworks: vec![],
+      genres: vec![],
+      styles: vec![],
+      mbid: None,
+    };
+    store.save_release(&release).unwrap();
+    assert_eq!(store.find_release(release.id).unwrap(), Some(release.clone()));
+
+    let mut retyped = release.clone();
+    retyped.release_type = vec![ReleaseType::Single];
+    store.save_release(&retyped).unwrap();
+    assert_eq!(store.list_releases().unwrap(), vec![retyped]);
+  }
+
+  #[test]
+  fn artists_are_found_by_normalized_name() {
+    let store = LibraryStore::in_memory().unwrap();
+
+    let beatles =
+      Artist { id: ArtistId::new(), name: "The Beatles".into(), variations: vec![], bio: None, sites: vec![] };
+    store.save_artist(&beatles).unwrap();
+
+    assert_eq!(store.find_artist_by_name("the beatles ").unwrap().map(|a| a.id), Some(beatles.id));
+    assert_eq!(store.find_artist_by_name("THE  BEATLES").unwrap().map(|a| a.id), Some(beatles.id));
+    assert!(store.find_artist_by_name("The Rolling Stones").unwrap().is_none());
+
+    // A second id under the same canonical name is rejected by the unique index.
+    let duplicate = Artist { id: ArtistId::new(), name: "the beatles ".into(), ..beatles.clone() };
+    assert!(store.save_artist(&duplicate).is_err());
+  }
+
+  #[test]
+  fn artist_names_are_backfilled_and_merged_with_the_rust_normalization() {
+    use crate::schema::{artist_variations, artists};
+
+    let store = LibraryStore::in_memory().unwrap();
+    let (bjork, shouted) = (ArtistId::new(), ArtistId::new());
+    {
+      // State right after the column migration: no index and an empty column. SQL's lower()
+      // would keep these two apart.
+      let mut conn = store.get_conn().unwrap();
+      diesel::sql_query(format!("DROP INDEX {ARTIST_NAME_INDEX}")).execute(&mut conn).unwrap();
+      diesel::insert_into(artists::table)
+        .values(vec![
+          (artists::id.eq(bjork.to_string()), artists::name.eq("Björk"), artists::name_normalized.eq("")),
+          (artists::id.eq(shouted.to_string()), artists::name.eq("  BJÖRK "), artists::name_normalized.eq("")),
+        ])
+        .execute(&mut conn)
+        .unwrap();
+      diesel::insert_into(artist_variations::table)
+        .values((
+          artist_variations::id.eq(Uuid::new_v4().to_string()),
+          artist_variations::artist_id.eq(shouted.to_string()),
+          artist_variations::variation.eq("Bjork"),
+        ))
+        .execute(&mut conn)
+        .unwrap();
+
+      normalize_artist_names(&mut conn).unwrap();
+    }
+
+    let merged = store.list_artists().unwrap();
+    assert_eq!(merged.len(), 1);
+    assert_eq!((merged[0].id, merged[0].variations.as_slice()), (bjork, &["Bjork".to_string()][..]));
+    assert_eq!(store.find_artist_by_name("björk").unwrap().map(|a| a.id), Some(bjork));
+    let duplicate = Artist { id: ArtistId::new(), ..merged[0].clone() };
+    assert!(store.save_artist(&duplicate).is_err(), "the unique index is back");
+  }
+
+  #[test]
+  fn a_batch_repeating_an_artist_keeps_its_last_occurrence() {
+    let store = LibraryStore::in_memory().unwrap();
+
+    let first =
+      Artist { id: ArtistId::new(), name: "Boards of Canada".into(), variations: vec![], bio: None, sites: vec![] };
+    let last = Artist { name: "boards of canada".into(), bio: Some("Scottish duo".into()), ..first.clone() };
+    store.save_artists_batch(&[first.clone(), last.clone()]).unwrap();
+
+    assert_eq!(store.list_artists().unwrap(), vec![last]);
+    assert_eq!(store.find_artist_by_name("BOARDS OF CANADA").unwrap().map(|a| a.id), Some(first.id));
+  }
+
+  #[test]
+  fn counts_follow_inserts_and_deletes() {
+    use crate::schema::releases;
+
+    let store = LibraryStore::in_memory().unwrap();
+    let counts = |store: &LibraryStore| {
+      (
+        store.count_songs().unwrap(),
+        store.count_releases().unwrap(),
+        store.count_artists().unwrap(),
+        store.count_tracks().unwrap(),
+      )
+    };
+    assert_eq!(counts(&store), (0, 0, 0, 0));
+
+    let kept = track_at("/music/kept.flac");
+    let gone = track_at("/music/gone.flac");
+    save_with_parents(&store, &kept);
+    save_with_parents(&store, &gone);
+    store
+      .save_artist(&Artist { id: ArtistId::new(), name: "Nina".into(), variations: vec![], bio: None, sites: vec![] })
+      .unwrap();
+    assert_eq!(counts(&store), (2, 2, 1, 2));
+    assert_eq!(store.count_tracks().unwrap(), store.list_tracks_page(0, 100).unwrap().len() as i64);
+
+    {
+      let mut conn = store.get_conn().unwrap();
+      diesel::delete(releases::table.find(gone.release_id.to_string())).execute(&mut conn).unwrap();
+    }
+    // The release delete cascades to its track and file; the song stays behind as an orphan.
+    assert_eq!(counts(&store), (2, 1, 1, 1));
+  }
+
+  #[test]
+  fn extraction_failures_count_attempts_and_can_be_forgotten() {
+    let store = LibraryStore::in_memory().unwrap();
+    let (drm, corrupt) = (Path::new("/music/drm.m4p"), Path::new("/music/corrupt.flac"));
+
+    store.record_extraction_failure(drm, "encrypted", 10, 1_000).unwrap();
+    store.record_extraction_failure(drm, "still encrypted", 10, 2_000).unwrap();
+    store.record_extraction_failure(corrupt, "bad header", 20, 1_500).unwrap();
+
+    let failures = store.list_extraction_failures().unwrap();
+    assert_eq!(failures.len(), 2);
+    assert_eq!(
+      failures[1],
+      ExtractionFailure {
+        path: drm.into(),
+        error: "still encrypted".into(),
+        failed_at: 2_000,
+        attempts: 2,
+        modified_unix: 10
+      }
+    );
+
+    store.forget_extraction_failure(drm).unwrap();
+    assert_eq!(store.list_extraction_failures().unwrap()[0].path, corrupt);
+    assert_eq!(store.clear_extraction_failures().unwrap(), 1);
+    assert!(store.list_extraction_failures().unwrap().is_empty());
+  }
+
+  #[test]
+  fn facets_count_releases_per_genre_and_style() {
+    use crate::schema::release_genres;
+
+    let store = LibraryStore::in_memory().unwrap();
+    let release = |genres: Vec<Genre>, styles: Vec<Style>| {
+      let release = Release {
+        id: ReleaseId::new(),
+        title: "Release".into(),
+        release_type: vec![],
+        main_artist_ids: vec![],
+        release_tracks: vec![],
+        release_date: None,
+        artworks: vec![],
+        genres,
+        styles,
+        mbid: None,
+      };
+      store.save_release(&release).unwrap();
+      release.id
+    };
+    let first = release(vec![Genre::Electronic, Genre::HipHop], vec![Style::Ambient]);
+    release(vec![Genre::Electronic], vec![Style::Ambient, Style::Custom("Glitch".into())]);
+
+    // Another spelling of a known genre merges with it, without counting the release twice;
+    // an unknown value is left out.
+    {
+      let mut conn = store.get_conn().unwrap();
+      for raw in ["hip-hop", "Vaporwave"] {
+        diesel::insert_into(release_genres::table)
+          .values((
+            release_genres::id.eq(Uuid::new_v4().to_string()),
+            release_genres::release_id.eq(first.to_string()),
+            release_genres::genre.eq(raw),
+          ))
+          .execute(&mut conn)
+          .unwrap();
+      }
+    }
+
+    assert_eq!(store.distinct_genres().unwrap(), vec![(Genre::Electronic, 2), (Genre::HipHop, 1)]);
+    assert_eq!(store.distinct_styles().unwrap(), vec![(Style::Ambient, 2), (Style::Custom("Glitch".into()), 1)]);
+  }
+
+  #[test]
+  fn browse_combines_only_the_filters_that_are_set() {
+    use crate::schema::library_files;
+
+    let store = LibraryStore::in_memory().unwrap();
+    let artist = Artist { id: ArtistId::new(), name: "Boards".into(), variations: vec![], bio: None, sites: vec![] };
+    store.save_artist(&artist).unwrap();
+    let release = |title: &str, date: Option<&str>, genre: Genre, score: Option<f32>, artists: Vec<ArtistId>| {
+      let track = track_at(&format!("/music/{title}.flac"));
+      save_with_parents(&store, &track);
+      let release = Release {
+        id: track.release_id,
+        title: title.into(),
+        release_type: vec![],
+        main_artist_ids: artists,
+        release_tracks: vec![],
+        release_date: date.map(Into::into),
+        artworks: vec![],
+        genres: vec![genre],
+        styles: vec![],
+        mbid: None,
+      };
+      store.save_release(&release).unwrap();
+      let mut conn = store.get_conn().unwrap();
+      diesel::update(library_files::table.filter(library_files::release_track_id.eq(track.id.to_string())))
+        .set(library_files::quality_score.eq(score))
+        .execute(&mut conn)
+        .unwrap();
+      release.id
+    };
+    let beta = release("beta", Some("1999-05-01"), Genre::Electronic, Some(9.0), vec![artist.id, artist.id]);
+    let alpha = release("Alpha", Some("2012"), Genre::Rock, Some(4.0), vec![]);
+    let gamma = release("gamma", Some("unknown"), Genre::Electronic, None, vec![]);
+    assert_eq!(store.find_release(beta).unwrap().unwrap().main_artist_ids, [artist.id]);
+
+    let browse = |filter: ReleaseFilter, sort: SortBy| {
+      let page = store.browse_releases(filter, sort, Page::default()).unwrap();
+      page.items.iter().map(|r| r.id).collect::<Vec<_>>()
+    };
+
+    let first = store.browse_releases(ReleaseFilter::default(), SortBy::Title, Page { offset: 0, limit: 2 }).unwrap();
+    assert_eq!(first.total, 3);
+    assert_eq!(first.items.iter().map(|r| r.id).collect::<Vec<_>>(), [alpha, beta]);
+    assert_eq!(
+      first.items[1],
+      ReleaseSummary {
+        id: beta,
+        title: "beta".into(),
+        artist_names: vec!["Boards".into()],
+        year: Some(1999),
+        track_count: 1,
+        avg_quality: Some(9.0),
+      }
+    );
+
+    // Releases without a year or a score sort last.
+    assert_eq!(browse(ReleaseFilter::default(), SortBy::Year), [alpha, beta, gamma]);
+    assert_eq!(browse(ReleaseFilter::default(), SortBy::Quality), [beta, alpha, gamma]);
+
+    let electronic = ReleaseFilter { genre: Some(Genre::Electronic), ..ReleaseFilter::default() };
+    assert_eq!(browse(electronic.clone(), SortBy::Title), [beta, gamma]);
+    assert_eq!(browse(ReleaseFilter { year_to: Some(2000), ..electronic }, SortBy::Title), [beta]);
+    assert_eq!(browse(ReleaseFilter { year_from: Some(2000), ..ReleaseFilter::default() }, SortBy::Title), [alpha]);
+    assert_eq!(browse(ReleaseFilter { min_quality: Some(5.0), ..ReleaseFilter::default() }, SortBy::Title), [beta]);
+    assert_eq!(browse(ReleaseFilter { artist: Some(artist.id), ..ReleaseFilter::default() }, SortBy::Title), [beta]);
+  }
+
+  #[test]
+  fn similar_tracks_are_ranked_by_embedding_and_unembedded_ones_skipped() {
+    let store = LibraryStore::in_memory().unwrap();
+    let with_features = |path: &str, features: Option<Vec<f32>>| {
+      let mut track = track_at(path);
+      track.audio_details.analysis = Some(AudioAnalysis { quality: None, features, bpm: None });
+      save_with_parents(&store, &track);
+      track.id
+    };
+    let query = with_features("/music/query.flac", Some(vec![1.0, 0.0]));
+    let close = with_features("/music/close.flac", Some(vec![0.9, 0.1]));
+    let far = with_features("/music/far.flac", Some(vec![0.0, 1.0]));
+    let bare = with_features("/music/bare.flac", None);
+
+    let similar = store.find_similar(query, 10).unwrap();
+    assert_eq!(similar.iter().map(|(id, _)| *id).collect::<Vec<_>>(), [close, far]);
+    assert!(similar[0].1 > 0.99 && similar[1].1.abs() < 1e-6);
+
+    assert_eq!(store.find_similar(query, 1).unwrap().len(), 1);
+    assert!(matches!(store.find_similar(bare, 10), Err(CoreError::InvalidInput(_))));
+    assert!(matches!(store.find_similar(ReleaseTrackId::new(), 10), Err(CoreError::NotFound)));
   }
 }
@@ -1,5 +1,18 @@
+use crate::schema::artist_sites;
+use crate::schema::artist_variations;
 use crate::schema::artists;
+use crate::schema::artworks;
+use crate::schema::library_files;
+use crate::schema::library_roots;
+use crate::schema::release_genres;
+use crate::schema::release_main_artists;
+use crate::schema::release_styles;
+use crate::schema::release_track_artists;
+use crate::schema::release_tracks;
+use crate::schema::release_types;
 use crate::schema::releases;
+use crate::schema::song_comments;
+use crate::schema::song_lyrics;
 use crate::schema::songs;
 
 use diesel::prelude::*;
@@ -16,14 +29,61 @@ pub struct ArtistRow {
   pub bio: Option<String>,
   pub created_at: String,
   pub updated_at: String,
+  pub name_normalized: String,
 }
 
 #[derive(Debug, Insertable)]
 #[diesel(table_name = artists)]
+// Binds `None` as NULL instead of DEFAULT, which SQLite needs for multi-row inserts.
+#[diesel(treat_none_as_default_value = false)]
 pub struct NewArtistRow {
   pub id: String,
   pub name: String,
   pub bio: Option<String>,
+  pub name_normalized: String,
+}
+
+/// Partial update of an artist; `None` fields are left untouched.
+#[derive(Debug, AsChangeset)]
+#[diesel(table_name = artists)]
+pub struct ArtistChangeset {
+  pub name: Option<String>,
+  /// Set together with `name`.
+  pub name_normalized: Option<String>,
+  /// `Some(None)` clears the bio.
+  pub bio: Option<Option<String>>,
+}
+
+#[derive(Debug, Queryable)]
+#[diesel(table_name = artist_variations)]
+pub struct ArtistVariationRow {
+  pub id: String,
+  pub artist_id: String,
+  pub variation: String,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = artist_variations)]
+pub struct NewArtistVariationRow {
+  pub id: String,
+  pub artist_id: String,
+  pub variation: String,
+}
+
+#[derive(Debug, Queryable)]
+#[diesel(table_name = artist_sites)]
+pub struct ArtistSiteRow {
+  pub id: String,
+  pub artist_id: String,
+  pub url: String,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = artist_sites)]
+pub struct NewArtistSiteRow {
+  pub id: String,
+  pub artist_id: String,
+  pub url: String,
 }
 
 // ====================
@@ -38,6 +98,8 @@ pub struct SongRow {
   pub acoustid: Option<String>,
   pub created_at: String,
   pub updated_at: String,
+  pub isrc: Option<String>,
+  pub mbid: Option<String>,
 }
 
 #[derive(Debug, Insertable)]
@@ -46,6 +108,51 @@ pub struct NewSongRow {
   pub id: String,
   pub title: String,
   pub acoustid: Option<String>,
+  pub isrc: Option<String>,
+  pub mbid: Option<String>,
+}
+
+/// Partial update of a song; `None` fields are left untouched, `Some(None)` clears them.
+#[derive(Debug, AsChangeset)]
+#[diesel(table_name = songs)]
+pub struct SongChangeset {
+  pub title: Option<String>,
+  pub acoustid: Option<Option<String>>,
+  pub isrc: Option<Option<String>>,
+  pub mbid: Option<Option<String>>,
+}
+
+#[derive(Debug, Queryable)]
+#[diesel(table_name = song_lyrics)]
+pub struct SongLyricsRow {
+  pub song_id: String,
+  pub lyrics: String,
+  pub created_at: String,
+  pub updated_at: String,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = song_lyrics)]
+pub struct NewSongLyricsRow {
+  pub song_id: String,
+  pub lyrics: String,
+}
+
+#[derive(Debug, Queryable)]
+#[diesel(table_name = song_comments)]
+pub struct SongCommentRow {
+  pub id: String,
+  pub song_id: String,
+  pub comment: String,
+  pub created_at: String,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = song_comments)]
+pub struct NewSongCommentRow {
+  pub id: String,
+  pub song_id: String,
+  pub comment: String,
 }
 
 // ====================
@@ -60,6 +167,7 @@ pub struct ReleaseRow {
   pub release_date: Option<String>,
   pub created_at: String,
   pub updated_at: String,
+  pub mbid: Option<String>,
 }
 
 #[derive(Debug, Insertable)]
@@ -68,4 +176,282 @@ pub struct NewReleaseRow {
   pub id: String,
   pub title: String,
   pub release_date: Option<String>,
+  pub mbid: Option<String>,
+}
+
+/// Partial update of a release; `None` fields are left untouched, `Some(None)` clears them.
+#[derive(Debug, AsChangeset)]
+#[diesel(table_name = releases)]
+pub struct ReleaseChangeset {
+  pub title: Option<String>,
+  pub release_date: Option<Option<String>>,
+  pub mbid: Option<Option<String>>,
+}
+
+// ====================
+// ARTWORKS
+// ====================
+
+#[derive(Debug, Queryable)]
+#[diesel(table_name = artworks)]
+pub struct ArtworkRow {
+  pub id: String,
+  pub release_id: String,
+  pub path: String,
+  pub mime_type: String,
+  pub description: Option<String>,
+  pub hash: Option<String>,
+  pub credits: Option<String>,
+  pub thumbnail_path: Option<String>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = artworks)]
+pub struct NewArtworkRow {
+  pub id: String,
+  pub release_id: String,
+  pub path: String,
+  pub mime_type: String,
+  pub description: Option<String>,
+  pub hash: Option<String>,
+  pub credits: Option<String>,
+  pub thumbnail_path: Option<String>,
+}
+
+// ====================
+// RELEASE TYPES
+// ====================
+
+#[derive(Debug, Queryable)]
+#[diesel(table_name = release_types)]
+pub struct ReleaseTypeRow {
+  pub id: String,
+  pub release_id: String,
+  pub kind: String,
+  pub custom: Option<String>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = release_types)]
+pub struct NewReleaseTypeRow {
+  pub id: String,
+  pub release_id: String,
+  pub kind: String,
+  pub custom: Option<String>,
+}
+
+// ====================
+// RELEASE MAIN ARTISTS
+// ====================
+
+#[derive(Debug, Queryable)]
+#[diesel(table_name = release_main_artists)]
+pub struct ReleaseMainArtistRow {
+  pub id: String,
+  pub release_id: String,
+  pub artist_id: String,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = release_main_artists)]
+pub struct NewReleaseMainArtistRow {
+  pub id: String,
+  pub release_id: String,
+  pub artist_id: String,
+}
+
+// ====================
+// RELEASE GENRES / STYLES
+// ====================
+
+#[derive(Debug, Queryable)]
+#[diesel(table_name = release_genres)]
+pub struct ReleaseGenreRow {
+  pub id: String,
+  pub release_id: String,
+  pub genre: String,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = release_genres)]
+pub struct NewReleaseGenreRow {
+  pub id: String,
+  pub release_id: String,
+  pub genre: String,
+}
+
+#[derive(Debug, Queryable)]
+#[diesel(table_name = release_styles)]
+pub struct ReleaseStyleRow {
+  pub id: String,
+  pub release_id: String,
+  pub style: String,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = release_styles)]
+pub struct NewReleaseStyleRow {
+  pub id: String,
+  pub release_id: String,
+  pub style: String,
+}
+
+// ====================
+// RELEASE TRACKS
+// ====================
+
+#[derive(Debug, Queryable)]
+#[diesel(table_name = release_tracks)]
+pub struct ReleaseTrackRow {
+  pub id: String,
+  pub release_id: String,
+  pub song_id: String,
+  pub disc_number: i32,
+  pub track_number: i32,
+  pub title_override: Option<String>,
+  pub created_at: String,
+  pub updated_at: String,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = release_tracks)]
+pub struct NewReleaseTrackRow {
+  pub id: String,
+  pub release_id: String,
+  pub song_id: String,
+  pub disc_number: i32,
+  pub track_number: i32,
+  pub title_override: Option<String>,
+}
+
+/// Manual corrections to a track; `None` fields are left untouched.
+#[derive(Debug, AsChangeset)]
+#[diesel(table_name = release_tracks)]
+pub struct ReleaseTrackMetadataChangeset {
+  pub track_number: Option<i32>,
+  pub disc_number: Option<i32>,
+  /// `Some(None)` clears the override.
+  pub title_override: Option<Option<String>>,
+}
+
+// ====================
+// RELEASE TRACK CREDITS
+// ====================
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = release_track_artists)]
+pub struct NewReleaseTrackArtistRow {
+  pub id: String,
+  pub release_track_id: String,
+  pub artist_id: String,
+  pub role: String,
+  pub position: Option<i32>,
+}
+
+// ====================
+// LIBRARY FILES
+// ====================
+
+#[derive(Debug, Queryable)]
+#[diesel(table_name = library_files)]
+pub struct LibraryFileRow {
+  pub id: String,
+  pub release_track_id: String,
+  pub path: String,
+  pub size_bytes: i64,
+  pub modified_unix: i64,
+  pub duration_ms: i64,
+  pub bitrate_kbps: Option<i32>,
+  pub sample_rate_hz: Option<i32>,
+  pub channels: Option<i32>,
+  pub fingerprint: Option<String>,
+  pub bpm: Option<f32>,
+  pub quality_score: Option<f32>,
+  pub quality_assessment: Option<String>,
+  /// Codificado con [`crate::features::encode_features`].
+  pub features: Option<Vec<u8>>,
+  pub added_at: String,
+  pub updated_at: String,
+  pub codec: Option<String>,
+  pub container: Option<String>,
+  pub is_lossless: Option<bool>,
+  /// `QualityLevel` en su forma `Display`; `None` si el archivo no se analizó.
+  pub quality_level: Option<String>,
+  pub content_hash: Option<String>,
+  /// `AudioQualityReport.cutoff_freq_hz`; `None` si no se detectó corte.
+  pub quality_cutoff_hz: Option<f32>,
+  /// `AudioQualityReport.details`.
+  pub quality_details: Option<String>,
+  pub replaygain_track_db: Option<f32>,
+  pub replaygain_album_db: Option<f32>,
+  pub replaygain_track_peak: Option<f32>,
+  pub replaygain_album_peak: Option<f32>,
+  /// Raíz a la que es relativo `path` (ver [`crate::paths`]).
+  pub root_id: String,
+  /// `AudioQuality` completo en JSON; las demás columnas `quality_*` salen de él.
+  pub quality_json: Option<String>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = library_files)]
+pub struct NewLibraryFileRow {
+  pub id: String,
+  pub release_track_id: String,
+  pub path: String,
+  pub size_bytes: i64,
+  pub modified_unix: i64,
+  pub duration_ms: i64,
+  pub bitrate_kbps: Option<i32>,
+  pub sample_rate_hz: Option<i32>,
+  pub channels: Option<i32>,
+  pub fingerprint: Option<String>,
+  pub bpm: Option<f32>,
+  pub quality_score: Option<f32>,
+  pub quality_assessment: Option<String>,
+  /// Codificado con [`crate::features::encode_features`].
+  pub features: Option<Vec<u8>>,
+  pub codec: Option<String>,
+  pub container: Option<String>,
+  pub is_lossless: Option<bool>,
+  /// `QualityLevel` en su forma `Display`; `None` si el archivo no se analizó.
+  pub quality_level: Option<String>,
+  pub content_hash: Option<String>,
+  /// `AudioQualityReport.cutoff_freq_hz`; `None` si no se detectó corte.
+  pub quality_cutoff_hz: Option<f32>,
+  /// `AudioQualityReport.details`.
+  pub quality_details: Option<String>,
+  pub replaygain_track_db: Option<f32>,
+  pub replaygain_album_db: Option<f32>,
+  pub replaygain_track_peak: Option<f32>,
+  pub replaygain_album_peak: Option<f32>,
+  /// Raíz a la que es relativo `path` (ver [`crate::paths`]).
+  pub root_id: String,
+  /// `AudioQuality` completo en JSON; las demás columnas `quality_*` salen de él.
+  pub quality_json: Option<String>,
+}
+
+/// Analysis columns of `library_files`, rewritten together when a file is (re)analysed.
+#[derive(Debug, AsChangeset)]
+#[diesel(table_name = library_files, treat_none_as_null = true)]
+pub struct LibraryFileAnalysisChangeset {
+  pub bpm: Option<f32>,
+  pub quality_score: Option<f32>,
+  pub quality_assessment: Option<String>,
+  /// Codificado con [`crate::features::encode_features`].
+  pub features: Option<Vec<u8>>,
+  pub quality_level: Option<String>,
+  pub quality_cutoff_hz: Option<f32>,
+  pub quality_details: Option<String>,
+  pub quality_json: Option<String>,
+}
+
+// ====================
+// LIBRARY ROOTS
+// ====================
+
+#[derive(Debug, Queryable, Insertable)]
+#[diesel(table_name = library_roots)]
+pub struct LibraryRootRow {
+  pub id: String,
+  pub path: String,
 }
@@ -0,0 +1,110 @@
+//! Library roots and the root-relative paths stored under them.
+//!
+//! `library_files.path` is stored relative to the deepest registered root containing the
+//! file, next to that root's id, with `/` as separator whatever the platform. Moving the
+//! database to another machine or remounting a drive elsewhere then only needs the root
+//! re-pointed (see [`crate::LibraryStore::relocate_library_root`]); file rows stay as they
+//! are. Files outside every root are stored absolute under [`ABSOLUTE_ROOT_ID`].
+
+use std::path::{Component, Path, PathBuf};
+
+use serde::Serialize;
+
+/// Sentinel root id for files stored with their absolute path.
+///
+/// Also the column default, so rows written before roots existed read back unchanged.
+pub const ABSOLUTE_ROOT_ID: &str = "absolute";
+
+/// A registered library root: files under `path` are stored relative to it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LibraryRoot {
+  pub id: String,
+  pub path: PathBuf,
+}
+
+/// A path as stored in `library_files`: relative to `root_id`, or absolute under
+/// [`ABSOLUTE_ROOT_ID`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredPath {
+  pub root_id: String,
+  pub path: String,
+}
+
+/// Converts between absolute paths and [`StoredPath`]s for a set of roots.
+#[derive(Debug, Clone, Default)]
+pub struct PathResolver {
+  /// Deepest first, so nested roots win over the ones containing them.
+  roots: Vec<LibraryRoot>,
+}
+
+impl PathResolver {
+  pub fn new(roots: impl IntoIterator<Item = LibraryRoot>) -> Self {
+    let mut roots: Vec<LibraryRoot> = roots.into_iter().collect();
+    roots.sort_by_key(|root| std::cmp::Reverse(root.path.components().count()));
+    Self { roots }
+  }
+
+  /// Registered roots, deepest first.
+  pub fn roots(&self) -> &[LibraryRoot] {
+    &self.roots
+  }
+
+  pub fn root(&self, id: &str) -> Option<&LibraryRoot> {
+    self.roots.iter().find(|root| root.id == id)
+  }
+
+  /// Stored form of `path`: relative to the deepest root containing it, absolute under
+  /// [`ABSOLUTE_ROOT_ID`] otherwise (including `path` being a root itself).
+  pub fn to_stored(&self, path: &Path) -> StoredPath {
+    for root in &self.roots {
+      let Ok(relative) = path.strip_prefix(&root.path) else { continue };
+      let segments: Vec<_> = relative
+        .components()
+        .map(|c| match c {
+          Component::Normal(segment) => Some(segment.to_string_lossy()),
+          _ => None,
+        })
+        .collect::<Option<_>>()
+        .unwrap_or_default();
+      if !segments.is_empty() {
+        return StoredPath { root_id: root.id.clone(), path: segments.join("/") };
+      }
+    }
+    StoredPath { root_id: ABSOLUTE_ROOT_ID.to_string(), path: path.to_string_lossy().into_owned() }
+  }
+
+  /// Absolute path of a stored row.
+  ///
+  /// A root id that is not registered (it never should be: roots are not removed) yields
+  /// the stored path unchanged, so the row is still identifiable.
+  pub fn to_absolute(&self, root_id: &str, path: &str) -> PathBuf {
+    match self.root(root_id) {
+      Some(root) => path.split('/').fold(root.path.clone(), |acc, s| acc.join(s)),
+      None => PathBuf::from(path),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn root(id: &str, path: &str) -> LibraryRoot {
+    LibraryRoot { id: id.into(), path: path.into() }
+  }
+
+  #[test]
+  fn paths_are_stored_under_the_deepest_root_and_rebuilt_from_it() {
+    let resolver = PathResolver::new([root("music", "/music"), root("masters", "/music/masters")]);
+
+    let nested = resolver.to_stored(Path::new("/music/masters/Artist/01.flac"));
+    assert_eq!(nested, StoredPath { root_id: "masters".into(), path: "Artist/01.flac".into() });
+    let outside = resolver.to_stored(Path::new("/downloads/song.mp3"));
+    assert_eq!(outside, StoredPath { root_id: ABSOLUTE_ROOT_ID.into(), path: "/downloads/song.mp3".into() });
+
+    // Re-pointing a root moves every file under it without touching the stored form.
+    let moved = PathResolver::new([root("music", "/mnt/usb/music"), root("masters", "/music/masters")]);
+    assert_eq!(moved.to_absolute("music", "Artist/02.flac"), PathBuf::from("/mnt/usb/music/Artist/02.flac"));
+    assert_eq!(moved.to_absolute(&outside.root_id, &outside.path), PathBuf::from("/downloads/song.mp3"));
+  }
+}
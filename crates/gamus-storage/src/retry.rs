@@ -0,0 +1,43 @@
+//! Retry wrapper for writes hitting transient SQLite lock contention.
+
+use std::thread;
+use std::time::Duration;
+
+use diesel::result::{DatabaseErrorKind, Error as DieselError, QueryResult};
+
+use crate::config::RetryConfig;
+
+/// Runs `f`, retrying with exponential backoff while it fails with a transient lock error.
+///
+/// `f` must be safe to re-run from scratch: callers pass a whole transaction, which
+/// SQLite has already rolled back when the error reaches us. Any other error (constraint
+/// violations, missing tables, ...) is returned on the first failure.
+pub(crate) fn with_retry<T>(cfg: &RetryConfig, mut f: impl FnMut() -> QueryResult<T>) -> QueryResult<T> {
+  let attempts = cfg.max_attempts.max(1);
+  let mut backoff = Duration::from_millis(cfg.initial_backoff_ms);
+  let max_backoff = Duration::from_millis(cfg.max_backoff_ms);
+
+  let mut attempt = 1;
+  loop {
+    match f() {
+      Err(ref e) if attempt < attempts && is_transient(e) => {
+        thread::sleep(backoff);
+        backoff = (backoff * 2).min(max_backoff);
+        attempt += 1;
+      }
+      result => return result,
+    }
+  }
+}
+
+/// `SQLITE_BUSY` and `SQLITE_LOCKED` reach diesel as `DatabaseErrorKind::Unknown`, so the
+/// message is the only thing that tells them apart from other engine errors.
+fn is_transient(err: &DieselError) -> bool {
+  match err {
+    DieselError::DatabaseError(DatabaseErrorKind::Unknown, info) => {
+      let msg = info.message();
+      msg.contains("database is locked") || msg.contains("database table is locked") || msg.contains("database is busy")
+    }
+    _ => false,
+  }
+}
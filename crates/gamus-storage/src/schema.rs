@@ -23,6 +23,7 @@ diesel::table! {
         bio -> Nullable<Text>,
         created_at -> Text,
         updated_at -> Text,
+        name_normalized -> Text,
     }
 }
 
@@ -35,6 +36,25 @@ diesel::table! {
         description -> Nullable<Text>,
         hash -> Nullable<Text>,
         credits -> Nullable<Text>,
+        thumbnail_path -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    extraction_failures (root_id, path) {
+        path -> Text,
+        error -> Text,
+        failed_at -> BigInt,
+        attempts -> Integer,
+        modified_unix -> BigInt,
+        root_id -> Text,
+    }
+}
+
+diesel::table! {
+    import_checkpoint (id) {
+        id -> Integer,
+        started_at -> BigInt,
     }
 }
 
@@ -56,6 +76,27 @@ diesel::table! {
         features -> Nullable<Binary>,
         added_at -> Text,
         updated_at -> Text,
+        codec -> Nullable<Text>,
+        container -> Nullable<Text>,
+        is_lossless -> Nullable<Bool>,
+        quality_level -> Nullable<Text>,
+        content_hash -> Nullable<Text>,
+        quality_cutoff_hz -> Nullable<Float>,
+        quality_details -> Nullable<Text>,
+        replaygain_track_db -> Nullable<Float>,
+        replaygain_album_db -> Nullable<Float>,
+        replaygain_track_peak -> Nullable<Float>,
+        replaygain_album_peak -> Nullable<Float>,
+        root_id -> Text,
+        quality_json -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    library_roots (id) {
+        id -> Text,
+        path -> Text,
+        added_at -> Text,
     }
 }
 
@@ -111,6 +152,7 @@ diesel::table! {
         id -> Text,
         release_id -> Text,
         kind -> Text,
+        custom -> Nullable<Text>,
     }
 }
 
@@ -121,6 +163,7 @@ diesel::table! {
         release_date -> Nullable<Text>,
         created_at -> Text,
         updated_at -> Text,
+        mbid -> Nullable<Text>,
     }
 }
 
@@ -133,6 +176,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    song_lyrics (song_id) {
+        song_id -> Text,
+        lyrics -> Text,
+        created_at -> Text,
+        updated_at -> Text,
+    }
+}
+
 diesel::table! {
     song_ratings (id) {
         id -> Text,
@@ -149,6 +201,8 @@ diesel::table! {
         acoustid -> Nullable<Text>,
         created_at -> Text,
         updated_at -> Text,
+        isrc -> Nullable<Text>,
+        mbid -> Nullable<Text>,
     }
 }
 
@@ -166,6 +220,7 @@ diesel::joinable!(release_tracks -> releases (release_id));
 diesel::joinable!(release_tracks -> songs (song_id));
 diesel::joinable!(release_types -> releases (release_id));
 diesel::joinable!(song_comments -> songs (song_id));
+diesel::joinable!(song_lyrics -> songs (song_id));
 diesel::joinable!(song_ratings -> songs (song_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
@@ -173,7 +228,10 @@ diesel::allow_tables_to_appear_in_same_query!(
   artist_variations,
   artists,
   artworks,
+  extraction_failures,
+  import_checkpoint,
   library_files,
+  library_roots,
   release_genres,
   release_main_artists,
   release_styles,
@@ -182,6 +240,7 @@ diesel::allow_tables_to_appear_in_same_query!(
   release_types,
   releases,
   song_comments,
+  song_lyrics,
   song_ratings,
   songs,
 );